@@ -0,0 +1,343 @@
+//! Verificação das assinaturas XMLDSig de DF-e recebidos ou armazenados.
+//!
+//! Confere cada `<Signature>` do documento (`NFe`/`nfeProc`, `procEventoNFe`,
+//! conteúdo de `docZip`…): o digest da `Reference`, a assinatura RSA do `SignedInfo`
+//! e o titular do certificado embutido. Em um `nfeProc`, confere também se o
+//! `digVal` do protocolo é o digest assinado pelo emitente.
+//!
+//! ```no_run
+//! use dfe::assinatura;
+//!
+//! # fn example() -> Result<(), dfe::DfeError> {
+//! let xml = std::fs::read_to_string("./35250111222333000181550010000000011000000019-procNFe.xml")?;
+//! let verificacao = assinatura::verificar(&xml)?;
+//! for a in &verificacao.assinaturas {
+//!     println!("{} assinado por {:?}: {}", a.referencia, a.cnpj, a.valida());
+//! }
+//! assert!(verificacao.valida());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{DfeError, Result};
+use crate::interno::c14n;
+use crate::interno::cert::DigestValue;
+use crate::interno::x509;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+const ALG_RSA_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#rsa-sha1";
+const ALG_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+const ALG_ENVELOPED: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+
+/// Resultado da verificação de todas as assinaturas de um documento.
+#[derive(Debug, Clone)]
+pub struct VerificacaoAssinatura {
+    /// Uma entrada por `<Signature>`, na ordem em que aparecem.
+    pub assinaturas: Vec<AssinaturaVerificada>,
+    /// `protNFe/infProt/digVal` confere com o `DigestValue` assinado da NF-e.
+    /// `None` quando o documento não tem protocolo de autorização.
+    pub dig_val_confere: Option<bool>,
+}
+
+impl VerificacaoAssinatura {
+    /// Todas as assinaturas íntegras e, havendo protocolo, `digVal` conferindo.
+    pub fn valida(&self) -> bool {
+        self.assinaturas.iter().all(AssinaturaVerificada::valida) && self.dig_val_confere != Some(false)
+    }
+}
+
+/// Veredito de uma `<Signature>`.
+#[derive(Debug, Clone)]
+pub struct AssinaturaVerificada {
+    /// `Id` referenciado (`Reference URI` sem o `#`), ex.: `NFe3525...` ou `ID1101113525...`.
+    pub referencia: String,
+    /// `<DigestValue>` declarado no `SignedInfo`.
+    pub digest_informado: String,
+    /// SHA-1 (base64) da forma canônica do elemento referenciado.
+    pub digest_calculado: String,
+    pub digest_confere: bool,
+    /// `<SignatureValue>` confere com o `SignedInfo` e a chave do certificado.
+    pub assinatura_confere: bool,
+    /// Subject do certificado embutido (`CN=..., OU=..., O=..., C=BR`).
+    pub titular: String,
+    /// CNPJ do titular do certificado (ICP-Brasil), quando presente.
+    pub cnpj: Option<String>,
+    /// Motivo pelo qual a conferência não pôde ser feita (algoritmo não suportado,
+    /// elemento referenciado ausente, certificado ilegível…).
+    pub erro: Option<String>,
+}
+
+impl AssinaturaVerificada {
+    pub fn valida(&self) -> bool {
+        self.erro.is_none() && self.digest_confere && self.assinatura_confere
+    }
+}
+
+/// Verifica as assinaturas de um XML assinado.
+///
+/// Retorna `Err` apenas se o XML for ilegível ou não tiver nenhuma `<Signature>`;
+/// assinaturas inválidas são reportadas no [`VerificacaoAssinatura`].
+pub fn verificar(xml: &str) -> Result<VerificacaoAssinatura> {
+    let documento = ler_documento(xml)?;
+    if documento.assinaturas.is_empty() {
+        return Err(DfeError::Assinatura("Nenhuma <Signature> encontrada no documento".to_string()));
+    }
+
+    let assinaturas: Vec<AssinaturaVerificada> =
+        documento.assinaturas.iter().map(|s| verificar_assinatura(xml, s)).collect();
+
+    let dig_val_confere = documento.dig_val.map(|dig_val| {
+        let id = documento.ch_nfe.map(|ch| format!("NFe{}", ch));
+        assinaturas
+            .iter()
+            .find(|a| Some(&a.referencia) == id.as_ref())
+            .or_else(|| assinaturas.iter().find(|a| a.referencia.starts_with("NFe")))
+            .is_some_and(|a| a.digest_informado == dig_val)
+    });
+
+    Ok(VerificacaoAssinatura { assinaturas, dig_val_confere })
+}
+
+/// Lê o arquivo e verifica as assinaturas (ver [`verificar`]).
+pub fn verificar_arquivo(path: &str) -> Result<VerificacaoAssinatura> {
+    let xml = std::fs::read_to_string(path)
+        .map_err(|e| DfeError::Io(format!("Falha ao ler {}: {}", path, e)))?;
+    verificar(&xml)
+}
+
+/// Descompacta um `docZip` da distribuição (base64 + gzip) e verifica as assinaturas.
+///
+/// Resumos (`resNFe`, `resEvento`) não são assinados e retornam `Err`.
+pub fn verificar_doc_zip(conteudo: &str) -> Result<VerificacaoAssinatura> {
+    let xml = crate::distribuicao::decode_doczip_content(conteudo)
+        .map_err(|e| DfeError::Xml(format!("docZip inválido: {}", e)))?;
+    verificar(&xml)
+}
+
+/// Campos de uma `<Signature>` necessários à verificação.
+#[derive(Default)]
+struct SignatureLida {
+    signed_info_offset: usize,
+    referencia: String,
+    transforms: Vec<String>,
+    signature_method: String,
+    digest_method: String,
+    digest_value: String,
+    signature_value: String,
+    certificado: String,
+}
+
+struct DocumentoLido {
+    assinaturas: Vec<SignatureLida>,
+    dig_val: Option<String>,
+    ch_nfe: Option<String>,
+}
+
+fn ler_documento(xml: &str) -> Result<DocumentoLido> {
+    let mut reader = Reader::from_str(xml);
+    let mut documento = DocumentoLido { assinaturas: Vec::new(), dig_val: None, ch_nfe: None };
+    let mut atual: Option<SignatureLida> = None;
+    let mut tag = String::new();
+    let mut em_prot_nfe = false;
+
+    loop {
+        let pos = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|e| DfeError::Xml(format!("XML inválido: {}", e)))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let name = e.local_name();
+                tag = String::from_utf8_lossy(name.as_ref()).to_string();
+                let algorithm = || {
+                    e.try_get_attribute("Algorithm")
+                        .ok()
+                        .flatten()
+                        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+                        .unwrap_or_default()
+                };
+                match (tag.as_str(), atual.as_mut()) {
+                    ("Signature", None) => atual = Some(SignatureLida::default()),
+                    ("protNFe", None) => em_prot_nfe = true,
+                    ("SignedInfo", Some(s)) => s.signed_info_offset = pos,
+                    ("SignatureMethod", Some(s)) => s.signature_method = algorithm(),
+                    ("DigestMethod", Some(s)) => s.digest_method = algorithm(),
+                    ("Transform", Some(s)) => s.transforms.push(algorithm()),
+                    ("Reference", Some(s)) => {
+                        let uri = e
+                            .try_get_attribute("URI")
+                            .ok()
+                            .flatten()
+                            .map(|a| String::from_utf8_lossy(&a.value).to_string())
+                            .unwrap_or_default();
+                        s.referencia = uri.trim_start_matches('#').to_string();
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(ref t) => {
+                let text = t.unescape().map_err(|e| DfeError::Xml(e.to_string()))?;
+                let text: String = text.split_whitespace().collect();
+                match (tag.as_str(), atual.as_mut()) {
+                    ("DigestValue", Some(s)) => s.digest_value.push_str(&text),
+                    ("SignatureValue", Some(s)) => s.signature_value.push_str(&text),
+                    // Só o primeiro: os demais, se houver, são da cadeia.
+                    ("X509Certificate", Some(s)) if s.certificado.is_empty() => s.certificado = text,
+                    ("digVal", None) if em_prot_nfe => documento.dig_val = Some(text),
+                    ("chNFe", None) if em_prot_nfe => documento.ch_nfe = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(ref e) => {
+                tag.clear();
+                match e.local_name().as_ref() {
+                    b"Signature" => documento.assinaturas.extend(atual.take()),
+                    b"protNFe" => em_prot_nfe = false,
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(documento)
+}
+
+fn verificar_assinatura(xml: &str, s: &SignatureLida) -> AssinaturaVerificada {
+    let mut veredito = AssinaturaVerificada {
+        referencia: s.referencia.clone(),
+        digest_informado: s.digest_value.clone(),
+        digest_calculado: String::new(),
+        digest_confere: false,
+        assinatura_confere: false,
+        titular: String::new(),
+        cnpj: None,
+        erro: None,
+    };
+    if let Err(erro) = conferir(xml, s, &mut veredito) {
+        veredito.erro = Some(erro.to_string());
+    }
+    veredito
+}
+
+fn conferir(xml: &str, s: &SignatureLida, veredito: &mut AssinaturaVerificada) -> Result<()> {
+    let cert_der = STANDARD
+        .decode(&s.certificado)
+        .map_err(|e| DfeError::Assinatura(format!("<X509Certificate> inválido: {}", e)))?;
+    let info = x509::info(&cert_der).map_err(DfeError::Certificado)?;
+    veredito.titular = info.subject;
+    veredito.cnpj = x509::cnpj(&cert_der);
+
+    if s.digest_method != ALG_SHA1 {
+        return Err(DfeError::Assinatura(format!("DigestMethod não suportado: {}", s.digest_method)));
+    }
+    if s.signature_method != ALG_RSA_SHA1 {
+        return Err(DfeError::Assinatura(format!("SignatureMethod não suportado: {}", s.signature_method)));
+    }
+
+    let enveloped = s.transforms.iter().any(|t| t == ALG_ENVELOPED);
+    let referenciado = c14n::canonicalize_by_id(xml, &s.referencia, enveloped)?;
+    veredito.digest_calculado = DigestValue::sha1(&referenciado)?;
+    veredito.digest_confere = veredito.digest_calculado == s.digest_value;
+
+    let signed_info = c14n::canonicalize_at(xml, s.signed_info_offset)?;
+    let signature = STANDARD
+        .decode(&s.signature_value)
+        .map_err(|e| DfeError::Assinatura(format!("<SignatureValue> inválido: {}", e)))?;
+    veredito.assinatura_confere = x509::verify_rsa_sha1(&cert_der, signed_info.as_bytes(), &signature)
+        .map_err(DfeError::Certificado)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Certificado;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const CHAVE: &str = "35250111222333000181550010000000011000000019";
+
+    /// `nfeProc` assinado com o certificado de teste, como o `emissao` monta.
+    fn nfe_proc(x_prod: &str, dig_val: Option<&str>) -> String {
+        let cert = Certificado::from_bytes(include_bytes!("../../testes/certificado_teste_legacy.pfx"), "1234").unwrap();
+        let inf_nfe = format!(
+            r#"<infNFe xmlns="http://www.portalfiscal.inf.br/nfe" Id="NFe{}" versao="4.00"><det nItem="1"><prod><xProd>PRODUTO &amp; CIA</xProd></prod></det></infNFe>"#,
+            CHAVE
+        );
+        let digest = DigestValue::c14n_sha1(&inf_nfe, "infNFe").unwrap();
+        let signed_info = format!(
+            concat!(
+                r#"<SignedInfo xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+                r#"<CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"/>"#,
+                r#"<SignatureMethod Algorithm="http://www.w3.org/2000/09/xmldsig#rsa-sha1"/>"#,
+                r##"<Reference URI="#NFe{}"><Transforms>"##,
+                r#"<Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/>"#,
+                r#"<Transform Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"/>"#,
+                r#"</Transforms><DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"/>"#,
+                r#"<DigestValue>{}</DigestValue></Reference></SignedInfo>"#
+            ),
+            CHAVE, digest
+        );
+        let canonico = c14n::canonicalize(&signed_info, "SignedInfo").unwrap();
+        let assinatura = STANDARD.encode(cert.sign_rsa_sha1(canonico.as_bytes()).unwrap());
+        let prot = dig_val.unwrap_or(&digest);
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#,
+                r#"<NFe>{}<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">{}<SignatureValue>{}</SignatureValue>"#,
+                r#"<KeyInfo><X509Data><X509Certificate>{}</X509Certificate></X509Data></KeyInfo></Signature></NFe>"#,
+                r#"<protNFe versao="4.00"><infProt><chNFe>{}</chNFe><digVal>{}</digVal></infProt></protNFe></nfeProc>"#
+            ),
+            inf_nfe.replace("PRODUTO &amp; CIA", x_prod),
+            signed_info,
+            assinatura,
+            STANDARD.encode(cert.cert_der()),
+            CHAVE,
+            prot
+        )
+    }
+
+    #[test]
+    fn test_verifica_nfe_proc_valido() {
+        let v = verificar(&nfe_proc("PRODUTO &amp; CIA", None)).unwrap();
+        assert!(v.valida(), "{:?}", v);
+        let a = &v.assinaturas[0];
+        assert_eq!(a.referencia, format!("NFe{}", CHAVE));
+        assert_eq!(a.cnpj.as_deref(), Some("11222333000181"));
+        assert!(a.titular.starts_with("CN=EMPRESA TESTE LTDA"));
+        assert_eq!(v.dig_val_confere, Some(true));
+    }
+
+    #[test]
+    fn test_detecta_conteudo_alterado() {
+        let v = verificar(&nfe_proc("PRODUTO ALTERADO", None)).unwrap();
+        let a = &v.assinaturas[0];
+        assert!(!a.digest_confere);
+        // O SignedInfo não foi alterado: a assinatura RSA continua íntegra.
+        assert!(a.assinatura_confere);
+        assert!(!v.valida());
+    }
+
+    #[test]
+    fn test_detecta_dig_val_divergente() {
+        let v = verificar(&nfe_proc("PRODUTO &amp; CIA", Some("AAAAAAAAAAAAAAAAAAAAAAAAAAA="))).unwrap();
+        assert!(v.assinaturas[0].valida());
+        assert_eq!(v.dig_val_confere, Some(false));
+        assert!(!v.valida());
+    }
+
+    #[test]
+    fn test_verifica_doc_zip() {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(nfe_proc("PRODUTO &amp; CIA", None).as_bytes()).unwrap();
+        let doc_zip = STANDARD.encode(gz.finish().unwrap());
+        assert!(verificar_doc_zip(&doc_zip).unwrap().valida());
+    }
+
+    #[test]
+    fn test_documento_sem_assinatura() {
+        assert!(verificar("<resNFe><chNFe>1</chNFe></resNFe>").is_err());
+    }
+}
@@ -7,6 +7,7 @@ use dfe::tipos::{Cofins, Det, Emit, Icms, Ide, Pag, Pis, Total, Transp};
 use dfe::CancelarBuilder;
 use dfe::DanfeBuilder;
 use dfe::NFeBuilder;
+use rust_decimal_macros::dec;
 
 #[tokio::test]
 async fn test_service_status() {
@@ -35,7 +36,7 @@ async fn test_emit_nfe() {
     // Identificação da NF-e
     // mod_: 55 = NF-e | 65 = NFC-e
     // tp_amb: 1 = Produção | 2 = Homologação
-    // tp_emis: 1 = Normal | 5 = Contingência EPEC | 7 = Contingência SVC-RS | 8 = Contingência SVC-SP
+    // tp_emis: 1 = Normal | 4 = Contingência EPEC | 5 = FS-DA | 6 = SVC-AN | 7 = SVC-RS | 9 = NFC-e offline
     // tp_imp: 1 = DANFE Normal Retrato | 2 = DANFE Normal Paisagem | 4 = DANFE NFC-e
     // ind_final: 0 = Normal | 1 = Consumidor Final
     // ind_pres: 1 = Operação presencial | 2 = Não presencial / Internet | 9 = Outros
@@ -104,30 +105,30 @@ async fn test_emit_nfe() {
             ncm: "22030000".to_string(),
             cfop: 5102,
             u_com: "UN".to_string(),
-            q_com: 1.0,
-            v_un_com: 10.0,
-            v_prod: 10.0,
+            q_com: dec!(1.0),
+            v_un_com: dec!(10.0),
+            v_prod: dec!(10.0),
             u_trib: "UN".to_string(),
-            q_trib: 1.0,
-            v_un_trib: 10.0,
+            q_trib: dec!(1.0),
+            v_un_trib: dec!(10.0),
             icms: Icms::Icms00 {
                 orig: 0,
                 mod_bc: 3,
-                v_bc: 10.0,
-                p_icms: 12.0,
-                v_icms: 1.20,
+                v_bc: dec!(10.0),
+                p_icms: dec!(12.0),
+                v_icms: dec!(1.20),
             },
             pis: Pis::Aliq {
                 cst: "01".to_string(),
-                v_bc: 10.0,
-                p_pis: 0.65,
-                v_pis: 0.07,
+                v_bc: dec!(10.0),
+                p_pis: dec!(0.65),
+                v_pis: dec!(0.07),
             },
             cofins: Cofins::Aliq {
                 cst: "01".to_string(),
-                v_bc: 10.0,
-                p_cofins: 3.0,
-                v_cofins: 0.30,
+                v_bc: dec!(10.0),
+                p_cofins: dec!(3.0),
+                v_cofins: dec!(0.30),
             },
             ..Default::default()
         },
@@ -137,30 +138,30 @@ async fn test_emit_nfe() {
             ncm: "22030000".to_string(),
             cfop: 5102,
             u_com: "UN".to_string(),
-            q_com: 2.0,
-            v_un_com: 10.0,
-            v_prod: 20.0,
+            q_com: dec!(2.0),
+            v_un_com: dec!(10.0),
+            v_prod: dec!(20.0),
             u_trib: "UN".to_string(),
-            q_trib: 2.0,
-            v_un_trib: 10.0,
+            q_trib: dec!(2.0),
+            v_un_trib: dec!(10.0),
             icms: Icms::Icms00 {
                 orig: 0,
                 mod_bc: 3,
-                v_bc: 20.0,
-                p_icms: 12.0,
-                v_icms: 2.40,
+                v_bc: dec!(20.0),
+                p_icms: dec!(12.0),
+                v_icms: dec!(2.40),
             },
             pis: Pis::Aliq {
                 cst: "01".to_string(),
-                v_bc: 20.0,
-                p_pis: 0.65,
-                v_pis: 0.13,
+                v_bc: dec!(20.0),
+                p_pis: dec!(0.65),
+                v_pis: dec!(0.13),
             },
             cofins: Cofins::Aliq {
                 cst: "01".to_string(),
-                v_bc: 20.0,
-                p_cofins: 3.0,
-                v_cofins: 0.60,
+                v_bc: dec!(20.0),
+                p_cofins: dec!(3.0),
+                v_cofins: dec!(0.60),
             },
             ..Default::default()
         },
@@ -184,7 +185,7 @@ async fn test_emit_nfe() {
     let pagamento = Pag {
         ind_pag: 0,
         t_pag: "01".to_string(),
-        v_pag: 30.0,
+        v_pag: dec!(30.0),
         ..Default::default()
     };
 
@@ -268,13 +269,13 @@ async fn test_emit_nfce() {
             ncm: "22030000".to_string(),
             cfop: 5102,
             u_com: "UN".to_string(),
-            q_com: 1.0,
-            v_un_com: 15.0,
-            v_prod: 15.0,
+            q_com: dec!(1.0),
+            v_un_com: dec!(15.0),
+            v_prod: dec!(15.0),
             u_trib: "UN".to_string(),
-            q_trib: 1.0,
-            v_un_trib: 15.0,
-            icms: Icms::icms00(0, 3, 15.0, 12.0, 1.80),
+            q_trib: dec!(1.0),
+            v_un_trib: dec!(15.0),
+            icms: Icms::icms00(0, 3, dec!(15.0), dec!(12.0), dec!(1.80)),
             pis: Pis::Outr,
             cofins: Cofins::Outr {
                 cst: "99".to_string(),
@@ -287,13 +288,13 @@ async fn test_emit_nfce() {
             ncm: "22030000".to_string(),
             cfop: 5102,
             u_com: "UN".to_string(),
-            q_com: 2.0,
-            v_un_com: 7.50,
-            v_prod: 15.0,
+            q_com: dec!(2.0),
+            v_un_com: dec!(7.50),
+            v_prod: dec!(15.0),
             u_trib: "UN".to_string(),
-            q_trib: 2.0,
-            v_un_trib: 7.50,
-            icms: Icms::icms00(0, 3, 15.0, 12.0, 1.80),
+            q_trib: dec!(2.0),
+            v_un_trib: dec!(7.50),
+            icms: Icms::icms00(0, 3, dec!(15.0), dec!(12.0), dec!(1.80)),
             pis: Pis::Outr,
             cofins: Cofins::Outr {
                 cst: "99".to_string(),
@@ -307,7 +308,7 @@ async fn test_emit_nfce() {
     let pagamento = Pag {
         ind_pag: 0,
         t_pag: "01".to_string(),
-        v_pag: 30.0,
+        v_pag: dec!(30.0),
         ..Default::default()
     };
 
@@ -657,3 +657,36 @@ async fn test_danfe_nfce_80mm_qr_side_salva_arquivo() {
         }
     }
 }
+
+/// NFC-e emitida offline (tpEmis 9): só a `<NFe>` assinada, ainda sem `protNFe`.
+fn nfce_offline_xml(tp_emis: &str) -> String {
+    let inicio = NFCE_XML_HOMOLOG.find("<NFe ").unwrap();
+    let fim = NFCE_XML_HOMOLOG.find("</NFe>").unwrap() + "</NFe>".len();
+    NFCE_XML_HOMOLOG[inicio..fim].replace("<tpEmis>1</tpEmis>", &format!("<tpEmis>{}</tpEmis>", tp_emis))
+}
+
+#[tokio::test]
+async fn test_danfe_nfce_80mm_offline_sem_protocolo() {
+    let xml = nfce_offline_xml("9");
+    let result = DanfeBuilder::new()
+        .xml(&xml)
+        .paper_size("80mm")
+        .as_base64()
+        .build()
+        .await;
+
+    match &result {
+        Err(e) => panic!("Erro ao gerar DANFE NFC-e offline: {}", e),
+        Ok(b64) => assert!(b64.starts_with("JVBE"), "saída deve ser PDF em base64"),
+    }
+
+    // Sem protocolo, só a contingência offline é aceita
+    let xml = nfce_offline_xml("1");
+    let result = DanfeBuilder::new()
+        .xml(&xml)
+        .paper_size("80mm")
+        .as_base64()
+        .build()
+        .await;
+    assert!(result.is_err(), "NFC-e normal sem nfeProc deveria retornar Err");
+}
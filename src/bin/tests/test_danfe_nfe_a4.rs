@@ -293,3 +293,23 @@ async fn test_danfe_nfe_a4_modelo65_retorna_erro() {
         result.unwrap_err()
     );
 }
+
+#[tokio::test]
+async fn test_danfe_nfe_a4_fsda_sem_protocolo() {
+    // FS-DA (tpEmis 5): a NF-e assinada ainda não foi transmitida
+    let inicio = NFE_XML_55.find("<NFe").unwrap();
+    let fim = NFE_XML_55.find("</NFe>").unwrap() + "</NFe>".len();
+    let xml = NFE_XML_55[inicio..fim].replace("<tpEmis>1</tpEmis>", "<tpEmis>5</tpEmis>");
+
+    let result = DanfeBuilder::new()
+        .xml(&xml)
+        .paper_size("a4")
+        .as_base64()
+        .build()
+        .await;
+
+    match &result {
+        Err(e) => panic!("Erro ao gerar DANFE FS-DA: {}", e),
+        Ok(b64) => assert!(b64.starts_with("JVBE"), "saída deve ser PDF em base64"),
+    }
+}
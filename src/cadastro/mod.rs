@@ -0,0 +1,371 @@
+//! Consulta ao cadastro de contribuintes do ICMS (`CadConsultaCadastro4`).
+//!
+//! Consulta um CNPJ, CPF ou IE na UF e devolve os registros `infCad` — situação,
+//! IE, endereço, CNAE e regime de apuração. [`InfCad::dest`] transforma um registro
+//! em [`Dest`] já com o `indIEDest` coerente com a situação cadastral, evitando
+//! rejeições por IE do destinatário.
+//!
+//! ```no_run
+//! use dfe::ConsultaCadastroBuilder;
+//!
+//! # async fn example() -> Result<(), dfe::DfeError> {
+//! let r = ConsultaCadastroBuilder::new()
+//!     .cert("./cert.pfx", "senha")
+//!     .tp_amb(1)
+//!     .uf("SP")
+//!     .cnpj("11222333000181")
+//!     .send()
+//!     .await?;
+//!
+//! if let Some(cad) = r.cadastros.iter().find(|c| c.habilitado()) {
+//!     let dest = cad.dest();
+//!     println!("{:?} — indIEDest {:?}", dest.x_nome, dest.ind_ie_dest);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::cert::Certificado;
+use crate::interno::cnpj_cpf::{sanitize_cnpj, validate_cnpj, validate_cpf};
+use crate::interno::connection::WebService;
+use crate::interno::ws::{nfe_consulta_cadastro, uf_codigo, Webservices};
+use crate::tipos::Dest;
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Resposta de [`ConsultaCadastroBuilder::send`].
+#[derive(Debug, Clone)]
+pub struct ConsultaCadastroResposta {
+    /// `cStat` da consulta. `111` = uma ocorrência · `112` = várias ocorrências · `259` = não cadastrado.
+    pub c_stat: i32,
+    /// Descrição do status retornado pela SEFAZ.
+    pub x_motivo: String,
+    /// Data e hora da consulta.
+    pub dh_cons: Option<String>,
+    /// Registros encontrados — um por IE do contribuinte na UF.
+    pub cadastros: Vec<InfCad>,
+    /// Envelope SOAP enviado.
+    pub send_xml: String,
+    /// Resposta SOAP recebida.
+    pub receive_xml: String,
+}
+
+/// Registro de contribuinte (`<infCad>`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct InfCad {
+    /// Inscrição estadual.
+    #[serde(rename = "IE")]
+    pub ie: String,
+    #[serde(rename = "CNPJ")]
+    pub cnpj: Option<String>,
+    #[serde(rename = "CPF")]
+    pub cpf: Option<String>,
+    #[serde(rename = "UF")]
+    pub uf: String,
+    /// Situação: `0` = não habilitado · `1` = habilitado.
+    #[serde(rename = "cSit")]
+    pub c_sit: u8,
+    /// Credenciamento para NF-e: `0` não credenciado · `1` credenciado · `2` obrigatório total · `3` obrigatório parcial · `4` a SEFAZ não informa.
+    #[serde(rename = "indCredNFe")]
+    pub ind_cred_nfe: Option<u8>,
+    #[serde(rename = "indCredCTe")]
+    pub ind_cred_cte: Option<u8>,
+    /// Razão social ou nome.
+    #[serde(rename = "xNome")]
+    pub x_nome: String,
+    #[serde(rename = "xFant")]
+    pub x_fant: Option<String>,
+    /// Regime de apuração do ICMS (ex.: `NORMAL - REGIME PERIÓDICO DE APURAÇÃO`, `SIMPLES NACIONAL`).
+    #[serde(rename = "xRegApur")]
+    pub x_reg_apur: Option<String>,
+    /// CNAE principal.
+    #[serde(rename = "CNAE")]
+    pub cnae: Option<String>,
+    #[serde(rename = "dIniAtiv")]
+    pub d_ini_ativ: Option<String>,
+    /// Data da última alteração da situação cadastral.
+    #[serde(rename = "dUltSit")]
+    pub d_ult_sit: Option<String>,
+    #[serde(rename = "dBaixa")]
+    pub d_baixa: Option<String>,
+    #[serde(rename = "ender")]
+    pub ender: Option<EnderCad>,
+}
+
+/// Endereço do contribuinte (`<ender>`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnderCad {
+    #[serde(rename = "xLgr")]
+    pub x_lgr: Option<String>,
+    #[serde(rename = "nro")]
+    pub nro: Option<String>,
+    #[serde(rename = "xCpl")]
+    pub x_cpl: Option<String>,
+    #[serde(rename = "xBairro")]
+    pub x_bairro: Option<String>,
+    #[serde(rename = "cMun")]
+    pub c_mun: Option<String>,
+    #[serde(rename = "xMun")]
+    pub x_mun: Option<String>,
+    #[serde(rename = "CEP")]
+    pub cep: Option<String>,
+}
+
+impl InfCad {
+    /// `true` quando a IE está habilitada (`cSit` 1).
+    pub fn habilitado(&self) -> bool {
+        self.c_sit == 1
+    }
+
+    /// `indIEDest` para este contribuinte: `1` = contribuinte com IE habilitada,
+    /// `2` = contribuinte isento de inscrição, `9` = não contribuinte (IE não habilitada).
+    pub fn ind_ie_dest(&self) -> u8 {
+        match self.ie.trim() {
+            _ if !self.habilitado() => 9,
+            "" => 9,
+            ie if ie.eq_ignore_ascii_case("ISENTO") => 2,
+            _ => 1,
+        }
+    }
+
+    /// [`Dest`] preenchido com nome, documento, endereço, IE e `ind_ie_dest`.
+    ///
+    /// A IE só é levada quando `ind_ie_dest == 1`; telefone e e-mail ficam para o chamador.
+    pub fn dest(&self) -> Dest {
+        let ender = self.ender.clone().unwrap_or(EnderCad {
+            x_lgr: None, nro: None, x_cpl: None, x_bairro: None, c_mun: None, x_mun: None, cep: None,
+        });
+        let ind_ie_dest = self.ind_ie_dest();
+        Dest {
+            cnpj: self.cnpj.clone(),
+            cpf: self.cpf.clone(),
+            x_nome: Some(self.x_nome.clone()),
+            x_lgr: ender.x_lgr,
+            nro: ender.nro,
+            x_bairro: ender.x_bairro,
+            c_mun: ender.c_mun,
+            x_mun: ender.x_mun,
+            uf: Some(self.uf.clone()),
+            cep: ender.cep,
+            ind_ie_dest: Some(ind_ie_dest),
+            ie: (ind_ie_dest == 1).then(|| self.ie.trim().to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RetConsCad {
+    #[serde(rename = "infCons")]
+    inf_cons: RetInfCons,
+}
+
+#[derive(Deserialize)]
+struct RetInfCons {
+    #[serde(rename = "cStat")]
+    c_stat: i32,
+    #[serde(rename = "xMotivo")]
+    x_motivo: String,
+    #[serde(rename = "dhCons")]
+    dh_cons: Option<String>,
+    #[serde(rename = "infCad", default)]
+    inf_cad: Vec<InfCad>,
+}
+
+// ─── Builder público ──────────────────────────────────────────────────────────
+
+/// Builder da consulta ao cadastro de contribuintes de uma UF.
+pub struct ConsultaCadastroBuilder {
+    certificado: Option<Certificado>,
+    cert_path:   Option<String>,
+    cert_pass:   Option<String>,
+    tp_amb:      Option<u8>,
+    uf:          Option<String>,
+    cnpj:        Option<String>,
+    cpf:         Option<String>,
+    ie:          Option<String>,
+    webservices: Option<Webservices>,
+    artefatos:   Option<Arc<dyn ArtefatoSink>>,
+}
+
+impl ConsultaCadastroBuilder {
+    pub fn new() -> Self {
+        Self {
+            certificado: None, cert_path: None, cert_pass: None, tp_amb: None, uf: None,
+            cnpj: None, cpf: None, ie: None, webservices: None, artefatos: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+
+    /// 1 = Produção | 2 = Homologação
+    pub fn tp_amb(mut self, v: u8) -> Self { self.tp_amb = Some(v); self }
+
+    /// Sigla da UF consultada (ex.: `"SP"`)
+    pub fn uf(mut self, v: &str) -> Self { self.uf = Some(v.to_string()); self }
+
+    /// CNPJ consultado. Informe apenas um entre CNPJ, CPF e IE.
+    pub fn cnpj(mut self, v: &str) -> Self { self.cnpj = Some(v.to_string()); self }
+
+    /// CPF consultado (produtor rural)
+    pub fn cpf(mut self, v: &str) -> Self { self.cpf = Some(v.to_string()); self }
+
+    /// Inscrição estadual consultada
+    pub fn ie(mut self, v: &str) -> Self { self.ie = Some(v.to_string()); self }
+
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    /// Destino ([`ArtefatoSink`]) do envelope e do retorno. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    pub async fn send(self) -> Result<ConsultaCadastroResposta> {
+        let tp_amb   = self.tp_amb.ok_or_else(|| DfeError::Configuracao("tp_amb não informado".to_string()))?;
+        let uf       = self.uf.as_deref().ok_or_else(|| DfeError::Validacao("uf não informada".to_string()))?;
+        let filtro   = self.filtro()?;
+        uf_codigo(uf)?;
+        let uf       = uf.to_ascii_uppercase();
+
+        let certificado = Certificado::resolver(self.certificado, self.cert_path, self.cert_pass)?;
+        let url      = nfe_consulta_cadastro(tp_amb, &uf, self.webservices.as_ref())?;
+        let envelope = envelope_xml(&uf, &filtro);
+        let client   = WebService::client(certificado.identity())?;
+        let doc      = self.cnpj.as_ref().or(self.cpf.as_ref()).or(self.ie.as_ref()).cloned().unwrap_or_default();
+        let artefatos = Artefatos::new(self.artefatos, "consulta-cadastro", &doc);
+        let body     = WebService::transmitir(client, &url, envelope.clone(), &artefatos).await?;
+        resposta(envelope, body)
+    }
+
+    // <CNPJ>, <CPF> ou <IE> do pedido — exatamente um deles
+    fn filtro(&self) -> Result<String> {
+        match (&self.cnpj, &self.cpf, &self.ie) {
+            (Some(cnpj), None, None) => {
+                if !validate_cnpj(cnpj) {
+                    return Err(DfeError::Validacao(format!("CNPJ inválido: {}", cnpj)));
+                }
+                Ok(format!("<CNPJ>{}</CNPJ>", sanitize_cnpj(cnpj)))
+            }
+            (None, Some(cpf), None) => {
+                if !validate_cpf(cpf) {
+                    return Err(DfeError::Validacao(format!("CPF inválido: {}", cpf)));
+                }
+                Ok(format!("<CPF>{}</CPF>", cpf.chars().filter(char::is_ascii_digit).collect::<String>()))
+            }
+            (None, None, Some(ie)) => {
+                let ie: String = ie.chars().filter(char::is_ascii_alphanumeric).collect();
+                if ie.is_empty() || ie.len() > 14 {
+                    return Err(DfeError::Validacao(format!("IE inválida: {}", ie)));
+                }
+                Ok(format!("<IE>{}</IE>", ie))
+            }
+            _ => Err(DfeError::Validacao("informe apenas um entre cnpj, cpf e ie".to_string())),
+        }
+    }
+}
+
+impl Default for ConsultaCadastroBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─── Lógica interna ───────────────────────────────────────────────────────────
+
+fn envelope_xml(uf: &str, filtro: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/CadConsultaCadastro4"><ConsCad xmlns="http://www.portalfiscal.inf.br/nfe" versao="2.00"><infCons><xServ>CONS-CAD</xServ><UF>{}</UF>{}</infCons></ConsCad></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        uf, filtro
+    )
+}
+
+fn resposta(send_xml: String, receive_xml: String) -> Result<ConsultaCadastroResposta> {
+    let ret_cons_cad = Regex::new(r"(?s)<retConsCad\b.*?</retConsCad>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?
+        .find(&receive_xml)
+        .map(|m| m.as_str().to_string())
+        .ok_or_else(|| DfeError::Xml(format!("retConsCad não encontrado: {}", receive_xml)))?;
+    let ret: RetConsCad = quick_xml::de::from_str(&ret_cons_cad)
+        .map_err(|e| DfeError::Xml(format!("Erro desserializar retConsCad: {} — {}", e, ret_cons_cad)))?;
+    let ret = ret.inf_cons;
+
+    Ok(ConsultaCadastroResposta {
+        c_stat: ret.c_stat,
+        x_motivo: ret.x_motivo,
+        dh_cons: ret.dh_cons,
+        cadastros: ret.inf_cad,
+        send_xml,
+        receive_xml,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RETORNO: &str = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><nfeResultMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/CadConsultaCadastro4"><retConsCad versao="2.00" xmlns="http://www.portalfiscal.inf.br/nfe"><infCons><verAplic>SP_NFE_PL009_V4</verAplic><cStat>112</cStat><xMotivo>Consulta cadastro com mais de uma ocorrencia</xMotivo><UF>SP</UF><CNPJ>11222333000181</CNPJ><dhCons>2025-01-10T10:00:00-03:00</dhCons><cUF>35</cUF><infCad><IE>111222333444</IE><CNPJ>11222333000181</CNPJ><UF>SP</UF><cSit>1</cSit><indCredNFe>1</indCredNFe><indCredCTe>4</indCredCTe><xNome>EMPRESA EXEMPLO LTDA</xNome><xRegApur>NORMAL - REGIME PERIODICO DE APURACAO</xRegApur><CNAE>4711302</CNAE><dIniAtiv>2010-03-01</dIniAtiv><dUltSit>2010-03-01</dUltSit><ender><xLgr>RUA DAS FLORES</xLgr><nro>100</nro><xBairro>CENTRO</xBairro><cMun>3550308</cMun><xMun>SAO PAULO</xMun><CEP>01001000</CEP></ender></infCad><infCad><IE>555666777888</IE><CNPJ>11222333000181</CNPJ><UF>SP</UF><cSit>0</cSit><indCredNFe>0</indCredNFe><indCredCTe>4</indCredCTe><xNome>EMPRESA EXEMPLO LTDA</xNome><dUltSit>2020-05-04</dUltSit><dBaixa>2020-05-04</dBaixa></infCad></infCons></retConsCad></nfeResultMsg></soap:Body></soap:Envelope>"#;
+
+    #[test]
+    fn test_resposta_tipada() {
+        let r = resposta(String::new(), RETORNO.to_string()).unwrap();
+        assert_eq!(r.c_stat, 112);
+        assert_eq!(r.cadastros.len(), 2);
+
+        let ativo = &r.cadastros[0];
+        assert!(ativo.habilitado());
+        assert_eq!(ativo.cnae.as_deref(), Some("4711302"));
+        assert_eq!(ativo.ender.as_ref().unwrap().c_mun.as_deref(), Some("3550308"));
+
+        let baixado = &r.cadastros[1];
+        assert!(!baixado.habilitado());
+        assert_eq!(baixado.d_baixa.as_deref(), Some("2020-05-04"));
+        assert!(baixado.ender.is_none());
+
+        let vazio = RETORNO.replace("<cStat>112</cStat>", "<cStat>259</cStat>");
+        let vazio = Regex::new(r"(?s)<infCad>.*</infCad>").unwrap().replace(&vazio, "").to_string();
+        let r = resposta(String::new(), vazio).unwrap();
+        assert_eq!(r.c_stat, 259);
+        assert!(r.cadastros.is_empty());
+    }
+
+    #[test]
+    fn test_dest_com_ind_ie_dest() {
+        let r = resposta(String::new(), RETORNO.to_string()).unwrap();
+        let dest = r.cadastros[0].dest();
+        assert_eq!(dest.ind_ie_dest, Some(1));
+        assert_eq!(dest.ie.as_deref(), Some("111222333444"));
+        assert_eq!(dest.cnpj.as_deref(), Some("11222333000181"));
+        assert_eq!(dest.x_mun.as_deref(), Some("SAO PAULO"));
+        assert_eq!(dest.c_pais.as_deref(), Some("1058"));
+
+        // IE não habilitada: destinatário vira não contribuinte, sem IE
+        let dest = r.cadastros[1].dest();
+        assert_eq!(dest.ind_ie_dest, Some(9));
+        assert!(dest.ie.is_none());
+
+        let mut isento = r.cadastros[0].clone();
+        isento.ie = "ISENTO".to_string();
+        assert_eq!(isento.ind_ie_dest(), 2);
+        assert!(isento.dest().ie.is_none());
+    }
+
+    #[test]
+    fn test_filtro_exige_um_documento() {
+        let b = ConsultaCadastroBuilder::new();
+        assert!(b.filtro().is_err());
+        assert_eq!(ConsultaCadastroBuilder::new().cnpj("11.222.333/0001-81").filtro().unwrap(), "<CNPJ>11222333000181</CNPJ>");
+        assert_eq!(ConsultaCadastroBuilder::new().ie("111.222.333.444").filtro().unwrap(), "<IE>111222333444</IE>");
+        assert!(ConsultaCadastroBuilder::new().cnpj("11222333000180").filtro().is_err());
+        assert!(ConsultaCadastroBuilder::new().cnpj("11222333000181").ie("1").filtro().is_err());
+        assert!(envelope_xml("SP", "<IE>1</IE>").contains("<infCons><xServ>CONS-CAD</xServ><UF>SP</UF><IE>1</IE></infCons>"));
+    }
+}
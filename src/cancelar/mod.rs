@@ -1,23 +1,27 @@
 use crate::error::{DfeError, Result};
-use crate::interno::cert::{Cert, DigestValue, RawPubKey, Sign};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::c14n;
+use crate::interno::cert::{Certificado, DigestValue};
+use crate::interno::signer::{Credencial, Signer};
 use crate::interno::chave_acesso::ChaveAcesso;
 use crate::interno::cleaner::Strings;
 use crate::interno::connection::WebService;
 use crate::interno::dates::get_current_date_time;
-use crate::interno::ws::nfe_recepcao_evento;
+use crate::interno::ws::{nfe_recepcao_evento, uf_sigla, Webservices};
 use crate::tipos::cancelar::{InfEvento, Response};
 use quick_xml::de;
 use quick_xml::events::BytesText;
 use quick_xml::writer::Writer;
-use std::fs::File;
 use std::io::Cursor;
-use std::io::Write;
+use std::sync::Arc;
 
 const TP_EVENTO: &str = "110111";
 
 // ─── Builder público ──────────────────────────────────────────────────────────
 
 pub struct CancelarBuilder {
+    signer:       Option<Arc<dyn Signer>>,
+    certificado:  Option<Certificado>,
     cert_path:    Option<String>,
     cert_pass:    Option<String>,
     tp_amb:       Option<u8>,
@@ -25,13 +29,15 @@ pub struct CancelarBuilder {
     chave:        Option<String>,
     protocolo:    Option<String>,
     justificativa: Option<String>,
+    webservices:  Option<Webservices>,
+    artefatos:    Option<Arc<dyn ArtefatoSink>>,
 }
 
 impl CancelarBuilder {
     pub fn new() -> Self {
         Self {
-            cert_path: None, cert_pass: None, tp_amb: None, mod_: None,
-            chave: None, protocolo: None, justificativa: None,
+            signer: None, certificado: None, cert_path: None, cert_pass: None, tp_amb: None, mod_: None,
+            chave: None, protocolo: None, justificativa: None, webservices: None, artefatos: None,
         }
     }
 
@@ -41,6 +47,12 @@ impl CancelarBuilder {
         self
     }
 
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+
+    /// Assinante externo do evento; a conexão com a SEFAZ ainda usa `cert` ou `certificado`
+    pub fn signer(mut self, s: Arc<dyn Signer>) -> Self { self.signer = Some(s); self }
+
     /// 1 = Produção | 2 = Homologação
     pub fn tp_amb(mut self, v: u8) -> Self { self.tp_amb = Some(v); self }
 
@@ -56,9 +68,14 @@ impl CancelarBuilder {
     /// Justificativa do cancelamento (mínimo 15 caracteres)
     pub fn justificativa(mut self, v: &str) -> Self { self.justificativa = Some(v.to_string()); self }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    /// Destino ([`ArtefatoSink`]) do `infEvento`, do envelope e do retorno. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
     pub async fn send(self) -> Result<Response> {
-        let cert_path     = self.cert_path    .ok_or_else(|| DfeError::Configuracao("cert_path não informado".to_string()))?;
-        let cert_pass     = self.cert_pass    .ok_or_else(|| DfeError::Configuracao("cert_pass não informado".to_string()))?;
+        let credencial    = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
         let tp_amb        = self.tp_amb       .ok_or_else(|| DfeError::Configuracao("tp_amb não informado".to_string()))?;
         let chave         = self.chave        .ok_or_else(|| DfeError::Validacao("chave não informada".to_string()))?;
         let protocolo     = self.protocolo    .ok_or_else(|| DfeError::Validacao("protocolo não informado".to_string()))?;
@@ -68,50 +85,43 @@ impl CancelarBuilder {
         if justificativa.len() < 15 {
             return Err(DfeError::Validacao("justificativa deve ter no mínimo 15 caracteres".to_string()));
         }
-
-        cancelar_nfe(cert_path, cert_pass, tp_amb, mod_, chave, protocolo, justificativa).await
+        // O CNPJ do emitente ocupa as posições 7–20 da chave de acesso.
+        credencial.validar(chave.get(6..20))?;
+
+        // O cUF do emitente ocupa as duas primeiras posições da chave de acesso.
+        let c_uf      = chave.get(..2).and_then(|c| c.parse().ok()).unwrap_or_default();
+        let url       = nfe_recepcao_evento(tp_amb, uf_sigla(c_uf)?, mod_, false, self.webservices.as_ref())?;
+        let artefatos = Artefatos::new(self.artefatos, "cancelamento", &chave);
+        cancelar_nfe(credencial, tp_amb, chave, protocolo, justificativa, &url, &artefatos).await
     }
 }
 
 // ─── Lógica interna ───────────────────────────────────────────────────────────
 
 async fn cancelar_nfe(
-    cert_path: String, cert_pass: String,
-    tp_amb: u8, mod_: u32,
+    credencial: Credencial,
+    tp_amb: u8,
     chave: String, protocolo: String, justificativa: String,
+    url: &str,
+    artefatos: &Artefatos,
 ) -> Result<Response> {
     let inf_evento_xml = inf_evento_xml(&chave, tp_amb, &protocolo, &justificativa)?;
+    artefatos.gravar("inf-evento", &inf_evento_xml);
     let inf_evento_xml = Strings::clear_xml_string(&inf_evento_xml);
 
-    let digest_value = DigestValue::sha1(&inf_evento_xml)?;
-    let signed_info  = signed_info_xml(&digest_value, &chave)?;
+    let digest_value = DigestValue::c14n_sha1(&inf_evento_xml, "infEvento")?;
+    let signed_info  = c14n::canonicalize(&signed_info_xml(&digest_value, &chave)?, "SignedInfo")?;
 
-    let signature_base64 = Sign::xml_string(&signed_info, &cert_path, &cert_pass).await?;
-    let x509_cert        = RawPubKey::get_from_file(&cert_path, &cert_pass).await?;
+    let signature_base64 = credencial.sign_base64(&signed_info)?;
+    let x509_cert        = credencial.cert_base64()?;
     let signature        = signature_xml(&signed_info, &signature_base64, &x509_cert)?;
     let envelope         = envelope_xml(&inf_evento_xml, &signature)?;
     let envelope         = Strings::clear_xml_string(&envelope);
 
-    let mut file = File::create("cancelar.xml")?;
-    file.write_all(envelope.as_bytes())?;
-
-    let url    = nfe_recepcao_evento(tp_amb, "SP", mod_, false)?;
-    let cert   = Cert::from_pfx(&cert_path, &cert_pass)?;
-    let client = WebService::client(cert.identity)?;
+    let client = WebService::client(credencial.identity()?)?;
 
     let send_envelope = envelope.clone();
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/soap+xml; charset=utf-8")
-        .header("Content-Length", envelope.len().to_string())
-        .body(envelope)
-        .send()
-        .await?;
-
-    let response = response.text().await?;
-
-    let mut file = File::create("cancelar_response.xml")?;
-    file.write_all(response.as_bytes())?;
+    let response = WebService::transmitir(client, url, envelope, artefatos).await?;
 
     let re = regex::bytes::Regex::new(r"(?s)<infEvento.*?</infEvento>").unwrap();
     match re.captures(response.as_bytes()) {
@@ -163,8 +173,6 @@ fn inf_evento_xml(chave: &str, tp_amb: u8, protocolo: &str, justificativa: &str)
         })?;
 
     let xml = String::from_utf8(writer.into_inner().into_inner())?;
-    let mut f = File::create("inf_evento.xml")?;
-    f.write_all(xml.as_bytes())?;
     Ok(xml)
 }
 
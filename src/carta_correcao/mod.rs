@@ -0,0 +1,283 @@
+//! Carta de Correção Eletrônica — CC-e (evento `110110`).
+//!
+//! Corrige dados de uma NF-e já autorizada que não alterem imposto, valores,
+//! remetente/destinatário ou data de emissão (ex.: transportadora, endereço de
+//! entrega, descrição complementar). Cada nova CC-e da mesma nota usa o próximo
+//! `nSeqEvento` e substitui integralmente a anterior.
+//!
+//! ```no_run
+//! use dfe::CartaCorrecaoBuilder;
+//!
+//! # async fn example() -> Result<(), dfe::DfeError> {
+//! let r = CartaCorrecaoBuilder::new()
+//!     .cert("./cert.pfx", "senha")
+//!     .tp_amb(2)
+//!     .chave("35241211111111111111550010000000361491395167")
+//!     .n_seq_evento(1)
+//!     .correcao("Transportadora correta: TRANSPORTES EXEMPLO LTDA, CNPJ 11.222.333/0001-81")
+//!     .send()
+//!     .await?;
+//!
+//! println!("{} — {}", r.c_stat, r.x_motivo);
+//! if let Some(xml) = r.proc_evento_nfe {
+//!     std::fs::write("./cce-procEventoNFe.xml", xml)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::cert::Certificado;
+use crate::interno::chave_acesso::ChaveAcesso;
+use crate::interno::connection::WebService;
+use crate::interno::dates::get_current_date_time;
+use crate::interno::evento::{self, Evento};
+use crate::interno::signer::{Credencial, Signer};
+use crate::interno::ws::{nfe_recepcao_evento, uf_sigla, Webservices};
+use quick_xml::escape::escape;
+use std::sync::Arc;
+
+const TP_EVENTO: &str = "110110";
+
+/// Texto fixo das condições de uso da CC-e (`xCondUso`), exigido literalmente pelo schema.
+pub const X_COND_USO: &str = "A Carta de Correcao e disciplinada pelo paragrafo 1o-A do art. 7o do Convenio S/N, de 15 de dezembro de 1970 e pode ser utilizada para regularizacao de erro ocorrido na emissao de documento fiscal, desde que o erro nao esteja relacionado com: I - as variaveis que determinam o valor do imposto tais como: base de calculo, aliquota, diferenca de preco, quantidade, valor da operacao ou da prestacao; II - a correcao de dados cadastrais que implique mudanca do remetente ou do destinatario; III - a data de emissao ou de saida.";
+
+/// Resposta de [`CartaCorrecaoBuilder::send`].
+#[derive(Debug, Clone)]
+pub struct CartaCorrecaoResposta {
+    /// `cStat` do evento. `135` = registrado e vinculado à NF-e.
+    pub c_stat: i32,
+    /// Descrição do status retornado pela SEFAZ.
+    pub x_motivo: String,
+    /// Sequência do evento enviada.
+    pub n_seq_evento: u32,
+    /// Protocolo do evento. Presente somente quando registrado.
+    pub n_prot: Option<String>,
+    /// Data e hora do registro do evento.
+    pub dh_reg_evento: Option<String>,
+    /// XML `procEventoNFe` (evento assinado + retorno) — deve ser persistido em disco.
+    pub proc_evento_nfe: Option<String>,
+    /// Envelope SOAP enviado.
+    pub send_xml: String,
+    /// Resposta SOAP recebida.
+    pub receive_xml: String,
+}
+
+// ─── Builder público ──────────────────────────────────────────────────────────
+
+/// Builder da Carta de Correção Eletrônica de uma NF-e autorizada.
+pub struct CartaCorrecaoBuilder {
+    signer:       Option<Arc<dyn Signer>>,
+    certificado:  Option<Certificado>,
+    cert_path:    Option<String>,
+    cert_pass:    Option<String>,
+    tp_amb:       Option<u8>,
+    mod_:         Option<u32>,
+    chave:        Option<String>,
+    n_seq_evento: Option<u32>,
+    correcao:     Option<String>,
+    webservices:  Option<Webservices>,
+    artefatos:    Option<Arc<dyn ArtefatoSink>>,
+}
+
+impl CartaCorrecaoBuilder {
+    pub fn new() -> Self {
+        Self {
+            signer: None, certificado: None, cert_path: None, cert_pass: None, tp_amb: None, mod_: None,
+            chave: None, n_seq_evento: None, correcao: None, webservices: None, artefatos: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+
+    /// Assinante externo do evento; a conexão com a SEFAZ ainda usa `cert` ou `certificado`
+    pub fn signer(mut self, s: Arc<dyn Signer>) -> Self { self.signer = Some(s); self }
+
+    /// 1 = Produção | 2 = Homologação
+    pub fn tp_amb(mut self, v: u8) -> Self { self.tp_amb = Some(v); self }
+
+    /// Modelo do documento (padrão: 55). A CC-e não existe para NFC-e.
+    pub fn mod_(mut self, v: u32) -> Self { self.mod_ = Some(v); self }
+
+    /// Chave de acesso de 44 dígitos
+    pub fn chave(mut self, v: &str) -> Self { self.chave = Some(v.to_string()); self }
+
+    /// Sequência do evento para a nota, de 1 a 20 (padrão: 1). Incremente a cada nova CC-e.
+    pub fn n_seq_evento(mut self, v: u32) -> Self { self.n_seq_evento = Some(v); self }
+
+    /// Texto da correção (15 a 1000 caracteres); substitui as correções anteriores
+    pub fn correcao(mut self, v: &str) -> Self { self.correcao = Some(v.to_string()); self }
+
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    /// Destino ([`ArtefatoSink`]) do envelope e do retorno. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    /// Monta e assina o `evento` sem enviar à SEFAZ.
+    pub fn gerar_xml(self) -> Result<String> {
+        let pedido     = self.pedido()?;
+        let credencial = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
+        pedido.evento_xml(&credencial)
+    }
+
+    pub async fn send(self) -> Result<CartaCorrecaoResposta> {
+        let pedido     = self.pedido()?;
+        let credencial = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
+        // O CNPJ do emitente ocupa as posições 7–20 da chave de acesso.
+        credencial.validar(pedido.chave.get(6..20))?;
+
+        let evento_xml = pedido.evento_xml(&credencial)?;
+        let c_uf       = pedido.chave[..2].parse().unwrap_or_default();
+        let url        = nfe_recepcao_evento(pedido.tp_amb, uf_sigla(c_uf)?, pedido.mod_, false, self.webservices.as_ref())?;
+        let envelope   = evento::envelope_xml(&evento_xml);
+        let client     = WebService::client(credencial.identity()?)?;
+        let artefatos  = Artefatos::new(self.artefatos, "carta-correcao", &pedido.chave);
+        let body       = WebService::transmitir(client, &url, envelope.clone(), &artefatos).await?;
+
+        let ret = evento::ret_evento(&body)?;
+        Ok(CartaCorrecaoResposta {
+            proc_evento_nfe: ret.proc_evento_nfe(&evento_xml),
+            c_stat: ret.c_stat,
+            x_motivo: ret.x_motivo,
+            n_seq_evento: pedido.n_seq_evento,
+            n_prot: ret.n_prot,
+            dh_reg_evento: ret.dh_reg_evento,
+            send_xml: envelope,
+            receive_xml: body,
+        })
+    }
+
+    fn pedido(&self) -> Result<Pedido> {
+        let tp_amb       = self.tp_amb.ok_or_else(|| DfeError::Configuracao("tp_amb não informado".to_string()))?;
+        let chave        = self.chave.as_deref().ok_or_else(|| DfeError::Validacao("chave não informada".to_string()))?;
+        let correcao     = self.correcao.as_deref().map(str::trim)
+            .ok_or_else(|| DfeError::Validacao("correção não informada".to_string()))?;
+        let n_seq_evento = self.n_seq_evento.unwrap_or(1);
+        let mod_         = self.mod_.unwrap_or(55);
+
+        if chave.len() != 44 || !chave.chars().all(|c| c.is_ascii_digit()) {
+            return Err(DfeError::Validacao(format!("chave de acesso deve ter 44 dígitos: {}", chave)));
+        }
+        if chave[43..] != ChaveAcesso::gerar_dv(&chave[..43])?.to_string() {
+            return Err(DfeError::Validacao(format!("dígito verificador inválido na chave {}", chave)));
+        }
+        if mod_ != 55 {
+            return Err(DfeError::Validacao("Carta de Correção só é aceita para NF-e (modelo 55)".to_string()));
+        }
+        if !(1..=20).contains(&n_seq_evento) {
+            return Err(DfeError::Validacao(format!("nSeqEvento deve estar entre 1 e 20: {}", n_seq_evento)));
+        }
+        let len = correcao.chars().count();
+        if !(15..=1000).contains(&len) {
+            return Err(DfeError::Validacao("correção deve ter de 15 a 1000 caracteres".to_string()));
+        }
+
+        Ok(Pedido { tp_amb, mod_, chave: chave.to_string(), n_seq_evento, correcao: correcao.to_string() })
+    }
+}
+
+impl Default for CartaCorrecaoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─── Lógica interna ───────────────────────────────────────────────────────────
+
+struct Pedido {
+    tp_amb: u8,
+    mod_: u32,
+    chave: String,
+    n_seq_evento: u32,
+    correcao: String,
+}
+
+impl Pedido {
+    fn evento_xml(&self, credencial: &Credencial) -> Result<String> {
+        let det_evento = format!(
+            "<descEvento>Carta de Correcao</descEvento><xCorrecao>{}</xCorrecao><xCondUso>{}</xCondUso>",
+            escape(self.correcao.as_str()), X_COND_USO
+        );
+        Evento {
+            c_orgao: &self.chave[..2],
+            tp_amb: self.tp_amb,
+            cnpj: &self.chave[6..20],
+            chave: &self.chave,
+            dh_evento: &get_current_date_time(),
+            tp_evento: TP_EVENTO,
+            n_seq_evento: self.n_seq_evento,
+            det_evento: &det_evento,
+        }
+        .assinar(credencial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interno::evento::ret_evento;
+
+    const PFX: &[u8] = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+    const CHAVE: &str = "35250111222333000181550010000000011000000010";
+
+    fn builder() -> CartaCorrecaoBuilder {
+        CartaCorrecaoBuilder::new()
+            .certificado(Certificado::from_bytes(PFX, "1234").unwrap())
+            .tp_amb(2)
+            .chave(CHAVE)
+            .n_seq_evento(2)
+            .correcao("Endereco de entrega: Rua B, 200 & fundos")
+    }
+
+    #[test]
+    fn test_gera_evento_assinado() {
+        let xml = builder().gerar_xml().unwrap();
+        assert!(xml.contains(&format!(r#"Id="ID110110{}02""#, CHAVE)));
+        assert!(xml.contains("<cOrgao>35</cOrgao><tpAmb>2</tpAmb><CNPJ>11222333000181</CNPJ>"));
+        assert!(xml.contains("<tpEvento>110110</tpEvento><nSeqEvento>2</nSeqEvento>"));
+        assert!(xml.contains("<xCorrecao>Endereco de entrega: Rua B, 200 &amp; fundos</xCorrecao>"));
+        assert!(xml.contains(&format!("<xCondUso>{}</xCondUso>", X_COND_USO)));
+
+        let verificacao = crate::assinatura::verificar(&xml).unwrap();
+        assert!(verificacao.valida());
+    }
+
+    #[test]
+    fn test_recusa_pedido_invalido() {
+        assert!(builder().correcao("curta").gerar_xml().is_err());
+        assert!(builder().correcao(&"x".repeat(1001)).gerar_xml().is_err());
+        assert!(builder().n_seq_evento(21).gerar_xml().is_err());
+        assert!(builder().n_seq_evento(0).gerar_xml().is_err());
+        assert!(builder().mod_(65).gerar_xml().is_err());
+        assert!(builder().chave(&format!("{}9", &CHAVE[..43])).gerar_xml().is_err());
+    }
+
+    #[test]
+    fn test_retorno_registrado_monta_proc_evento() {
+        let body = format!(
+            r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><nfeResultMsg><retEnvEvento versao="1.00" xmlns="http://www.portalfiscal.inf.br/nfe"><idLote>1</idLote><tpAmb>2</tpAmb><verAplic>SP_EVENTOS_PL_100</verAplic><cOrgao>35</cOrgao><cStat>128</cStat><xMotivo>Lote de Evento Processado</xMotivo><retEvento versao="1.00"><infEvento><tpAmb>2</tpAmb><verAplic>SP_EVENTOS_PL_100</verAplic><cOrgao>35</cOrgao><cStat>135</cStat><xMotivo>Evento registrado e vinculado a NF-e</xMotivo><chNFe>{}</chNFe><tpEvento>110110</tpEvento><xEvento>Carta de Correcao registrada</xEvento><nSeqEvento>2</nSeqEvento><dhRegEvento>2025-01-10T10:00:00-03:00</dhRegEvento><nProt>135250000000010</nProt></infEvento></retEvento></retEnvEvento></nfeResultMsg></soap:Body></soap:Envelope>"#,
+            CHAVE
+        );
+        let ret = ret_evento(&body).unwrap();
+        assert_eq!(ret.c_stat, 135);
+        assert_eq!(ret.n_prot.as_deref(), Some("135250000000010"));
+        let proc_evento = ret.proc_evento_nfe("<evento/>").unwrap();
+        assert!(proc_evento.contains(r#"versao="1.00"><evento/><retEvento versao="1.00">"#));
+        assert!(proc_evento.ends_with("</retEvento></procEventoNFe>"));
+
+        let rejeitado = ret_evento(&body.replace("<cStat>135</cStat>", "<cStat>573</cStat>")).unwrap();
+        assert!(rejeitado.proc_evento_nfe("<evento/>").is_none());
+
+        let lote = ret_evento("<retEnvEvento><cStat>215</cStat><xMotivo>Rejeicao: Falha no schema XML</xMotivo></retEnvEvento>").unwrap();
+        assert_eq!(lote.c_stat, 215);
+    }
+}
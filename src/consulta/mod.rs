@@ -0,0 +1,423 @@
+//! Consulta da situação de uma NF-e/NFC-e pela chave de acesso (`NfeConsultaProtocolo4`).
+//!
+//! Retorna a situação atual da nota, o `protNFe` de autorização e os eventos
+//! vinculados (`procEventoNFe`: cancelamento, CC-e…). Com o XML assinado guardado
+//! localmente, remonta o `nfeProc` — é o caminho para recuperar uma nota cuja
+//! resposta da emissão se perdeu.
+//!
+//! ```no_run
+//! use dfe::ConsultaProtocoloBuilder;
+//!
+//! # async fn example() -> Result<(), dfe::DfeError> {
+//! let nfe_assinada = std::fs::read_to_string("./nfe_assinada.xml")?;
+//! let r = ConsultaProtocoloBuilder::new()
+//!     .cert("./cert.pfx", "senha")
+//!     .tp_amb(2)
+//!     .chave("35250111222333000181550010000000011000000010")
+//!     .nfe_assinada(&nfe_assinada)
+//!     .send()
+//!     .await?;
+//!
+//! println!("{} — {} ({:?})", r.c_stat, r.x_motivo, r.situacao);
+//! if let Some(nfe_proc) = r.nfe_proc {
+//!     std::fs::write("./procNFe.xml", nfe_proc)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::emissao::TagInfProt;
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::cert::Certificado;
+use crate::interno::chave_acesso::ChaveAcesso;
+use crate::interno::connection::WebService;
+use crate::interno::ws::{nfe_consulta_protocolo, uf_sigla, Webservices};
+use regex::Regex;
+use std::sync::Arc;
+
+/// Evento de cancelamento (`tpEvento` 110111).
+const TP_EVENTO_CANCELAMENTO: &str = "110111";
+
+/// Situação da NF-e na SEFAZ, no sentido do `cSitNFe` da distribuição.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SituacaoNFe {
+    /// Autorizada (`cStat` 100/150).
+    Autorizada,
+    /// Uso denegado (`cStat` 110/301/302/303).
+    Denegada,
+    /// Cancelada (`cStat` 101/151/155 ou evento 110111 homologado).
+    Cancelada,
+    /// A SEFAZ não tem a chave na base (`cStat` 217).
+    NaoEncontrada,
+    /// Consulta rejeitada; o motivo está em `c_stat`/`x_motivo`.
+    Rejeitada,
+}
+
+impl SituacaoNFe {
+    /// `cSitNFe`: `1` = autorizada · `2` = denegada · `3` = cancelada.
+    pub fn c_sit_nfe(&self) -> Option<u8> {
+        match self {
+            SituacaoNFe::Autorizada => Some(1),
+            SituacaoNFe::Denegada => Some(2),
+            SituacaoNFe::Cancelada => Some(3),
+            SituacaoNFe::NaoEncontrada | SituacaoNFe::Rejeitada => None,
+        }
+    }
+}
+
+/// Evento vinculado à nota (`procEventoNFe`) retornado pela consulta.
+#[derive(Debug, Clone)]
+pub struct EventoVinculado {
+    /// Tipo do evento, ex.: `110111` (cancelamento), `110110` (CC-e).
+    pub tp_evento: String,
+    /// Sequencial do evento para o mesmo tipo.
+    pub n_seq_evento: String,
+    /// `cStat` do registro do evento (`135`/`155` = registrado).
+    pub c_stat: i32,
+    /// Descrição do status do evento.
+    pub x_motivo: String,
+    /// Protocolo de registro do evento.
+    pub n_prot: Option<String>,
+    /// Data e hora do registro do evento.
+    pub dh_reg_evento: Option<String>,
+    /// XML `procEventoNFe` completo — pode ser persistido como está.
+    pub xml: String,
+}
+
+/// Resposta de [`ConsultaProtocoloBuilder::send`].
+#[derive(Debug)]
+pub struct ConsultaProtocoloResposta {
+    /// `cStat` da consulta.
+    pub c_stat: i32,
+    /// Descrição do status retornado pela SEFAZ.
+    pub x_motivo: String,
+    /// Situação atual da nota.
+    pub situacao: SituacaoNFe,
+    /// Protocolo de autorização (ou denegação), quando a nota existe na SEFAZ.
+    pub protocolo: Option<TagInfProt>,
+    /// XML `protNFe` como retornado pela SEFAZ.
+    pub prot_nfe_xml: Option<String>,
+    /// Eventos vinculados (cancelamento, CC-e…), na ordem da resposta.
+    pub eventos: Vec<EventoVinculado>,
+    /// `nfeProc` remontado a partir de [`nfe_assinada`](ConsultaProtocoloBuilder::nfe_assinada).
+    pub nfe_proc: Option<String>,
+    /// Por que o `nfeProc` não foi montado apesar da autorização: a chave ou o `digVal` do
+    /// protocolo não conferem com o XML de [`nfe_assinada`](ConsultaProtocoloBuilder::nfe_assinada)
+    /// (ex.: XML de outra tentativa da mesma nota). A situação e o protocolo continuam válidos.
+    pub nfe_proc_divergencia: Option<DfeError>,
+    /// Envelope SOAP enviado.
+    pub send_xml: String,
+    /// Resposta SOAP recebida.
+    pub receive_xml: String,
+}
+
+impl ConsultaProtocoloResposta {
+    /// Monta o `nfeProc` a partir do XML `NFe` assinado e do `protNFe` da consulta.
+    ///
+    /// Aceita o `NFe` isolado ou dentro de outro XML (ex.: um `nfeProc` sem protocolo).
+    /// Recusa se a chave ou o `digVal` do protocolo não corresponderem ao XML informado.
+    pub fn montar_nfe_proc(&self, nfe_assinada: &str) -> Result<String> {
+        let (protocolo, prot_nfe_xml) = match (&self.protocolo, &self.prot_nfe_xml) {
+            (Some(p), Some(x)) => (p, x),
+            _ => {
+                return Err(DfeError::Validacao(format!(
+                    "consulta sem protocolo de autorização: {} — {}",
+                    self.c_stat, self.x_motivo
+                )))
+            }
+        };
+        let inf_prot = &protocolo.inf_prot;
+        if !matches!(inf_prot.c_stat, 100 | 150) {
+            return Err(DfeError::Validacao(format!(
+                "protocolo não é de autorização: {} — {}",
+                inf_prot.c_stat, inf_prot.x_motivo
+            )));
+        }
+
+        let nfe = capturar(nfe_assinada, r"(?s)<NFe[\s>].*?</NFe>")
+            .ok_or_else(|| DfeError::Xml("elemento <NFe> não encontrado no XML assinado".to_string()))?;
+
+        let id = format!("Id=\"NFe{}\"", inf_prot.ch_nfe);
+        if !nfe.contains(&id) {
+            return Err(DfeError::Validacao(format!(
+                "o XML assinado não é da chave {}",
+                inf_prot.ch_nfe
+            )));
+        }
+        if let Some(dig_val) = &inf_prot.dig_val {
+            let digest = extrair_tag(nfe, "DigestValue").unwrap_or_default();
+            if &digest != dig_val {
+                return Err(DfeError::Validacao(format!(
+                    "digVal do protocolo ({}) difere do DigestValue do XML assinado ({})",
+                    dig_val, digest
+                )));
+            }
+        }
+
+        Ok(format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">{}{}</nfeProc>"#,
+            nfe, prot_nfe_xml
+        ))
+    }
+
+    // Nota autorizada: preenche `nfe_proc` ou, se o XML não conferir, `nfe_proc_divergencia`
+    fn anexar_nfe_proc(&mut self, nfe_assinada: &str) {
+        if !self.protocolo.as_ref().is_some_and(|p| matches!(p.inf_prot.c_stat, 100 | 150)) {
+            return;
+        }
+        match self.montar_nfe_proc(nfe_assinada) {
+            Ok(xml) => self.nfe_proc = Some(xml),
+            Err(e) => self.nfe_proc_divergencia = Some(e),
+        }
+    }
+}
+
+/// Builder da consulta de situação da NF-e/NFC-e pela chave de acesso.
+pub struct ConsultaProtocoloBuilder {
+    certificado:  Option<Certificado>,
+    cert_path:    Option<String>,
+    cert_pass:    Option<String>,
+    tp_amb:       Option<u8>,
+    chave:        Option<String>,
+    nfe_assinada: Option<String>,
+    webservices:  Option<Webservices>,
+    artefatos:    Option<Arc<dyn ArtefatoSink>>,
+}
+
+impl ConsultaProtocoloBuilder {
+    pub fn new() -> Self {
+        Self {
+            certificado: None, cert_path: None, cert_pass: None, tp_amb: None, chave: None,
+            nfe_assinada: None, webservices: None, artefatos: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+
+    /// 1 = Produção | 2 = Homologação
+    pub fn tp_amb(mut self, v: u8) -> Self { self.tp_amb = Some(v); self }
+
+    /// Chave de acesso de 44 dígitos; UF e modelo do webservice saem dela
+    pub fn chave(mut self, v: &str) -> Self { self.chave = Some(v.to_string()); self }
+
+    /// XML `NFe` assinado guardado localmente; se a nota estiver autorizada, a resposta traz o `nfeProc`
+    pub fn nfe_assinada(mut self, xml: &str) -> Self { self.nfe_assinada = Some(xml.to_string()); self }
+
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    /// Destino ([`ArtefatoSink`]) do envelope e do retorno. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    pub async fn send(self) -> Result<ConsultaProtocoloResposta> {
+        let certificado = Certificado::resolver(self.certificado, self.cert_path, self.cert_pass)?;
+        let tp_amb      = self.tp_amb.ok_or_else(|| DfeError::Configuracao("tp_amb não informado".to_string()))?;
+        let chave       = self.chave .ok_or_else(|| DfeError::Validacao("chave não informada".to_string()))?;
+        validar_chave(&chave)?;
+
+        // cUF nas posições 1–2 e modelo nas posições 21–22 da chave de acesso.
+        let c_uf: u16  = chave[..2].parse().unwrap_or_default();
+        let mod_: u32  = chave[20..22].parse().unwrap_or_default();
+        let url        = nfe_consulta_protocolo(tp_amb, uf_sigla(c_uf)?, mod_, false, self.webservices.as_ref())?;
+
+        let envelope = envelope_xml(tp_amb, &chave);
+        let client   = WebService::client(certificado.identity())?;
+        let artefatos = Artefatos::new(self.artefatos, "consulta-protocolo", &chave);
+        let body     = WebService::transmitir(client, &url, envelope.clone(), &artefatos).await?;
+
+        let mut resposta = parse_resposta(envelope, body)?;
+        if let Some(nfe_assinada) = &self.nfe_assinada {
+            resposta.anexar_nfe_proc(nfe_assinada);
+        }
+        Ok(resposta)
+    }
+}
+
+impl Default for ConsultaProtocoloBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─── Lógica interna ───────────────────────────────────────────────────────────
+
+fn validar_chave(chave: &str) -> Result<()> {
+    if chave.len() != 44 || !chave.is_ascii() {
+        return Err(DfeError::Validacao(format!("chave de acesso deve ter 44 posições: {}", chave)));
+    }
+    let dv = ChaveAcesso::gerar_dv(&chave[..43])?;
+    if chave[43..] != dv.to_string() {
+        return Err(DfeError::Validacao(format!("dígito verificador inválido na chave {}", chave)));
+    }
+    Ok(())
+}
+
+fn envelope_xml(tp_amb: u8, chave: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeConsultaProtocolo4"><consSitNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><tpAmb>{}</tpAmb><xServ>CONSULTAR</xServ><chNFe>{}</chNFe></consSitNFe></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        tp_amb, chave
+    )
+}
+
+fn parse_resposta(send_xml: String, receive_xml: String) -> Result<ConsultaProtocoloResposta> {
+    let ret = capturar(&receive_xml, r"(?s)<retConsSitNFe\b.*?</retConsSitNFe>")
+        .ok_or_else(|| DfeError::Xml(format!("retConsSitNFe não encontrado: {}", receive_xml)))?;
+
+    // cStat/xMotivo do nível da consulta vêm antes de protNFe e dos eventos.
+    let cabecalho = ret.split("<protNFe").next().unwrap_or(ret);
+    let cabecalho = cabecalho.split("<procEventoNFe").next().unwrap_or(cabecalho);
+    let c_stat: i32 = extrair_tag(cabecalho, "cStat")
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| DfeError::Xml(format!("cStat não encontrado: {}", ret)))?;
+    let x_motivo = extrair_tag(cabecalho, "xMotivo").unwrap_or_default();
+
+    let prot_nfe_xml = capturar(ret, r"(?s)<protNFe\b.*?</protNFe>").map(str::to_string);
+    let protocolo = match &prot_nfe_xml {
+        Some(xml) => Some(
+            quick_xml::de::from_str::<TagInfProt>(xml)
+                .map_err(|e| DfeError::Xml(format!("Erro desserializar protocolo: {} — {}", e, xml)))?,
+        ),
+        None => None,
+    };
+
+    let re = Regex::new(r"(?s)<procEventoNFe\b.*?</procEventoNFe>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?;
+    let eventos: Vec<EventoVinculado> = re.find_iter(ret).map(|m| evento_vinculado(m.as_str())).collect();
+
+    let cancelada = eventos
+        .iter()
+        .any(|e| e.tp_evento == TP_EVENTO_CANCELAMENTO && matches!(e.c_stat, 135 | 155));
+    let situacao = match c_stat {
+        101 | 151 | 155 => SituacaoNFe::Cancelada,
+        _ if cancelada => SituacaoNFe::Cancelada,
+        100 | 150 => SituacaoNFe::Autorizada,
+        110 | 301 | 302 | 303 => SituacaoNFe::Denegada,
+        217 => SituacaoNFe::NaoEncontrada,
+        _ => SituacaoNFe::Rejeitada,
+    };
+
+    Ok(ConsultaProtocoloResposta {
+        c_stat, x_motivo, situacao, protocolo, prot_nfe_xml, eventos,
+        nfe_proc: None, nfe_proc_divergencia: None, send_xml, receive_xml,
+    })
+}
+
+fn evento_vinculado(xml: &str) -> EventoVinculado {
+    let evento = capturar(xml, r"(?s)<evento\b.*?</evento>").unwrap_or(xml);
+    let ret = capturar(xml, r"(?s)<retEvento\b.*?</retEvento>").unwrap_or(xml);
+    EventoVinculado {
+        tp_evento: extrair_tag(evento, "tpEvento").unwrap_or_default(),
+        n_seq_evento: extrair_tag(evento, "nSeqEvento").unwrap_or_default(),
+        c_stat: extrair_tag(ret, "cStat").and_then(|s| s.parse().ok()).unwrap_or(0),
+        x_motivo: extrair_tag(ret, "xMotivo").unwrap_or_default(),
+        n_prot: extrair_tag(ret, "nProt"),
+        dh_reg_evento: extrair_tag(ret, "dhRegEvento"),
+        xml: xml.to_string(),
+    }
+}
+
+fn capturar<'a>(xml: &'a str, padrao: &str) -> Option<&'a str> {
+    Regex::new(padrao).ok()?.find(xml).map(|m| m.as_str())
+}
+
+/// Conteúdo da primeira ocorrência de `<tag>…</tag>`.
+fn extrair_tag(xml: &str, tag: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"(?s)<{0}[^>]*>(.*?)</{0}>", regex::escape(tag))).ok()?;
+    re.captures(xml)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAVE: &str = "35250111222333000181550010000000011000000010";
+
+    fn ret_cons_sit(c_stat: &str, eventos: &str) -> String {
+        format!(
+            r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><nfeResultMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeConsultaProtocolo4"><retConsSitNFe versao="4.00" xmlns="http://www.portalfiscal.inf.br/nfe"><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><cStat>{c_stat}</cStat><xMotivo>Situacao da NF-e</xMotivo><cUF>35</cUF><dhRecbto>2025-01-10T10:00:00-03:00</dhRecbto><chNFe>{CHAVE}</chNFe><protNFe versao="4.00"><infProt><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><chNFe>{CHAVE}</chNFe><dhRecbto>2025-01-10T09:00:00-03:00</dhRecbto><nProt>135250000000001</nProt><digVal>q3d9Jq2ZrX0aZbq1m3gkCw7lq6g=</digVal><cStat>100</cStat><xMotivo>Autorizado o uso da NF-e</xMotivo></infProt></protNFe>{eventos}</retConsSitNFe></nfeResultMsg></soap:Body></soap:Envelope>"#
+        )
+    }
+
+    const CANCELAMENTO: &str = r#"<procEventoNFe versao="1.00"><evento versao="1.00"><infEvento Id="ID1101113525011122233300018155001000000001100000001001"><cOrgao>35</cOrgao><tpEvento>110111</tpEvento><nSeqEvento>1</nSeqEvento></infEvento></evento><retEvento versao="1.00"><infEvento><tpAmb>2</tpAmb><cStat>135</cStat><xMotivo>Evento registrado e vinculado a NF-e</xMotivo><tpEvento>110111</tpEvento><dhRegEvento>2025-01-10T11:00:00-03:00</dhRegEvento><nProt>135250000000002</nProt></infEvento></retEvento></procEventoNFe>"#;
+
+    fn nfe_assinada(digest: &str) -> String {
+        format!(
+            r##"<?xml version="1.0" encoding="UTF-8"?><NFe xmlns="http://www.portalfiscal.inf.br/nfe"><infNFe Id="NFe{CHAVE}" versao="4.00"><ide><cUF>35</cUF></ide></infNFe><Signature xmlns="http://www.w3.org/2000/09/xmldsig#"><SignedInfo><Reference URI="#NFe{CHAVE}"><DigestValue>{digest}</DigestValue></Reference></SignedInfo></Signature></NFe>"##
+        )
+    }
+
+    #[test]
+    fn test_autorizada_com_protocolo() {
+        let r = parse_resposta(String::new(), ret_cons_sit("100", "")).unwrap();
+        assert_eq!(r.c_stat, 100);
+        assert_eq!(r.situacao, SituacaoNFe::Autorizada);
+        assert_eq!(r.situacao.c_sit_nfe(), Some(1));
+        assert_eq!(r.protocolo.unwrap().inf_prot.n_prot.as_deref(), Some("135250000000001"));
+        assert!(r.eventos.is_empty());
+    }
+
+    #[test]
+    fn test_cancelada_lista_eventos() {
+        let r = parse_resposta(String::new(), ret_cons_sit("101", CANCELAMENTO)).unwrap();
+        assert_eq!(r.situacao, SituacaoNFe::Cancelada);
+        assert_eq!(r.situacao.c_sit_nfe(), Some(3));
+        assert_eq!(r.eventos.len(), 1);
+        let e = &r.eventos[0];
+        assert_eq!((e.tp_evento.as_str(), e.c_stat), ("110111", 135));
+        assert_eq!(e.n_prot.as_deref(), Some("135250000000002"));
+        assert!(e.xml.starts_with("<procEventoNFe"));
+    }
+
+    #[test]
+    fn test_nao_encontrada() {
+        let body = r#"<retConsSitNFe versao="4.00"><tpAmb>2</tpAmb><cStat>217</cStat><xMotivo>Rejeicao: NF-e nao consta na base de dados da SEFAZ</xMotivo></retConsSitNFe>"#;
+        let r = parse_resposta(String::new(), body.to_string()).unwrap();
+        assert_eq!(r.situacao, SituacaoNFe::NaoEncontrada);
+        assert!(r.protocolo.is_none());
+        assert!(r.montar_nfe_proc(&nfe_assinada("x")).is_err());
+    }
+
+    #[test]
+    fn test_monta_nfe_proc() {
+        let r = parse_resposta(String::new(), ret_cons_sit("100", "")).unwrap();
+        let nfe_proc = r.montar_nfe_proc(&nfe_assinada("q3d9Jq2ZrX0aZbq1m3gkCw7lq6g=")).unwrap();
+        assert!(nfe_proc.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><NFe "#));
+        assert!(nfe_proc.ends_with("</NFe><protNFe versao=\"4.00\"><infProt><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><chNFe>35250111222333000181550010000000011000000010</chNFe><dhRecbto>2025-01-10T09:00:00-03:00</dhRecbto><nProt>135250000000001</nProt><digVal>q3d9Jq2ZrX0aZbq1m3gkCw7lq6g=</digVal><cStat>100</cStat><xMotivo>Autorizado o uso da NF-e</xMotivo></infProt></protNFe></nfeProc>"));
+
+        // XML assinado de outra versão da nota: digVal não confere.
+        assert!(r.montar_nfe_proc(&nfe_assinada("outro")).is_err());
+    }
+
+    #[test]
+    fn test_divergencia_nao_descarta_a_resposta() {
+        let mut r = parse_resposta(String::new(), ret_cons_sit("100", "")).unwrap();
+        r.anexar_nfe_proc(&nfe_assinada("outro"));
+        assert_eq!(r.situacao, SituacaoNFe::Autorizada);
+        assert!(r.protocolo.is_some());
+        assert!(r.nfe_proc.is_none());
+        assert!(matches!(r.nfe_proc_divergencia, Some(DfeError::Validacao(ref m)) if m.contains("digVal")));
+
+        let mut r = parse_resposta(String::new(), ret_cons_sit("100", "")).unwrap();
+        r.anexar_nfe_proc(&nfe_assinada("q3d9Jq2ZrX0aZbq1m3gkCw7lq6g="));
+        assert!(r.nfe_proc.is_some());
+        assert!(r.nfe_proc_divergencia.is_none());
+    }
+
+    #[test]
+    fn test_valida_chave() {
+        assert!(validar_chave(CHAVE).is_ok());
+        assert!(validar_chave(&CHAVE[..43]).is_err());
+        assert!(validar_chave("35250111222333000181550010000000011000000019").is_err());
+    }
+}
@@ -2,7 +2,8 @@ pub mod pdf_builder_80mm;
 pub mod pdf_builder_a4;
 pub mod pdf_builder_nfce_80mm;
 
-use crate::xml_extractor::structs::NFeProc;
+use crate::interno::chave_acesso::ChaveAcesso;
+use crate::xml_extractor::structs::{InfNFe, NFeProc, Prod};
 use pdf_builder_80mm::PdfItem;
 use pdf_builder_a4::PdfItemA4;
 use pdf_builder_nfce_80mm::NfcePayment;
@@ -81,7 +82,7 @@ impl DanfeBuilderActions {
             .unwrap_or_default();
 
         // Observação do contribuinte
-        let inf_cpl = inf.inf_adic.inf_cpl.clone().unwrap_or_default();
+        let inf_cpl = Self::inf_cpl_55(ide.tp_emis.as_deref(), inf.inf_adic.inf_cpl.clone());
 
         // Forma de pagamento
         let t_pag = inf
@@ -112,6 +113,7 @@ impl DanfeBuilderActions {
             &chave_acesso,
             &n_prot,
             &dh_recbto,
+            &Self::dados_nfe(inf),
             &emit_x_nome,
             &emit_uf,
             &emit_cnpj,
@@ -223,7 +225,7 @@ impl DanfeBuilderActions {
         };
 
         // Observação
-        let inf_cpl = inf.inf_adic.inf_cpl.clone().unwrap_or_default();
+        let inf_cpl = Self::inf_cpl_55(ide.tp_emis.as_deref(), inf.inf_adic.inf_cpl.clone());
 
         // Itens
         let items: Vec<PdfItemA4> = inf
@@ -248,6 +250,7 @@ impl DanfeBuilderActions {
                     p_icms: icms.and_then(|i| i.p_icms.clone()).unwrap_or_default(),
                     v_icms: icms.and_then(|i| i.v_icms.clone()).unwrap_or_default(),
                     p_ipi: String::new(),
+                    det_esp: Self::det_esp_55(prod),
                 }
             })
             .collect();
@@ -257,6 +260,7 @@ impl DanfeBuilderActions {
             &chave_acesso,
             &n_prot,
             &dh_recbto,
+            &Self::dados_nfe(inf),
             &tp_nf,
             &n_nf,
             &serie,
@@ -357,6 +361,7 @@ impl DanfeBuilderActions {
 
         // Ambiente / série / número / data
         let tp_amb = ide.tp_amb.clone().unwrap_or_default();
+        let tp_emis = ide.tp_emis.clone().unwrap_or_default();
         let serie = ide.serie.clone().unwrap_or_default();
         let n_nf = ide.n_nf.clone().unwrap_or_default();
         let dh_emi = ide.dh_emi.clone().unwrap_or_default();
@@ -439,6 +444,7 @@ impl DanfeBuilderActions {
             &emit_x_bairro,
             &emit_x_mun,
             &tp_amb,
+            &tp_emis,
             &serie,
             &n_nf,
             &dh_emi,
@@ -456,4 +462,152 @@ impl DanfeBuilderActions {
             qr_side,
         )
     }
+
+    /// Observação do DANFE NF-e; em contingência EPEC (`tpEmis = 4`) ou FS-DA
+    /// (`tpEmis = 5`) recebe a mensagem exigida pelo MOC antes do texto do contribuinte.
+    fn inf_cpl_55(tp_emis: Option<&str>, inf_cpl: Option<String>) -> String {
+        let inf_cpl = inf_cpl.unwrap_or_default();
+        let aviso = match tp_emis {
+            Some("4") => "DANFE impresso em contingência - EPEC regularmente recebido pela Receita Federal do Brasil.",
+            Some("5") => "DANFE em contingência - impresso em decorrência de problemas técnicos.",
+            _ => return inf_cpl,
+        };
+        format!("{} {}", aviso, inf_cpl).trim_end().to_string()
+    }
+
+    /// Detalhamento específico do produto impresso abaixo da descrição do item:
+    /// lotes (`rastro`), medicamento, veículo, armas e combustível (ANP, CODIF e encerrante).
+    fn det_esp_55(prod: &Prod) -> String {
+        let v = |s: &Option<String>| s.clone().unwrap_or_default();
+        let num = |s: &Option<String>| v(s).replace('.', ",");
+        let data = |s: &Option<String>| {
+            let d = v(s);
+            match (d.get(0..4), d.get(5..7), d.get(8..10)) {
+                (Some(a), Some(m), Some(dd)) => format!("{}/{}/{}", dd, m, a),
+                _ => d,
+            }
+        };
+
+        let mut partes: Vec<String> = prod
+            .rastro
+            .iter()
+            .map(|r| format!("LOTE {} QTD {} FAB {} VAL {}", v(&r.n_lote), num(&r.q_lote), data(&r.d_fab), data(&r.d_val)))
+            .collect();
+        if let Some(m) = &prod.med {
+            partes.push(format!("ANVISA {} PMC R$ {}", v(&m.c_prod_anvisa), num(&m.v_pmc)));
+        }
+        if let Some(vc) = &prod.veic_prod {
+            partes.push(format!(
+                "CHASSI {} COR {} MOTOR {} ANO MOD/FAB {}/{}",
+                v(&vc.chassi), v(&vc.x_cor), v(&vc.n_motor), v(&vc.ano_mod), v(&vc.ano_fab)
+            ));
+        }
+        for a in &prod.arma {
+            let tipo = if a.tp_arma.as_deref() == Some("1") { "USO RESTRITO" } else { "USO PERMITIDO" };
+            partes.push(format!("ARMA {} SÉRIE {} CANO {} {}", tipo, v(&a.n_serie), v(&a.n_cano), v(&a.descr)));
+        }
+        if let Some(c) = &prod.comb {
+            let mut txt = format!("ANP {} {}", v(&c.c_prod_anp), v(&c.desc_anp));
+            if let Some(codif) = &c.codif {
+                txt.push_str(&format!(" CODIF {}", codif));
+            }
+            txt.push_str(&format!(" UF CONS {}", v(&c.uf_cons)));
+            if let Some(e) = &c.encerrante {
+                txt.push_str(&format!(" BICO {}", v(&e.n_bico)));
+                if let Some(bomba) = &e.n_bomba {
+                    txt.push_str(&format!(" BOMBA {}", bomba));
+                }
+                txt.push_str(&format!(
+                    " TANQUE {} ENCERRANTE {} A {}",
+                    v(&e.n_tanque), num(&e.v_enc_ini), num(&e.v_enc_fin)
+                ));
+            }
+            partes.push(txt);
+        }
+        partes.join(" - ")
+    }
+
+    /// Dados da NF-e impressos na FS-DA (`tpEmis = 5`) no lugar do protocolo:
+    /// cUF, tpEmis, CNPJ/CPF do destinatário, vNF em centavos, destaque de ICMS
+    /// próprio e de ST (1 = há, 2 = não há), dia da emissão e DV — 36 dígitos.
+    /// Vazio fora da FS-DA.
+    fn dados_nfe(inf: &InfNFe) -> String {
+        if inf.ide.tp_emis.as_deref() != Some("5") {
+            return String::new();
+        }
+        let centavos = |v: Option<&String>| {
+            v.and_then(|v| v.parse::<f64>().ok()).map(|v| (v * 100.0).round() as u64).unwrap_or(0)
+        };
+        let icms_tot = inf.total.icms_tot.as_ref();
+        let destaque = |v: Option<&String>| if centavos(v) > 0 { 1 } else { 2 };
+        let doc_dest = inf
+            .dest
+            .as_ref()
+            .and_then(|d| d.cnpj.clone().or_else(|| d.cpf.clone()))
+            .unwrap_or_default();
+        let dia = inf.ide.dh_emi.as_deref().and_then(|d| d.get(8..10)).unwrap_or("00");
+
+        let dados = format!(
+            "{:0>2}5{:0>14}{:014}{}{}{}",
+            inf.ide.c_uf.as_deref().unwrap_or_default(),
+            doc_dest,
+            centavos(icms_tot.and_then(|t| t.v_nf.as_ref())),
+            destaque(icms_tot.and_then(|t| t.v_icms.as_ref())),
+            destaque(icms_tot.and_then(|t| t.v_st.as_ref())),
+            dia,
+        );
+        match ChaveAcesso::gerar_dv(&dados) {
+            Ok(dv) => format!("{}{}", dados, dv),
+            Err(_) => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml_extractor::{XmlExtractor, XmlExtractorSignature};
+
+    fn inf_nfe(ide: &str, dest: &str, icms_tot: &str) -> InfNFe {
+        let xml = format!(
+            r#"<NFe><infNFe Id="NFe35250111222333000181550010000000035000000033"><ide>{}</ide><emit><CNPJ>11222333000181</CNPJ><enderEmit/></emit>{}<det nItem="1"><prod><xProd>ITEM</xProd></prod><imposto/></det><total><ICMSTot>{}</ICMSTot></total><transp/><pag/><infAdic/></infNFe></NFe>"#,
+            ide, dest, icms_tot
+        );
+        XmlExtractor::new().nfe_from_string(&xml).unwrap().inf_nfe
+    }
+
+    #[test]
+    fn test_dados_nfe_fsda() {
+        let inf = inf_nfe(
+            "<cUF>35</cUF><dhEmi>2024-03-01T10:00:00-03:00</dhEmi><tpEmis>5</tpEmis>",
+            "<dest><CNPJ>44555666000177</CNPJ></dest>",
+            "<vICMS>54.00</vICMS><vST>0.00</vST><vNF>460.00</vNF>",
+        );
+        assert_eq!(DanfeBuilderActions::dados_nfe(&inf), "355445556660001770000000004600012011");
+
+        // Sem destinatário e sem ICMS: documento zerado e indicadores 2
+        let inf = inf_nfe("<cUF>35</cUF><dhEmi>2025-01-10T09:00:00-03:00</dhEmi><tpEmis>5</tpEmis>", "", "<vNF>10.50</vNF>");
+        assert_eq!(DanfeBuilderActions::dados_nfe(&inf), "355000000000000000000000000105022108");
+
+        let inf = inf_nfe("<cUF>35</cUF><tpEmis>1</tpEmis>", "", "<vNF>10.50</vNF>");
+        assert_eq!(DanfeBuilderActions::dados_nfe(&inf), "");
+    }
+
+    #[test]
+    fn test_det_esp_55() {
+        let xml = r#"<NFe><infNFe Id="NFe35250111222333000181550010000000035000000033"><ide/><emit><CNPJ>11222333000181</CNPJ><enderEmit/></emit><det nItem="1"><prod><xProd>GASOLINA</xProd><indTot>1</indTot><rastro><nLote>L01</nLote><qLote>10.000</qLote><dFab>2026-01-10</dFab><dVal>2027-01-10</dVal></rastro><comb><cProdANP>320102001</cProdANP><descANP>GASOLINA C COMUM</descANP><CODIF>123456789</CODIF><UFCons>SP</UFCons><encerrante><nBico>1</nBico><nTanque>3</nTanque><vEncIni>1500.000</vEncIni><vEncFin>1540.000</vEncFin></encerrante><origComb><indImport>0</indImport><cUFOrig>35</cUFOrig><pOrig>100.0000</pOrig></origComb></comb></prod><imposto/></det><det nItem="2"><prod><xProd>ITEM</xProd></prod><imposto/></det><total/><transp/><pag/><infAdic/></infNFe></NFe>"#;
+        let inf = XmlExtractor::new().nfe_from_string(xml).unwrap().inf_nfe;
+        assert_eq!(
+            DanfeBuilderActions::det_esp_55(&inf.det[0].prod),
+            "LOTE L01 QTD 10,000 FAB 10/01/2026 VAL 10/01/2027 - ANP 320102001 GASOLINA C COMUM CODIF 123456789 UF CONS SP BICO 1 TANQUE 3 ENCERRANTE 1500,000 A 1540,000"
+        );
+        assert_eq!(DanfeBuilderActions::det_esp_55(&inf.det[1].prod), "");
+    }
+
+    #[test]
+    fn test_inf_cpl_contingencia() {
+        assert_eq!(DanfeBuilderActions::inf_cpl_55(Some("1"), Some("OBS".into())), "OBS");
+        assert!(DanfeBuilderActions::inf_cpl_55(Some("5"), None).starts_with("DANFE em contingência"));
+        assert!(DanfeBuilderActions::inf_cpl_55(Some("4"), Some("OBS".into())).ends_with("Brasil. OBS"));
+    }
 }
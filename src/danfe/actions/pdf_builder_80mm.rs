@@ -30,6 +30,7 @@ pub fn build_pdf_80mm(
     chave_acesso: &str,
     n_prot: &str,
     dh_recbto: &str,
+    dados_nfe: &str,
     emit_x_nome: &str,
     emit_uf: &str,
     emit_cnpj: &str,
@@ -293,12 +294,18 @@ pub fn build_pdf_80mm(
     y -= SECTION_GAP;
 
     // ── PROTOCOLO DE AUTORIZACAO ────────────
+    // FS-DA: ainda sem protocolo, imprime os dados da NF-e
+    let (titulo_prot, valor_prot) = if dados_nfe.is_empty() {
+        ("PROTOCOLO DE AUTORIZACAO", format!("{} - {}", n_prot, format_datetime(dh_recbto)))
+    } else {
+        ("DADOS DA NF-E", format_chave_acesso(dados_nfe))
+    };
     write_center(
         &layer,
         &font_bold,
         FONT_SIZE_HEADER,
         y,
-        "PROTOCOLO DE AUTORIZACAO",
+        titulo_prot,
     );
     y -= LINE_HEIGHT;
     write_center(
@@ -306,7 +313,7 @@ pub fn build_pdf_80mm(
         &font,
         FONT_SIZE_NORMAL,
         y,
-        &format!("{} - {}", n_prot, format_datetime(dh_recbto)),
+        &valor_prot,
     );
     y -= 1.5;
     draw_line(&layer, y, 0.6);
@@ -21,6 +21,8 @@ const H_IMP: f32 = 13.0;
 const H_TRANSP: f32 = 22.0;
 const H_ITEMS_HDR: f32 = 8.5; // faixa título (3.5 mm) + faixa cabeçalhos colunas (5.0 mm)
 const H_ITEM: f32 = 4.0;
+const H_ITEM_DET: f32 = 2.5; // linha extra do detalhamento específico do produto
+const W_DESCR: f32 = 58.0; // largura da coluna DESCRIÇÃO DO PRODUTO
 const H_ADIC_MIN: f32 = 18.0;
 const H_PROT: f32 = 5.0;
 
@@ -53,6 +55,9 @@ pub struct PdfItemA4 {
     pub p_icms: String,
     pub v_icms: String,
     pub p_ipi: String,
+    /// Detalhamento específico do produto (lote, medicamento, veículo, arma,
+    /// combustível), impresso abaixo da descrição. Vazio quando não há.
+    pub det_esp: String,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -61,6 +66,7 @@ pub fn build_pdf_a4(
     chave_acesso: &str,
     n_prot: &str,
     dh_recbto: &str,
+    dados_nfe: &str,
     tp_nf: &str,
     n_nf: &str,
     serie: &str,
@@ -126,7 +132,7 @@ pub fn build_pdf_a4(
         + H_IMP
         + H_TRANSP
         + H_ITEMS_HDR
-        + (items.len().max(1) as f32 * H_ITEM)
+        + altura_itens(items)
         + H_ADIC_MIN
         + H_PROT;
     let page_h = (2.0 * M + fixed_h).max(PAGE_H);
@@ -330,7 +336,20 @@ pub fn build_pdf_a4(
             yi -= 2.5;
         }
 
-        if !n_prot.is_empty() && yi > subrow_top + 1.0 {
+        if !dados_nfe.is_empty() && yi > subrow_top + 1.0 {
+            // FS-DA: dados da NF-e (texto e código de barras) no lugar do protocolo
+            t(&layer, &font, x, yi, FS_LBL, "DADOS DA NF-E");
+            yi -= LH;
+            if yi > subrow_top + 1.0 {
+                t(&layer, &font_b, x, yi, FS_SM, &format_chave_acesso(dados_nfe));
+                yi -= 1.0;
+            }
+            let bar_h = 6.0;
+            if yi - bar_h > subrow_top + 1.0 {
+                let _ = draw_barcode_a4(&layer, dados_nfe, x_chave + 2.0, COL_CHAVE - 4.0, yi, bar_h);
+                yi -= bar_h + 1.0;
+            }
+        } else if !n_prot.is_empty() && yi > subrow_top + 1.0 {
             t(
                 &layer,
                 &font,
@@ -848,7 +867,7 @@ pub fn build_pdf_a4(
     // BLOCO 5 — DADOS DOS PRODUTOS / SERVIÇOS
     // ══════════════════════════════════════════════════════════════════════
     let items_top = y;
-    let items_rows_h = items.len().max(1) as f32 * H_ITEM;
+    let items_rows_h = altura_itens(items);
     let items_total_h = H_ITEMS_HDR + items_rows_h;
     let items_bot = items_top - items_total_h;
     draw_rect(&layer, M, items_bot, BODY_W, items_total_h, 0.4);
@@ -871,7 +890,7 @@ pub fn build_pdf_a4(
     let cols: &[(&str, f32)] = &[
         ("#", 6.0),
         ("CÓDIGO", 14.0),
-        ("DESCRIÇÃO DO PRODUTO", 58.0),
+        ("DESCRIÇÃO DO PRODUTO", W_DESCR),
         ("NCM/SH", 12.0),
         ("CFOP", 8.0),
         ("UN", 7.0),
@@ -910,12 +929,14 @@ pub fn build_pdf_a4(
     // Data rows
     let mut row_y = col_hdr_sep_y; // top of first data row (abaixo do separador de col-headers)
     for item in items {
-        if row_y - H_ITEM < items_bot - 0.1 {
+        let det_linhas = linhas_det_esp(item);
+        let row_h = H_ITEM + det_linhas.len() as f32 * H_ITEM_DET;
+        if row_y - row_h < items_bot - 0.1 {
             break;
         }
-        let row_bot = row_y - H_ITEM;
+        let row_bot = row_y - row_h;
         hline(&layer, M, row_bot, BODY_W, 0.1);
-        let text_y = row_bot + H_ITEM * 0.28; // baseline ~28% from bottom of row
+        let text_y = row_y - H_ITEM * 0.72; // baseline ~28% from bottom of the first line
 
         let row_vals: &[(&str, bool)] = &[
             (&item.n_item, false),
@@ -946,6 +967,11 @@ pub fn build_pdf_a4(
             xi += w;
         }
 
+        let descr_x = M + cols[0].1 + cols[1].1 + 0.5;
+        for (k, linha) in det_linhas.iter().enumerate() {
+            t(&layer, &font, descr_x, text_y - (k + 1) as f32 * H_ITEM_DET, FS_LBL, linha);
+        }
+
         row_y = row_bot;
     }
 
@@ -1246,6 +1272,18 @@ fn text_wrap_clipped(
     }
 }
 
+fn linhas_det_esp(item: &PdfItemA4) -> Vec<String> {
+    if item.det_esp.is_empty() {
+        return Vec::new();
+    }
+    wrap_text_by_width(&item.det_esp, W_DESCR - 1.0, FS_LBL)
+}
+
+fn altura_itens(items: &[PdfItemA4]) -> f32 {
+    let h: f32 = items.iter().map(|i| H_ITEM + linhas_det_esp(i).len() as f32 * H_ITEM_DET).sum();
+    h.max(H_ITEM)
+}
+
 fn wrap_text_by_width(text: &str, max_w: f32, size: f32) -> Vec<String> {
     let mut lines: Vec<String> = Vec::new();
     let mut current = String::new();
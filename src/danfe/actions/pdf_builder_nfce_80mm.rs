@@ -36,6 +36,7 @@ pub fn build_pdf_nfce_80mm(
     emit_x_bairro: &str,
     emit_x_mun: &str,
     tp_amb: &str,
+    tp_emis: &str,
     serie: &str,
     n_nf: &str,
     dh_emi: &str,
@@ -94,6 +95,11 @@ pub fn build_pdf_nfce_80mm(
     } else {
         0.0
     };
+    let contingencia_h = if tp_emis == "9" {
+        LINE_HEIGHT * 2.0 + SECTION_GAP
+    } else {
+        0.0
+    };
     let emit_h = 15.0 + extra_emit_lines * LINE_HEIGHT; // CNPJ/IE/addr + extra name lines
     let items_h = items.len() as f32 * (LINE_HEIGHT * 2.0);
     let payments_h = payments.len() as f32 * LINE_HEIGHT + LINE_HEIGHT; // each + troco
@@ -135,6 +141,7 @@ pub fn build_pdf_nfce_80mm(
         };
 
     let base_h = homolog_h
+        + contingencia_h
         + LINE_HEIGHT * 1.0       // subtitle only
         + SECTION_GAP
         + emit_h
@@ -183,6 +190,16 @@ pub fn build_pdf_nfce_80mm(
         y -= SECTION_GAP;
     }
 
+    // ── Contingência offline (tpEmis 9) ───────────────────
+    if tp_emis == "9" {
+        write_center(&layer, &font_bold, 7.0, y, "EMITIDA EM CONTINGENCIA");
+        y -= LINE_HEIGHT;
+        write_center(&layer, &font, 6.0, y, "Pendente de autorizacao");
+        y -= LINE_HEIGHT;
+        draw_line(&layer, y, 0.6);
+        y -= SECTION_GAP;
+    }
+
     // ── Título ────────────────────────────────────────────
     write_center(
         &layer,
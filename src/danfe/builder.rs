@@ -34,6 +34,8 @@ pub struct DanfeBuilder<'a> {
     /// Caminho de arquivo (PNG/JPG) ou string base64 do logotipo do emitente.
     /// Data URIs (`data:image/png;base64,...`) também são aceitos.
     pub logo: Option<&'a str>,
+    /// `procEventoNFe` do EPEC (XML ou caminho `.xml`) para imprimir a NF-e em contingência.
+    pub epec: Option<&'a str>,
 }
 
 impl<'a> DanfeBuilder<'a> {
@@ -45,6 +47,7 @@ impl<'a> DanfeBuilder<'a> {
             as_file: None,
             qr_side: false,
             logo: None,
+            epec: None,
         }
     }
     /// Configura o XML a partir de uma string ou caminho de arquivo.
@@ -83,6 +86,15 @@ impl<'a> DanfeBuilder<'a> {
         self
     }
 
+    /// Imprime o DANFE de uma NF-e emitida em contingência EPEC (`tpEmis = 4`).
+    /// Recebe o `procEventoNFe` do evento registrado (XML ou caminho `.xml`); o XML
+    /// configurado em [`xml`](Self::xml) pode ser a `<NFe>` assinada, ainda sem protocolo.
+    /// O número do protocolo EPEC substitui o de autorização no quadro de protocolo.
+    pub fn epec(mut self, proc_evento_nfe: &'a str) -> Self {
+        self.epec = Some(proc_evento_nfe);
+        self
+    }
+
     /// Usa layout lateral para o QR Code (NFC-e 80mm):
     /// QR Code à esquerda (~33 mm) com chave de acesso e protocolo à direita.
     /// Padrão: QR Code centralizado.
@@ -1,5 +1,5 @@
 use super::builder::DanfeBuilder;
-use crate::xml_extractor::{structs::*, XmlExtractor, XmlExtractorSignature};
+use crate::xml_extractor::{nfe_proc_ou_offline, structs::*, XmlExtractor, XmlExtractorSignature};
 
 pub struct Validations;
 
@@ -13,7 +13,10 @@ impl Validations {
                 xml_content = xml.to_string();
             }
         }
-        let nfe_proc = Self::convert_xml_to_struct(&xml_content)?;
+        let nfe_proc = match data.epec {
+            Some(epec) => Self::nfe_proc_epec(&xml_content, epec)?,
+            None => Self::convert_xml_to_struct(&xml_content)?,
+        };
 
         match nfe_proc.nfe.inf_nfe.ide.mod_.clone() {
             Some(modelo) => {
@@ -44,10 +47,76 @@ impl Validations {
         std::fs::read_to_string(file_path).map_err(|e| format!("Erro ao ler o arquivo XML: {}", e))
     }
 
+    /// Monta o `NFeProc` de uma NF-e em contingência EPEC: a NF-e (com ou sem
+    /// `nfeProc`) recebe como protocolo os dados do evento 110140 registrado.
+    fn nfe_proc_epec(xml: &str, epec: &str) -> Result<NFeProc, String> {
+        let proc_evento = if epec.ends_with(".xml") {
+            Self::xml_file_to_string(epec)?
+        } else {
+            epec.to_string()
+        };
+
+        let nfe = XmlExtractor::new()
+            .nfe_from_string(xml)
+            .map_err(|e| format!("Erro ao converter XML para struct NFe: {}", e))?;
+        if nfe.inf_nfe.ide.tp_emis.as_deref() != Some("4") {
+            return Err("A NF-e não foi emitida em contingência EPEC (tpEmis deve ser 4).".to_string());
+        }
+
+        let inf_prot = Self::protocolo_epec(&proc_evento)?;
+        let chave = nfe.inf_nfe.id.as_deref().unwrap_or_default().trim_start_matches("NFe");
+        if inf_prot.ch_nfe.as_deref() != Some(chave) {
+            return Err(format!(
+                "O evento EPEC não pertence à NF-e informada (chave do evento: {}).",
+                inf_prot.ch_nfe.unwrap_or_default()
+            ));
+        }
+
+        Ok(NFeProc {
+            versao: "4.00".to_string(),
+            nfe,
+            prot_nfe: ProtNFe { inf_prot: Some(inf_prot) },
+        })
+    }
+
+    fn protocolo_epec(proc_evento: &str) -> Result<InfProt, String> {
+        let ret_evento = match proc_evento.find("<retEvento") {
+            Some(pos) => &proc_evento[pos..],
+            None => return Err("procEventoNFe sem <retEvento>.".to_string()),
+        };
+        let tag = |xml: &str, nome: &str| {
+            let inicio = xml.find(&format!("<{}>", nome))? + nome.len() + 2;
+            let fim = xml[inicio..].find(&format!("</{}>", nome))? + inicio;
+            Some(xml[inicio..fim].trim().to_string())
+        };
+
+        if tag(proc_evento, "tpEvento").as_deref() != Some("110140") {
+            return Err("O evento informado não é um EPEC (tpEvento 110140).".to_string());
+        }
+        let c_stat = tag(ret_evento, "cStat");
+        if !matches!(c_stat.as_deref(), Some("135") | Some("136")) {
+            return Err(format!(
+                "EPEC não registrado (cStat {}).",
+                c_stat.unwrap_or_default()
+            ));
+        }
+        let n_prot = tag(ret_evento, "nProt")
+            .ok_or("Protocolo (nProt) do EPEC ausente no retEvento.")?;
+
+        Ok(InfProt {
+            tp_amb: tag(ret_evento, "tpAmb"),
+            ver_aplic: tag(ret_evento, "verAplic"),
+            ch_nfe: tag(ret_evento, "chNFe").or_else(|| tag(proc_evento, "chNFe")),
+            dh_recbto: tag(ret_evento, "dhRegEvento"),
+            n_prot: Some(format!("EPEC {}", n_prot)),
+            dig_val: None,
+            c_stat,
+            x_motivo: tag(ret_evento, "xMotivo"),
+        })
+    }
+
     fn convert_xml_to_struct(xml: &str) -> Result<NFeProc, String> {
-        let extractor = XmlExtractor::new();
-        extractor
-            .nfe_proc_from_string(xml)
+        nfe_proc_ou_offline(xml)
             .map_err(|e| format!("Erro ao converter XML para struct NFeProc: {}", e))
     }
 }
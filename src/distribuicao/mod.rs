@@ -4,6 +4,12 @@ use serde::{Deserialize, Deserializer, Serialize};
 use std::io::Read;
 use std::path::PathBuf;
 
+use crate::interno::artefatos::ArtefatoSink;
+use crate::interno::cert::Certificado;
+use crate::interno::signer::Signer;
+use crate::interno::ws::Webservices;
+use std::sync::Arc;
+
 mod service;
 #[cfg(test)]
 mod test;
@@ -122,7 +128,7 @@ where
         .filter(|docs| !docs.is_empty()))
 }
 
-fn decode_doczip_content(encoded: &str) -> Result<String, String> {
+pub(crate) fn decode_doczip_content(encoded: &str) -> Result<String, String> {
     let compressed = base64::engine::general_purpose::STANDARD
         .decode(encoded.trim())
         .map_err(|e| e.to_string())?;
@@ -177,6 +183,8 @@ pub struct Consulta {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
     /// CNPJ do destinatário (14 dígitos).
     pub cnpj: String,
     /// Código IBGE da UF (ex.: `35` para SP).
@@ -185,6 +193,10 @@ pub struct Consulta {
     pub ambiente: u8,
     /// Diretório do `flag.json`; `None` desativa o mecanismo de flag.
     pub flag_dir: Option<FlagDir>,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino dos XML de envio e retorno (e dos documentos de cada NSU); `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Builder para consulta de documentos a partir de um NSU específico.
@@ -194,6 +206,8 @@ pub struct ConsultaNSU {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
     /// CNPJ do destinatário (14 dígitos).
     pub cnpj: String,
     /// Código IBGE da UF.
@@ -204,6 +218,10 @@ pub struct ConsultaNSU {
     pub nsu: String,
     /// Diretório do `flag.json`; `None` desativa o mecanismo de flag.
     pub flag_dir: Option<FlagDir>,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino dos XML de envio e retorno (e dos documentos de cada NSU); `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Builder para consulta de um documento pela chave de acesso.
@@ -213,6 +231,8 @@ pub struct ConsultaChaveAcesso {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
     /// CNPJ do destinatário (14 dígitos).
     pub cnpj: String,
     /// Código IBGE da UF.
@@ -223,6 +243,10 @@ pub struct ConsultaChaveAcesso {
     pub chave_acesso: String,
     /// Diretório do `flag.json`; `None` desativa o mecanismo de flag.
     pub flag_dir: Option<FlagDir>,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino dos XML de envio e retorno (e dos documentos de cada NSU); `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Manifestação **Ciência da Operação** (evento `210210`).
@@ -231,12 +255,20 @@ pub struct CienciaOperacao {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
+    /// Assinante externo do evento; a conexão com o AN ainda usa o certificado.
+    pub signer: Option<Arc<dyn Signer>>,
     /// CNPJ do destinatário.
     pub cnpj: String,
     /// Ambiente: `1` = Produção · `2` = Homologação.
     pub ambiente: u8,
     /// Chave de acesso da NF-e (44 dígitos).
     pub chave_acesso: String,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas; `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Manifestação **Confirmação da Operação** (evento `210200`).
@@ -245,12 +277,20 @@ pub struct ConfirmacaoOperacao {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
+    /// Assinante externo do evento; a conexão com o AN ainda usa o certificado.
+    pub signer: Option<Arc<dyn Signer>>,
     /// CNPJ do destinatário.
     pub cnpj: String,
     /// Ambiente: `1` = Produção · `2` = Homologação.
     pub ambiente: u8,
     /// Chave de acesso da NF-e (44 dígitos).
     pub chave_acesso: String,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas; `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Manifestação **Desconhecimento da Operação** (evento `210220`).
@@ -259,12 +299,20 @@ pub struct DesconhecimentoOperacao {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
+    /// Assinante externo do evento; a conexão com o AN ainda usa o certificado.
+    pub signer: Option<Arc<dyn Signer>>,
     /// CNPJ do destinatário.
     pub cnpj: String,
     /// Ambiente: `1` = Produção · `2` = Homologação.
     pub ambiente: u8,
     /// Chave de acesso da NF-e (44 dígitos).
     pub chave_acesso: String,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas; `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Manifestação **Operação Não Realizada** (evento `210240`).
@@ -273,6 +321,10 @@ pub struct OperacaoNaoRealizada {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
+    /// Assinante externo do evento; a conexão com o AN ainda usa o certificado.
+    pub signer: Option<Arc<dyn Signer>>,
     /// CNPJ do destinatário.
     pub cnpj: String,
     /// Ambiente: `1` = Produção · `2` = Homologação.
@@ -281,6 +333,10 @@ pub struct OperacaoNaoRealizada {
     pub chave_acesso: String,
     /// Justificativa (mínimo 15 caracteres).
     pub justificativa: String,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas; `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Resposta das operações de manifestação do destinatário.
@@ -299,10 +355,13 @@ impl Consulta {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
             cnpj: String::new(),
             uf: 0,
             ambiente: 0,
             flag_dir: None,
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -318,6 +377,12 @@ impl Consulta {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
     /// CNPJ do destinatário (14 dígitos, sem formatação).
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
@@ -348,12 +413,24 @@ impl Consulta {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) dos XML de envio e retorno e dos documentos descompactados de cada NSU.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     /// Envia a consulta ao Ambiente Nacional e retorna [`DistribuicaoResposta`].
     pub async fn send(self) -> Result<DistribuicaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -375,11 +452,14 @@ impl ConsultaNSU {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
             cnpj: String::new(),
             uf: 0,
             ambiente: 0,
             nsu: String::new(),
             flag_dir: None,
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -393,6 +473,12 @@ impl ConsultaNSU {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -423,11 +509,23 @@ impl ConsultaNSU {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) dos XML de envio e retorno e dos documentos descompactados de cada NSU.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<DistribuicaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -452,11 +550,14 @@ impl ConsultaChaveAcesso {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
             cnpj: String::new(),
             uf: 0,
             ambiente: 0,
             chave_acesso: String::new(),
             flag_dir: None,
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -470,6 +571,12 @@ impl ConsultaChaveAcesso {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -500,11 +607,23 @@ impl ConsultaChaveAcesso {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) dos XML de envio e retorno e dos documentos descompactados de cada NSU.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<DistribuicaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -529,9 +648,13 @@ impl CienciaOperacao {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
+            signer: None,
             cnpj: String::new(),
             ambiente: 0,
             chave_acesso: String::new(),
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -545,6 +668,18 @@ impl CienciaOperacao {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
+    /// Assinante externo ([`Signer`]) do evento de manifestação.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -560,11 +695,23 @@ impl CienciaOperacao {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<ManifestacaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -586,9 +733,13 @@ impl ConfirmacaoOperacao {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
+            signer: None,
             cnpj: String::new(),
             ambiente: 0,
             chave_acesso: String::new(),
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -602,6 +753,18 @@ impl ConfirmacaoOperacao {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
+    /// Assinante externo ([`Signer`]) do evento de manifestação.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -617,11 +780,23 @@ impl ConfirmacaoOperacao {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<ManifestacaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -643,9 +818,13 @@ impl DesconhecimentoOperacao {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
+            signer: None,
             cnpj: String::new(),
             ambiente: 0,
             chave_acesso: String::new(),
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -659,6 +838,18 @@ impl DesconhecimentoOperacao {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
+    /// Assinante externo ([`Signer`]) do evento de manifestação.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -674,11 +865,23 @@ impl DesconhecimentoOperacao {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<ManifestacaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
@@ -700,10 +903,14 @@ impl OperacaoNaoRealizada {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
+            signer: None,
             cnpj: String::new(),
             ambiente: 0,
             chave_acesso: String::new(),
             justificativa: String::new(),
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -717,6 +924,18 @@ impl OperacaoNaoRealizada {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
+    /// Assinante externo ([`Signer`]) do evento de manifestação.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     pub fn cnpj(mut self, cnpj: &str) -> Self {
         self.cnpj = cnpj.to_string();
         self
@@ -737,11 +956,23 @@ impl OperacaoNaoRealizada {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) do evento assinado, do envelope SOAP, do retorno do Ambiente Nacional e das falhas.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     pub async fn send(self) -> Result<ManifestacaoResposta, String> {
-        if self.cert_path.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_path.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_path".to_string());
         }
-        if self.cert_pass.trim().is_empty() {
+        if self.certificado.is_none() && self.cert_pass.trim().is_empty() {
             return Err("Campo obrigatório não informado: cert_pass".to_string());
         }
         if self.cnpj.trim().is_empty() {
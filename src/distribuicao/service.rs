@@ -1,13 +1,13 @@
-use crate::interno::cert::Cert;
+use crate::interno::artefatos::Artefatos;
+use crate::interno::cert::Certificado;
+use crate::interno::signer::Credencial;
+use crate::interno::ws::nfe_distribuicao_dfe;
 use crate::manifestacao::{
-    nfe_ciencia_operacao, nfe_confirmacao_operacao, nfe_desconhecimento_operacao,
-    nfe_operacao_nao_realizada,
-};
-use crate::tipos::manifestacao::{
-    Manifestacao as NfeManifestacao, OperacaoNaoRealizada as NfeOperacaoNaoRealizada,
+    manifestar, TP_EVENTO_CIENCIA_OPERACAO, TP_EVENTO_CONFIRMACAO_OPERACAO,
+    TP_EVENTO_DESCONHECIMENTO_OPERACAO, TP_EVENTO_OPERACAO_NAO_REALIZADA,
 };
+use crate::tipos::manifestacao::Manifestacao as NfeManifestacao;
 use base64::Engine;
-use chrono::Local;
 use flate2::read::GzDecoder;
 use quick_xml::de::from_str;
 use regex::Regex;
@@ -37,14 +37,14 @@ impl Consulta {
             self.validar_flag_pendente()?;
         }
 
-        let cert = Cert::from_pfx(&self.cert_path, &self.cert_pass).map_err(|e| {
+        let cert = carregar_certificado(&self.certificado, &self.cert_path, &self.cert_pass).map_err(|e| {
             let mensagem = format!("Erro ao criar a identidade do certificado PKCS12: {}", e);
             self.registrar_erro_flag(&mensagem);
             self.log_and_return_error(mensagem)
         })?;
 
         let client = reqwest::Client::builder()
-            .identity(cert.identity)
+            .identity(cert.identity())
             .build()
             .map_err(|e| {
                 let mensagem = format!("Erro ao construir o cliente HTTP: {}", e);
@@ -52,11 +52,11 @@ impl Consulta {
                 self.log_and_return_error(mensagem)
             })?;
 
-        let endpoint = if self.ambiente == 1 {
-            "https://www1.nfe.fazenda.gov.br/NFeDistribuicaoDFe/NFeDistribuicaoDFe.asmx"
-        } else {
-            "https://hom1.nfe.fazenda.gov.br/NFeDistribuicaoDFe/NFeDistribuicaoDFe.asmx"
-        };
+        let endpoint = nfe_distribuicao_dfe(if self.ambiente == 1 { 1 } else { 2 }, self.webservices.as_ref()).map_err(|e| {
+            let mensagem = e.to_string();
+            self.registrar_erro_flag(&mensagem);
+            self.log_and_return_error(mensagem)
+        })?;
 
         let soap_envelope = format!(
             "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
@@ -76,7 +76,7 @@ impl Consulta {
             self.uf, xml
         );
 
-        self.salvar_log("requests", "xml", &soap_envelope)?;
+        self.sink_artefatos().gravar("envelope", &soap_envelope);
 
         let response = client
             .post(endpoint)
@@ -99,7 +99,7 @@ impl Consulta {
             self.log_and_return_error(mensagem)
         })?;
 
-        self.salvar_log("responses", "xml", &body)?;
+        self.sink_artefatos().gravar("retorno", &body);
         self.salvar_nsu_descompactado(&body);
 
         let re = Regex::new(r"(?s)<retDistDFeInt\b[^>]*>.*?</retDistDFeInt>").map_err(|e| {
@@ -217,58 +217,12 @@ impl Consulta {
         }
     }
 
-    fn salvar_log(&self, categoria: &str, extensao: &str, conteudo: &str) -> Result<(), String> {
-        let now = Local::now();
-        let ano = now.format("%Y").to_string();
-        let mes = now.format("%m").to_string();
-        let arquivo = format!(
-            "{}-{}{}.{}",
-            now.format("%d"),
-            now.format("%H%M%S"),
-            now.format("%3f"),
-            extensao
-        );
-        let cnpj_dir: String = self.cnpj.chars().filter(|c| c.is_ascii_digit()).collect();
-        let ambiente_dir = self.ambiente_dir();
-
-        let mut dir = PathBuf::from("./distribuicao-logs");
-        dir.push(categoria);
-        dir.push(cnpj_dir);
-        dir.push(ambiente_dir);
-        dir.push(ano);
-        dir.push(mes);
-
-        fs::create_dir_all(&dir).map_err(|e| {
-            format!(
-                "Erro ao criar diretórios de saída ({}) para logs da distribuição: {}",
-                categoria, e
-            )
-        })?;
-
-        let mut file_path = dir;
-        file_path.push(arquivo);
-
-        fs::write(&file_path, conteudo).map_err(|e| {
-            format!(
-                "Erro ao salvar arquivo de log da distribuição em {}: {}",
-                file_path.display(),
-                e
-            )
-        })?;
-
-        Ok(())
-    }
-
-    fn ambiente_dir(&self) -> &'static str {
-        match self.ambiente {
-            1 => "producao",
-            2 => "homologacao",
-            _ => "desconhecido",
-        }
+    fn sink_artefatos(&self) -> Artefatos {
+        Artefatos::new(self.artefatos.clone(), "distribuicao", &self.cnpj)
     }
 
     fn log_and_return_error(&self, mensagem: String) -> String {
-        let _ = self.salvar_log("errors", "txt", &mensagem);
+        self.sink_artefatos().gravar("erro", &mensagem);
 
         mensagem
     }
@@ -279,11 +233,7 @@ impl Consulta {
             Err(_) => return,
         };
 
-        let dir = PathBuf::from(format!("./distribuicao-logs/nsu/{}", self.cnpj));
-        if fs::create_dir_all(&dir).is_err() {
-            return;
-        }
-
+        let artefatos = self.sink_artefatos();
         for cap in re.captures_iter(body) {
             let nsu = &cap[1];
             let b64 = cap[2].trim();
@@ -299,13 +249,7 @@ impl Consulta {
                 continue;
             }
 
-            let ext = if xml_str.trim_start().starts_with('<') {
-                "xml"
-            } else {
-                "txt"
-            };
-            let file_path = dir.join(format!("{}-{}.{}", nsu, self.cnpj, ext));
-            let _ = fs::write(&file_path, &xml_str);
+            artefatos.gravar(&format!("nsu-{}", nsu), &xml_str);
         }
     }
 }
@@ -324,10 +268,13 @@ impl ConsultaNSU {
         let consulta = Consulta {
             cert_path: self.cert_path.clone(),
             cert_pass: self.cert_pass.clone(),
+            certificado: self.certificado.clone(),
             cnpj: self.cnpj.clone(),
             uf: self.uf,
             ambiente: self.ambiente,
             flag_dir: self.flag_dir.clone(),
+            webservices: self.webservices.clone(),
+            artefatos: self.artefatos.clone(),
         };
 
         consulta.enviar_soap12_xml(xml).await
@@ -353,10 +300,13 @@ impl ConsultaChaveAcesso {
         let consulta = Consulta {
             cert_path: self.cert_path.clone(),
             cert_pass: self.cert_pass.clone(),
+            certificado: self.certificado.clone(),
             cnpj: self.cnpj.clone(),
             uf: self.uf,
             ambiente: self.ambiente,
             flag_dir: self.flag_dir.clone(),
+            webservices: self.webservices.clone(),
+            artefatos: self.artefatos.clone(),
         };
 
         consulta.enviar_soap12_xml(xml).await
@@ -374,7 +324,15 @@ impl CienciaOperacao {
             chave: self.chave_acesso.clone(),
         };
 
-        nfe_ciencia_operacao(payload)
+        let credencial = Credencial::resolver(
+            self.signer.clone(),
+            Some(carregar_certificado(&self.certificado, &self.cert_path, &self.cert_pass)
+                .map_err(|e| e.to_string())?),
+            None,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+        manifestar(&payload, &credencial, TP_EVENTO_CIENCIA_OPERACAO, None, self.webservices.as_ref(), self.artefatos.clone())
             .await
             .map_err(|e| e.to_string())
     }
@@ -393,7 +351,15 @@ impl ConfirmacaoOperacao {
             chave: self.chave_acesso.clone(),
         };
 
-        nfe_confirmacao_operacao(payload)
+        let credencial = Credencial::resolver(
+            self.signer.clone(),
+            Some(carregar_certificado(&self.certificado, &self.cert_path, &self.cert_pass)
+                .map_err(|e| e.to_string())?),
+            None,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+        manifestar(&payload, &credencial, TP_EVENTO_CONFIRMACAO_OPERACAO, None, self.webservices.as_ref(), self.artefatos.clone())
             .await
             .map_err(|e| e.to_string())
     }
@@ -412,7 +378,15 @@ impl DesconhecimentoOperacao {
             chave: self.chave_acesso.clone(),
         };
 
-        nfe_desconhecimento_operacao(payload)
+        let credencial = Credencial::resolver(
+            self.signer.clone(),
+            Some(carregar_certificado(&self.certificado, &self.cert_path, &self.cert_pass)
+                .map_err(|e| e.to_string())?),
+            None,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+        manifestar(&payload, &credencial, TP_EVENTO_DESCONHECIMENTO_OPERACAO, None, self.webservices.as_ref(), self.artefatos.clone())
             .await
             .map_err(|e| e.to_string())
     }
@@ -422,18 +396,44 @@ impl OperacaoNaoRealizada {
     pub(crate) async fn executar_operacao_nao_realizada(
         &self,
     ) -> Result<ManifestacaoResposta, String> {
-        let payload = NfeOperacaoNaoRealizada {
+        let payload = NfeManifestacao {
             cert_path: self.cert_path.clone(),
             cert_pass: self.cert_pass.clone(),
             cnpj: self.cnpj.clone(),
             tp_amb: self.ambiente,
             mod_: Some(55),
             chave: self.chave_acesso.clone(),
-            justificativa: self.justificativa.clone(),
         };
 
-        nfe_operacao_nao_realizada(payload)
-            .await
-            .map_err(|e| e.to_string())
+        let credencial = Credencial::resolver(
+            self.signer.clone(),
+            Some(carregar_certificado(&self.certificado, &self.cert_path, &self.cert_pass)
+                .map_err(|e| e.to_string())?),
+            None,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+        manifestar(
+            &payload,
+            &credencial,
+            TP_EVENTO_OPERACAO_NAO_REALIZADA,
+            Some(self.justificativa.as_str()),
+            self.webservices.as_ref(),
+            self.artefatos.clone(),
+        )
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Usa o certificado já carregado ou lê o PFX de `cert_path`/`cert_pass`.
+fn carregar_certificado(
+    certificado: &Option<Certificado>,
+    cert_path: &str,
+    cert_pass: &str,
+) -> crate::error::Result<Certificado> {
+    match certificado {
+        Some(certificado) => Ok(certificado.clone()),
+        None => Certificado::from_pfx(cert_path, cert_pass),
     }
 }
@@ -0,0 +1,150 @@
+use crate::error::{DfeError, Result};
+use crate::interno::ws::{svc_tp_emis, uf_sigla};
+use crate::tipos::Ide;
+use chrono::DateTime;
+
+/// `tpEmis` 6 (SVC-AN) ou 7 (SVC-RS): a autorização vai para a SEFAZ Virtual de Contingência.
+pub(crate) fn usa_svc(tp_emis: u8) -> bool {
+    matches!(tp_emis, 6 | 7)
+}
+
+/// Confere os campos de contingência do `<ide>` antes de montar o XML.
+///
+/// Fora da emissão normal (`tp_emis != 1`) a SEFAZ exige `dhCont` e `xJust`.
+/// Na SVC, o `tp_emis` tem de ser o da SVC que atende a UF (NT 2013/007) e só a
+/// NF-e (modelo 55) é aceita. A NFC-e só tem a contingência offline (`tp_emis` 9)
+/// e a FS-DA (`tp_emis` 5) é exclusiva da NF-e.
+///
+/// Só são aceitos `tp_emis` 1, 4, 5, 6, 7 e 9: o formulário de segurança (2) e o
+/// SCAN (3) foram extintos e o 8 não existe no leiaute 4.00.
+pub(crate) fn validar(ide: &Ide) -> Result<()> {
+    match ide.tp_emis {
+        1 => return Ok(()),
+        4..=7 | 9 => {}
+        outro => {
+            return Err(DfeError::Validacao(format!(
+                "tp_emis inválido: {} (use 1, 4 EPEC, 5 FS-DA, 6 SVC-AN, 7 SVC-RS ou 9 offline NFC-e)", outro
+            )))
+        }
+    }
+
+    let dh_cont = ide.dh_cont.as_deref().ok_or_else(|| {
+        DfeError::Validacao(format!("dh_cont é obrigatório em contingência (tp_emis {})", ide.tp_emis))
+    })?;
+    let dh_cont = DateTime::parse_from_rfc3339(dh_cont).map_err(|_| {
+        DfeError::Validacao(format!("dh_cont inválido (esperado AAAA-MM-DDThh:mm:ssTZD): {}", dh_cont))
+    })?;
+    if dh_cont > chrono::Utc::now() {
+        return Err(DfeError::Validacao("dh_cont não pode ser posterior à data atual".to_string()));
+    }
+
+    let x_just = ide.x_just.as_deref().map(str::trim).unwrap_or_default();
+    let len = x_just.chars().count();
+    if !(15..=256).contains(&len) {
+        return Err(DfeError::Validacao(format!(
+            "x_just deve ter de 15 a 256 caracteres em contingência (informado: {})", len
+        )));
+    }
+
+    if ide.tp_emis == 9 && ide.mod_ != 65 {
+        return Err(DfeError::Validacao("contingência offline (tp_emis 9) é exclusiva da NFC-e (modelo 65)".to_string()));
+    }
+    if ide.tp_emis == 5 && ide.mod_ != 55 {
+        return Err(DfeError::Validacao("contingência FS-DA (tp_emis 5) é exclusiva da NF-e (modelo 55)".to_string()));
+    }
+    if ide.mod_ == 65 && ide.tp_emis != 9 {
+        return Err(DfeError::Validacao(format!(
+            "NFC-e aceita tp_emis 1 (normal) ou 9 (offline); informado: {}", ide.tp_emis
+        )));
+    }
+
+    if usa_svc(ide.tp_emis) {
+        if ide.mod_ != 55 {
+            return Err(DfeError::Validacao("contingência SVC é exclusiva da NF-e (modelo 55)".to_string()));
+        }
+        let uf = uf_sigla(ide.c_uf)?;
+        let esperado = svc_tp_emis(uf)?;
+        if ide.tp_emis != esperado {
+            return Err(DfeError::Validacao(format!(
+                "{} usa a {} em contingência: informe tp_emis {}",
+                uf, if esperado == 6 { "SVC-AN" } else { "SVC-RS" }, esperado
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ide_svc(c_uf: u16, tp_emis: u8) -> Ide {
+        Ide {
+            c_uf,
+            tp_emis,
+            dh_cont: Some("2025-01-10T08:00:00-03:00".to_string()),
+            x_just: Some("SEFAZ AUTORIZADORA FORA DO AR".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_emissao_normal_dispensa_campos() {
+        assert!(validar(&Ide::default()).is_ok());
+    }
+
+    #[test]
+    fn test_svc_da_uf() {
+        assert!(validar(&ide_svc(35, 6)).is_ok());
+        assert!(validar(&ide_svc(41, 7)).is_ok());
+        // PR é atendido pela SVC-RS, não pela SVC-AN.
+        assert!(validar(&ide_svc(41, 6)).is_err());
+    }
+
+    #[test]
+    fn test_svc_exige_dh_cont_e_x_just() {
+        let mut ide = ide_svc(35, 6);
+        ide.x_just = Some("CURTA".to_string());
+        assert!(validar(&ide).is_err());
+
+        let mut ide = ide_svc(35, 6);
+        ide.dh_cont = None;
+        assert!(validar(&ide).is_err());
+
+        let mut ide = ide_svc(35, 6);
+        ide.dh_cont = Some("10/01/2025 08:00".to_string());
+        assert!(validar(&ide).is_err());
+    }
+
+    #[test]
+    fn test_svc_recusa_nfce() {
+        let mut ide = ide_svc(35, 6);
+        ide.mod_ = 65;
+        assert!(validar(&ide).is_err());
+    }
+
+    #[test]
+    fn test_offline_e_fsda_por_modelo() {
+        let mut ide = ide_svc(35, 9);
+        ide.mod_ = 65;
+        assert!(validar(&ide).is_ok());
+
+        ide.mod_ = 55;
+        assert!(validar(&ide).is_err());
+
+        // FS-DA: o inverso
+        ide.tp_emis = 5;
+        assert!(validar(&ide).is_ok());
+        ide.mod_ = 65;
+        assert!(validar(&ide).is_err());
+    }
+
+    #[test]
+    fn test_tp_emis_extintos() {
+        for tp_emis in [0, 2, 3, 8, 10] {
+            let mut ide = ide_svc(35, tp_emis);
+            ide.mod_ = 55;
+            assert!(validar(&ide).is_err(), "tp_emis {}", tp_emis);
+        }
+    }
+}
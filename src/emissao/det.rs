@@ -1,7 +1,7 @@
 use super::det_process::entity::*;
-use crate::tipos::{Cofins, Det, IbsCbs, Icms, Ipi, Pis};
-use crate::error::Result;
-use rust_decimal::prelude::FromPrimitive;
+use crate::tipos::{Arma, Cofins, Comb, Det, DetExport, Di, Fcp, IbsCbs, Icms, IcmsUfDest, Ipi, Med, Pis, Rastro, VeicProd};
+use crate::error::{DfeError, Result};
+use crate::interno::decimal::{arredondar, fmt_casas, fmt_casas_var};
 use rust_decimal::Decimal;
 
 pub fn det_process(
@@ -15,16 +15,16 @@ pub fn det_process(
     let mut first_item = 0;
 
     // desconto por rateio nos itens *********************************************************
-    let desconto_rateado = if desconto_rateio.is_some() {
-        desconto_rateio.unwrap()
+    let desconto_rateado = if let Some(desconto) = desconto_rateio {
+        arredondar(desconto, 2)
     } else {
         Decimal::new(0, 2)
     };
 
     let mut total_produtos = Decimal::new(0, 2);
     for d in &prod {
-        // soma o valor dos produtos para calcular o percentual do desconto
-        total_produtos += Decimal::from_f64(d.v_prod).unwrap_or(Decimal::new(0, 2));
+        // soma o valor dos produtos, com as 2 casas do XML, para calcular o percentual do desconto
+        total_produtos += arredondar(d.v_prod, 2);
     }
 
     // vamos definir a porcentagem do desconto, temos o valor total dos produtos e o valor do desconto
@@ -39,8 +39,8 @@ pub fn det_process(
     // armazenar cada desconto em um vetor para aplicar depois
     let mut descontos_itens: Vec<Decimal> = Vec::new();
     for d in &prod {
-        let v_prod_decimal = Decimal::from_f64(d.v_prod).unwrap_or(Decimal::new(0, 2));
-        let desconto_item = (v_prod_decimal * desconto_percentual).round_dp(2);
+        let v_prod_decimal = arredondar(d.v_prod, 2);
+        let desconto_item = arredondar(v_prod_decimal * desconto_percentual, 2);
         descontos_itens.push(desconto_item);
     }
 
@@ -49,7 +49,7 @@ pub fn det_process(
     if soma_descontos != desconto_rateado {
         let diferenca = desconto_rateado - soma_descontos;
         if let Some(last) = descontos_itens.last_mut() {
-            *last += diferenca;
+            *last = arredondar(*last + diferenca, 2);
         }
     }
     /* println!(
@@ -66,6 +66,14 @@ pub fn det_process(
         }
         first_item += 1;
 
+        // veicProd, med, arma e comb formam um choice no leiaute
+        let grupos = [d.veic_prod.is_some(), d.med.is_some(), !d.arma.is_empty(), d.comb.is_some()];
+        if grupos.iter().filter(|g| **g).count() > 1 {
+            return Err(DfeError::Validacao(format!(
+                "Item {}: informe apenas um dos grupos veic_prod, med, arma ou comb", first_item
+            )));
+        }
+
         // pegar o valor do desconto do item
         let v_desc_value: Option<Decimal> = if *desconto_item > Decimal::new(0, 2) {
             Some(*desconto_item)
@@ -82,24 +90,37 @@ pub fn det_process(
                 cfop: d.cfop.to_string(),
                 cest: d.cest.clone(),
                 u_com: d.u_com.to_string(),
-                q_com: format!("{:.3}", d.q_com),
-                v_un_com: format!("{:.2}", d.v_un_com),
-                v_prod: format!("{:.2}", d.v_prod),
+                q_com: fmt_casas_var(d.q_com, 3, 4),
+                v_un_com: fmt_casas_var(d.v_un_com, 2, 10),
+                // valores do item com as 2 casas do XML: o total soma exatamente o que vai nos itens
+                v_prod: arredondar(d.v_prod, 2),
                 c_ean_trib: d.c_ean_trib.to_string(),
                 u_trib: d.u_trib.to_string(),
-                q_trib: format!("{:.3}", d.q_trib),
-                v_un_trib: format!("{:.2}", d.v_un_trib),
+                q_trib: fmt_casas_var(d.q_trib, 3, 4),
+                v_un_trib: fmt_casas_var(d.v_un_trib, 2, 10),
+                v_frete: d.v_frete.filter(|v| !v.is_zero()),
+                v_seg: d.v_seg.filter(|v| !v.is_zero()),
                 v_desc: v_desc_value,
+                v_outro: d.v_outro.filter(|v| !v.is_zero()),
                 ind_tot: d.ind_tot.to_string(),
+                di: d.di.iter().map(di_process).collect(),
+                det_export: d.det_export.iter().map(det_export_process).collect(),
                 x_ped: d.x_ped.clone(),
                 n_item_ped: d.n_item_ped.clone(),
+                rastro: d.rastro.iter().map(rastro_process).collect(),
+                veic_prod: d.veic_prod.as_ref().map(veic_prod_process),
+                med: d.med.as_ref().map(med_process),
+                arma: d.arma.iter().map(arma_process).collect(),
+                comb: d.comb.as_ref().map(comb_process),
             },
             imposto: ImpostoProcess {
-                v_tot_trib: format!("{:.2}", d.v_tot_trib),
-                icms: select_icms_process(&d.icms),
+                v_tot_trib: arredondar(d.v_tot_trib, 2),
+                icms: select_icms_process(&d.icms, d.fcp, d.fcp_st),
                 ipi: d.ipi.as_ref().map(select_ipi_process),
+                ii: d.ii.map(|ii| IIProcess { v_bc: ii.v_bc, v_desp_adu: ii.v_desp_adu, v_ii: ii.v_ii, v_iof: ii.v_iof }),
                 pis: select_pis_process(&d.pis),
                 cofins: select_cofins_process(&d.cofins),
+                icms_uf_dest: d.icms_uf_dest.as_ref().map(icms_uf_dest_process),
                 ibs_cbs: ibs_cbs_process(d.ibs_cbs.as_ref()),
             },
             inf_ad_prod: d.inf_ad_prod.clone(),
@@ -108,27 +129,150 @@ pub fn det_process(
     Ok(det_process_values)
 }
 
+fn di_process(di: &Di) -> DIProcess {
+    DIProcess {
+        n_di: di.n_di.clone(),
+        d_di: di.d_di.clone(),
+        x_loc_desemb: di.x_loc_desemb.clone(),
+        uf_desemb: di.uf_desemb.clone(),
+        d_desemb: di.d_desemb.clone(),
+        tp_via_transp: di.tp_via_transp.to_string(),
+        v_afrmm: di.v_afrmm,
+        tp_intermedio: di.tp_intermedio.to_string(),
+        cnpj: di.cnpj.clone(),
+        cpf: if di.cnpj.is_some() { None } else { di.cpf.clone() },
+        uf_terceiro: di.uf_terceiro.clone(),
+        c_exportador: di.c_exportador.clone(),
+        adi: di.adi.iter().map(|a| AdiProcess {
+            n_adicao: a.n_adicao.map(|n| n.to_string()),
+            n_seq_adic: a.n_seq_adic.to_string(),
+            c_fabricante: a.c_fabricante.clone(),
+            v_desc_di: a.v_desc_di.filter(|v| !v.is_zero()),
+            n_draw: a.n_draw.clone(),
+        }).collect(),
+    }
+}
+
+fn det_export_process(e: &DetExport) -> DetExportProcess {
+    DetExportProcess {
+        n_draw: e.n_draw.clone(),
+        export_ind: e.export_ind.as_ref().map(|x| ExportIndProcess {
+            n_re: x.n_re.clone(),
+            ch_nfe: x.ch_nfe.clone(),
+            q_export: fmt_casas_var(x.q_export, 0, 4),
+        }),
+    }
+}
+
+fn rastro_process(r: &Rastro) -> RastroProcess {
+    RastroProcess {
+        n_lote: r.n_lote.clone(),
+        q_lote: fmt_casas(r.q_lote, 3),
+        d_fab: r.d_fab.clone(),
+        d_val: r.d_val.clone(),
+        c_agreg: r.c_agreg.clone(),
+    }
+}
+
+fn veic_prod_process(v: &VeicProd) -> VeicProdProcess {
+    VeicProdProcess {
+        tp_op: v.tp_op.to_string(),
+        chassi: v.chassi.clone(),
+        c_cor: v.c_cor.clone(),
+        x_cor: v.x_cor.clone(),
+        pot: v.pot.clone(),
+        cilin: v.cilin.clone(),
+        peso_l: v.peso_l.clone(),
+        peso_b: v.peso_b.clone(),
+        n_serie: v.n_serie.clone(),
+        tp_comb: v.tp_comb.clone(),
+        n_motor: v.n_motor.clone(),
+        cmt: v.cmt.clone(),
+        dist: v.dist.clone(),
+        ano_mod: format!("{:04}", v.ano_mod),
+        ano_fab: format!("{:04}", v.ano_fab),
+        tp_pint: v.tp_pint.clone(),
+        tp_veic: v.tp_veic.to_string(),
+        esp_veic: v.esp_veic.to_string(),
+        vin: v.vin.clone(),
+        cond_veic: v.cond_veic.to_string(),
+        c_mod: v.c_mod.clone(),
+        c_cor_denatran: v.c_cor_denatran.clone(),
+        lota: v.lota.to_string(),
+        tp_rest: v.tp_rest.to_string(),
+    }
+}
+
+fn med_process(m: &Med) -> MedProcess {
+    MedProcess {
+        c_prod_anvisa: m.c_prod_anvisa.clone(),
+        x_motivo_isencao: m.x_motivo_isencao.clone(),
+        v_pmc: m.v_pmc,
+    }
+}
+
+fn arma_process(a: &Arma) -> ArmaProcess {
+    ArmaProcess {
+        tp_arma: a.tp_arma.to_string(),
+        n_serie: a.n_serie.clone(),
+        n_cano: a.n_cano.clone(),
+        descr: a.descr.clone(),
+    }
+}
+
+fn comb_process(c: &Comb) -> CombProcess {
+    CombProcess {
+        c_prod_anp: c.c_prod_anp.clone(),
+        desc_anp: c.desc_anp.clone(),
+        p_glp: c.p_glp,
+        p_gn_n: c.p_gn_n,
+        p_gn_i: c.p_gn_i,
+        v_part: c.v_part,
+        codif: c.codif.clone(),
+        q_temp: c.q_temp,
+        uf_cons: c.uf_cons.clone(),
+        cide: c.cide.as_ref().map(|v| CideProcess {
+            q_bc_prod: v.q_bc_prod,
+            v_aliq_prod: v.v_aliq_prod,
+            v_cide: v.v_cide,
+        }),
+        encerrante: c.encerrante.as_ref().map(|v| EncerranteProcess {
+            n_bico: v.n_bico.to_string(),
+            n_bomba: v.n_bomba.map(|n| n.to_string()),
+            n_tanque: v.n_tanque.to_string(),
+            v_enc_ini: fmt_casas(v.v_enc_ini, 3),
+            v_enc_fin: fmt_casas(v.v_enc_fin, 3),
+        }),
+        p_bio: c.p_bio,
+        orig_comb: c.orig_comb.iter().map(|o| OrigCombProcess {
+            ind_import: o.ind_import.to_string(),
+            c_uf_orig: o.c_uf_orig.to_string(),
+            p_orig: o.p_orig,
+        }).collect(),
+    }
+}
+
 fn ibs_cbs_process(ibs_cbs: Option<&IbsCbs>) -> Option<IBSCBSProcess> {
     let ibs = ibs_cbs?;
     Some(IBSCBSProcess {
         cst: ibs.cst.clone(),
         c_class_trib: ibs.class_trib.clone(),
         g_ibscbs: GIBSCBS {
-            v_bc: format!("{:.2}", ibs.v_bc),
+            v_bc: fmt_casas(ibs.v_bc, 2),
             g_ibs_uf: GIBSUF {
-                p_ibs_uf: format!("{:.4}", ibs.p_ibs_uf),
-                v_ibs_uf: format!("{:.2}", ibs.v_ibs_uf),
+                p_ibs_uf: fmt_casas(ibs.p_ibs_uf, 4),
+                v_ibs_uf: fmt_casas(ibs.v_ibs_uf, 2),
                 ..Default::default()
             },
             g_ibs_mun: GIBSMun {
-                p_ibs_mun: format!("{:.4}", ibs.p_ibs_mun),
-                v_ibs_mun: format!("{:.2}", ibs.v_ibs_mun),
+                p_ibs_mun: fmt_casas(ibs.p_ibs_mun, 4),
+                v_ibs_mun: fmt_casas(ibs.v_ibs_mun, 2),
                 ..Default::default()
             },
-            v_ibs: format!("{:.2}", ibs.v_ibs_uf + ibs.v_ibs_mun),
+            v_ibs: fmt_casas(ibs.v_ibs_uf + ibs.v_ibs_mun, 2),
             g_cbs: GCBS {
-                p_cbs: format!("{:.4}", ibs.p_cbs),
-                v_cbs: format!("{:.2}", ibs.v_cbs),
+                p_cbs: fmt_casas(ibs.p_cbs, 4),
+                v_cbs: fmt_casas(ibs.v_cbs, 2),
                 ..Default::default()
             },
             ..Default::default()
@@ -136,26 +280,45 @@ fn ibs_cbs_process(ibs_cbs: Option<&IbsCbs>) -> Option<IBSCBSProcess> {
     })
 }
 
-fn select_icms_process(icms: &Icms) -> ICMSProcess {
+fn icms_uf_dest_process(v: &IcmsUfDest) -> ICMSUFDest {
+    ICMSUFDest {
+        v_bc_uf_dest: v.v_bc_uf_dest,
+        v_bc_fcp_uf_dest: v.v_bc_fcp_uf_dest,
+        p_fcp_uf_dest: v.p_fcp_uf_dest,
+        p_icms_uf_dest: v.p_icms_uf_dest,
+        p_icms_inter: v.p_icms_inter,
+        p_icms_inter_part: v.p_icms_inter_part,
+        v_fcp_uf_dest: v.v_fcp_uf_dest,
+        v_icms_uf_dest: v.v_icms_uf_dest,
+        v_icms_uf_remet: v.v_icms_uf_remet,
+    }
+}
+
+// FCP próprio (`fcp`) e retido por ST (`fcp_st`) vão dentro do grupo do CST que os comporta
+fn select_icms_process(icms: &Icms, fcp: Option<Fcp>, fcp_st: Option<Fcp>) -> ICMSProcess {
+    let (v_bc_fcp, p_fcp, v_fcp) = (fcp.map(|f| f.v_bc), fcp.map(|f| f.p_fcp), fcp.map(|f| f.v_fcp));
+    let (v_bc_fcpst, p_fcpst, v_fcpst) = (fcp_st.map(|f| f.v_bc), fcp_st.map(|f| f.p_fcp), fcp_st.map(|f| f.v_fcp));
     match icms {
         Icms::Icms00 { orig, mod_bc, v_bc, p_icms, v_icms } =>
             ICMSProcess::ICMS00(ICMS00 {
                 orig: *orig, cst: "00".to_string(), mod_bc: *mod_bc,
-                v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms,
+                v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms, p_fcp, v_fcp,
             }),
 
         Icms::Icms10 { orig, mod_bc, v_bc, p_icms, v_icms, mod_bcst, p_mvast, p_red_bcst, v_bcst, p_icmsst, v_icmsst } =>
             ICMSProcess::ICMS10(ICMS10 {
                 orig: *orig, cst: "10".to_string(), mod_bc: *mod_bc,
-                v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms,
+                v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms, v_bc_fcp, p_fcp, v_fcp,
                 mod_bcst: *mod_bcst, p_mvast: *p_mvast, p_red_bcst: *p_red_bcst,
                 v_bcst: *v_bcst, p_icmsst: *p_icmsst, v_icmsst: *v_icmsst,
+                v_bc_fcpst, p_fcpst, v_fcpst,
             }),
 
         Icms::Icms20 { orig, mod_bc, p_red_bc, v_bc, p_icms, v_icms, v_icms_deson, mot_des_icms } =>
             ICMSProcess::ICMS20(ICMS20 {
                 orig: *orig, cst: "20".to_string(), mod_bc: *mod_bc,
                 p_red_bc: *p_red_bc, v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms,
+                v_bc_fcp, p_fcp, v_fcp,
                 v_icms_deson: *v_icms_deson, mot_des_icms: *mot_des_icms,
             }),
 
@@ -164,6 +327,7 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
                 orig: *orig, cst: "30".to_string(), mod_bcst: *mod_bcst,
                 p_mvast: *p_mvast, p_red_bcst: *p_red_bcst,
                 v_bcst: *v_bcst, p_icmsst: *p_icmsst, v_icmsst: *v_icmsst,
+                v_bc_fcpst, p_fcpst, v_fcpst,
                 v_icms_deson: *v_icms_deson, mot_des_icms: *mot_des_icms,
             }),
 
@@ -177,22 +341,22 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
                 orig: *orig, cst: "51".to_string(), mod_bc: *mod_bc,
                 p_red_bc: *p_red_bc, v_bc: *v_bc, p_icms: *p_icms,
                 v_icms_op: *v_icms_op, p_dif: *p_dif, v_icms_dif: *v_icms_dif,
-                v_icms: *v_icms,
+                v_icms: *v_icms, v_bc_fcp, p_fcp, v_fcp,
             }),
 
         Icms::Icms60 { orig, v_bcst_ret, p_st, v_icms_substituto, v_icmsst_ret } => {
             // xs:sequence minOccurs="0": todos presentes ou nenhum (NT 2011/004)
-            let bcst   = v_bcst_ret.filter(|&v| v > 0.0);
-            let pst    = p_st.filter(|&v| v > 0.0);
-            let subst  = v_icms_substituto.filter(|&v| v > 0.0);
-            let icmsst = v_icmsst_ret.filter(|&v| v > 0.0);
+            let bcst   = v_bcst_ret.filter(|v| !v.is_zero());
+            let pst    = p_st.filter(|v| !v.is_zero());
+            let subst  = v_icms_substituto.filter(|v| !v.is_zero());
+            let icmsst = v_icmsst_ret.filter(|v| !v.is_zero());
             let (v_bcst_ret, p_st, v_icms_substituto, v_icmsst_ret) =
                 if bcst.is_some() || pst.is_some() || icmsst.is_some() {
                     (
-                        Some(format!("{:.2}", bcst.unwrap_or(0.0))),
-                        Some(format!("{:.2}", pst.unwrap_or(0.0))),
-                        subst.map(|v| format!("{:.2}", v)),
-                        Some(format!("{:.2}", icmsst.unwrap_or(0.0))),
+                        Some(fmt_casas(bcst.unwrap_or_default(), 2)),
+                        Some(fmt_casas(pst.unwrap_or_default(), 2)),
+                        subst.map(|v| fmt_casas(v, 2)),
+                        Some(fmt_casas(icmsst.unwrap_or_default(), 2)),
                     )
                 } else {
                     (None, None, None, None)
@@ -207,8 +371,10 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
             ICMSProcess::ICMS70(ICMS70 {
                 orig: *orig, cst: "70".to_string(), mod_bc: *mod_bc,
                 p_red_bc: *p_red_bc, v_bc: *v_bc, p_icms: *p_icms, v_icms: *v_icms,
+                v_bc_fcp, p_fcp, v_fcp,
                 mod_bcst: *mod_bcst, p_mvast: *p_mvast, p_red_bcst: *p_red_bcst,
                 v_bcst: *v_bcst, p_icmsst: *p_icmsst, v_icmsst: *v_icmsst,
+                v_bc_fcpst, p_fcpst, v_fcpst,
                 v_icms_deson: *v_icms_deson, mot_des_icms: *mot_des_icms,
             }),
 
@@ -216,17 +382,18 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
             ICMSProcess::ICMS90(ICMS90 {
                 orig: *orig, cst: "90".to_string(),
                 mod_bc: *mod_bc, p_red_bc: *p_red_bc, v_bc: *v_bc,
-                p_icms: *p_icms, v_icms: *v_icms,
+                p_icms: *p_icms, v_icms: *v_icms, v_bc_fcp, p_fcp, v_fcp,
                 mod_bcst: *mod_bcst, p_mvast: *p_mvast, p_red_bcst: *p_red_bcst,
                 v_bcst: *v_bcst, p_icmsst: *p_icmsst, v_icmsst: *v_icmsst,
+                v_bc_fcpst, p_fcpst, v_fcpst,
                 v_icms_deson: *v_icms_deson, mot_des_icms: *mot_des_icms,
             }),
 
         Icms::Sn101 { orig, p_cred_sn, v_cred_icmssn } =>
             ICMSProcess::ICMSSN101(ICMSSN101 {
                 orig: *orig, csosn: "101".to_string(),
-                p_cred_sn: format!("{:.2}", p_cred_sn),
-                v_cred_icmssn: format!("{:.2}", v_cred_icmssn),
+                p_cred_sn: fmt_casas(*p_cred_sn, 2),
+                v_cred_icmssn: fmt_casas(*v_cred_icmssn, 2),
             }),
 
         Icms::Sn102 { orig, csosn } =>
@@ -235,8 +402,8 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
         Icms::Sn500 { orig, v_bcst_ret, v_icmsst_ret } =>
             ICMSProcess::ICMSSN500(ICMSSN500 {
                 orig: *orig, csosn: "500".to_string(),
-                vbcst_ret: v_bcst_ret.map(|v| format!("{:.2}", v)),
-                vicmsst_ret: v_icmsst_ret.map(|v| format!("{:.2}", v)),
+                vbcst_ret: v_bcst_ret.map(|v| fmt_casas(v, 2)),
+                vicmsst_ret: v_icmsst_ret.map(|v| fmt_casas(v, 2)),
             }),
 
         Icms::Sn900 { orig, mod_bc, v_bc, p_red_bc, p_icms, v_icms, p_cred_sn, v_cred_icmssn,
@@ -244,19 +411,21 @@ fn select_icms_process(icms: &Icms) -> ICMSProcess {
             ICMSProcess::ICMSSN900(ICMSSN900 {
                 orig: *orig, csosn: "900".to_string(),
                 modbc: mod_bc.map(|v| v.to_string()),
-                vbc: v_bc.map(|v| format!("{:.2}", v)),
-                pred_bc: p_red_bc.map(|v| format!("{:.4}", v)),
-                picms: p_icms.map(|v| format!("{:.4}", v)),
-                vicms: v_icms.map(|v| format!("{:.2}", v)),
-                pcred_sn: p_cred_sn.map(|v| format!("{:.4}", v)),
-                vcred_icmssn: v_cred_icmssn.map(|v| format!("{:.2}", v)),
+                vbc: v_bc.map(|v| fmt_casas(v, 2)),
+                pred_bc: p_red_bc.map(|v| fmt_casas(v, 4)),
+                picms: p_icms.map(|v| fmt_casas(v, 4)),
+                vicms: v_icms.map(|v| fmt_casas(v, 2)),
+                pcred_sn: p_cred_sn.map(|v| fmt_casas(v, 4)),
+                vcred_icmssn: v_cred_icmssn.map(|v| fmt_casas(v, 2)),
                 modbcst: mod_bcst.map(|v| v.to_string()),
-                pmvast: p_mvast.map(|v| format!("{:.4}", v)),
-                pred_bcst: p_red_bcst.map(|v| format!("{:.4}", v)),
-                vbcst: v_bcst.map(|v| format!("{:.2}", v)),
-                picmsst: p_icmsst.map(|v| format!("{:.4}", v)),
-                vicmsst: v_icmsst.map(|v| format!("{:.2}", v)),
-                ..Default::default()
+                pmvast: p_mvast.map(|v| fmt_casas(v, 4)),
+                pred_bcst: p_red_bcst.map(|v| fmt_casas(v, 4)),
+                vbcst: v_bcst.map(|v| fmt_casas(v, 2)),
+                picmsst: p_icmsst.map(|v| fmt_casas(v, 4)),
+                vicmsst: v_icmsst.map(|v| fmt_casas(v, 2)),
+                vbc_fcpst: v_bc_fcpst.map(|v| fmt_casas(v, 2)),
+                p_fcpst: p_fcpst.map(|v| fmt_casas(v, 4)),
+                v_fcpst: v_fcpst.map(|v| fmt_casas(v, 2)),
             }),
     }
 }
@@ -283,19 +452,19 @@ fn select_pis_process(pis: &Pis) -> PISProcess {
         Pis::Qtde { cst, q_bc_prod, v_aliq_prod, v_pis } => PISProcess {
             pis_qtde: Some(PISQtde {
                 cst: cst.clone(),
-                qbc_prod: format!("{:.3}", q_bc_prod),
-                valiq_prod: format!("{:.4}", v_aliq_prod),
-                vpis: format!("{:.2}", v_pis),
+                qbc_prod: fmt_casas_var(*q_bc_prod, 3, 4),
+                valiq_prod: fmt_casas(*v_aliq_prod, 4),
+                vpis: fmt_casas(*v_pis, 2),
             }),
             ..Default::default()
         },
         Pis::St { v_bc, p_pis, q_bc_prod, v_aliq_prod, v_pis } => PISProcess {
             pis_st: Some(PISST {
-                v_bc: v_bc.map(|v| format!("{:.2}", v)),
-                p_pis: p_pis.map(|v| format!("{:.4}", v)),
-                qbc_prod: q_bc_prod.map(|v| format!("{:.3}", v)),
-                valiq_prod: v_aliq_prod.map(|v| format!("{:.4}", v)),
-                vpis: Some(format!("{:.2}", v_pis)),
+                v_bc: v_bc.map(|v| fmt_casas(v, 2)),
+                p_pis: p_pis.map(|v| fmt_casas(v, 4)),
+                qbc_prod: q_bc_prod.map(|v| fmt_casas_var(v, 3, 4)),
+                valiq_prod: v_aliq_prod.map(|v| fmt_casas(v, 4)),
+                vpis: Some(fmt_casas(*v_pis, 2)),
             }),
             ..Default::default()
         },
@@ -309,7 +478,7 @@ fn select_cofins_process(cofins: &Cofins) -> COFINSProcess {
             ..Default::default()
         },
         Cofins::Outr { cst } => COFINSProcess {
-            cofins_outr: Some(COFINSOutr { cst: cst.clone(), v_bc: Some(0.0), p_cofins: Some(0.0), v_cofins: Some(0.0) }),
+            cofins_outr: Some(COFINSOutr { cst: cst.clone(), v_bc: Some(Decimal::ZERO), p_cofins: Some(Decimal::ZERO), v_cofins: Some(Decimal::ZERO) }),
             ..Default::default()
         },
         Cofins::Nt { cst } => COFINSProcess {
@@ -319,19 +488,19 @@ fn select_cofins_process(cofins: &Cofins) -> COFINSProcess {
         Cofins::Qtde { cst, q_bc_prod, v_aliq_prod, v_cofins } => COFINSProcess {
             cofins_qtde: Some(COFINSQtde {
                 cst: cst.clone(),
-                qbc_prod: format!("{:.3}", q_bc_prod),
-                valiq_prod: format!("{:.4}", v_aliq_prod),
-                vcofins: format!("{:.2}", v_cofins),
+                qbc_prod: fmt_casas_var(*q_bc_prod, 3, 4),
+                valiq_prod: fmt_casas(*v_aliq_prod, 4),
+                vcofins: fmt_casas(*v_cofins, 2),
             }),
             ..Default::default()
         },
         Cofins::St { v_bc, p_cofins, q_bc_prod, v_aliq_prod, v_cofins } => COFINSProcess {
             cofins_st: Some(COFINSST {
-                v_bc: v_bc.map(|v| format!("{:.2}", v)),
-                p_cofins: p_cofins.map(|v| format!("{:.4}", v)),
-                qbc_prod: q_bc_prod.map(|v| format!("{:.3}", v)),
-                valiq_prod: v_aliq_prod.map(|v| format!("{:.4}", v)),
-                vcofins: Some(format!("{:.2}", v_cofins)),
+                v_bc: v_bc.map(|v| fmt_casas(v, 2)),
+                p_cofins: p_cofins.map(|v| fmt_casas(v, 4)),
+                qbc_prod: q_bc_prod.map(|v| fmt_casas_var(v, 3, 4)),
+                valiq_prod: v_aliq_prod.map(|v| fmt_casas(v, 4)),
+                vcofins: Some(fmt_casas(*v_cofins, 2)),
             }),
             ..Default::default()
         },
@@ -348,9 +517,9 @@ fn select_ipi_process(ipi: &Ipi) -> IpiProcess {
             cst: ipi.cst.clone(),
             v_bc: ipi.v_bc,
             p_ipi: ipi.p_ipi,
-            q_bc_prod: ipi.q_bc_prod.map(|v| format!("{:.3}", v)),
-            v_aliq_prod: ipi.v_aliq_prod.map(|v| format!("{:.4}", v)),
-            v_ipi: ipi.v_ipi.unwrap_or(0.0),
+            q_bc_prod: ipi.q_bc_prod.map(|v| fmt_casas_var(v, 3, 4)),
+            v_aliq_prod: ipi.v_aliq_prod.map(|v| fmt_casas(v, 4)),
+            v_ipi: ipi.v_ipi.unwrap_or_default(),
         };
         let xml = to_string(&trib).unwrap_or_default();
         format!("<IPITrib>{}</IPITrib>", &xml[xml.find('>').map(|i| i + 1).unwrap_or(0)..xml.rfind('<').unwrap_or(xml.len())])
@@ -364,5 +533,217 @@ fn select_ipi_process(ipi: &Ipi) -> IpiProcess {
         q_selo: ipi.q_selo,
         tributado,
         inner,
+        v_ipi: if tributado { Some(ipi.v_ipi.unwrap_or_default()) } else { None },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emissao::total::total_process;
+    use crate::interno::validation::is_xml_valid;
+    use crate::emissao::exporta::exporta_process;
+    use crate::tipos::{Adi, CalculoIcms, Cide, Difal, Encerrante, ExportInd, Exporta, Ii, OrigComb, Total};
+    use rust_decimal_macros::dec;
+
+    /// `<NFe>` do `sample55.xml`, com o primeiro `<tag>` trocado por `novo`.
+    fn sample_com(nfe: Option<String>, tag: &str, novo: &str) -> String {
+        let sample = include_str!("../../sample55.xml");
+        let nfe = nfe.unwrap_or_else(|| sample[sample.find("<NFe ").unwrap()..sample.find("</NFe>").unwrap() + 6].to_string());
+        let ini = nfe.find(&format!("<{}>", tag)).unwrap();
+        let fim = nfe.find(&format!("</{}>", tag)).unwrap() + tag.len() + 3;
+        format!("{}{}{}", &nfe[..ini], novo, &nfe[fim..])
+    }
+
+    /// Troca o `<prod>` do primeiro item do `sample55.xml` e valida a NF-e contra o XSD.
+    fn validar_prod_no_sample(prod: &str) -> crate::error::Result<String> {
+        is_xml_valid(&sample_com(None, "prod", prod))
+    }
+
+    fn produto(grupos: Det) -> String {
+        let det = Det {
+            c_prod: "1".into(), x_prod: "PRODUTO".into(), ncm: "27101259".into(), u_com: "L".into(),
+            q_com: dec!(40), v_un_com: dec!(6.29), v_prod: dec!(251.60), u_trib: "L".into(),
+            q_trib: dec!(40), v_un_trib: dec!(6.29),
+            ..grupos
+        };
+        let dets = det_process(vec![det], 55, 1, None, None).unwrap();
+        quick_xml::se::to_string(&dets[0].prod).unwrap()
+    }
+
+    #[test]
+    fn test_fcp_e_difal_calculados_vao_ao_xml_e_aos_totais() {
+        let mut det = Det { c_prod: "1".into(), v_prod: dec!(1000.00), ..Default::default() };
+        CalculoIcms::cst(0, "10")
+            .p_icms(dec!(12)).p_fcp(dec!(2))
+            .mva(dec!(40)).p_icms_st(dec!(18)).p_fcp_st(dec!(2))
+            .difal(Difal::new(dec!(18), dec!(12)).p_fcp_uf_dest(dec!(2)))
+            .aplicar(&mut det)
+            .unwrap();
+
+        let dets = det_process(vec![det], 55, 1, None, None).unwrap();
+        let xml = dets[0].imposto.to_xml();
+        assert!(xml.contains("<vICMS>120.00</vICMS><vBCFCP>1000.00</vBCFCP><pFCP>2.0000</pFCP><vFCP>20.00</vFCP><modBCST>4</modBCST>"));
+        assert!(xml.contains("<vICMSST>132.00</vICMSST><vBCFCPST>1400.00</vBCFCPST><pFCPST>2.0000</pFCPST><vFCPST>8.00</vFCPST></ICMS10>"));
+        assert!(xml.contains("</COFINS><ICMSUFDest><vBCUFDest>1000.00</vBCUFDest><vBCFCPUFDest>1000.00</vBCFCPUFDest>"));
+        assert!(xml.contains("<pICMSInter>12.00</pICMSInter><pICMSInterPart>100.0000</pICMSInterPart><vFCPUFDest>20.00</vFCPUFDest><vICMSUFDest>60.00</vICMSUFDest><vICMSUFRemet>0.00</vICMSUFRemet></ICMSUFDest>"));
+
+        let tot = total_process(Total::default(), dets, 1, None).unwrap().icms_tot;
+        assert_eq!((tot.v_fcp.as_str(), tot.v_fcpst.as_str(), tot.v_st.as_str()), ("20.00", "8.00", "132.00"));
+        assert_eq!((tot.v_fcpuf_dest.as_str(), tot.v_icms_uf_dest.as_str()), ("20.00", "60.00"));
+        assert_eq!(tot.v_nf, "1140.00");
+    }
+
+    #[test]
+    fn test_grupos_do_produto_na_posicao_do_xsd() {
+        let rastro = vec![
+            Rastro { n_lote: "L01".into(), q_lote: dec!(10), d_fab: "2026-01-10".into(), d_val: "2027-01-10".into(), c_agreg: None },
+            Rastro { n_lote: "L02".into(), q_lote: dec!(30), d_fab: "2026-02-10".into(), d_val: "2027-02-10".into(), c_agreg: Some("AG1".into()) },
+        ];
+        let comb = Comb {
+            c_prod_anp: "320102001".into(),
+            desc_anp: "GASOLINA C COMUM".into(),
+            codif: Some("123456789".into()),
+            q_temp: Some(dec!(40)),
+            uf_cons: "SP".into(),
+            cide: Some(Cide { q_bc_prod: dec!(40), v_aliq_prod: dec!(0.1), v_cide: dec!(4) }),
+            encerrante: Some(Encerrante { n_bico: 1, n_bomba: Some(2), n_tanque: 3, v_enc_ini: dec!(1500), v_enc_fin: dec!(1540) }),
+            p_bio: Some(dec!(14)),
+            orig_comb: vec![
+                OrigComb { ind_import: 0, c_uf_orig: 35, p_orig: dec!(80) },
+                OrigComb { ind_import: 1, c_uf_orig: 41, p_orig: dec!(20) },
+            ],
+            ..Default::default()
+        };
+        let xml = produto(Det { rastro: rastro.clone(), comb: Some(comb), ..Default::default() });
+        assert!(xml.contains("<indTot>1</indTot><rastro><nLote>L01</nLote><qLote>10.000</qLote><dFab>2026-01-10</dFab><dVal>2027-01-10</dVal></rastro><rastro>"));
+        assert!(xml.contains("<cAgreg>AG1</cAgreg></rastro><comb><cProdANP>320102001</cProdANP><descANP>GASOLINA C COMUM</descANP><CODIF>123456789</CODIF><qTemp>40.0000</qTemp><UFCons>SP</UFCons>"));
+        assert!(xml.contains("<CIDE><qBCProd>40.0000</qBCProd><vAliqProd>0.1000</vAliqProd><vCIDE>4.00</vCIDE></CIDE>"));
+        assert!(xml.contains("<encerrante><nBico>1</nBico><nBomba>2</nBomba><nTanque>3</nTanque><vEncIni>1500.000</vEncIni><vEncFin>1540.000</vEncFin></encerrante><pBio>14.0000</pBio>"));
+        assert!(xml.contains("<origComb><indImport>1</indImport><cUFOrig>41</cUFOrig><pOrig>20.0000</pOrig></origComb></comb></prod>"));
+        validar_prod_no_sample(&xml).unwrap();
+
+        let med = Med { c_prod_anvisa: "1234567890123".into(), x_motivo_isencao: None, v_pmc: dec!(39.9) };
+        let xml = produto(Det { rastro, med: Some(med), ..Default::default() });
+        assert!(xml.ends_with("</rastro><med><cProdANVISA>1234567890123</cProdANVISA><vPMC>39.90</vPMC></med></prod>"));
+        validar_prod_no_sample(&xml).unwrap();
+
+        let veic = VeicProd {
+            tp_op: 1, chassi: "9BWZZZ377VT004251".into(), c_cor: "0001".into(), x_cor: "PRETO".into(),
+            pot: "80".into(), cilin: "1000".into(), peso_l: "900".into(), peso_b: "1200".into(),
+            n_serie: "1".into(), tp_comb: "16".into(), n_motor: "MOTOR123".into(), cmt: "1.5".into(),
+            dist: "2.4".into(), ano_mod: 2027, ano_fab: 2026, tp_pint: "S".into(), tp_veic: 6,
+            esp_veic: 1, vin: "N".into(), cond_veic: 1, c_mod: "123456".into(),
+            c_cor_denatran: "02".into(), lota: 5, tp_rest: 0,
+        };
+        let xml = produto(Det { veic_prod: Some(veic), ..Default::default() });
+        assert!(xml.contains("<indTot>1</indTot><veicProd><tpOp>1</tpOp><chassi>9BWZZZ377VT004251</chassi>"));
+        validar_prod_no_sample(&xml).unwrap();
+
+        let arma = Arma { tp_arma: 0, n_serie: "S1".into(), n_cano: "C1".into(), descr: "PISTOLA .380".into() };
+        let xml = produto(Det { arma: vec![arma.clone(), arma], ..Default::default() });
+        assert!(xml.contains("</descr></arma><arma><tpArma>0</tpArma>"));
+        validar_prod_no_sample(&xml).unwrap();
+    }
+
+    #[test]
+    fn test_grupos_do_produto_sao_exclusivos() {
+        let det = Det {
+            med: Some(Med { c_prod_anvisa: "ISENTO".into(), ..Default::default() }),
+            comb: Some(Comb::default()),
+            ..Default::default()
+        };
+        assert!(matches!(det_process(vec![det], 55, 1, None, None), Err(DfeError::Validacao(_))));
+    }
+
+    #[test]
+    fn test_importacao_e_exportacao_no_xml_e_no_total() {
+        let di = Di {
+            n_di: "2612345678".into(), d_di: "2026-03-01".into(), x_loc_desemb: "PORTO DE SANTOS".into(),
+            uf_desemb: "SP".into(), d_desemb: "2026-03-03".into(), tp_via_transp: 1, v_afrmm: Some(dec!(25)),
+            tp_intermedio: 2, cnpj: Some("00000000000191".into()), uf_terceiro: Some("SP".into()),
+            c_exportador: "EXP01".into(),
+            adi: vec![Adi { n_adicao: Some(1), n_seq_adic: 1, c_fabricante: "FAB01".into(), v_desc_di: Some(dec!(0)), n_draw: None }],
+            ..Default::default()
+        };
+        let det = Det {
+            c_prod: "1".into(), x_prod: "PRODUTO".into(), ncm: "84713012".into(), u_com: "UN".into(),
+            q_com: dec!(2), v_un_com: dec!(50), v_prod: dec!(100), q_trib: dec!(2), v_un_trib: dec!(50),
+            di: vec![di],
+            det_export: vec![DetExport {
+                n_draw: None,
+                export_ind: Some(ExportInd { n_re: "123456789012".into(), ch_nfe: "3".repeat(44), q_export: dec!(2) }),
+            }],
+            ii: Some(Ii { v_bc: dec!(100), v_desp_adu: dec!(5), v_ii: dec!(14.5), v_iof: dec!(0) }),
+            ..Default::default()
+        };
+        let dets = det_process(vec![det], 55, 1, None, None).unwrap();
+        let prod = quick_xml::se::to_string(&dets[0].prod).unwrap();
+        assert!(prod.contains("<indTot>1</indTot><DI><nDI>2612345678</nDI>"));
+        assert!(prod.contains("<tpViaTransp>1</tpViaTransp><vAFRMM>25.00</vAFRMM><tpIntermedio>2</tpIntermedio><CNPJ>00000000000191</CNPJ><UFTerceiro>SP</UFTerceiro>"));
+        assert!(prod.contains("<adi><nAdicao>1</nAdicao><nSeqAdic>1</nSeqAdic><cFabricante>FAB01</cFabricante></adi></DI><detExport><exportInd><nRE>123456789012</nRE>"));
+        assert!(prod.ends_with("<qExport>2</qExport></exportInd></detExport></prod>"));
+        let imposto = dets[0].imposto.to_xml();
+        assert!(imposto.contains("<II><vBC>100.00</vBC><vDespAdu>5.00</vDespAdu><vII>14.50</vII><vIOF>0.00</vIOF></II><PIS>"));
+
+        let exporta = Exporta { uf_saida_pais: "SP".into(), x_loc_exporta: "PORTO DE SANTOS".into(), x_loc_despacho: None };
+        let exporta = quick_xml::se::to_string(&exporta_process(Some(&exporta)).unwrap()).unwrap();
+        assert_eq!(exporta, "<exporta><UFSaidaPais>SP</UFSaidaPais><xLocExporta>PORTO DE SANTOS</xLocExporta></exporta>");
+        let nfe = sample_com(Some(sample_com(None, "prod", &prod)), "imposto", &imposto);
+        is_xml_valid(&nfe.replace("</infAdic>", &format!("</infAdic>{}", exporta))).unwrap();
+
+        // Total.v_ii é ignorado: o II do item entra uma única vez
+        let total = total_process(Total { v_ii: dec!(14.5), ..Default::default() }, dets, 1, None).unwrap();
+        assert_eq!((total.icms_tot.v_ii.as_str(), total.icms_tot.v_nf.as_str()), ("14.50", "114.50"));
+    }
+
+    #[test]
+    fn test_total_soma_os_valores_dos_itens_com_2_casas() {
+        let det = Det {
+            c_prod: "1".into(), x_prod: "PRODUTO".into(), ncm: "84713012".into(), u_com: "UN".into(),
+            q_com: dec!(1), v_un_com: dec!(10.005), v_prod: dec!(10.005), q_trib: dec!(1), v_un_trib: dec!(10.005),
+            v_tot_trib: dec!(1.005),
+            ..Default::default()
+        };
+        let dets = det_process(vec![det.clone(), det.clone(), det], 55, 1, Some(dec!(1.001)), None).unwrap();
+        let valor = |xml: &str, tag: &str| -> Decimal {
+            let inicio = xml.find(&format!("<{}>", tag)).unwrap() + tag.len() + 2;
+            xml[inicio..xml[inicio..].find('<').unwrap() + inicio].parse().unwrap()
+        };
+        let (mut v_prod, mut v_desc, mut v_tot_trib) = (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO);
+        for d in &dets {
+            let prod = quick_xml::se::to_string(&d.prod).unwrap();
+            v_prod += valor(&prod, "vProd");
+            v_desc += valor(&prod, "vDesc");
+            v_tot_trib += valor(&d.imposto.to_xml(), "vTotTrib");
+        }
+        assert_eq!((v_prod, v_desc, v_tot_trib), (dec!(30.03), dec!(1.00), dec!(3.03)));
+
+        let total = total_process(Total::default(), dets, 1, None).unwrap().icms_tot;
+        assert_eq!(
+            (total.v_prod.as_str(), total.v_desc.as_str(), total.v_tot_trib.as_str(), total.v_nf.as_str()),
+            ("30.03", "1.00", "3.03", "29.03")
+        );
+    }
+
+    #[test]
+    fn test_ipi_e_ipi_devolvido_somam_no_total() {
+        let det = Det {
+            c_prod: "1".into(), x_prod: "PRODUTO".into(), ncm: "84713012".into(), u_com: "UN".into(),
+            q_com: dec!(1), v_un_com: dec!(100), v_prod: dec!(100), q_trib: dec!(1), v_un_trib: dec!(100),
+            ..Default::default()
+        };
+        let tributado = Det { ipi: Some(Ipi::tributado("999", dec!(100), dec!(5.005), dec!(5.005))), ..det.clone() };
+        let nao_tributado = Det { ipi: Some(Ipi::nao_tributado("999", "53")), ..det };
+        let dets = det_process(vec![tributado, nao_tributado], 55, 1, None, None).unwrap();
+        assert_eq!(dets[0].prod.v_prod, dec!(100));
+        assert_eq!((dets[0].imposto.ipi.as_ref().unwrap().v_ipi, dets[1].imposto.ipi.as_ref().unwrap().v_ipi), (Some(dec!(5.005)), None));
+        let total = Total { v_ipi_devol: dec!(12.5), ..Default::default() };
+        let total = total_process(total, dets, 1, None).unwrap().icms_tot;
+        // Regra 610: vNF = vProd - vDesc + ... + vIPI + vIPIDevol
+        assert_eq!(
+            (total.v_ipi.as_str(), total.v_ipi_devol.as_str(), total.v_nf.as_str()),
+            ("5.01", "12.50", "217.51")
+        );
     }
 }
@@ -1,5 +1,6 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize, Serializer};
+use crate::interno::decimal::fmt_casas;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename = "prod")]
@@ -22,8 +23,8 @@ pub struct ProdProcess {
     pub q_com: String,
     #[serde(rename = "vUnCom")]
     pub v_un_com: String,
-    #[serde(rename = "vProd")]
-    pub v_prod: String,
+    #[serde(rename = "vProd", serialize_with = "serialize_2_decimals")]
+    pub v_prod: Decimal,
     #[serde(rename = "cEANTrib")]
     pub c_ean_trib: String,
     #[serde(rename = "uTrib")]
@@ -32,14 +33,279 @@ pub struct ProdProcess {
     pub q_trib: String,
     #[serde(rename = "vUnTrib")]
     pub v_un_trib: String,
-    #[serde(rename = "vDesc", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "vFrete", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_frete: Option<Decimal>,
+    #[serde(rename = "vSeg", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_seg: Option<Decimal>,
+    #[serde(rename = "vDesc", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
     pub v_desc: Option<Decimal>,
+    #[serde(rename = "vOutro", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_outro: Option<Decimal>,
     #[serde(rename = "indTot")]
     pub ind_tot: String,
+    #[serde(rename = "DI", skip_serializing_if = "Vec::is_empty")]
+    pub di: Vec<DIProcess>,
+    #[serde(rename = "detExport", skip_serializing_if = "Vec::is_empty")]
+    pub det_export: Vec<DetExportProcess>,
     #[serde(rename = "xPed", skip_serializing_if = "Option::is_none")]
     pub x_ped: Option<String>,
     #[serde(rename = "nItemPed", skip_serializing_if = "Option::is_none")]
     pub n_item_ped: Option<String>,
+    #[serde(rename = "rastro", skip_serializing_if = "Vec::is_empty")]
+    pub rastro: Vec<RastroProcess>,
+    // choice do leiaute: veicProd | med | arma | comb
+    #[serde(rename = "veicProd", skip_serializing_if = "Option::is_none")]
+    pub veic_prod: Option<VeicProdProcess>,
+    #[serde(rename = "med", skip_serializing_if = "Option::is_none")]
+    pub med: Option<MedProcess>,
+    #[serde(rename = "arma", skip_serializing_if = "Vec::is_empty")]
+    pub arma: Vec<ArmaProcess>,
+    #[serde(rename = "comb", skip_serializing_if = "Option::is_none")]
+    pub comb: Option<CombProcess>,
+}
+
+// ─── Comércio exterior ────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DIProcess {
+    #[serde(rename = "nDI")]
+    pub n_di: String,
+    #[serde(rename = "dDI")]
+    pub d_di: String,
+    #[serde(rename = "xLocDesemb")]
+    pub x_loc_desemb: String,
+    #[serde(rename = "UFDesemb")]
+    pub uf_desemb: String,
+    #[serde(rename = "dDesemb")]
+    pub d_desemb: String,
+    #[serde(rename = "tpViaTransp")]
+    pub tp_via_transp: String,
+    #[serde(rename = "vAFRMM", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_afrmm: Option<Decimal>,
+    #[serde(rename = "tpIntermedio")]
+    pub tp_intermedio: String,
+    #[serde(rename = "CNPJ", skip_serializing_if = "Option::is_none")]
+    pub cnpj: Option<String>,
+    #[serde(rename = "CPF", skip_serializing_if = "Option::is_none")]
+    pub cpf: Option<String>,
+    #[serde(rename = "UFTerceiro", skip_serializing_if = "Option::is_none")]
+    pub uf_terceiro: Option<String>,
+    #[serde(rename = "cExportador")]
+    pub c_exportador: String,
+    #[serde(rename = "adi")]
+    pub adi: Vec<AdiProcess>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdiProcess {
+    #[serde(rename = "nAdicao", skip_serializing_if = "Option::is_none")]
+    pub n_adicao: Option<String>,
+    #[serde(rename = "nSeqAdic")]
+    pub n_seq_adic: String,
+    #[serde(rename = "cFabricante")]
+    pub c_fabricante: String,
+    #[serde(rename = "vDescDI", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_desc_di: Option<Decimal>,
+    #[serde(rename = "nDraw", skip_serializing_if = "Option::is_none")]
+    pub n_draw: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DetExportProcess {
+    #[serde(rename = "nDraw", skip_serializing_if = "Option::is_none")]
+    pub n_draw: Option<String>,
+    #[serde(rename = "exportInd", skip_serializing_if = "Option::is_none")]
+    pub export_ind: Option<ExportIndProcess>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportIndProcess {
+    #[serde(rename = "nRE")]
+    pub n_re: String,
+    #[serde(rename = "chNFe")]
+    pub ch_nfe: String,
+    #[serde(rename = "qExport")]
+    pub q_export: String,
+}
+
+/// Grupo II — Imposto de Importação
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename = "II")]
+pub struct IIProcess {
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "vDespAdu", serialize_with = "serialize_2_decimals")]
+    pub v_desp_adu: Decimal,
+    #[serde(rename = "vII", serialize_with = "serialize_2_decimals")]
+    pub v_ii: Decimal,
+    #[serde(rename = "vIOF", serialize_with = "serialize_2_decimals")]
+    pub v_iof: Decimal,
+}
+
+// ─── Grupos específicos do produto ────────────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RastroProcess {
+    #[serde(rename = "nLote")]
+    pub n_lote: String,
+    #[serde(rename = "qLote")]
+    pub q_lote: String,
+    #[serde(rename = "dFab")]
+    pub d_fab: String,
+    #[serde(rename = "dVal")]
+    pub d_val: String,
+    #[serde(rename = "cAgreg", skip_serializing_if = "Option::is_none")]
+    pub c_agreg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VeicProdProcess {
+    #[serde(rename = "tpOp")]
+    pub tp_op: String,
+    #[serde(rename = "chassi")]
+    pub chassi: String,
+    #[serde(rename = "cCor")]
+    pub c_cor: String,
+    #[serde(rename = "xCor")]
+    pub x_cor: String,
+    #[serde(rename = "pot")]
+    pub pot: String,
+    #[serde(rename = "cilin")]
+    pub cilin: String,
+    #[serde(rename = "pesoL")]
+    pub peso_l: String,
+    #[serde(rename = "pesoB")]
+    pub peso_b: String,
+    #[serde(rename = "nSerie")]
+    pub n_serie: String,
+    #[serde(rename = "tpComb")]
+    pub tp_comb: String,
+    #[serde(rename = "nMotor")]
+    pub n_motor: String,
+    #[serde(rename = "CMT")]
+    pub cmt: String,
+    #[serde(rename = "dist")]
+    pub dist: String,
+    #[serde(rename = "anoMod")]
+    pub ano_mod: String,
+    #[serde(rename = "anoFab")]
+    pub ano_fab: String,
+    #[serde(rename = "tpPint")]
+    pub tp_pint: String,
+    #[serde(rename = "tpVeic")]
+    pub tp_veic: String,
+    #[serde(rename = "espVeic")]
+    pub esp_veic: String,
+    #[serde(rename = "VIN")]
+    pub vin: String,
+    #[serde(rename = "condVeic")]
+    pub cond_veic: String,
+    #[serde(rename = "cMod")]
+    pub c_mod: String,
+    #[serde(rename = "cCorDENATRAN")]
+    pub c_cor_denatran: String,
+    #[serde(rename = "lota")]
+    pub lota: String,
+    #[serde(rename = "tpRest")]
+    pub tp_rest: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MedProcess {
+    #[serde(rename = "cProdANVISA")]
+    pub c_prod_anvisa: String,
+    #[serde(rename = "xMotivoIsencao", skip_serializing_if = "Option::is_none")]
+    pub x_motivo_isencao: Option<String>,
+    #[serde(rename = "vPMC", serialize_with = "serialize_2_decimals")]
+    pub v_pmc: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArmaProcess {
+    #[serde(rename = "tpArma")]
+    pub tp_arma: String,
+    #[serde(rename = "nSerie")]
+    pub n_serie: String,
+    #[serde(rename = "nCano")]
+    pub n_cano: String,
+    #[serde(rename = "descr")]
+    pub descr: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CombProcess {
+    #[serde(rename = "cProdANP")]
+    pub c_prod_anp: String,
+    #[serde(rename = "descANP")]
+    pub desc_anp: String,
+    #[serde(rename = "pGLP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_glp: Option<Decimal>,
+    #[serde(rename = "pGNn", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_gn_n: Option<Decimal>,
+    #[serde(rename = "pGNi", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_gn_i: Option<Decimal>,
+    #[serde(rename = "vPart", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_part: Option<Decimal>,
+    #[serde(rename = "CODIF", skip_serializing_if = "Option::is_none")]
+    pub codif: Option<String>,
+    #[serde(rename = "qTemp", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub q_temp: Option<Decimal>,
+    #[serde(rename = "UFCons")]
+    pub uf_cons: String,
+    #[serde(rename = "CIDE", skip_serializing_if = "Option::is_none")]
+    pub cide: Option<CideProcess>,
+    #[serde(rename = "encerrante", skip_serializing_if = "Option::is_none")]
+    pub encerrante: Option<EncerranteProcess>,
+    #[serde(rename = "pBio", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_bio: Option<Decimal>,
+    #[serde(rename = "origComb", skip_serializing_if = "Vec::is_empty")]
+    pub orig_comb: Vec<OrigCombProcess>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CideProcess {
+    #[serde(rename = "qBCProd", serialize_with = "serialize_4_decimals")]
+    pub q_bc_prod: Decimal,
+    #[serde(rename = "vAliqProd", serialize_with = "serialize_4_decimals")]
+    pub v_aliq_prod: Decimal,
+    #[serde(rename = "vCIDE", serialize_with = "serialize_2_decimals")]
+    pub v_cide: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EncerranteProcess {
+    #[serde(rename = "nBico")]
+    pub n_bico: String,
+    #[serde(rename = "nBomba", skip_serializing_if = "Option::is_none")]
+    pub n_bomba: Option<String>,
+    #[serde(rename = "nTanque")]
+    pub n_tanque: String,
+    #[serde(rename = "vEncIni")]
+    pub v_enc_ini: String,
+    #[serde(rename = "vEncFin")]
+    pub v_enc_fin: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OrigCombProcess {
+    #[serde(rename = "indImport")]
+    pub ind_import: String,
+    #[serde(rename = "cUFOrig")]
+    pub c_uf_orig: String,
+    #[serde(rename = "pOrig", serialize_with = "serialize_4_decimals")]
+    pub p_orig: Decimal,
 }
 
 // ─── IPI ──────────────────────────────────────────────────────────────────────
@@ -50,17 +316,17 @@ pub struct IPITrib {
     #[serde(rename = "CST")]
     pub cst: String,
     #[serde(rename = "vBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_bc: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc: Option<Decimal>,
     #[serde(rename = "pIPI", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_ipi: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_ipi: Option<Decimal>,
     #[serde(rename = "qBCProd", skip_serializing_if = "Option::is_none")]
     pub q_bc_prod: Option<String>,
     #[serde(rename = "vAliqProd", skip_serializing_if = "Option::is_none")]
     pub v_aliq_prod: Option<String>,
-    #[serde(rename = "vIPI", serialize_with = "serialize_f64_2_decimals")]
-    pub v_ipi: f64,
+    #[serde(rename = "vIPI", serialize_with = "serialize_2_decimals")]
+    pub v_ipi: Decimal,
 }
 
 /// IPINT — CST de saída não tributada
@@ -78,6 +344,7 @@ pub struct IpiProcess {
     pub q_selo: Option<u32>,
     pub tributado: bool, // true = IPITrib, false = IPINT
     pub inner: String,   // XML pré-serializado da variante interna
+    pub v_ipi: Option<Decimal>, // vIPI do IPITrib, somado em ICMSTot
 }
 
 // ─── ImpostoProcess ───────────────────────────────────────────────────────────
@@ -85,20 +352,52 @@ pub struct IpiProcess {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename = "imposto")]
 pub struct ImpostoProcess {
-    #[serde(rename = "vTotTrib")]
-    pub v_tot_trib: String,
+    #[serde(rename = "vTotTrib", serialize_with = "serialize_2_decimals")]
+    pub v_tot_trib: Decimal,
     #[serde(rename = "ICMS")]
     pub icms: ICMSProcess,
     #[serde(skip)]
     pub ipi: Option<IpiProcess>,
+    #[serde(rename = "II", skip_serializing_if = "Option::is_none")]
+    pub ii: Option<IIProcess>,
     #[serde(rename = "PIS")]
     pub pis: PISProcess,
     #[serde(rename = "COFINS")]
     pub cofins: COFINSProcess,
+    #[serde(rename = "ICMSUFDest", skip_serializing_if = "Option::is_none")]
+    pub icms_uf_dest: Option<ICMSUFDest>,
     #[serde(rename = "IBSCBS", skip_serializing_if = "Option::is_none")]
     pub ibs_cbs: Option<IBSCBSProcess>,
 }
 
+/// Grupo ICMSUFDest — partilha do ICMS interestadual para consumidor final (DIFAL)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename = "ICMSUFDest")]
+pub struct ICMSUFDest {
+    #[serde(rename = "vBCUFDest", serialize_with = "serialize_2_decimals")]
+    pub v_bc_uf_dest: Decimal,
+    #[serde(rename = "vBCFCPUFDest", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp_uf_dest: Option<Decimal>,
+    #[serde(rename = "pFCPUFDest", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp_uf_dest: Option<Decimal>,
+    #[serde(rename = "pICMSUFDest", serialize_with = "serialize_4_decimals")]
+    pub p_icms_uf_dest: Decimal,
+    /// Enumerado no XSD: 4.00, 7.00 ou 12.00
+    #[serde(rename = "pICMSInter", serialize_with = "serialize_2_decimals")]
+    pub p_icms_inter: Decimal,
+    #[serde(rename = "pICMSInterPart", serialize_with = "serialize_4_decimals")]
+    pub p_icms_inter_part: Decimal,
+    #[serde(rename = "vFCPUFDest", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp_uf_dest: Option<Decimal>,
+    #[serde(rename = "vICMSUFDest", serialize_with = "serialize_2_decimals")]
+    pub v_icms_uf_dest: Decimal,
+    #[serde(rename = "vICMSUFRemet", serialize_with = "serialize_2_decimals")]
+    pub v_icms_uf_remet: Decimal,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename = "IBSCBS")]
 pub struct IBSCBSProcess {
@@ -365,12 +664,18 @@ pub struct ICMS00 {
     pub cst: String,
     #[serde(rename = "modBC")]
     pub mod_bc: u8,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pICMS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icms: f64,
-    #[serde(rename = "vICMS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icms: f64,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pICMS", serialize_with = "serialize_4_decimals")]
+    pub p_icms: Decimal,
+    #[serde(rename = "vICMS", serialize_with = "serialize_2_decimals")]
+    pub v_icms: Decimal,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
 }
 
 /// CST 10 — Tributada e com cobrança do ICMS por substituição tributária
@@ -381,25 +686,43 @@ pub struct ICMS10 {
     pub cst: String,
     #[serde(rename = "modBC")]
     pub mod_bc: u8,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pICMS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icms: f64,
-    #[serde(rename = "vICMS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icms: f64,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pICMS", serialize_with = "serialize_4_decimals")]
+    pub p_icms: Decimal,
+    #[serde(rename = "vICMS", serialize_with = "serialize_2_decimals")]
+    pub v_icms: Decimal,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp: Option<Decimal>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
     #[serde(rename = "modBCST")]
     pub mod_bcst: u8,
-    #[serde(rename = "pMVAST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_mvast: f64,
+    #[serde(rename = "pMVAST", serialize_with = "serialize_4_decimals")]
+    pub p_mvast: Decimal,
     #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bcst: Option<f64>,
-    #[serde(rename = "vBCST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bcst: f64,
-    #[serde(rename = "pICMSST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icmsst: f64,
-    #[serde(rename = "vICMSST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icmsst: f64,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bcst: Option<Decimal>,
+    #[serde(rename = "vBCST", serialize_with = "serialize_2_decimals")]
+    pub v_bcst: Decimal,
+    #[serde(rename = "pICMSST", serialize_with = "serialize_4_decimals")]
+    pub p_icmsst: Decimal,
+    #[serde(rename = "vICMSST", serialize_with = "serialize_2_decimals")]
+    pub v_icmsst: Decimal,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcpst: Option<Decimal>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcpst: Option<Decimal>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcpst: Option<Decimal>,
 }
 
 /// CST 20 — Com redução de base de cálculo
@@ -410,17 +733,26 @@ pub struct ICMS20 {
     pub cst: String,
     #[serde(rename = "modBC")]
     pub mod_bc: u8,
-    #[serde(rename = "pRedBC", serialize_with = "serialize_f64_4_decimals")]
-    pub p_red_bc: f64,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pICMS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icms: f64,
-    #[serde(rename = "vICMS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icms: f64,
+    #[serde(rename = "pRedBC", serialize_with = "serialize_4_decimals")]
+    pub p_red_bc: Decimal,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pICMS", serialize_with = "serialize_4_decimals")]
+    pub p_icms: Decimal,
+    #[serde(rename = "vICMS", serialize_with = "serialize_2_decimals")]
+    pub v_icms: Decimal,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp: Option<Decimal>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
     #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_deson: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_deson: Option<Decimal>,
     #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
     pub mot_des_icms: Option<u16>,
 }
@@ -433,20 +765,29 @@ pub struct ICMS30 {
     pub cst: String,
     #[serde(rename = "modBCST")]
     pub mod_bcst: u8,
-    #[serde(rename = "pMVAST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_mvast: f64,
+    #[serde(rename = "pMVAST", serialize_with = "serialize_4_decimals")]
+    pub p_mvast: Decimal,
     #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bcst: Option<f64>,
-    #[serde(rename = "vBCST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bcst: f64,
-    #[serde(rename = "pICMSST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icmsst: f64,
-    #[serde(rename = "vICMSST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icmsst: f64,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bcst: Option<Decimal>,
+    #[serde(rename = "vBCST", serialize_with = "serialize_2_decimals")]
+    pub v_bcst: Decimal,
+    #[serde(rename = "pICMSST", serialize_with = "serialize_4_decimals")]
+    pub p_icmsst: Decimal,
+    #[serde(rename = "vICMSST", serialize_with = "serialize_2_decimals")]
+    pub v_icmsst: Decimal,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcpst: Option<Decimal>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcpst: Option<Decimal>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcpst: Option<Decimal>,
     #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_deson: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_deson: Option<Decimal>,
     #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
     pub mot_des_icms: Option<u16>,
 }
@@ -469,8 +810,9 @@ pub struct ICMS40 {
     /// a) com produtos beneficiados com a desoneração condicional do ICMS.
     /// b) destinadas à SUFRAMA, informando-se o valor que seria devido se não houvesse isenção.
     /// c) de venda a órgão da administração pública direta e suas Nota Fiscal eletrônica fundações e autarquias com isenção do ICMS. (NT 2011/004)
-    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none")]
-    pub vicmsdeson: Option<f64>,
+    #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub vicmsdeson: Option<Decimal>,
     /// Campo será preenchido quando o campo anterior estiver preenchido.
     /// Informar o motivo da desoneração:
     /// 1 = Táxi;
@@ -509,26 +851,35 @@ pub struct ICMS51 {
     #[serde(rename = "modBC", skip_serializing_if = "Option::is_none")]
     pub mod_bc: Option<u8>,
     #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bc: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bc: Option<Decimal>,
     #[serde(rename = "vBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_bc: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc: Option<Decimal>,
     #[serde(rename = "pICMS", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_icms: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_icms: Option<Decimal>,
     #[serde(rename = "vICMSOp", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_op: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_op: Option<Decimal>,
     #[serde(rename = "pDif", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_dif: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_dif: Option<Decimal>,
     #[serde(rename = "vICMSDif", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_dif: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_dif: Option<Decimal>,
     #[serde(rename = "vICMS", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms: Option<Decimal>,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp: Option<Decimal>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
 }
 
 /// ICMS60 — ICMS cobrado anteriormente por substituição tributária
@@ -562,30 +913,48 @@ pub struct ICMS70 {
     #[serde(rename = "modBC")]
     pub mod_bc: u8,
     #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bc: Option<f64>,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pICMS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icms: f64,
-    #[serde(rename = "vICMS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icms: f64,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bc: Option<Decimal>,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pICMS", serialize_with = "serialize_4_decimals")]
+    pub p_icms: Decimal,
+    #[serde(rename = "vICMS", serialize_with = "serialize_2_decimals")]
+    pub v_icms: Decimal,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp: Option<Decimal>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
     #[serde(rename = "modBCST")]
     pub mod_bcst: u8,
-    #[serde(rename = "pMVAST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_mvast: f64,
+    #[serde(rename = "pMVAST", serialize_with = "serialize_4_decimals")]
+    pub p_mvast: Decimal,
     #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bcst: Option<f64>,
-    #[serde(rename = "vBCST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bcst: f64,
-    #[serde(rename = "pICMSST", serialize_with = "serialize_f64_4_decimals")]
-    pub p_icmsst: f64,
-    #[serde(rename = "vICMSST", serialize_with = "serialize_f64_2_decimals")]
-    pub v_icmsst: f64,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bcst: Option<Decimal>,
+    #[serde(rename = "vBCST", serialize_with = "serialize_2_decimals")]
+    pub v_bcst: Decimal,
+    #[serde(rename = "pICMSST", serialize_with = "serialize_4_decimals")]
+    pub p_icmsst: Decimal,
+    #[serde(rename = "vICMSST", serialize_with = "serialize_2_decimals")]
+    pub v_icmsst: Decimal,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcpst: Option<Decimal>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcpst: Option<Decimal>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcpst: Option<Decimal>,
     #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_deson: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_deson: Option<Decimal>,
     #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
     pub mot_des_icms: Option<u16>,
 }
@@ -598,38 +967,56 @@ pub struct ICMS90 {
     pub cst: String,
     #[serde(rename = "modBC", skip_serializing_if = "Option::is_none")]
     pub mod_bc: Option<u8>,
-    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bc: Option<f64>,
     #[serde(rename = "vBC", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_bc: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc: Option<Decimal>,
+    #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bc: Option<Decimal>,
     #[serde(rename = "pICMS", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_icms: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_icms: Option<Decimal>,
     #[serde(rename = "vICMS", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms: Option<Decimal>,
+    #[serde(rename = "vBCFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcp: Option<Decimal>,
+    #[serde(rename = "pFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcp: Option<Decimal>,
+    #[serde(rename = "vFCP", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcp: Option<Decimal>,
     #[serde(rename = "modBCST", skip_serializing_if = "Option::is_none")]
     pub mod_bcst: Option<u8>,
     #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_mvast: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_mvast: Option<Decimal>,
     #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_red_bcst: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_red_bcst: Option<Decimal>,
     #[serde(rename = "vBCST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_bcst: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bcst: Option<Decimal>,
     #[serde(rename = "pICMSST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_4_decimals")]
-    pub p_icmsst: Option<f64>,
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_icmsst: Option<Decimal>,
     #[serde(rename = "vICMSST", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icmsst: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icmsst: Option<Decimal>,
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_bc_fcpst: Option<Decimal>,
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_4_decimals")]
+    pub p_fcpst: Option<Decimal>,
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_fcpst: Option<Decimal>,
     #[serde(rename = "vICMSDeson", skip_serializing_if = "Option::is_none",
-            serialize_with = "serialize_option_f64_2_decimals")]
-    pub v_icms_deson: Option<f64>,
+            serialize_with = "serialize_option_2_decimals")]
+    pub v_icms_deson: Option<Decimal>,
     #[serde(rename = "motDesICMS", skip_serializing_if = "Option::is_none")]
     pub mot_des_icms: Option<u16>,
 }
@@ -638,9 +1025,11 @@ impl Default for ICMS90 {
     fn default() -> Self {
         ICMS90 {
             orig: 0, cst: "90".to_string(),
-            mod_bc: None, p_red_bc: None, v_bc: None, p_icms: None, v_icms: None,
+            mod_bc: None, v_bc: None, p_red_bc: None, p_icms: None, v_icms: None,
+            v_bc_fcp: None, p_fcp: None, v_fcp: None,
             mod_bcst: None, p_mvast: None, p_red_bcst: None, v_bcst: None,
-            p_icmsst: None, v_icmsst: None, v_icms_deson: None, mot_des_icms: None,
+            p_icmsst: None, v_icmsst: None, v_bc_fcpst: None, p_fcpst: None, v_fcpst: None,
+            v_icms_deson: None, mot_des_icms: None,
         }
     }
 }
@@ -651,17 +1040,17 @@ pub struct ICMSPart {
     // pub orig: u8,
     // #[serde(rename = "CST")] pub cst: String,         // "10" ou "90"
     // #[serde(rename = "modBC")] pub mod_bc: u8,
-    // #[serde(rename = "vBC")] pub v_bc: f64,
-    // #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")] pub p_red_bc: Option<f64>,
-    // #[serde(rename = "pICMS")] pub p_icms: f64,
-    // #[serde(rename = "vICMS")] pub v_icms: f64,
+    // #[serde(rename = "vBC")] pub v_bc: Decimal,
+    // #[serde(rename = "pRedBC", skip_serializing_if = "Option::is_none")] pub p_red_bc: Option<Decimal>,
+    // #[serde(rename = "pICMS")] pub p_icms: Decimal,
+    // #[serde(rename = "vICMS")] pub v_icms: Decimal,
     // #[serde(rename = "modBCST")] pub mod_bcst: u8,
-    // #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")] pub p_mvast: Option<f64>,
-    // #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")] pub p_red_bcst: Option<f64>,
-    // #[serde(rename = "vBCST")] pub v_bcst: f64,
-    // #[serde(rename = "pICMSST")] pub p_icmsst: f64,
-    // #[serde(rename = "vICMSST")] pub v_icmsst: f64,
-    // #[serde(rename = "pBCOp")] pub p_bcop: f64,       // percentual da BC operação própria
+    // #[serde(rename = "pMVAST", skip_serializing_if = "Option::is_none")] pub p_mvast: Option<Decimal>,
+    // #[serde(rename = "pRedBCST", skip_serializing_if = "Option::is_none")] pub p_red_bcst: Option<Decimal>,
+    // #[serde(rename = "vBCST")] pub v_bcst: Decimal,
+    // #[serde(rename = "pICMSST")] pub p_icmsst: Decimal,
+    // #[serde(rename = "vICMSST")] pub v_icmsst: Decimal,
+    // #[serde(rename = "pBCOp")] pub p_bcop: Decimal,       // percentual da BC operação própria
     // #[serde(rename = "UFST")] pub ufst: String,        // UF para qual é devido o ICMS ST
 }
 
@@ -745,6 +1134,12 @@ pub struct ICMSSN900 {
     pub picmsst: Option<String>, // Alíquota do ICMS ST
     #[serde(rename = "vICMSST", skip_serializing_if = "Option::is_none")]
     pub vicmsst: Option<String>, // Valor do ICMS ST
+    #[serde(rename = "vBCFCPST", skip_serializing_if = "Option::is_none")]
+    pub vbc_fcpst: Option<String>, // Valor da BC do FCP retido por ST
+    #[serde(rename = "pFCPST", skip_serializing_if = "Option::is_none")]
+    pub p_fcpst: Option<String>, // Percentual do FCP retido por ST
+    #[serde(rename = "vFCPST", skip_serializing_if = "Option::is_none")]
+    pub v_fcpst: Option<String>, // Valor do FCP retido por ST
     // 245.52 N27.1 -x- Sequência XML G N10h  0-1  Grupo opcional.
     #[serde(rename = "pCredSN", skip_serializing_if = "Option::is_none")]
     pub pcred_sn: Option<String>, // Alíquota aplicável de cálculo do crédito (Simples Nacional)
@@ -789,12 +1184,12 @@ pub struct PISAliq {
     /// 02 = Operação Tributável (base de cálculo = valor da operação (alíquota diferenciada));
     #[serde(rename = "CST")]
     pub cst: String,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pPIS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_pis: f64,
-    #[serde(rename = "vPIS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_pis: f64,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pPIS", serialize_with = "serialize_4_decimals")]
+    pub p_pis: Decimal,
+    #[serde(rename = "vPIS", serialize_with = "serialize_2_decimals")]
+    pub v_pis: Decimal,
 }
 
 /// Grupo PIS tributado por Qtde
@@ -891,12 +1286,12 @@ pub struct COFINSAliq {
     /// 02 = Operação Tributável (base de cálculo = valor da operação (alíquota diferenciada));
     #[serde(rename = "CST")]
     pub cst: String,
-    #[serde(rename = "vBC", serialize_with = "serialize_f64_2_decimals")]
-    pub v_bc: f64,
-    #[serde(rename = "pCOFINS", serialize_with = "serialize_f64_4_decimals")]
-    pub p_cofins: f64,
-    #[serde(rename = "vCOFINS", serialize_with = "serialize_f64_2_decimals")]
-    pub v_cofins: f64,
+    #[serde(rename = "vBC", serialize_with = "serialize_2_decimals")]
+    pub v_bc: Decimal,
+    #[serde(rename = "pCOFINS", serialize_with = "serialize_4_decimals")]
+    pub p_cofins: Decimal,
+    #[serde(rename = "vCOFINS", serialize_with = "serialize_2_decimals")]
+    pub v_cofins: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -934,21 +1329,21 @@ pub struct COFINSOutr {
     #[serde(
         rename = "vBC",
         skip_serializing_if = "Option::is_none",
-        serialize_with = "serialize_option_f64_2_decimals"
+        serialize_with = "serialize_option_2_decimals"
     )]
-    pub v_bc: Option<f64>,
+    pub v_bc: Option<Decimal>,
     #[serde(
         rename = "pCOFINS",
         skip_serializing_if = "Option::is_none",
-        serialize_with = "serialize_option_f64_2_decimals"
+        serialize_with = "serialize_option_2_decimals"
     )]
-    pub p_cofins: Option<f64>,
+    pub p_cofins: Option<Decimal>,
     #[serde(
         rename = "vCOFINS",
         skip_serializing_if = "Option::is_none",
-        serialize_with = "serialize_option_f64_2_decimals"
+        serialize_with = "serialize_option_2_decimals"
     )]
-    pub v_cofins: Option<f64>,
+    pub v_cofins: Option<Decimal>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -988,14 +1383,20 @@ impl ImpostoProcess {
     pub fn to_xml(&self) -> String {
         let icms    = icms_xml(&self.icms);
         let ipi     = self.ipi.as_ref().map(ipi_xml).unwrap_or_default();
+        let ii      = self.ii.as_ref()
+            .map(|v| quick_xml::se::to_string(v).unwrap_or_default())
+            .unwrap_or_default();
         let pis     = pis_xml(&self.pis);
         let cofins  = cofins_xml(&self.cofins);
+        let uf_dest = self.icms_uf_dest.as_ref()
+            .map(|v| quick_xml::se::to_string(v).unwrap_or_default())
+            .unwrap_or_default();
         let ibs_cbs = self.ibs_cbs.as_ref()
             .map(|v| quick_xml::se::to_string(v).unwrap_or_default())
             .unwrap_or_default();
         format!(
-            "<imposto><vTotTrib>{}</vTotTrib>{}{}{}{}{}</imposto>",
-            self.v_tot_trib, icms, ipi, pis, cofins, ibs_cbs
+            "<imposto><vTotTrib>{}</vTotTrib>{}{}{}{}{}{}{}</imposto>",
+            fmt_casas(self.v_tot_trib, 2), icms, ipi, ii, pis, cofins, uf_dest, ibs_cbs
         )
     }
 }
@@ -1055,47 +1456,36 @@ fn cofins_xml(cofins: &COFINSProcess) -> String {
     format!("<COFINS>{}</COFINS>", inner)
 }
 
-fn serialize_f64_2_decimals<S>(x: &f64, s: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    s.serialize_str(&format!("{:.2}", x))
-}
-
-fn serialize_f64_4_decimals<S>(x: &f64, s: S) -> Result<S::Ok, S::Error>
+fn serialize_2_decimals<S>(x: &Decimal, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    s.serialize_str(&format!("{:.4}", x))
+    s.serialize_str(&fmt_casas(*x, 2))
 }
 
-fn serialize_option_f64_2_decimals<S>(x: &Option<f64>, s: S) -> Result<S::Ok, S::Error>
+fn serialize_4_decimals<S>(x: &Decimal, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    match x {
-        Some(val) => s.serialize_str(&format!("{:.2}", val)),
-        None => s.serialize_none(),
-    }
+    s.serialize_str(&fmt_casas(*x, 4))
 }
 
-fn serialize_option_f64_4_decimals<S>(x: &Option<f64>, s: S) -> Result<S::Ok, S::Error>
+fn serialize_option_2_decimals<S>(x: &Option<Decimal>, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     match x {
-        Some(val) => s.serialize_str(&format!("{:.4}", val)),
+        Some(val) => s.serialize_str(&fmt_casas(*val, 2)),
         None => s.serialize_none(),
     }
 }
 
-/* fn serialize_option_f64_4_decimals<S>(x: &Option<f64>, s: S) -> Result<S::Ok, S::Error>
+fn serialize_option_4_decimals<S>(x: &Option<Decimal>, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     match x {
-        Some(val) => s.serialize_str(&format!("{:.4}", val)),
+        Some(val) => s.serialize_str(&fmt_casas(*val, 4)),
         None => s.serialize_none(),
     }
 }
- */
@@ -0,0 +1,542 @@
+// Diário de emissão: a etapa em que cada nota (emitente, ambiente, modelo, série e
+// número) se encontra no envio à SEFAZ. Uma queda entre a transmissão e a resposta
+// deixa a nota em `Requested`/`SemResposta`; ela só volta a ser emitida depois que a
+// consulta do protocolo disser se a SEFAZ a autorizou.
+
+use super::numeracao::{Numeracao, SerieNumeracao, UsoNumero};
+use super::{nfe_proc, TagInfProt};
+use crate::consulta::{ConsultaProtocoloBuilder, ConsultaProtocoloResposta, SituacaoNFe};
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::ArtefatoSink;
+use crate::interno::cert::Certificado;
+use crate::interno::dates::get_current_date_time;
+use crate::interno::ws::Webservices;
+use crate::tipos::{Emit, Ide};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Etapa da emissão de uma nota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EstadoEmissao {
+    /// XML assinado e validado, ainda não transmitido.
+    XMLGerado,
+    /// Transmitido; aguardando a resposta da SEFAZ.
+    Requested,
+    /// Autorizada (`cStat` 100/150). O número não pode ser reutilizado.
+    Autorizado,
+    /// Rejeitada ou não recebida pela SEFAZ; a nota pode ser emitida de novo.
+    NaoAutorizado,
+    /// Uso denegado (`cStat` 110/205/301/302/303). O número fica consumido.
+    Denegado,
+    /// A transmissão falhou sem resposta conclusiva (rede, HTTP, lote em processamento).
+    SemResposta,
+}
+
+impl EstadoEmissao {
+    /// `true` quando o resultado na SEFAZ é desconhecido — resolva com
+    /// [`RecuperacaoEmissaoBuilder`] antes de tentar de novo.
+    pub fn pendente(&self) -> bool {
+        matches!(self, EstadoEmissao::Requested | EstadoEmissao::SemResposta)
+    }
+
+    /// Etapa final correspondente ao `cStat` do protocolo da nota.
+    pub(super) fn de_c_stat(c_stat: i32) -> Self {
+        match c_stat {
+            100 | 150 => EstadoEmissao::Autorizado,
+            110 | 205 | 301 | 302 | 303 => EstadoEmissao::Denegado,
+            _ => EstadoEmissao::NaoAutorizado,
+        }
+    }
+}
+
+/// Identificação da nota no diário. A numeração é única por emitente, ambiente,
+/// modelo e série — a chave de acesso muda a cada tentativa (`cNF`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NotaDiario {
+    /// CNPJ (ou CPF) do emitente.
+    pub emitente: String,
+    /// Ambiente: `1` = produção · `2` = homologação.
+    pub tp_amb: u8,
+    /// Modelo: `55` ou `65`.
+    pub modelo: u32,
+    /// Série.
+    pub serie: u32,
+    /// Número da nota.
+    pub n_nf: u64,
+}
+
+impl NotaDiario {
+    pub(super) fn de(ide: &Ide, emit: &Emit) -> Self {
+        NotaDiario {
+            emitente: emit.cnpj.clone().or_else(|| emit.cpf.clone()).unwrap_or_default(),
+            tp_amb: ide.tp_amb,
+            modelo: ide.mod_,
+            serie: ide.serie,
+            n_nf: ide.n_nf,
+        }
+    }
+}
+
+/// Situação de uma nota no diário.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistroEmissao {
+    /// Nota a que o registro se refere.
+    pub nota: NotaDiario,
+    /// Etapa atual.
+    pub estado: EstadoEmissao,
+    /// Chave de acesso da última tentativa.
+    pub chave: String,
+    /// NF-e assinada; `nfeProc` depois da autorização.
+    pub xml: String,
+    /// `cStat` da SEFAZ, quando houve resposta.
+    pub c_stat: Option<i32>,
+    /// `xMotivo` da SEFAZ, quando houve resposta.
+    pub x_motivo: Option<String>,
+    /// Data/hora da última atualização.
+    pub atualizado_em: String,
+}
+
+/// Onde a emissão registra a etapa de cada nota.
+///
+/// [`DiarioArquivo`] grava um arquivo por nota num diretório; [`DiarioMemoria`] serve
+/// a testes e a processos que não precisam sobreviver a um reinício. Implemente o
+/// trait para guardar o diário no banco da aplicação.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use dfe::{DiarioArquivo, NFeBuilder};
+///
+/// let diario = Arc::new(DiarioArquivo::new("./diario-emissao"));
+/// let nfe = NFeBuilder::new().diario(diario.clone());
+/// # let _ = nfe;
+/// ```
+pub trait DiarioEmissao: Send + Sync {
+    /// Grava o registro, substituindo o da mesma [`NotaDiario`].
+    fn registrar(&self, registro: &RegistroEmissao) -> Result<()>;
+
+    /// Registro da nota, se houver.
+    fn consultar(&self, nota: &NotaDiario) -> Result<Option<RegistroEmissao>>;
+
+    /// Registros do emitente (CNPJ ou CPF).
+    fn registros(&self, emitente: &str) -> Result<Vec<RegistroEmissao>>;
+}
+
+/// Diário em disco: `<dir>/<emitente>/<tpAmb>-<mod>-<serie>-<nNF>.json`.
+#[derive(Debug, Clone)]
+pub struct DiarioArquivo {
+    dir: PathBuf,
+}
+
+impl DiarioArquivo {
+    /// Diário no diretório informado; é criado no primeiro registro.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Diretório do diário.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn arquivo(&self, nota: &NotaDiario) -> PathBuf {
+        self.dir
+            .join(&nota.emitente)
+            .join(format!("{}-{}-{}-{}.json", nota.tp_amb, nota.modelo, nota.serie, nota.n_nf))
+    }
+}
+
+impl DiarioEmissao for DiarioArquivo {
+    fn registrar(&self, registro: &RegistroEmissao) -> Result<()> {
+        let arquivo = self.arquivo(&registro.nota);
+        if let Some(dir) = arquivo.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Grava ao lado e renomeia: uma queda no meio não deixa o registro truncado
+        let temporario = arquivo.with_extension("json.tmp");
+        std::fs::write(&temporario, serde_json::to_vec_pretty(registro)?)?;
+        std::fs::rename(&temporario, &arquivo)?;
+        Ok(())
+    }
+
+    fn consultar(&self, nota: &NotaDiario) -> Result<Option<RegistroEmissao>> {
+        let arquivo = self.arquivo(nota);
+        if !arquivo.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&std::fs::read(arquivo)?)?))
+    }
+
+    fn registros(&self, emitente: &str) -> Result<Vec<RegistroEmissao>> {
+        let dir = self.dir.join(emitente);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut registros = Vec::new();
+        for entrada in std::fs::read_dir(dir)? {
+            let arquivo = entrada?.path();
+            if arquivo.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            registros.push(serde_json::from_slice::<RegistroEmissao>(&std::fs::read(arquivo)?)?);
+        }
+        registros.sort_by_key(|r| (r.nota.tp_amb, r.nota.modelo, r.nota.serie, r.nota.n_nf));
+        Ok(registros)
+    }
+}
+
+/// Diário em memória, perdido ao fim do processo.
+#[derive(Debug, Default)]
+pub struct DiarioMemoria {
+    registros: Mutex<HashMap<NotaDiario, RegistroEmissao>>,
+}
+
+impl DiarioMemoria {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn registros_lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<NotaDiario, RegistroEmissao>>> {
+        self.registros.lock().map_err(|_| DfeError::Io("diário em memória envenenado".to_string()))
+    }
+}
+
+impl DiarioEmissao for DiarioMemoria {
+    fn registrar(&self, registro: &RegistroEmissao) -> Result<()> {
+        self.registros_lock()?.insert(registro.nota.clone(), registro.clone());
+        Ok(())
+    }
+
+    fn consultar(&self, nota: &NotaDiario) -> Result<Option<RegistroEmissao>> {
+        Ok(self.registros_lock()?.get(nota).cloned())
+    }
+
+    fn registros(&self, emitente: &str) -> Result<Vec<RegistroEmissao>> {
+        let mut registros: Vec<RegistroEmissao> =
+            self.registros_lock()?.values().filter(|r| r.nota.emitente == emitente).cloned().collect();
+        registros.sort_by_key(|r| (r.nota.tp_amb, r.nota.modelo, r.nota.serie, r.nota.n_nf));
+        Ok(registros)
+    }
+}
+
+// Etapas de uma emissão em curso; sem diário configurado nada é registrado
+pub(super) struct Etapas {
+    diario: Option<Arc<dyn DiarioEmissao>>,
+    nota: NotaDiario,
+}
+
+impl Etapas {
+    pub(super) fn new(diario: Option<Arc<dyn DiarioEmissao>>, ide: &Ide, emit: &Emit) -> Self {
+        Etapas { diario, nota: NotaDiario::de(ide, emit) }
+    }
+
+    // Recusa a emissão de nota já autorizada, denegada ou com resultado pendente
+    pub(super) fn liberar(&self) -> Result<()> {
+        let Some(diario) = &self.diario else { return Ok(()) };
+        let Some(registro) = diario.consultar(&self.nota)? else { return Ok(()) };
+        let nota = &self.nota;
+        if registro.estado.pendente() {
+            return Err(DfeError::Validacao(format!(
+                "nota {}/{} do emitente {} está em {:?} (chave {}); resolva com RecuperacaoEmissaoBuilder antes de emitir de novo",
+                nota.serie, nota.n_nf, nota.emitente, registro.estado, registro.chave
+            )));
+        }
+        if registro.estado == EstadoEmissao::Autorizado {
+            return Err(DfeError::Validacao(format!(
+                "nota {}/{} do emitente {} já foi autorizada (chave {})",
+                nota.serie, nota.n_nf, nota.emitente, registro.chave
+            )));
+        }
+        if registro.estado == EstadoEmissao::Denegado {
+            return Err(DfeError::Validacao(format!(
+                "nota {}/{} do emitente {} teve o uso denegado (chave {}); o número não pode ser reutilizado",
+                nota.serie, nota.n_nf, nota.emitente, registro.chave
+            )));
+        }
+        Ok(())
+    }
+
+    pub(super) fn registrar(&self, estado: EstadoEmissao, chave: &str, xml: &str, retorno: Option<(i32, &str)>) -> Result<()> {
+        let Some(diario) = &self.diario else { return Ok(()) };
+        diario.registrar(&RegistroEmissao {
+            nota: self.nota.clone(),
+            estado,
+            chave: chave.to_string(),
+            xml: xml.to_string(),
+            c_stat: retorno.map(|(c_stat, _)| c_stat),
+            x_motivo: retorno.map(|(_, x_motivo)| x_motivo.to_string()),
+            atualizado_em: get_current_date_time(),
+        })
+    }
+}
+
+/// Resultado da recuperação de uma nota pendente.
+#[derive(Debug)]
+pub struct Recuperacao {
+    /// Nota pendente.
+    pub nota: NotaDiario,
+    /// Chave de acesso consultada.
+    pub chave: String,
+    /// Registro atualizado no diário, ou a falha da consulta (a nota segue pendente).
+    pub resultado: Result<RegistroEmissao>,
+}
+
+impl Recuperacao {
+    /// `true` quando a SEFAZ não autorizou a nota e ela pode ser emitida de novo.
+    /// Nota denegada não é liberada: o número fica consumido.
+    pub fn liberada(&self) -> bool {
+        matches!(&self.resultado, Ok(r) if r.estado == EstadoEmissao::NaoAutorizado)
+    }
+}
+
+/// Resolve as notas do emitente paradas em `Requested`/`SemResposta` consultando o
+/// protocolo de cada chave na SEFAZ.
+///
+/// Autorizada (ou cancelada depois) vira `Autorizado` com o `nfeProc` remontado; denegada
+/// vira `Denegado` e não encontrada (`cStat` 217) vira `NaoAutorizado`. Se a consulta
+/// falhar a nota continua pendente.
+///
+/// # Exemplo
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use dfe::{DiarioArquivo, RecuperacaoEmissaoBuilder};
+///
+/// # async fn example(cert: dfe::Certificado) -> Result<(), dfe::DfeError> {
+/// let diario = Arc::new(DiarioArquivo::new("./diario-emissao"));
+/// for r in RecuperacaoEmissaoBuilder::new().certificado(cert).diario(diario).emitente("11222333000181").send().await? {
+///     match &r.resultado {
+///         Ok(registro) => println!("{} -> {:?}", r.chave, registro.estado),
+///         Err(e) => eprintln!("{} segue pendente: {}", r.chave, e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RecuperacaoEmissaoBuilder {
+    certificado: Option<Certificado>,
+    cert_path:   Option<String>,
+    cert_pass:   Option<String>,
+    diario:      Option<Arc<dyn DiarioEmissao>>,
+    emitente:    Option<String>,
+    webservices: Option<Webservices>,
+    artefatos:   Option<Arc<dyn ArtefatoSink>>,
+    numeracao:   Option<Numeracao>,
+}
+
+impl RecuperacaoEmissaoBuilder {
+    pub fn new() -> Self {
+        Self {
+            certificado: None, cert_path: None, cert_pass: None, diario: None, emitente: None, webservices: None,
+            artefatos: None, numeracao: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+    /// Diário com as notas pendentes
+    pub fn diario(mut self, d: Arc<dyn DiarioEmissao>) -> Self { self.diario = Some(d); self }
+    /// CNPJ (ou CPF) do emitente
+    pub fn emitente(mut self, doc: &str) -> Self { self.emitente = Some(doc.to_string()); self }
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+    /// Destino ([`ArtefatoSink`]) dos envelopes e retornos das consultas. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+    /// Numeração ([`Numeracao`]) onde o resultado de cada nota resolvida é registrado
+    pub fn numeracao(mut self, n: Numeracao) -> Self { self.numeracao = Some(n); self }
+
+    /// Um [`Recuperacao`] por nota pendente do emitente.
+    pub async fn send(self) -> Result<Vec<Recuperacao>> {
+        let certificado = Certificado::resolver(self.certificado, self.cert_path, self.cert_pass)?;
+        let diario      = self.diario.ok_or_else(|| DfeError::Validacao("diário não informado".to_string()))?;
+        let emitente    = self.emitente.ok_or_else(|| DfeError::Validacao("emitente não informado".to_string()))?;
+
+        let mut recuperacoes = Vec::new();
+        for registro in diario.registros(&emitente)?.into_iter().filter(|r| r.estado.pendente()) {
+            let consulta = ConsultaProtocoloBuilder::new()
+                .certificado(certificado.clone())
+                .tp_amb(registro.nota.tp_amb)
+                .chave(&registro.chave)
+                .nfe_assinada(&registro.xml);
+            let consulta = match self.webservices.clone() {
+                Some(w) => consulta.webservices(w),
+                None => consulta,
+            };
+            let consulta = match self.artefatos.clone() {
+                Some(a) => consulta.artefatos(a),
+                None => consulta,
+            };
+            let (nota, chave) = (registro.nota.clone(), registro.chave.clone());
+            let resultado = match consulta.send().await {
+                // XML do diário diferente do autorizado: a nota continua pendente
+                Ok(ConsultaProtocoloResposta { nfe_proc_divergencia: Some(e), .. }) => Err(e),
+                Ok(resposta) => resolver(registro, resposta.situacao, resposta.c_stat, &resposta.x_motivo, resposta.protocolo.as_ref(), resposta.nfe_proc)
+                    .and_then(|registro| diario.registrar(&registro).map(|_| registro))
+                    .and_then(|registro| registrar_numeracao(self.numeracao.as_ref(), &registro).map(|_| registro)),
+                Err(e) => Err(e),
+            };
+            recuperacoes.push(Recuperacao { nota, chave, resultado });
+        }
+        Ok(recuperacoes)
+    }
+}
+
+impl Default for RecuperacaoEmissaoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Autorizada consome o número; as demais seguem o cStat da consulta (217 = não usado)
+fn registrar_numeracao(numeracao: Option<&Numeracao>, registro: &RegistroEmissao) -> Result<()> {
+    let Some(numeracao) = numeracao else { return Ok(()) };
+    let nota = &registro.nota;
+    let serie = SerieNumeracao { emitente: nota.emitente.clone(), tp_amb: nota.tp_amb, modelo: nota.modelo, serie: nota.serie };
+    let uso = match registro.estado {
+        EstadoEmissao::Autorizado => UsoNumero::Autorizado,
+        _ => registro.c_stat.map(UsoNumero::de_c_stat).unwrap_or(UsoNumero::Rejeitado),
+    };
+    numeracao.registrar(&serie, nota.n_nf, uso)
+}
+
+// Novo registro a partir da situação consultada; Err mantém a nota pendente
+fn resolver(
+    registro: RegistroEmissao,
+    situacao: SituacaoNFe,
+    c_stat: i32,
+    x_motivo: &str,
+    protocolo: Option<&TagInfProt>,
+    nfe_proc_xml: Option<String>,
+) -> Result<RegistroEmissao> {
+    let (c_stat, x_motivo) = protocolo
+        .map(|p| (p.inf_prot.c_stat, p.inf_prot.x_motivo.clone()))
+        .unwrap_or((c_stat, x_motivo.to_string()));
+    let (estado, xml) = match situacao {
+        SituacaoNFe::Autorizada | SituacaoNFe::Cancelada => {
+            let xml = match (nfe_proc_xml, protocolo) {
+                (Some(xml), _) => xml,
+                (None, Some(p)) => nfe_proc(&registro.xml, p),
+                _ => registro.xml,
+            };
+            (EstadoEmissao::Autorizado, xml)
+        }
+        SituacaoNFe::Denegada => (EstadoEmissao::Denegado, registro.xml),
+        SituacaoNFe::NaoEncontrada => (EstadoEmissao::NaoAutorizado, registro.xml),
+        SituacaoNFe::Rejeitada => {
+            return Err(DfeError::Webservice(format!(
+                "consulta da chave {} rejeitada: cStat {} — {}", registro.chave, c_stat, x_motivo
+            )));
+        }
+    };
+    Ok(RegistroEmissao {
+        estado,
+        xml,
+        c_stat: Some(c_stat),
+        x_motivo: Some(x_motivo),
+        atualizado_em: get_current_date_time(),
+        ..registro
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAVE: &str = "35250111222333000181550010000000011000000010";
+
+    fn etapas(diario: Arc<dyn DiarioEmissao>, n_nf: u64) -> Etapas {
+        let ide = Ide { tp_amb: 2, mod_: 55, serie: 1, n_nf, ..Default::default() };
+        let emit = Emit { cnpj: Some("11222333000181".to_string()), ..Default::default() };
+        Etapas::new(Some(diario), &ide, &emit)
+    }
+
+    fn verificar_diario(diario: Arc<dyn DiarioEmissao>) {
+        let nota_1 = etapas(diario.clone(), 1);
+        let nota_2 = etapas(diario.clone(), 2);
+        nota_1.liberar().unwrap();
+
+        nota_2.registrar(EstadoEmissao::XMLGerado, CHAVE, "<NFe/>", None).unwrap();
+        nota_1.registrar(EstadoEmissao::Requested, CHAVE, "<NFe/>", None).unwrap();
+        assert!(nota_1.liberar().is_err());
+        nota_2.liberar().unwrap();
+
+        nota_1.registrar(EstadoEmissao::NaoAutorizado, CHAVE, "<NFe/>", Some((539, "Duplicidade"))).unwrap();
+        nota_1.liberar().unwrap();
+        nota_2.registrar(EstadoEmissao::Denegado, CHAVE, "<NFe/>", Some((302, "Uso Denegado"))).unwrap();
+        assert!(nota_2.liberar().is_err());
+        nota_2.registrar(EstadoEmissao::Autorizado, CHAVE, "<nfeProc/>", Some((100, "Autorizado"))).unwrap();
+        assert!(nota_2.liberar().is_err());
+
+        let registros = diario.registros("11222333000181").unwrap();
+        assert_eq!(registros.iter().map(|r| r.nota.n_nf).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(registros[0].c_stat, Some(539));
+        assert_eq!(registros[1].estado, EstadoEmissao::Autorizado);
+        assert!(diario.registros("99999999000191").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diario_memoria() {
+        verificar_diario(Arc::new(DiarioMemoria::new()));
+    }
+
+    #[test]
+    fn test_diario_arquivo() {
+        let dir = std::env::temp_dir().join(format!("dfe-diario-{}", std::process::id()));
+        verificar_diario(Arc::new(DiarioArquivo::new(&dir)));
+        assert!(dir.join("11222333000181").join("2-55-1-2.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sem_diario_nada_e_registrado() {
+        let ide = Ide::default();
+        let etapas = Etapas::new(None, &ide, &Emit::default());
+        etapas.registrar(EstadoEmissao::Requested, CHAVE, "<NFe/>", None).unwrap();
+        etapas.liberar().unwrap();
+    }
+
+    #[test]
+    fn test_estado_pelo_c_stat() {
+        assert_eq!(EstadoEmissao::de_c_stat(150), EstadoEmissao::Autorizado);
+        assert_eq!(EstadoEmissao::de_c_stat(301), EstadoEmissao::Denegado);
+        assert_eq!(EstadoEmissao::de_c_stat(205), EstadoEmissao::Denegado);
+        assert_eq!(EstadoEmissao::de_c_stat(539), EstadoEmissao::NaoAutorizado);
+    }
+
+    #[test]
+    fn test_recuperacao_resolve_pela_situacao() {
+        let registro = RegistroEmissao {
+            nota: NotaDiario { emitente: "11222333000181".to_string(), tp_amb: 2, modelo: 55, serie: 1, n_nf: 1 },
+            estado: EstadoEmissao::SemResposta,
+            chave: CHAVE.to_string(),
+            xml: "<NFe/>".to_string(),
+            c_stat: None,
+            x_motivo: None,
+            atualizado_em: String::new(),
+        };
+
+        let autorizada = resolver(registro.clone(), SituacaoNFe::Autorizada, 100, "Autorizado", None, Some("<nfeProc/>".to_string())).unwrap();
+        assert_eq!(autorizada.estado, EstadoEmissao::Autorizado);
+        assert_eq!(autorizada.xml, "<nfeProc/>");
+
+        let ausente = resolver(registro.clone(), SituacaoNFe::NaoEncontrada, 217, "NF-e não consta na base de dados da SEFAZ", None, None).unwrap();
+        assert_eq!(ausente.estado, EstadoEmissao::NaoAutorizado);
+        assert_eq!(ausente.c_stat, Some(217));
+        let liberada = Recuperacao { nota: registro.nota.clone(), chave: CHAVE.to_string(), resultado: Ok(ausente) };
+        assert!(liberada.liberada());
+
+        let denegada = resolver(registro.clone(), SituacaoNFe::Denegada, 302, "Uso Denegado", None, None).unwrap();
+        assert_eq!(denegada.estado, EstadoEmissao::Denegado);
+        let recuperacao = Recuperacao { nota: registro.nota.clone(), chave: CHAVE.to_string(), resultado: Ok(denegada) };
+        assert!(!recuperacao.liberada());
+
+        assert!(resolver(registro, SituacaoNFe::Rejeitada, 656, "Consumo Indevido", None, None).is_err());
+    }
+}
@@ -0,0 +1,311 @@
+// Contingência EPEC (evento 110140): a NF-e gerada com tpEmis 4 é declarada ao
+// Ambiente Nacional antes de circular e, quando o autorizador da UF voltar, a mesma
+// NF-e assinada é transmitida e conciliada.
+
+use super::diario::{DiarioEmissao, Etapas};
+use super::numeracao::{Numeracao, Reserva};
+use super::{build_signed_xml, chave_nfe, extract_xml_tag, transmitir_assinada, EstadoEmissao, NFeBuilder, Response};
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::ArtefatoSink;
+use crate::interno::cert::Certificado;
+use crate::interno::connection::WebService;
+use crate::interno::dates::get_current_date_time;
+use crate::interno::evento::{self, Evento};
+use crate::interno::signer::Credencial;
+use crate::interno::ws::{nfe_recepcao_evento_an, Webservices};
+use std::sync::Arc;
+
+const TP_EVENTO: &str = "110140";
+
+/// `cOrgao` do Ambiente Nacional, destinatário do evento EPEC.
+const C_ORGAO_AN: &str = "91";
+
+/// Resposta de [`EpecBuilder::send`].
+#[derive(Debug, Clone)]
+pub struct EpecResposta {
+    /// Chave de acesso da NF-e em contingência.
+    pub chave: String,
+    /// `cStat` do evento. `136` = EPEC registrado (ainda sem NF-e vinculada).
+    pub c_stat: i32,
+    /// Descrição do status retornado pelo Ambiente Nacional.
+    pub x_motivo: String,
+    /// Protocolo do EPEC — impresso no DANFE de contingência.
+    pub n_prot: Option<String>,
+    /// Data e hora do registro do EPEC.
+    pub dh_reg_evento: Option<String>,
+    /// NF-e assinada com `tpEmis` 4. Guarde-a: é este XML, sem alterações, que deve ser transmitido
+    /// depois por [`RegularizacaoEpecBuilder`].
+    pub nfe_xml: String,
+    /// XML `procEventoNFe` do EPEC, quando registrado. Use com [`DanfeBuilder::epec`](crate::DanfeBuilder::epec).
+    pub proc_evento_nfe: Option<String>,
+    /// Envelope SOAP enviado.
+    pub send_xml: String,
+    /// Resposta SOAP recebida.
+    pub receive_xml: String,
+}
+
+impl EpecResposta {
+    /// `true` quando o Ambiente Nacional registrou o EPEC e a NF-e pode circular com o DANFE de contingência.
+    pub fn registrado(&self) -> bool {
+        self.proc_evento_nfe.is_some()
+    }
+}
+
+/// Builder do Evento Prévio de Emissão em Contingência (EPEC) de uma NF-e.
+///
+/// A nota deve ter `ide.tp_emis = 4`, `dh_cont` e `x_just`. O XML é gerado e
+/// assinado uma única vez; o EPEC declara seus dados ao Ambiente Nacional.
+///
+/// Com a [`numeracao`](NFeBuilder::numeracao) da nota, o número fica reservado enquanto
+/// o EPEC registrado aguarda a regularização; falha antes do envio ou EPEC não
+/// registrado devolvem o número reservado como não usado. Com o [`diario`](NFeBuilder::diario)
+/// da nota, a NF-e assinada fica em `XMLGerado` até a [`RegularizacaoEpecBuilder`].
+///
+/// # Exemplo
+///
+/// ```no_run
+/// use dfe::{EpecBuilder, NFeBuilder, RegularizacaoEpecBuilder, DfeError};
+///
+/// # async fn example(nfe: NFeBuilder, cert: dfe::Certificado) -> Result<(), DfeError> {
+/// let epec = EpecBuilder::new().nfe(nfe).send().await?;
+/// if epec.registrado() {
+///     std::fs::write(format!("{}-nfe.xml", epec.chave), &epec.nfe_xml)?;
+///     // … imprime o DANFE com DanfeBuilder::epec e libera a mercadoria
+/// }
+///
+/// // Com o autorizador da UF de volta:
+/// let r = RegularizacaoEpecBuilder::new()
+///     .certificado(cert)
+///     .nfe_assinada(&epec.nfe_xml)
+///     .send()
+///     .await?;
+/// println!("{} — {}", r.protocolo.inf_prot.c_stat, r.protocolo.inf_prot.x_motivo);
+/// # Ok(())
+/// # }
+/// ```
+pub struct EpecBuilder {
+    nfe: Option<NFeBuilder>,
+    webservices: Option<Webservices>,
+}
+
+impl EpecBuilder {
+    pub fn new() -> Self {
+        Self { nfe: None, webservices: None }
+    }
+
+    /// NF-e a declarar, com `tp_emis` 4. O certificado da nota assina o evento e abre a conexão;
+    /// os [`artefatos`](NFeBuilder::artefatos) da nota recebem também o evento.
+    pub fn nfe(mut self, nfe: NFeBuilder) -> Self { self.nfe = Some(nfe); self }
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    pub async fn send(self) -> Result<EpecResposta> {
+        let mut nfe = self.nfe.ok_or_else(|| DfeError::Validacao("nfe não informada".to_string()))?.interno()?;
+        if nfe.ide.tp_emis != 4 {
+            return Err(DfeError::Validacao(format!("EPEC exige tp_emis 4 (informado: {})", nfe.ide.tp_emis)));
+        }
+        if nfe.ide.mod_ != 55 {
+            return Err(DfeError::Validacao("EPEC pelo Ambiente Nacional é exclusivo da NF-e (modelo 55)".to_string()));
+        }
+        if nfe.dest.is_none() {
+            return Err(DfeError::Validacao("EPEC exige o destinatário da NF-e".to_string()));
+        }
+        nfe.credencial.validar(nfe.emit.cnpj.as_deref())?;
+        let reserva = Reserva::new(nfe.numeracao.take(), &mut nfe.ide, &nfe.emit)?;
+        let etapas  = Etapas::new(nfe.diario.take(), &nfe.ide, &nfe.emit);
+
+        let preparado = async {
+            etapas.liberar()?;
+            let signed     = build_signed_xml(nfe).await?;
+            let evento_xml = evento_epec(&signed.validated_xml, &signed.chave, &signed.credencial)?;
+            let url        = nfe_recepcao_evento_an(signed.ide_tp_amb, self.webservices.as_ref())?;
+            let client     = WebService::client(signed.credencial.identity()?)?;
+            Ok::<_, DfeError>((signed, evento_xml, url, client))
+        }
+        .await;
+        // Nada foi transmitido: o número reservado não chegou a ser usado
+        let (signed, evento_xml, url, client) = match preparado {
+            Ok(preparado) => preparado,
+            Err(e) => {
+                reserva.descartar()?;
+                return Err(e);
+            }
+        };
+        etapas.registrar(EstadoEmissao::XMLGerado, &signed.chave, &signed.validated_xml, None)?;
+        let envelope  = evento::envelope_xml(&evento_xml);
+        let artefatos = signed.artefatos.para("epec", &signed.chave);
+        // Sem resposta não se sabe se o EPEC foi registrado: o número segue reservado
+        let body      = WebService::transmitir(client, &url, envelope.clone(), &artefatos).await?;
+
+        let ret = evento::ret_evento(&body)?;
+        let proc_evento_nfe = ret.proc_evento_nfe(&evento_xml);
+        if proc_evento_nfe.is_none() {
+            reserva.descartar()?;
+        }
+        Ok(EpecResposta {
+            chave: signed.chave,
+            proc_evento_nfe,
+            c_stat: ret.c_stat,
+            x_motivo: ret.x_motivo,
+            n_prot: ret.n_prot,
+            dh_reg_evento: ret.dh_reg_evento,
+            nfe_xml: signed.validated_xml,
+            send_xml: envelope,
+            receive_xml: body,
+        })
+    }
+}
+
+impl Default for EpecBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Transmite ao autorizador da UF a NF-e emitida em EPEC e concilia o resultado.
+///
+/// Se a SEFAZ acusar duplicidade (`cStat` 204 — a nota já foi autorizada numa
+/// tentativa anterior cuja resposta se perdeu), o protocolo é recuperado pela
+/// consulta da chave e o `nfeProc` é montado do mesmo jeito.
+pub struct RegularizacaoEpecBuilder {
+    certificado:  Option<Certificado>,
+    cert_path:    Option<String>,
+    cert_pass:    Option<String>,
+    nfe_assinada: Option<String>,
+    webservices:  Option<Webservices>,
+    artefatos:    Option<Arc<dyn ArtefatoSink>>,
+    numeracao:    Option<Numeracao>,
+    diario:       Option<Arc<dyn DiarioEmissao>>,
+}
+
+impl RegularizacaoEpecBuilder {
+    pub fn new() -> Self {
+        Self {
+            certificado: None, cert_path: None, cert_pass: None, nfe_assinada: None, webservices: None, artefatos: None,
+            numeracao: None, diario: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+    /// NF-e assinada devolvida em [`EpecResposta::nfe_xml`]; não é assinada de novo
+    pub fn nfe_assinada(mut self, xml: &str) -> Self { self.nfe_assinada = Some(xml.to_string()); self }
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+    /// Destino ([`ArtefatoSink`]) do envelope, do retorno e do `nfeProc`. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+    /// Numeração ([`Numeracao`]) onde o resultado da SEFAZ é registrado
+    pub fn numeracao(mut self, n: Numeracao) -> Self { self.numeracao = Some(n); self }
+    /// Diário ([`DiarioEmissao`]) onde as etapas da transmissão são registradas
+    pub fn diario(mut self, d: Arc<dyn DiarioEmissao>) -> Self { self.diario = Some(d); self }
+
+    /// Retorna [`Response`] com o `nfeProc` quando autorizada; caso contrário, o protocolo de rejeição e a NF-e assinada.
+    pub async fn send(self) -> Result<Response> {
+        let certificado = Certificado::resolver(self.certificado, self.cert_path, self.cert_pass)?;
+        let nfe_xml     = self.nfe_assinada.ok_or_else(|| DfeError::Validacao("nfe_assinada não informada".to_string()))?;
+
+        let chave   = chave_nfe(&nfe_xml)?;
+        let tp_emis = extract_xml_tag(&nfe_xml, "tpEmis").unwrap_or_default();
+        if tp_emis != "4" {
+            return Err(DfeError::Validacao(format!("NF-e {} não foi emitida em EPEC (tpEmis {})", chave, tp_emis)));
+        }
+        transmitir_assinada(certificado, &nfe_xml, self.webservices, self.artefatos, self.numeracao.as_ref(), self.diario).await
+    }
+}
+
+impl Default for RegularizacaoEpecBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Primeiro bloco <tag>…</tag> do XML
+fn bloco<'a>(xml: &'a str, tag: &str) -> &'a str {
+    let inicio = xml.find(&format!("<{}>", tag));
+    let fim = xml.find(&format!("</{}>", tag));
+    match (inicio, fim) {
+        (Some(i), Some(f)) if i < f => &xml[i..f],
+        _ => "",
+    }
+}
+
+/// `evento` EPEC assinado com os dados da NF-e em contingência.
+fn evento_epec(nfe_xml: &str, chave: &str, credencial: &Credencial) -> Result<String> {
+    let ide   = bloco(nfe_xml, "ide");
+    let emit  = bloco(nfe_xml, "emit");
+    let dest  = bloco(nfe_xml, "dest");
+    let total = bloco(nfe_xml, "ICMSTot");
+    let campo = |bloco: &str, tag: &str| extract_xml_tag(bloco, tag).unwrap_or_default();
+
+    let documento = ["CNPJ", "CPF", "idEstrangeiro"]
+        .iter()
+        .find_map(|tag| extract_xml_tag(dest, tag).map(|v| format!("<{0}>{1}</{0}>", tag, v)))
+        .unwrap_or_default();
+    let ie_dest = extract_xml_tag(dest, "IE").map(|v| format!("<IE>{}</IE>", v)).unwrap_or_default();
+
+    let det_evento = format!(
+        "<descEvento>EPEC</descEvento><cOrgaoAutor>{}</cOrgaoAutor><tpAutor>1</tpAutor><verAplic>{}</verAplic><dhEmi>{}</dhEmi><tpNF>{}</tpNF><IE>{}</IE><dest><UF>{}</UF>{}{}<vNF>{}</vNF><vICMS>{}</vICMS><vST>{}</vST></dest>",
+        &chave[..2], campo(ide, "verProc"), campo(ide, "dhEmi"), campo(ide, "tpNF"), campo(emit, "IE"),
+        campo(dest, "UF"), documento, ie_dest,
+        campo(total, "vNF"), campo(total, "vICMS"), campo(total, "vST"),
+    );
+
+    let dh_evento = get_current_date_time();
+    let tp_amb: u8 = campo(ide, "tpAmb").parse().unwrap_or_default();
+    Evento {
+        c_orgao: C_ORGAO_AN,
+        tp_amb,
+        cnpj: &chave[6..20],
+        chave,
+        dh_evento: &dh_evento,
+        tp_evento: TP_EVENTO,
+        n_seq_evento: 1,
+        det_evento: &det_evento,
+    }
+    .assinar(credencial)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAVE: &str = "35250111222333000181550010000000014000000014";
+
+    fn nfe_xml() -> String {
+        format!(
+            r#"<NFe xmlns="http://www.portalfiscal.inf.br/nfe"><infNFe Id="NFe{}" versao="4.00"><ide><cUF>35</cUF><mod>55</mod><dhEmi>2025-01-10T09:00:00-03:00</dhEmi><tpNF>1</tpNF><tpEmis>4</tpEmis><tpAmb>2</tpAmb><verProc>dfe 0.6</verProc><dhCont>2025-01-10T08:00:00-03:00</dhCont></ide><emit><CNPJ>11222333000181</CNPJ><enderEmit><UF>SP</UF></enderEmit><IE>111222333444</IE></emit><dest><CPF>12345678909</CPF><enderDest><UF>RJ</UF></enderDest><indIEDest>9</indIEDest></dest><total><ICMSTot><vICMS>18.00</vICMS><vST>0.00</vST><vNF>100.00</vNF></ICMSTot></total></infNFe></NFe>"#,
+            CHAVE
+        )
+    }
+
+    #[test]
+    fn test_evento_epec_com_dados_da_nfe() {
+        let credencial = Credencial::resolver(
+            None, None, Some("testes/certificado_teste_legacy.pfx".into()), Some("1234".into()),
+        ).unwrap();
+        let xml = evento_epec(&nfe_xml(), CHAVE, &credencial).unwrap();
+
+        assert!(xml.contains(&format!(r#"Id="ID110140{}01""#, CHAVE)));
+        assert!(xml.contains("<cOrgao>91</cOrgao><tpAmb>2</tpAmb><CNPJ>11222333000181</CNPJ>"));
+        assert!(xml.contains("</dhEvento><tpEvento>110140</tpEvento><nSeqEvento>1</nSeqEvento>"));
+        assert!(xml.contains(
+            "<descEvento>EPEC</descEvento><cOrgaoAutor>35</cOrgaoAutor><tpAutor>1</tpAutor><verAplic>dfe 0.6</verAplic><dhEmi>2025-01-10T09:00:00-03:00</dhEmi><tpNF>1</tpNF><IE>111222333444</IE>"
+        ));
+        assert!(xml.contains("<dest><UF>RJ</UF><CPF>12345678909</CPF><vNF>100.00</vNF><vICMS>18.00</vICMS><vST>0.00</vST></dest>"));
+        assert!(crate::assinatura::verificar(&xml).unwrap().valida());
+    }
+
+    #[test]
+    fn test_chave_da_nfe_assinada() {
+        assert_eq!(chave_nfe(&nfe_xml()).unwrap(), CHAVE);
+        assert!(chave_nfe("<NFe/>").is_err());
+        assert_eq!(bloco(&nfe_xml(), "dest").matches("<UF>").count(), 1);
+        assert_eq!(bloco("<a/>", "dest"), "");
+    }
+}
@@ -0,0 +1,21 @@
+use crate::tipos::Exporta;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename = "exporta")]
+pub struct ExportaProcess {
+    #[serde(rename = "UFSaidaPais")]
+    pub uf_saida_pais: String,
+    #[serde(rename = "xLocExporta")]
+    pub x_loc_exporta: String,
+    #[serde(rename = "xLocDespacho", skip_serializing_if = "Option::is_none")]
+    pub x_loc_despacho: Option<String>,
+}
+
+pub fn exporta_process(exporta: Option<&Exporta>) -> Option<ExportaProcess> {
+    exporta.map(|e| ExportaProcess {
+        uf_saida_pais: e.uf_saida_pais.clone(),
+        x_loc_exporta: e.x_loc_exporta.clone(),
+        x_loc_despacho: e.x_loc_despacho.clone(),
+    })
+}
@@ -137,4 +137,12 @@ pub struct IdeProcess {
     /// Ex: 1.0.0
     #[serde(rename = "verProc")]
     pub ver_proc: String,
+
+    /// Data e Hora da entrada em contingência no formato UTC: AAAA-MM-DDThh:mm:ssTZD
+    #[serde(rename = "dhCont", skip_serializing_if = "Option::is_none")]
+    pub dh_cont: Option<String>,
+
+    /// Justificativa da entrada em contingência (15 a 256 caracteres)
+    #[serde(rename = "xJust", skip_serializing_if = "Option::is_none")]
+    pub x_just: Option<String>,
 }
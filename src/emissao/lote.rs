@@ -0,0 +1,404 @@
+// Emissão em lote: um único <enviNFe> com até 50 notas, processamento síncrono
+// (indSinc=1, só aceito pela SEFAZ com uma nota) ou assíncrono (indSinc=0) com
+// consulta do recibo em NFeRetAutorizacao4.
+
+use super::diario::Etapas;
+use super::numeracao::{Reserva, UsoNumero};
+use super::{build_signed_xml, contingencia, gerar_id_lote, nfe_proc, EstadoEmissao, NFeBuilder, SignedNfe, TagInfProt};
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::connection::WebService;
+use crate::interno::ws::{nfe_autorizacao, nfe_ret_autorizacao, Webservices};
+use regex::Regex;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Quantidade máxima de NF-e em um mesmo `enviNFe`.
+pub const MAX_NOTAS_LOTE: usize = 50;
+
+// Tentativas padrão de consulta do recibo antes de devolver o lote em processamento
+const TENTATIVAS_PADRAO: u32 = 10;
+
+/// Resultado de uma nota do lote.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct NotaLote {
+    /// Chave de acesso da NF-e (44 dígitos).
+    pub chave: String,
+    /// Protocolo devolvido pela SEFAZ. `None` se o lote foi rejeitado ou ainda está em processamento.
+    pub protocolo: Option<TagInfProt>,
+    /// XML `nfeProc` quando autorizada (`cStat` 100/150); caso contrário, a NF-e assinada.
+    pub xml: String,
+}
+
+impl NotaLote {
+    /// `true` quando a nota foi autorizada (`cStat` 100 ou 150).
+    pub fn autorizada(&self) -> bool {
+        self.protocolo.as_ref().is_some_and(|p| matches!(p.inf_prot.c_stat, 100 | 150))
+    }
+}
+
+/// Resposta do envio de lote retornada por [`LoteNFeBuilder::emitir`].
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct LoteResponse {
+    /// Identificador do lote enviado (`idLote`).
+    pub id_lote: String,
+    /// Status do lote: `104` = processado; `103`/`105` = ainda em processamento; demais = rejeição do lote.
+    pub c_stat: i32,
+    /// Descrição do status do lote.
+    pub x_motivo: String,
+    /// Número do recibo (`nRec`) no processamento assíncrono.
+    pub n_rec: Option<String>,
+    /// Resultado de cada nota, na ordem em que foram adicionadas.
+    pub notas: Vec<NotaLote>,
+}
+
+/// Builder para emissão de um lote de NF-e/NFC-e em um único `enviNFe`.
+///
+/// Todas as notas devem ser da mesma UF, modelo, ambiente e tipo de emissão
+/// (vão para o mesmo webservice). A conexão mTLS usa o certificado da primeira nota.
+///
+/// # Exemplo
+///
+/// ```no_run
+/// use dfe::{LoteNFeBuilder, NFeBuilder, DfeError};
+///
+/// # async fn example(notas: Vec<NFeBuilder>) -> Result<(), DfeError> {
+/// let lote = LoteNFeBuilder::new().notas(notas).emitir().await?;
+/// for nota in lote.notas.iter().filter(|n| n.autorizada()) {
+///     std::fs::write(format!("{}-procNFe.xml", nota.chave), &nota.xml)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LoteNFeBuilder {
+    notas: Vec<NFeBuilder>,
+    id_lote: Option<String>,
+    sincrono: bool,
+    tentativas: Option<u32>,
+    webservices: Option<Webservices>,
+    artefatos: Option<Arc<dyn ArtefatoSink>>,
+}
+
+impl LoteNFeBuilder {
+    /// Cria um lote vazio, assíncrono (`indSinc=0`).
+    pub fn new() -> Self {
+        Self { notas: Vec::new(), id_lote: None, sincrono: false, tentativas: None, webservices: None, artefatos: None }
+    }
+
+    /// Adiciona uma nota ao lote.
+    pub fn nota(mut self, nfe: NFeBuilder) -> Self { self.notas.push(nfe); self }
+    /// Adiciona várias notas ao lote.
+    pub fn notas(mut self, nfes: Vec<NFeBuilder>) -> Self { self.notas.extend(nfes); self }
+    /// `idLote` (até 15 dígitos). Se omitido, é gerado a partir da data/hora.
+    pub fn id_lote(mut self, id: &str) -> Self { self.id_lote = Some(id.to_string()); self }
+    /// `indSinc=1`: a SEFAZ responde com o protocolo na mesma requisição. Só aceito para lote de uma nota.
+    pub fn sincrono(mut self, v: bool) -> Self { self.sincrono = v; self }
+    /// Máximo de consultas ao recibo (padrão: 10). Entre consultas aguarda o `tMed` informado pela SEFAZ.
+    pub fn tentativas(mut self, n: u32) -> Self { self.tentativas = Some(n); self }
+    /// Endpoints que substituem os da tabela embutida. Os `webservices` das notas individuais são ignorados.
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+    /// Destino ([`ArtefatoSink`]) das notas assinadas, do envelope do lote e dos retornos. Substitui o das notas individuais.
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    /// Assina, valida e transmite o lote; no modo assíncrono consulta o recibo até o
+    /// lote ser processado ou as tentativas se esgotarem.
+    ///
+    /// # Erros
+    ///
+    /// Retorna [`DfeError::Validacao`] se o lote estiver vazio, tiver mais de
+    /// [`MAX_NOTAS_LOTE`] notas, misturar UF/modelo/ambiente ou pedir resposta
+    /// síncrona com mais de uma nota.
+    pub async fn emitir(self) -> Result<LoteResponse> {
+        validar_lote(self.notas.len(), self.sincrono)?;
+        if let Some(id) = &self.id_lote {
+            if id.is_empty() || id.len() > 15 || !id.chars().all(|c| c.is_ascii_digit()) {
+                return Err(DfeError::Validacao(format!("idLote inválido: [{}]. Informe até 15 dígitos.", id)));
+            }
+        }
+
+        let mut assinadas: Vec<SignedNfe> = Vec::with_capacity(self.notas.len());
+        let mut etapas: Vec<Etapas> = Vec::with_capacity(self.notas.len());
+        let mut reservas: Vec<Reserva> = Vec::with_capacity(self.notas.len());
+        let webservices = self.webservices.as_ref();
+        let preparado = async {
+            for nota in self.notas {
+                let mut nfe = nota.interno()?;
+                nfe.credencial.validar(nfe.emit.cnpj.as_deref())?;
+                if self.artefatos.is_some() {
+                    nfe.artefatos = self.artefatos.clone();
+                }
+                nfe.webservices = webservices.cloned();
+                reservas.push(Reserva::new(nfe.numeracao.take(), &mut nfe.ide, &nfe.emit)?);
+                let etapa = Etapas::new(nfe.diario.take(), &nfe.ide, &nfe.emit);
+                etapa.liberar()?;
+                let signed = build_signed_xml(nfe).await?;
+                if let Some(primeira) = assinadas.first() {
+                    if (primeira.ide_uf, primeira.ide_mod, primeira.ide_tp_amb, primeira.ide_tp_emis)
+                        != (signed.ide_uf, signed.ide_mod, signed.ide_tp_amb, signed.ide_tp_emis)
+                    {
+                        return Err(DfeError::Validacao(format!(
+                            "NF-e {} difere da primeira nota do lote em UF, modelo, ambiente ou tipo de emissão.",
+                            signed.chave
+                        )));
+                    }
+                }
+                etapa.registrar(EstadoEmissao::XMLGerado, &signed.chave, &signed.validated_xml, None)?;
+                assinadas.push(signed);
+                etapas.push(etapa);
+            }
+
+            let primeira = &assinadas[0];
+            let svc = contingencia::usa_svc(primeira.ide_tp_emis);
+            let client = WebService::client(primeira.credencial.identity()?)?;
+            let url = nfe_autorizacao(primeira.ide_tp_amb, primeira.ide_uf, primeira.ide_mod, svc, webservices)?;
+            Ok((client, url))
+        }
+        .await;
+        // Nada foi transmitido: os números reservados não chegaram a ser usados
+        let (client, url) = match preparado {
+            Ok(preparado) => preparado,
+            Err(e) => {
+                for reserva in &reservas {
+                    reserva.descartar()?;
+                }
+                return Err(e);
+            }
+        };
+
+        let primeira = &assinadas[0];
+        let (uf, modelo, tp_amb) = (primeira.ide_uf, primeira.ide_mod, primeira.ide_tp_amb);
+        let svc = contingencia::usa_svc(primeira.ide_tp_emis);
+        let id_lote = self.id_lote.unwrap_or_else(gerar_id_lote);
+        let notas_xml: Vec<&str> = assinadas.iter().map(|s| s.nfe_xml.as_str()).collect();
+        let envelope = envi_nfe_xml(&id_lote, self.sincrono, &notas_xml);
+
+        for (etapa, signed) in etapas.iter().zip(&assinadas) {
+            etapa.registrar(EstadoEmissao::Requested, &signed.chave, &signed.validated_xml, None)?;
+        }
+        let artefatos = Artefatos::new(self.artefatos.clone(), "autorizacao", &id_lote);
+        let enviado = async {
+            let mut retorno = parse_retorno(&WebService::transmitir(client.clone(), &url, envelope, &artefatos).await?)?;
+
+            // 103 = Lote recebido com sucesso: o resultado sai na consulta do recibo
+            if retorno.c_stat == 103 {
+                let n_rec = retorno.n_rec.clone().ok_or_else(|| {
+                    DfeError::Xml("retEnviNFe com cStat 103 sem <nRec>.".to_string())
+                })?;
+                let url_recibo = nfe_ret_autorizacao(tp_amb, uf, modelo, svc, webservices)?;
+                let artefatos = artefatos.para("ret-autorizacao", &id_lote);
+                let mut t_med = retorno.t_med.unwrap_or(1);
+                for _ in 0..self.tentativas.unwrap_or(TENTATIVAS_PADRAO) {
+                    tokio::time::sleep(Duration::from_secs(t_med.max(1))).await;
+                    let envelope = cons_reci_xml(tp_amb, &n_rec);
+                    let consulta = parse_retorno(&WebService::transmitir(client.clone(), &url_recibo, envelope, &artefatos).await?)?;
+                    t_med = consulta.t_med.unwrap_or(t_med);
+                    retorno = Retorno { n_rec: Some(n_rec.clone()), ..consulta };
+                    // 105 = Lote em processamento
+                    if retorno.c_stat != 105 {
+                        break;
+                    }
+                }
+            }
+            Ok::<_, DfeError>(retorno)
+        }
+        .await;
+        let retorno = match enviado {
+            Ok(retorno) => retorno,
+            Err(e) => {
+                for (etapa, signed) in etapas.iter().zip(&assinadas) {
+                    etapa.registrar(EstadoEmissao::SemResposta, &signed.chave, &signed.validated_xml, None)?;
+                }
+                return Err(e);
+            }
+        };
+
+        let notas = montar_notas(assinadas, retorno.protocolos);
+        for ((etapa, reserva), nota) in etapas.iter().zip(&reservas).zip(&notas) {
+            let (estado, status) = match &nota.protocolo {
+                Some(p) => (EstadoEmissao::de_c_stat(p.inf_prot.c_stat), Some((p.inf_prot.c_stat, p.inf_prot.x_motivo.as_str()))),
+                // Lote ainda em processamento: o resultado sai na recuperação
+                None if matches!(retorno.c_stat, 103..=105) => (EstadoEmissao::SemResposta, None),
+                None => (EstadoEmissao::NaoAutorizado, Some((retorno.c_stat, retorno.x_motivo.as_str()))),
+            };
+            etapa.registrar(estado, &nota.chave, &nota.xml, status)?;
+            match (&nota.protocolo, estado) {
+                (Some(p), _) => reserva.registrar(UsoNumero::de_c_stat(p.inf_prot.c_stat))?,
+                (None, EstadoEmissao::NaoAutorizado) => reserva.registrar(UsoNumero::Rejeitado)?,
+                _ => {}
+            }
+        }
+        Ok(LoteResponse { id_lote, c_stat: retorno.c_stat, x_motivo: retorno.x_motivo, n_rec: retorno.n_rec, notas })
+    }
+}
+
+impl Default for LoteNFeBuilder {
+    fn default() -> Self { Self::new() }
+}
+
+fn validar_lote(quantidade: usize, sincrono: bool) -> Result<()> {
+    if quantidade == 0 {
+        return Err(DfeError::Validacao("O lote deve conter ao menos uma NF-e.".to_string()));
+    }
+    if quantidade > MAX_NOTAS_LOTE {
+        return Err(DfeError::Validacao(format!(
+            "O lote aceita no máximo {} NF-e; recebidas {}.", MAX_NOTAS_LOTE, quantidade
+        )));
+    }
+    if sincrono && quantidade > 1 {
+        // Rejeição 452 — resposta síncrona só para lote com uma única NF-e
+        return Err(DfeError::Validacao(
+            "Processamento síncrono (indSinc=1) só é aceito para lote com uma NF-e.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn envi_nfe_xml(id_lote: &str, sincrono: bool, notas: &[&str]) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeAutorizacao4"><enviNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><idLote>{}</idLote><indSinc>{}</indSinc>{}</enviNFe></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        id_lote, u8::from(sincrono), notas.concat()
+    )
+}
+
+fn cons_reci_xml(tp_amb: u8, n_rec: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeRetAutorizacao4"><consReciNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><tpAmb>{}</tpAmb><nRec>{}</nRec></consReciNFe></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        tp_amb, n_rec
+    )
+}
+
+// Campos comuns a <retEnviNFe> e <retConsReciNFe>
+#[derive(Debug)]
+pub(super) struct Retorno {
+    pub(super) c_stat: i32,
+    pub(super) x_motivo: String,
+    pub(super) n_rec: Option<String>,
+    pub(super) t_med: Option<u64>,
+    pub(super) protocolos: Vec<TagInfProt>,
+}
+
+pub(super) fn parse_retorno(xml: &str) -> Result<Retorno> {
+    let re = Regex::new(r"(?s)<protNFe[^>]*>.*?</protNFe>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?;
+    let mut protocolos = Vec::new();
+    for m in re.find_iter(xml) {
+        let prot: TagInfProt = quick_xml::de::from_str(m.as_str())
+            .map_err(|e| DfeError::Xml(format!("Erro desserializar protocolo: {} — {}", e, m.as_str())))?;
+        protocolos.push(prot);
+    }
+
+    // cStat/xMotivo do lote: lidos fora dos <protNFe>, que têm os seus próprios
+    let lote = re.replace_all(xml, "");
+    let c_stat = super::extract_xml_tag(&lote, "cStat")
+        .and_then(|s| s.parse::<i32>().ok())
+        .ok_or_else(|| DfeError::Xml(format!("Retorno do lote sem cStat: {}", xml)))?;
+
+    Ok(Retorno {
+        c_stat,
+        x_motivo: super::extract_xml_tag(&lote, "xMotivo").unwrap_or_default(),
+        n_rec: super::extract_xml_tag(&lote, "nRec"),
+        t_med: super::extract_xml_tag(&lote, "tMed").and_then(|s| s.parse().ok()),
+        protocolos,
+    })
+}
+
+// Associa cada protocolo à sua nota pela chave e monta o nfeProc das autorizadas
+fn montar_notas(assinadas: Vec<SignedNfe>, mut protocolos: Vec<TagInfProt>) -> Vec<NotaLote> {
+    assinadas
+        .into_iter()
+        .map(|signed| {
+            let protocolo = protocolos
+                .iter()
+                .position(|p| p.inf_prot.ch_nfe == signed.chave)
+                .map(|i| protocolos.swap_remove(i));
+            let mut nota = NotaLote { chave: signed.chave, protocolo, xml: signed.validated_xml };
+            if nota.autorizada() {
+                if let Some(p) = &nota.protocolo {
+                    nota.xml = nfe_proc(&nota.xml, p);
+                }
+            }
+            nota
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAVE_1: &str = "35250111222333000181550010000000011000000010";
+    const CHAVE_2: &str = "35250111222333000181550010000000021000000025";
+
+    fn prot(chave: &str, c_stat: i32) -> String {
+        format!(
+            r#"<protNFe versao="4.00"><infProt><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><chNFe>{}</chNFe><dhRecbto>2025-01-10T10:00:00-03:00</dhRecbto><nProt>135250000000001</nProt><digVal>abc=</digVal><cStat>{}</cStat><xMotivo>Motivo {}</xMotivo></infProt></protNFe>"#,
+            chave, c_stat, c_stat
+        )
+    }
+
+    fn assinada(chave: &str) -> SignedNfe {
+        SignedNfe {
+            nfe_xml: String::new(),
+            validated_xml: format!(r#"<NFe xmlns="http://www.portalfiscal.inf.br/nfe"><infNFe Id="NFe{}"/></NFe>"#, chave),
+            credencial: crate::interno::signer::Credencial::resolver(
+                None, None, Some("testes/certificado_teste_legacy.pfx".into()), Some("1234".into()),
+            ).expect("certificado de teste"),
+            ide_mod: 55, ide_tp_amb: 2, ide_uf: "SP", ide_tp_emis: 1,
+            chave: chave.to_string(),
+            artefatos: Artefatos::new(None, "autorizacao", chave),
+        }
+    }
+
+    #[test]
+    fn test_retorno_recibo_assincrono() {
+        let xml = r#"<soap:Envelope><soap:Body><retEnviNFe versao="4.00"><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><cStat>103</cStat><xMotivo>Lote recebido com sucesso</xMotivo><cUF>35</cUF><dhRecbto>2025-01-10T10:00:00-03:00</dhRecbto><infRec><nRec>351000000000001</nRec><tMed>3</tMed></infRec></retEnviNFe></soap:Body></soap:Envelope>"#;
+        let r = parse_retorno(xml).unwrap();
+        assert_eq!(r.c_stat, 103);
+        assert_eq!(r.n_rec.as_deref(), Some("351000000000001"));
+        assert_eq!(r.t_med, Some(3));
+        assert!(r.protocolos.is_empty());
+    }
+
+    #[test]
+    fn test_lote_processado_associa_protocolos_por_chave() {
+        // Protocolos fora da ordem do envio; o status do lote não deve ser confundido com o das notas
+        let xml = format!(
+            r#"<retConsReciNFe versao="4.00"><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><nRec>351000000000001</nRec><cStat>104</cStat><xMotivo>Lote processado</xMotivo><cUF>35</cUF>{}{}</retConsReciNFe>"#,
+            prot(CHAVE_2, 539), prot(CHAVE_1, 100)
+        );
+        let r = parse_retorno(&xml).unwrap();
+        assert_eq!((r.c_stat, r.x_motivo.as_str()), (104, "Lote processado"));
+        assert_eq!(r.protocolos.len(), 2);
+
+        let notas = montar_notas(vec![assinada(CHAVE_1), assinada(CHAVE_2)], r.protocolos);
+        assert!(notas[0].autorizada());
+        assert!(notas[0].xml.contains("<nfeProc"));
+        assert!(notas[0].xml.contains(&format!("<chNFe>{}</chNFe>", CHAVE_1)));
+        assert!(!notas[1].autorizada());
+        assert_eq!(notas[1].protocolo.as_ref().unwrap().inf_prot.c_stat, 539);
+        assert!(notas[1].xml.starts_with("<NFe"));
+    }
+
+    #[test]
+    fn test_lote_rejeitado_sem_protocolos() {
+        let xml = r#"<retEnviNFe versao="4.00"><tpAmb>2</tpAmb><cStat>225</cStat><xMotivo>Rejeição: Falha no Schema XML do lote de NFe</xMotivo></retEnviNFe>"#;
+        let r = parse_retorno(xml).unwrap();
+        assert_eq!(r.c_stat, 225);
+        let notas = montar_notas(vec![assinada(CHAVE_1)], r.protocolos);
+        assert!(notas[0].protocolo.is_none());
+        assert!(parse_retorno("<html>502</html>").is_err());
+    }
+
+    #[test]
+    fn test_validar_lote() {
+        assert!(validar_lote(0, false).is_err());
+        assert!(validar_lote(MAX_NOTAS_LOTE, false).is_ok());
+        assert!(validar_lote(MAX_NOTAS_LOTE + 1, false).is_err());
+        assert!(validar_lote(1, true).is_ok());
+        assert!(validar_lote(2, true).is_err());
+
+        let envelope = envi_nfe_xml("123", false, &["<NFe>1</NFe>", "<NFe>2</NFe>"]);
+        assert!(envelope.contains("<idLote>123</idLote><indSinc>0</indSinc><NFe>1</NFe><NFe>2</NFe></enviNFe>"));
+        assert!(cons_reci_xml(2, "351000000000001").contains("<tpAmb>2</tpAmb><nRec>351000000000001</nRec>"));
+    }
+}
@@ -1,15 +1,26 @@
+mod contingencia;
 mod det;
 mod det_process;
+mod diario;
 mod emit;
-mod flag;
+mod epec;
+mod exporta;
 mod ide;
 mod inf_adic;
+mod lote;
+mod numeracao;
+mod offline;
 pub mod pag;
+mod regras;
 mod total;
 mod transp;
 
+use crate::consulta::{ConsultaProtocoloBuilder, ConsultaProtocoloResposta};
 use crate::error::{DfeError, Result};
-use crate::interno::cert::{Cert, DigestValue, RawPubKey, Sign};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::c14n;
+use crate::interno::cert::{Certificado, DigestValue};
+use crate::interno::signer::{Credencial, Signer};
 use crate::interno::chave_acesso::ChaveAcesso;
 use crate::interno::chave_acesso_props::ChaveAcessoProps;
 use crate::interno::cleaner;
@@ -18,27 +29,39 @@ use crate::interno::connection::WebService;
 use crate::interno::dates::get_current_date_time;
 use crate::interno::dest_xml::DestTAG;
 use crate::interno::validation::is_xml_valid;
-use crate::interno::ws::nfe_autorizacao;
-use crate::tipos::{Dest, Det, Emit, Ide, InfAdic, Pag, Total, Transp};
+use crate::interno::ws::{nfce_consulta_qr, nfce_url_chave, nfe_autorizacao, uf_sigla, Webservices};
+use crate::tipos::{Dest, Det, Emit, Exporta, Ide, InfAdic, Pag, Total, Transp};
 use det::det_process;
 use emit::{EmitProcess, EnderEmitProcess};
-use flag::FlagAutorizacao;
-use flag::FlagAutorizacaoEnum;
+use diario::Etapas;
 use ide::*;
+use exporta::exporta_process;
 use inf_adic::inf_adic_process;
+use numeracao::Reserva;
 use pag::pag_process;
 use quick_xml::se::to_string;
 use regex::Regex;
 use rust_decimal::Decimal;
-use std::fs::File;
-use std::io::Write;
+use std::sync::Arc;
 use total::total_process;
 use transp::transp_process;
 
+pub use diario::{
+    DiarioArquivo, DiarioEmissao, DiarioMemoria, EstadoEmissao, NotaDiario, Recuperacao,
+    RecuperacaoEmissaoBuilder, RegistroEmissao,
+};
+pub use epec::{EpecBuilder, EpecResposta, RegularizacaoEpecBuilder};
+pub use lote::{LoteNFeBuilder, LoteResponse, NotaLote, MAX_NOTAS_LOTE};
+pub use numeracao::{
+    ArmazemNumeracao, EstadoNumeracao, FaixaNumeracao, Numeracao, NumeracaoArquivo, NumeracaoMemoria,
+    SerieNumeracao, UsoNumero, MAX_N_NF,
+};
+pub use offline::{FilaOffline, NotaOffline, TransmissaoOffline, TransmissaoOfflineBuilder, PRAZO_TRANSMISSAO_HORAS};
+pub use regras::Violacao;
+
 // Struct interna de montagem — não exposta como API pública
 struct NFeInterno {
-    pub cert_path: String,
-    pub cert_pass: String,
+    pub credencial: Credencial,
     pub id_csc: Option<String>,
     pub csc: Option<String>,
     pub ide: Ide,
@@ -49,9 +72,14 @@ struct NFeInterno {
     pub transp: Transp,
     pub pag: Pag,
     pub inf_adic: Option<InfAdic>,
+    pub exporta: Option<Exporta>,
     pub active_ibs_cbs: Option<String>,
     pub desconto_rateio: Option<Decimal>,
     pub referencias: Vec<String>,
+    pub webservices: Option<Webservices>,
+    pub diario: Option<Arc<dyn DiarioEmissao>>,
+    pub numeracao: Option<Numeracao>,
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 /// Resposta da emissão de NF-e ou NFC-e retornada por [`NFeBuilder::emitir`].
@@ -96,20 +124,27 @@ pub struct TagInfProt {
 struct SignedNfe {
     nfe_xml: String,
     validated_xml: String,
-    cert_path: String,
-    cert_pass: String,
+    credencial: Credencial,
     ide_mod: u32,
     ide_tp_amb: u8,
+    ide_uf: &'static str,
+    ide_tp_emis: u8,
+    chave: String,
+    artefatos: Artefatos,
 }
 
 // Constrói e assina o XML da NF-e sem enviar à SEFAZ
 async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
-    let cert_path = nfe.cert_path.clone();
-    let cert_pass = nfe.cert_pass.clone();
+    let credencial = nfe.credencial.clone();
+    let sink = nfe.artefatos.clone();
     let ide_mod = nfe.ide.mod_;
     let ide_tp_amb = nfe.ide.tp_amb;
+    let ide_uf = uf_sigla(nfe.ide.c_uf)?;
+    let ide_tp_emis = nfe.ide.tp_emis;
+    contingencia::validar(&nfe.ide)?;
     let id_csc = nfe.id_csc.clone();
     let csc = nfe.csc.clone();
+    let webservices = nfe.webservices.clone();
     let inf_adic = nfe.inf_adic.clone();
     let referencias = nfe.referencias.clone();
 
@@ -157,11 +192,13 @@ async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
         ind_pres: nfe.ide.ind_pres,
         proc_emi: nfe.ide.proc_emi,
         ver_proc: nfe.ide.ver_proc.clone(),
+        dh_cont: nfe.ide.dh_cont.clone(),
+        x_just: nfe.ide.x_just.clone(),
     };
     if nfe.ide.mod_ == 65 {
         ide_process.dh_sai_ent = None;
         ide_process.c_nf = Some(codigo_numerico);
-        ide_process.dh_emi = Some(dh_emi);
+        ide_process.dh_emi = Some(dh_emi.clone());
     }
 
     // Serializa <ide> e injeta tags <NFref> antes de </ide> quando houver referências
@@ -215,15 +252,19 @@ async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
     }
 
     let total_process_result = total_process(nfe.total.clone(), dets_total, nfe.ide.tp_amb, nfe.active_ibs_cbs.clone())?;
-    let v_nf: f64 = total_process_result.icms_tot.v_nf.parse().unwrap_or(0.0);
+    let v_nf_xml = total_process_result.icms_tot.v_nf.clone();
+    let v_nf = total_process_result.v_nf;
     let transp_process_result = transp_process(nfe.transp.clone())?;
     let inf_adic_process_result = inf_adic_process(inf_adic)?;
+    let exporta_xml = exporta_process(nfe.exporta.as_ref())
+        .map(|e| to_string(&e).unwrap_or_default())
+        .unwrap_or_default();
 
     // pag_process recebe NFeInterno por valor — chamado por último
     let pag_process_result = pag_process(nfe, v_nf)?;
 
     let xml = format!(
-        "<infNFe xmlns=\"http://www.portalfiscal.inf.br/nfe\" Id=\"NFe{}\" versao=\"4.00\">{}{}{}{}{}{}{}{}{}",
+        "<infNFe xmlns=\"http://www.portalfiscal.inf.br/nfe\" Id=\"NFe{}\" versao=\"4.00\">{}{}{}{}{}{}{}{}{}{}",
         chave_acesso,
         ide_xml,
         to_string(&emit_process).unwrap_or_default(),
@@ -232,12 +273,13 @@ async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
         to_string(&transp_process_result).unwrap_or_default(),
         to_string(&pag_process_result).unwrap_or_default(),
         to_string(&inf_adic_process_result).unwrap_or_default(),
+        exporta_xml,
         "</infNFe>"
     );
 
     let xml = Strings::clear_xml_string(&xml);
-    let digest_value = DigestValue::sha1(&xml)?;
-    let x509_cert = RawPubKey::get_from_file(&cert_path, &cert_pass).await?;
+    let digest_value = DigestValue::c14n_sha1(&xml, "infNFe")?;
+    let x509_cert = credencial.cert_base64()?;
 
     let mut signed_info = String::new()
         + "<SignedInfo xmlns=\"http://www.w3.org/2000/09/xmldsig#\">"
@@ -252,8 +294,9 @@ async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
         + "<DigestValue>" + &digest_value + "</DigestValue>"
         + "</Reference></SignedInfo>";
     signed_info = cleaner::Strings::clear_xml_string(&signed_info);
+    signed_info = c14n::canonicalize(&signed_info, "SignedInfo")?;
 
-    let signature_base64 = Sign::xml_string(&signed_info, &cert_path, &cert_pass).await?;
+    let signature_base64 = credencial.sign_base64(&signed_info)?;
     let signature_nodes = signed_info + "<SignatureValue>" + &signature_base64 + "</SignatureValue>";
     let signature_xml = "<Signature xmlns=\"http://www.w3.org/2000/09/xmldsig#\">".to_string()
         + &signature_nodes
@@ -262,104 +305,294 @@ async fn build_signed_xml(nfe: NFeInterno) -> Result<SignedNfe> {
 
     let mut qrcode = String::new();
     if ide_mod == 65 {
-        let url_base = if ide_tp_amb == 2 {
-            "https://www.homologacao.nfce.fazenda.sp.gov.br/NFCeConsultaPublica/Paginas/ConsultaQRCode.aspx"
-        } else {
-            "https://www.nfce.fazenda.sp.gov.br/qrcode"
-        };
-        let versao_qr = "2";
-        let ambiente = ide_tp_amb.to_string();
+        let url_base = nfce_consulta_qr(ide_tp_amb, ide_uf, webservices.as_ref())?;
+        let url_consulta = nfce_url_chave(ide_tp_amb, ide_uf, webservices.as_ref())?;
         let id_csc = id_csc.ok_or_else(|| DfeError::Validacao("ID do CSC não foi informado.".to_string()))?;
         let csc = csc.ok_or_else(|| DfeError::Validacao("CSC não foi informado.".to_string()))?;
-        let c_hash = qrcode_hash(&chave_acesso, versao_qr, &ambiente, &id_csc, &csc)?;
-        let url_consulta = if ide_tp_amb == 2 {
-            "https://www.homologacao.nfce.fazenda.sp.gov.br/consulta"
-        } else {
-            "https://www.nfce.fazenda.sp.gov.br/consulta"
-        };
+        // Offline (tpEmis 9) o QR Code leva também dia de emissão, vNF e digVal
+        let offline = (ide_tp_emis == 9).then(|| QrCodeOffline {
+            dia_emi: dh_emi.get(8..10).unwrap_or_default(),
+            v_nf: &v_nf_xml,
+            digest_value: &digest_value,
+        });
+        let qr_code = qrcode_nfce(&url_base, &chave_acesso, ide_tp_amb, &id_csc, &csc, offline);
         qrcode = cleaner::Strings::clear_xml_string(&format!(
-            r#"<infNFeSupl><qrCode><![CDATA[{url_base}?p={chave_acesso}|{versao_qr}|{ambiente}|{id_csc}|{c_hash}]]></qrCode><urlChave>{url_consulta}</urlChave></infNFeSupl>"#
+            r#"<infNFeSupl><qrCode><![CDATA[{qr_code}]]></qrCode><urlChave>{url_consulta}</urlChave></infNFeSupl>"#
         ));
     }
 
     let nfe_xml = "<NFe xmlns=\"http://www.portalfiscal.inf.br/nfe\">".to_string()
         + &xml + &qrcode + &signature_xml + "</NFe>";
 
-    let mut f = File::create("./nfe_request.xml").expect("nfe_request.xml");
-    f.write_all(nfe_xml.as_bytes()).expect("write");
+    let artefatos = Artefatos::new(sink, "autorizacao", &chave_acesso);
+    artefatos.gravar("nfe", &nfe_xml);
 
     let validated_xml = match is_xml_valid(&nfe_xml) {
         Ok(x) => x,
         Err(e) => return Err(DfeError::Validacao(format!("is_xml_valid: [{}]", e))),
     };
 
-    Ok(SignedNfe { nfe_xml, validated_xml, cert_path, cert_pass, ide_mod, ide_tp_amb })
+    Ok(SignedNfe { nfe_xml, validated_xml, credencial, ide_mod, ide_tp_amb, ide_uf, ide_tp_emis, chave: chave_acesso, artefatos })
 }
 
-async fn emit_nfe(nfe: NFeInterno) -> Result<Response> {
-    let flag = FlagAutorizacao::start().await.map_err(DfeError::Validacao)?;
-    match flag {
-        FlagAutorizacaoEnum::Ready => {}
-        _ => return Err(DfeError::Validacao(format!(
-            "Flag de autorização inválida para emissão: [{:?}].", flag
-        ))),
+async fn emit_nfe(mut nfe: NFeInterno) -> Result<Response> {
+    let reserva = Reserva::new(nfe.numeracao.take(), &mut nfe.ide, &nfe.emit)?;
+    let etapas = Etapas::new(nfe.diario.take(), &nfe.ide, &nfe.emit);
+    let webservices = nfe.webservices.clone();
+    let sink = nfe.artefatos.clone();
+
+    let preparado = async {
+        etapas.liberar()?;
+        let signed = build_signed_xml(nfe).await?;
+        // Em contingência SVC só a autorização muda de endereço; consultas e eventos
+        // continuam no autorizador normal da UF.
+        let svc = contingencia::usa_svc(signed.ide_tp_emis);
+        let url = nfe_autorizacao(signed.ide_tp_amb, signed.ide_uf, signed.ide_mod, svc, webservices.as_ref())?;
+        let client = WebService::client(signed.credencial.identity()?)?;
+        Ok::<_, DfeError>((signed, url, client))
     }
+    .await;
+    // Nada foi transmitido: o número reservado não chegou a ser usado
+    let (signed, url, client) = match preparado {
+        Ok(preparado) => preparado,
+        Err(e) => {
+            reserva.descartar()?;
+            return Err(e);
+        }
+    };
+    etapas.registrar(EstadoEmissao::XMLGerado, &signed.chave, &signed.validated_xml, None)?;
+    let envelope = lote::envi_nfe_xml(&gerar_id_lote(), true, &[&signed.nfe_xml]);
+
+    etapas.registrar(EstadoEmissao::Requested, &signed.chave, &signed.validated_xml, None)?;
+    let enviado = WebService::transmitir(client, &url, envelope, &signed.artefatos)
+        .await
+        .and_then(|body| xml_result(&body, signed.validated_xml.clone()));
+
+    // Sem protocolo não se sabe se a SEFAZ autorizou: a nota fica para a recuperação
+    let result = match enviado {
+        Ok(result) => result,
+        Err(e) => {
+            etapas.registrar(EstadoEmissao::SemResposta, &signed.chave, &signed.validated_xml, None)?;
+            return Err(e);
+        }
+    };
+    let (protocolo, nfe_proc_xml) = match result.protocolo.inf_prot.c_stat {
+        100 | 150 => {
+            let xml = nfe_proc(&result.xml, &result.protocolo).replace("\\", "");
+            (result.protocolo, xml)
+        }
+        // Duplicidade: autorizada numa tentativa cuja resposta se perdeu
+        204 => {
+            let consulta = match signed.credencial.certificado() {
+                Ok(certificado) => {
+                    consultar_chave(certificado.clone(), signed.ide_tp_amb, &signed.chave, &signed.nfe_xml, webservices, sink).await
+                }
+                Err(e) => Err(e),
+            };
+            match consulta {
+                Ok(ConsultaProtocoloResposta { protocolo: Some(protocolo), nfe_proc: Some(xml), .. }) => (protocolo, xml),
+                // Consultada sem autorização (ex.: denegada)
+                Ok(ConsultaProtocoloResposta { protocolo: Some(protocolo), nfe_proc_divergencia: None, .. }) => {
+                    let inf_prot = &protocolo.inf_prot;
+                    let retorno = Some((inf_prot.c_stat, inf_prot.x_motivo.as_str()));
+                    etapas.registrar(EstadoEmissao::de_c_stat(inf_prot.c_stat), &signed.chave, &signed.validated_xml, retorno)?;
+                    reserva.registrar(UsoNumero::de_c_stat(inf_prot.c_stat))?;
+                    return Ok(Response { protocolo, xml: signed.validated_xml });
+                }
+                // Sem o nfeProc da nota autorizada o resultado segue em aberto para a recuperação
+                Ok(consulta) => {
+                    etapas.registrar(EstadoEmissao::SemResposta, &signed.chave, &signed.validated_xml, None)?;
+                    return Err(consulta.nfe_proc_divergencia.unwrap_or_else(|| {
+                        DfeError::Webservice(format!(
+                            "NF-e {} em duplicidade sem protocolo na consulta: cStat {} — {}",
+                            signed.chave, consulta.c_stat, consulta.x_motivo
+                        ))
+                    }));
+                }
+                Err(e) => {
+                    etapas.registrar(EstadoEmissao::SemResposta, &signed.chave, &signed.validated_xml, None)?;
+                    return Err(e);
+                }
+            }
+        }
+        c_stat => {
+            let retorno = Some((c_stat, result.protocolo.inf_prot.x_motivo.as_str()));
+            etapas.registrar(EstadoEmissao::de_c_stat(c_stat), &signed.chave, &signed.validated_xml, retorno)?;
+            reserva.registrar(UsoNumero::de_c_stat(c_stat))?;
+            return Ok(result);
+        }
+    };
+    signed.artefatos.gravar("procNFe", &nfe_proc_xml);
+    let retorno = Some((protocolo.inf_prot.c_stat, protocolo.inf_prot.x_motivo.as_str()));
+    etapas.registrar(EstadoEmissao::Autorizado, &signed.chave, &nfe_proc_xml, retorno)?;
+    reserva.registrar(UsoNumero::Autorizado)?;
+    Ok(Response { protocolo, xml: nfe_proc_xml })
+}
 
-    let signed = build_signed_xml(nfe).await?;
-
-    let id_lote = 100;
-    let xml_envelope = format!(
-        r#"<soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeAutorizacao4"><enviNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><idLote>{}</idLote><indSinc>1</indSinc>{}</enviNFe></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
-        id_lote, &signed.nfe_xml
+// Anexa o <protNFe> à NF-e assinada, formando o <nfeProc> de distribuição
+fn nfe_proc(validated_xml: &str, protocolo: &TagInfProt) -> String {
+    let inf_prot = &protocolo.inf_prot;
+    let prot_nfe = format!(
+        r#"</NFe><protNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><infProt><tpAmb>{}</tpAmb><verAplic>{}</verAplic><chNFe>{}</chNFe><dhRecbto>{}</dhRecbto><nProt>{}</nProt><digVal>{}</digVal><cStat>{}</cStat><xMotivo>{}</xMotivo></infProt></protNFe></nfeProc>"#,
+        inf_prot.tp_amb, inf_prot.ver_aplic, inf_prot.ch_nfe, inf_prot.dh_recbto,
+        inf_prot.n_prot.clone().unwrap_or_default(),
+        inf_prot.dig_val.clone().unwrap_or_default(),
+        inf_prot.c_stat, inf_prot.x_motivo
     );
+    r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#.to_string()
+        + &validated_xml.replace("</NFe>", &prot_nfe)
+}
 
-    let url = nfe_autorizacao(signed.ide_tp_amb, "SP", signed.ide_mod, false)?;
-    let cert = Cert::from_pfx(&signed.cert_path, &signed.cert_pass)?;
-    let client = WebService::client(cert.identity)?;
-
-    let xml_with_declaration = if xml_envelope.starts_with("<?xml") {
-        xml_envelope.clone()
-    } else {
-        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}", xml_envelope)
+// Transmite uma NF-e já assinada (contingência EPEC, NFC-e offline ou FS-DA) e registra
+// as etapas no diário e o destino do número na numeração, se houver. Sem resposta a nota
+// fica em `SemResposta` e o número segue reservado.
+async fn transmitir_assinada(
+    certificado: Certificado,
+    nfe_xml: &str,
+    webservices: Option<Webservices>,
+    sink: Option<Arc<dyn ArtefatoSink>>,
+    numeracao: Option<&Numeracao>,
+    diario: Option<Arc<dyn DiarioEmissao>>,
+) -> Result<Response> {
+    let chave = chave_nfe(nfe_xml)?;
+    let (ide, emit) = identificacao_assinada(nfe_xml)?;
+    let etapas = Etapas::new(diario, &ide, &emit);
+
+    etapas.registrar(EstadoEmissao::Requested, &chave, nfe_xml, None)?;
+    let resposta = match enviar_assinada(certificado, nfe_xml, webservices, sink).await {
+        Ok(resposta) => resposta,
+        Err(e) => {
+            etapas.registrar(EstadoEmissao::SemResposta, &chave, nfe_xml, None)?;
+            return Err(e);
+        }
+    };
+    let inf_prot = &resposta.protocolo.inf_prot;
+    // 204 que a consulta não conciliou: o resultado segue em aberto para a recuperação
+    let estado = match inf_prot.c_stat {
+        204 => EstadoEmissao::SemResposta,
+        c_stat => EstadoEmissao::de_c_stat(c_stat),
     };
+    etapas.registrar(estado, &chave, &resposta.xml, Some((inf_prot.c_stat, inf_prot.x_motivo.as_str())))?;
+    if let Some(numeracao) = numeracao {
+        let uso = UsoNumero::de_c_stat(inf_prot.c_stat);
+        numeracao.registrar(&SerieNumeracao::de(&ide, &emit), ide.n_nf, uso)?;
+    }
+    Ok(resposta)
+}
 
-    let mut f = File::create("./nfe_request_envelope.xml").expect("envelope");
-    f.write_all(xml_with_declaration.as_bytes()).expect("write");
-    f.sync_all().expect("sync");
-
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/soap+xml; charset=utf-8")
-        .header("Content-Length", xml_with_declaration.len().to_string())
-        .body(xml_with_declaration)
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let result = xml_result(&response.text().await?, signed.validated_xml)?;
-        if result.protocolo.inf_prot.c_stat != 100 {
-            return Ok(result);
+// Envia a NF-e assinada ao autorizador normal da UF, sem assinar de novo. Com cStat 204
+// (duplicidade — autorizada numa tentativa cuja resposta se perdeu) o protocolo vem da
+// consulta pela chave.
+async fn enviar_assinada(
+    certificado: Certificado,
+    nfe_xml: &str,
+    webservices: Option<Webservices>,
+    sink: Option<Arc<dyn ArtefatoSink>>,
+) -> Result<Response> {
+    let nfe_xml = Regex::new(r"^\s*<\?xml[^>]*\?>\s*").map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?
+        .replace(nfe_xml, "")
+        .to_string();
+
+    let chave = chave_nfe(&nfe_xml)?;
+    let tp_amb: u8  = extract_xml_tag(&nfe_xml, "tpAmb").and_then(|v| v.parse().ok()).unwrap_or_default();
+    let c_uf: u16   = chave[..2].parse().unwrap_or_default();
+    let modelo: u32 = chave[20..22].parse().unwrap_or_default();
+
+    let url      = nfe_autorizacao(tp_amb, uf_sigla(c_uf)?, modelo, false, webservices.as_ref())?;
+    let envelope = lote::envi_nfe_xml(&gerar_id_lote(), true, &[&nfe_xml]);
+    let client   = WebService::client(certificado.identity())?;
+    let artefatos = Artefatos::new(sink.clone(), "autorizacao", &chave);
+    artefatos.gravar("nfe", &nfe_xml);
+    let retorno  = lote::parse_retorno(&WebService::transmitir(client, &url, envelope, &artefatos).await?)?;
+
+    let protocolo = retorno.protocolos.into_iter().find(|p| p.inf_prot.ch_nfe == chave);
+    match protocolo {
+        Some(p) if matches!(p.inf_prot.c_stat, 100 | 150) => {
+            let xml = nfe_proc(&nfe_xml, &p);
+            artefatos.gravar("procNFe", &xml);
+            Ok(Response { xml, protocolo: p })
         }
-        let protocolo = format!(
-            r#"</NFe><protNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><infProt><tpAmb>{}</tpAmb><verAplic>{}</verAplic><chNFe>{}</chNFe><dhRecbto>{}</dhRecbto><nProt>{}</nProt><digVal>{}</digVal><cStat>{}</cStat><xMotivo>{}</xMotivo></infProt></protNFe></nfeProc>"#,
-            result.protocolo.inf_prot.tp_amb, result.protocolo.inf_prot.ver_aplic,
-            result.protocolo.inf_prot.ch_nfe, result.protocolo.inf_prot.dh_recbto,
-            result.protocolo.inf_prot.n_prot.clone().unwrap_or_default(),
-            result.protocolo.inf_prot.dig_val.clone().unwrap_or_default(),
-            result.protocolo.inf_prot.c_stat, result.protocolo.inf_prot.x_motivo
-        );
-        let nfe_proc_xml = r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">"#.to_string()
-            + &result.xml.replace("</NFe>", &protocolo);
-        let mut f = File::create("./nfe_response.xml").expect("response.xml");
-        f.write_all(nfe_proc_xml.as_bytes()).expect("write");
-        Ok(Response { protocolo: result.protocolo, xml: nfe_proc_xml.replace("\\", "") })
-    } else {
-        let status = response.status();
-        let body = response.text().await?;
-        Err(DfeError::Webservice(format!("Erro na Requisição: {:?} -> Body: {:?}", status, body)))
+        Some(p) if p.inf_prot.c_stat == 204 => {
+            let consulta = consultar_chave(certificado, tp_amb, &chave, &nfe_xml, webservices, sink).await?;
+            // Autorizada, mas o XML enviado não é o que a SEFAZ tem: não há nfeProc a devolver
+            if let Some(e) = consulta.nfe_proc_divergencia {
+                return Err(e);
+            }
+            match (consulta.protocolo, consulta.nfe_proc) {
+                (Some(protocolo), Some(xml)) => Ok(Response { protocolo, xml }),
+                // Consultada sem autorização (ex.: denegada)
+                (Some(protocolo), None) => Ok(Response { protocolo, xml: nfe_xml }),
+                (None, _) => Ok(Response { protocolo: p, xml: nfe_xml }),
+            }
+        }
+        Some(p) => Ok(Response { protocolo: p, xml: nfe_xml }),
+        None => Err(DfeError::Xml(format!(
+            "Retorno sem protocolo da NF-e {}: cStat {} — {}", chave, retorno.c_stat, retorno.x_motivo
+        ))),
     }
 }
 
+// Consulta pela chave de uma nota em duplicidade (cStat 204); com a nota autorizada
+// e o XML conferindo, a resposta traz o nfeProc
+async fn consultar_chave(
+    certificado: Certificado,
+    tp_amb: u8,
+    chave: &str,
+    nfe_xml: &str,
+    webservices: Option<Webservices>,
+    sink: Option<Arc<dyn ArtefatoSink>>,
+) -> Result<ConsultaProtocoloResposta> {
+    let consulta = ConsultaProtocoloBuilder::new()
+        .certificado(certificado)
+        .tp_amb(tp_amb)
+        .chave(chave)
+        .nfe_assinada(nfe_xml);
+    let consulta = match webservices {
+        Some(w) => consulta.webservices(w),
+        None => consulta,
+    };
+    let consulta = match sink {
+        Some(s) => consulta.artefatos(s),
+        None => consulta,
+    };
+    consulta.send().await
+}
+
+// Ambiente, modelo, série, número e emitente de uma NF-e já assinada, no `Ide`/`Emit`
+// de que a numeração e o diário partem
+fn identificacao_assinada(nfe_xml: &str) -> Result<(Ide, Emit)> {
+    let chave = chave_nfe(nfe_xml)?;
+    let emit = Regex::new(r"(?s)<emit>.*?</emit>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?
+        .find(nfe_xml)
+        .map(|m| m.as_str())
+        .unwrap_or_default();
+    let ide = Ide {
+        tp_amb: extract_xml_tag(nfe_xml, "tpAmb").and_then(|v| v.parse().ok()).unwrap_or_default(),
+        mod_: chave[20..22].parse().unwrap_or_default(),
+        serie: chave[22..25].parse().unwrap_or_default(),
+        n_nf: chave[25..34].parse().unwrap_or_default(),
+        ..Default::default()
+    };
+    let emit = Emit { cnpj: extract_xml_tag(emit, "CNPJ"), cpf: extract_xml_tag(emit, "CPF"), ..Default::default() };
+    Ok((ide, emit))
+}
+
+// Chave de acesso do atributo Id da NF-e assinada
+fn chave_nfe(nfe_xml: &str) -> Result<String> {
+    Regex::new(r#"Id="NFe(\d{44})""#)
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?
+        .captures(nfe_xml)
+        .map(|c| c[1].to_string())
+        .ok_or_else(|| DfeError::Xml("NF-e sem Id de chave de acesso".to_string()))
+}
+
+// idLote: até 15 dígitos — data/hora local + um dígito aleatório
+fn gerar_id_lote() -> String {
+    let date = chrono::Local::now();
+    format!("{}{}", date.format("%Y%m%d%H%M%S"), rand::random::<u8>() % 10)
+}
+
 fn xml_result(response: &str, validated_xml: String) -> Result<Response> {
     let re = Regex::new(r#"<protNFe versao="4.00">(.*?)</protNFe>"#)
         .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?;
@@ -433,13 +666,60 @@ mod tests {
         let err = xml_result(resp, "<xml/>".into()).unwrap_err();
         assert!(matches!(err, DfeError::Xml(msg) if msg.contains("protNFe not found")));
     }
+
+    #[test]
+    fn qrcode_nfce_online_e_offline() {
+        let chave = "35250111222333000181650010000000019000000018";
+        let online = qrcode_nfce("https://qr", chave, 2, "1", "CSC", None);
+        assert_eq!(online, format!("https://qr?p={}|2|2|1|{}", chave, qrcode_hash(&format!("{}|2|2|1", chave), "CSC")));
+
+        let offline = QrCodeOffline { dia_emi: "10", v_nf: "25.90", digest_value: "AB=" };
+        let url = qrcode_nfce("https://qr", chave, 2, "1", "CSC", Some(offline));
+        let params = format!("{}|2|2|10|25.90|41423d|1", chave);
+        assert_eq!(url, format!("https://qr?p={}|{}", params, qrcode_hash(&params, "CSC")));
+        assert_eq!(qrcode_hash(&params, "CSC").len(), 40);
+    }
+
+    #[test]
+    fn identificacao_da_nota_assinada() {
+        let xml = r#"<?xml version="1.0"?><NFe><infNFe Id="NFe35250111222333000181650020000001239000000018"><ide><tpAmb>2</tpAmb><NFref><refNF><CNPJ>99999999000191</CNPJ></refNF></NFref></ide><emit><CNPJ>11222333000181</CNPJ></emit></infNFe></NFe>"#;
+        let (ide, emit) = identificacao_assinada(xml).unwrap();
+        assert_eq!((ide.tp_amb, ide.mod_, ide.serie, ide.n_nf), (2, 65, 2, 123));
+        assert_eq!(emit.cnpj.as_deref(), Some("11222333000181"));
+
+        let serie = SerieNumeracao::de(&ide, &emit);
+        let numeracao = Numeracao::new(Arc::new(NumeracaoMemoria::new()));
+        numeracao.registrar(&serie, 123, UsoNumero::Reservado).unwrap();
+        numeracao.registrar(&serie, 123, UsoNumero::de_c_stat(150)).unwrap();
+        assert_eq!(numeracao.uso(&serie, 123).unwrap(), Some(UsoNumero::Autorizado));
+    }
+}
+
+// Campos extras do QR Code da NFC-e emitida offline (tpEmis 9)
+struct QrCodeOffline<'a> {
+    dia_emi: &'a str,
+    v_nf: &'a str,
+    digest_value: &'a str,
+}
+
+// URL do QR Code versão 2: `p=chave|2|tpAmb|idCSC|hash` ou, offline,
+// `p=chave|2|tpAmb|dia|vNF|digVal(hex)|idCSC|hash`; hash = SHA-1 dos parâmetros + CSC
+fn qrcode_nfce(url_base: &str, chave_acesso: &str, tp_amb: u8, id_csc: &str, csc: &str, offline: Option<QrCodeOffline>) -> String {
+    let params = match offline {
+        Some(o) => {
+            let dig_val: String = o.digest_value.bytes().map(|b| format!("{:02x}", b)).collect();
+            format!("{chave_acesso}|2|{tp_amb}|{}|{}|{dig_val}|{id_csc}", o.dia_emi, o.v_nf)
+        }
+        None => format!("{chave_acesso}|2|{tp_amb}|{id_csc}"),
+    };
+    format!("{url_base}?p={params}|{}", qrcode_hash(&params, csc))
 }
 
-fn qrcode_hash(chave_acesso: &str, versao_qr: &str, ambiente: &str, id_csc: &str, csc: &str) -> Result<String> {
+fn qrcode_hash(params: &str, csc: &str) -> String {
     use sha1::{Digest, Sha1};
     let mut hasher = Sha1::new();
-    hasher.update(format!("{chave_acesso}|{versao_qr}|{ambiente}|{id_csc}{csc}").as_bytes());
-    Ok(format!("{:x}", hasher.finalize()))
+    hasher.update(format!("{params}{csc}").as_bytes());
+    format!("{:x}", hasher.finalize())
 }
 
 // ─── Builder público ──────────────────────────────────────────────────────────
@@ -476,6 +756,8 @@ fn qrcode_hash(chave_acesso: &str, versao_qr: &str, ambiente: &str, id_csc: &str
 /// # }
 /// ```
 pub struct NFeBuilder {
+    signer: Option<Arc<dyn Signer>>,
+    certificado: Option<Certificado>,
     cert_path: Option<String>,
     cert_pass: Option<String>,
     ide: Option<Ide>,
@@ -486,28 +768,41 @@ pub struct NFeBuilder {
     transporte: Option<Transp>,
     pagamento: Option<Pag>,
     informacoes_adicionais: Option<InfAdic>,
+    exportacao: Option<Exporta>,
     id_csc: Option<String>,
     csc: Option<String>,
     active_ibs_cbs: Option<String>,
     desconto_rateio: Option<Decimal>,
     referencias: Vec<String>,
+    webservices: Option<Webservices>,
+    x_just_offline: Option<String>,
+    diario: Option<Arc<dyn DiarioEmissao>>,
+    numeracao: Option<Numeracao>,
+    artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 impl NFeBuilder {
     /// Cria um builder vazio. Chame os métodos de configuração antes de [`emitir`](Self::emitir).
     pub fn new() -> Self {
         Self {
-            cert_path: None, cert_pass: None, ide: None, emitente: None,
+            signer: None, certificado: None, cert_path: None, cert_pass: None, ide: None, emitente: None,
             destinatario: None, itens: Vec::new(), total: None, transporte: None,
-            pagamento: None, informacoes_adicionais: None, id_csc: None, csc: None,
-            active_ibs_cbs: None, desconto_rateio: None, referencias: Vec::new(),
+            pagamento: None, informacoes_adicionais: None, exportacao: None, id_csc: None, csc: None,
+            active_ibs_cbs: None, desconto_rateio: None, referencias: Vec::new(), webservices: None,
+            x_just_offline: None, diario: None, numeracao: None, artefatos: None,
         }
     }
 
-    /// Caminho do certificado A1 (`.pfx`) e sua senha. **Obrigatório.**
+    /// Caminho do certificado A1 (`.pfx`) e sua senha. Obrigatório se [`certificado`](Self::certificado) não for informado.
+    ///
+    /// O PFX é lido e decifrado a cada emissão; para alto volume prefira [`certificado`](Self::certificado).
     pub fn cert(mut self, path: &str, pass: &str) -> Self {
         self.cert_path = Some(path.to_string()); self.cert_pass = Some(pass.to_string()); self
     }
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre emissões. Tem precedência sobre [`cert`](Self::cert).
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+    /// Assinante externo ([`Signer`]) para o XML. A transmissão ainda usa [`cert`](Self::cert) ou [`certificado`](Self::certificado) no mTLS.
+    pub fn signer(mut self, s: Arc<dyn Signer>) -> Self { self.signer = Some(s); self }
     /// Identificação do documento (`<ide>`). **Obrigatório.**
     pub fn ide(mut self, ide: Ide)       -> Self { self.ide = Some(ide); self }
     /// Dados do emitente (`<emit>`). **Obrigatório.**
@@ -524,6 +819,8 @@ impl NFeBuilder {
     pub fn pagamento(mut self, p: Pag)   -> Self { self.pagamento = Some(p); self }
     /// Informações adicionais (`<infAdic>`). Opcional.
     pub fn informacoes_adicionais(mut self, i: InfAdic) -> Self { self.informacoes_adicionais = Some(i); self }
+    /// Local de embarque da exportação (`<exporta>`). Obrigatório na NF-e de saída para o exterior (`id_dest` 3).
+    pub fn exportacao(mut self, e: Exporta) -> Self { self.exportacao = Some(e); self }
     /// ID do CSC (Código de Segurança do Contribuinte). **Obrigatório para NFC-e.**
     pub fn id_csc(mut self, id: &str)    -> Self { self.id_csc = Some(id.to_string()); self }
     /// Valor do CSC. **Obrigatório para NFC-e.**
@@ -534,15 +831,24 @@ impl NFeBuilder {
     pub fn desconto_rateio(mut self, v: Decimal) -> Self { self.desconto_rateio = Some(v); self }
     /// Adiciona uma chave de acesso referenciada (`<NFref><refNFe>`). Use para devolução (finNFe=4).
     pub fn referencia(mut self, chave: &str) -> Self { self.referencias.push(chave.to_string()); self }
-
-    /// Gera e valida o XML da NF-e sem enviar à SEFAZ.
-    ///
-    /// Útil para validação prévia (ex.: NF-e de devolução antes da emissão).
-    /// Retorna o XML assinado e validado pelo XSD oficial.
-    pub async fn gerar_xml(self) -> crate::error::Result<String> {
-        let cert_path  = self.cert_path.ok_or_else(|| DfeError::Configuracao("cert_path não informado".to_string()))?;
-        let cert_pass  = self.cert_pass.ok_or_else(|| DfeError::Configuracao("cert_pass não informado".to_string()))?;
-        let ide        = self.ide.ok_or_else(|| DfeError::Validacao("ide não informado".to_string()))?;
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+    /// Emite sem autorização prévia: NFC-e offline (`tp_emis` 9) ou NF-e em FS-DA (`tp_emis` 5),
+    /// com `dh_cont` (agora, se não informado no `ide`) e `x_just`. Finalize com [`emitir_offline`](Self::emitir_offline).
+    pub fn contingencia_offline(mut self, x_just: &str) -> Self { self.x_just_offline = Some(x_just.to_string()); self }
+    /// Diário ([`DiarioEmissao`]) onde cada etapa da emissão é registrada. Recusa emitir nota já
+    /// autorizada ou pendente de [`RecuperacaoEmissaoBuilder`].
+    pub fn diario(mut self, d: Arc<dyn DiarioEmissao>) -> Self { self.diario = Some(d); self }
+    /// Numeração ([`Numeracao`]) da série: com `n_nf: 0` no `ide` a emissão reserva o próximo
+    /// número e registra o resultado da SEFAZ. Recusa número já autorizado, denegado ou inutilizado.
+    pub fn numeracao(mut self, n: Numeracao) -> Self { self.numeracao = Some(n); self }
+    /// Destino ([`ArtefatoSink`]) da NF-e assinada, do envelope, do retorno e do `nfeProc`. Sem ele nada é gravado.
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    // Valida os campos obrigatórios e monta a struct interna de emissão
+    fn interno(self) -> Result<NFeInterno> {
+        let credencial = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
+        let mut ide    = self.ide.ok_or_else(|| DfeError::Validacao("ide não informado".to_string()))?;
         let emitente   = self.emitente.ok_or_else(|| DfeError::Validacao("emitente não informado".to_string()))?;
         let total      = self.total.ok_or_else(|| DfeError::Validacao("total não informado".to_string()))?;
         let transporte = self.transporte.ok_or_else(|| DfeError::Validacao("transporte não informado".to_string()))?;
@@ -551,17 +857,72 @@ impl NFeBuilder {
         if self.itens.is_empty() {
             return Err(DfeError::Validacao("pelo menos um item (det) deve ser informado".to_string()));
         }
+        if let Some(x_just) = self.x_just_offline {
+            ide.tp_emis = if ide.mod_ == 55 { 5 } else { 9 };
+            ide.dh_cont = ide.dh_cont.or_else(|| Some(get_current_date_time()));
+            ide.x_just = Some(x_just);
+        }
 
-        let signed = build_signed_xml(NFeInterno {
-            cert_path, cert_pass, id_csc: self.id_csc, csc: self.csc,
+        Ok(NFeInterno {
+            credencial, id_csc: self.id_csc, csc: self.csc,
             ide, emit: emitente, dest: self.destinatario,
             det: self.itens, total, transp: transporte, pag: pagamento,
             inf_adic: self.informacoes_adicionais,
+            exporta: self.exportacao,
             active_ibs_cbs: self.active_ibs_cbs,
             desconto_rateio: self.desconto_rateio,
             referencias: self.referencias,
-        }).await?;
+            webservices: self.webservices,
+            diario: self.diario,
+            numeracao: self.numeracao,
+            artefatos: self.artefatos,
+        })
+    }
+
+    /// Confere as regras de negócio do Anexo I do MOC que a SEFAZ aplica depois do XSD,
+    /// sem certificado e sem montar o XML.
+    ///
+    /// Cada [`Violacao`] traz o `cStat` da rejeição, o campo e a mensagem: CFOP × `id_dest`
+    /// e `tp_nf`, `ind_final` × `ind_ie_dest`, tolerância do `dh_emi`, NCM, CST/CSOSN × `Emit.crt`,
+    /// valores dos itens e totais de frete, seguro e outras despesas, importação (DI, II) e
+    /// exportação (`detExport`, `exporta`) pelos CFOP 3xxx/7xxx, e as restrições da NFC-e.
+    /// Lista vazia não garante a autorização: regras que dependem de cadastros da SEFAZ ficam de fora.
+    ///
+    /// # Erros
+    ///
+    /// Retorna [`DfeError::Validacao`] se `ide`, `emitente`, `total`, `pagamento` ou os itens não foram informados.
+    pub fn validar(&self) -> Result<Vec<Violacao>> {
+        let mut ide    = self.ide.clone().ok_or_else(|| DfeError::Validacao("ide não informado".to_string()))?;
+        let emitente   = self.emitente.as_ref().ok_or_else(|| DfeError::Validacao("emitente não informado".to_string()))?;
+        let total      = self.total.as_ref().ok_or_else(|| DfeError::Validacao("total não informado".to_string()))?;
+        let pagamento  = self.pagamento.as_ref().ok_or_else(|| DfeError::Validacao("pagamento não informado".to_string()))?;
+        if self.itens.is_empty() {
+            return Err(DfeError::Validacao("pelo menos um item (det) deve ser informado".to_string()));
+        }
+        if self.x_just_offline.is_some() {
+            ide.tp_emis = if ide.mod_ == 55 { 5 } else { 9 };
+        }
+
+        let nota = regras::Nota {
+            ide: &ide,
+            emit: emitente,
+            dest: self.destinatario.as_ref(),
+            itens: &self.itens,
+            total,
+            pag: pagamento,
+            exporta: self.exportacao.as_ref(),
+            referencias: &self.referencias,
+            desconto_rateio: self.desconto_rateio,
+        };
+        Ok(regras::validar(&nota, chrono::Local::now().fixed_offset()))
+    }
 
+    /// Gera e valida o XML da NF-e sem enviar à SEFAZ.
+    ///
+    /// Útil para validação prévia (ex.: NF-e de devolução antes da emissão).
+    /// Retorna o XML assinado e validado pelo XSD oficial.
+    pub async fn gerar_xml(self) -> crate::error::Result<String> {
+        let signed = build_signed_xml(self.interno()?).await?;
         Ok(signed.validated_xml)
     }
 
@@ -576,26 +937,44 @@ impl NFeBuilder {
     /// Retorna [`DfeError`] se algum campo obrigatório estiver ausente,
     /// a assinatura falhar ou a SEFAZ retornar erro de transmissão.
     pub async fn emitir(self) -> Result<Response> {
-        let cert_path  = self.cert_path.ok_or_else(|| DfeError::Configuracao("cert_path não informado".to_string()))?;
-        let cert_pass  = self.cert_pass.ok_or_else(|| DfeError::Configuracao("cert_pass não informado".to_string()))?;
-        let ide        = self.ide.ok_or_else(|| DfeError::Validacao("ide não informado".to_string()))?;
-        let emitente   = self.emitente.ok_or_else(|| DfeError::Validacao("emitente não informado".to_string()))?;
-        let total      = self.total.ok_or_else(|| DfeError::Validacao("total não informado".to_string()))?;
-        let transporte = self.transporte.ok_or_else(|| DfeError::Validacao("transporte não informado".to_string()))?;
-        let pagamento  = self.pagamento.ok_or_else(|| DfeError::Validacao("pagamento não informado".to_string()))?;
+        let nfe = self.interno()?;
+        nfe.credencial.validar(nfe.emit.cnpj.as_deref())?;
+        emit_nfe(nfe).await
+    }
 
-        if self.itens.is_empty() {
-            return Err(DfeError::Validacao("pelo menos um item (det) deve ser informado".to_string()));
+    /// Assina a nota sem autorização prévia e a guarda na [`FilaOffline`], sem contatar a SEFAZ:
+    /// NFC-e offline (`tp_emis` 9) ou NF-e em FS-DA (`tp_emis` 5).
+    ///
+    /// Na NFC-e o QR Code leva os parâmetros da contingência (dia da emissão, `vNF` e `digVal`)
+    /// e o DANFE impresso a partir de [`NotaOffline::xml`] traz "EMITIDA EM CONTINGÊNCIA". Na FS-DA
+    /// o DANFE sai com os avisos de contingência e o código de barras dos dados da NF-e.
+    /// Transmita as pendentes com [`TransmissaoOfflineBuilder`] dentro de [`NotaOffline::prazo`].
+    ///
+    /// Com [`diario`](Self::diario), a nota assinada fica em `XMLGerado`; informe o mesmo diário
+    /// em [`TransmissaoOfflineBuilder::diario`] para registrar a transmissão.
+    pub async fn emitir_offline(self, fila: &FilaOffline) -> Result<NotaOffline> {
+        let mut nfe = self.interno()?;
+        if !matches!((nfe.ide.mod_, nfe.ide.tp_emis), (65, 9) | (55, 5)) {
+            return Err(DfeError::Validacao(format!(
+                "emissão offline exige NFC-e com tp_emis 9 ou NF-e com tp_emis 5 (FS-DA); informado: modelo {}, tp_emis {}",
+                nfe.ide.mod_, nfe.ide.tp_emis
+            )));
         }
-
-        emit_nfe(NFeInterno {
-            cert_path, cert_pass, id_csc: self.id_csc, csc: self.csc,
-            ide, emit: emitente, dest: self.destinatario,
-            det: self.itens, total, transp: transporte, pag: pagamento,
-            inf_adic: self.informacoes_adicionais,
-            active_ibs_cbs: self.active_ibs_cbs,
-            desconto_rateio: self.desconto_rateio,
-            referencias: self.referencias,
-        }).await
+        nfe.credencial.validar(nfe.emit.cnpj.as_deref())?;
+        // O número segue reservado até a transmissão da fila
+        let reserva = Reserva::new(nfe.numeracao.take(), &mut nfe.ide, &nfe.emit)?;
+        let etapas = Etapas::new(nfe.diario.take(), &nfe.ide, &nfe.emit);
+        let guardada = async {
+            etapas.liberar()?;
+            let signed = build_signed_xml(nfe).await?;
+            let nota = fila.guardar(&signed.validated_xml)?;
+            etapas.registrar(EstadoEmissao::XMLGerado, &signed.chave, &signed.validated_xml, None)?;
+            Ok(nota)
+        }
+        .await;
+        if guardada.is_err() {
+            reserva.descartar()?;
+        }
+        guardada
     }
 }
@@ -0,0 +1,487 @@
+// Numeração de série/nNF: o próximo número de cada emitente, ambiente, modelo e série
+// vem de um armazém compartilhado entre terminais, para que dois caixas não emitam
+// o mesmo número (rejeição 539). Cada número guarda o destino que teve na SEFAZ;
+// os que nunca viraram nota ficam listados como lacunas a inutilizar.
+
+use crate::error::{DfeError, Result};
+use crate::inutilizacao::InutilizacaoBuilder;
+use crate::tipos::{Emit, Ide};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Maior `nNF` aceito pelo leiaute.
+pub const MAX_N_NF: u64 = 999_999_999;
+
+/// Série numerada: emitente, ambiente, modelo e série.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SerieNumeracao {
+    /// CNPJ (ou CPF) do emitente.
+    pub emitente: String,
+    /// Ambiente: `1` = produção · `2` = homologação.
+    pub tp_amb: u8,
+    /// Modelo: `55` ou `65`.
+    pub modelo: u32,
+    /// Série.
+    pub serie: u32,
+}
+
+impl SerieNumeracao {
+    pub(super) fn de(ide: &Ide, emit: &Emit) -> Self {
+        SerieNumeracao {
+            emitente: emit.cnpj.clone().or_else(|| emit.cpf.clone()).unwrap_or_default(),
+            tp_amb: ide.tp_amb,
+            modelo: ide.mod_,
+            serie: ide.serie,
+        }
+    }
+}
+
+/// Destino de um número da série.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsoNumero {
+    /// Entregue a uma emissão; o resultado na SEFAZ ainda não é conhecido.
+    Reservado,
+    /// Nota autorizada.
+    Autorizado,
+    /// Nota denegada — o número fica consumido.
+    Denegado,
+    /// Nota rejeitada ou descartada antes do envio: o número não chegou a ser usado.
+    Rejeitado,
+    /// Número inutilizado na SEFAZ.
+    Inutilizado,
+}
+
+impl UsoNumero {
+    /// Uso correspondente ao `cStat` do protocolo da nota.
+    pub fn de_c_stat(c_stat: i32) -> Self {
+        match c_stat {
+            // 204/539: duplicidade — o número já consta autorizado na SEFAZ
+            100 | 150 | 204 | 539 => UsoNumero::Autorizado,
+            110 | 205 | 301 | 302 | 303 => UsoNumero::Denegado,
+            _ => UsoNumero::Rejeitado,
+        }
+    }
+
+    /// `true` quando o número não pode mais ser usado nem inutilizado.
+    pub fn definitivo(&self) -> bool {
+        matches!(self, UsoNumero::Autorizado | UsoNumero::Denegado | UsoNumero::Inutilizado)
+    }
+}
+
+/// Situação de uma série no armazém.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EstadoNumeracao {
+    /// Primeiro número controlado; os anteriores ficam fora das lacunas.
+    pub inicio: u64,
+    /// Próximo número a reservar.
+    pub proximo: u64,
+    /// Destino de cada número já entregue ou informado.
+    pub usos: BTreeMap<u64, UsoNumero>,
+}
+
+impl Default for EstadoNumeracao {
+    fn default() -> Self {
+        EstadoNumeracao { inicio: 1, proximo: 1, usos: BTreeMap::new() }
+    }
+}
+
+impl EstadoNumeracao {
+    fn registrar(&mut self, n_nf: u64, uso: UsoNumero) {
+        let atual = self.usos.get(&n_nf);
+        if atual.is_some_and(|atual| atual.definitivo()) && !uso.definitivo() {
+            return;
+        }
+        self.usos.insert(n_nf, uso);
+        if n_nf >= self.proximo {
+            self.proximo = n_nf + 1;
+        }
+    }
+}
+
+/// Faixa contínua de números, de `inicio` a `fim`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaixaNumeracao {
+    /// Primeiro número.
+    pub inicio: u64,
+    /// Último número (igual a `inicio` para um número só).
+    pub fim: u64,
+}
+
+impl FaixaNumeracao {
+    /// Quantidade de números da faixa.
+    pub fn quantidade(&self) -> u64 {
+        self.fim - self.inicio + 1
+    }
+
+    /// [`InutilizacaoBuilder`] com ambiente, CNPJ, modelo, série e a faixa preenchidos.
+    /// Falta informar certificado, `c_uf` e justificativa.
+    pub fn inutilizacao(&self, serie: &SerieNumeracao) -> InutilizacaoBuilder {
+        InutilizacaoBuilder::new()
+            .tp_amb(serie.tp_amb)
+            .cnpj(&serie.emitente)
+            .modelo(serie.modelo)
+            .serie(serie.serie)
+            .n_nf_ini(self.inicio as u32)
+            .n_nf_fin(self.fim as u32)
+    }
+}
+
+/// Onde a numeração de cada série é guardada.
+///
+/// [`NumeracaoArquivo`] grava um arquivo por série com trava entre processos;
+/// [`NumeracaoMemoria`] serve a testes e a um único processo. Implemente o trait para
+/// guardar a numeração no banco da aplicação — `atualizar` precisa ser atômico
+/// (transação, `SELECT … FOR UPDATE`).
+pub trait ArmazemNumeracao: Send + Sync {
+    /// Lê o estado da série (o padrão, se ainda não existir), aplica `alterar` e grava o
+    /// resultado sem que outro terminal altere a série no meio. Se `alterar` falhar nada
+    /// é gravado.
+    fn atualizar(
+        &self,
+        serie: &SerieNumeracao,
+        alterar: &mut dyn FnMut(&mut EstadoNumeracao) -> Result<()>,
+    ) -> Result<EstadoNumeracao>;
+}
+
+/// Numeração em disco: `<dir>/<emitente>/<tpAmb>-<mod>-<serie>.json`, com trava
+/// exclusiva no arquivo `.lock` ao lado durante cada atualização.
+#[derive(Debug, Clone)]
+pub struct NumeracaoArquivo {
+    dir: PathBuf,
+}
+
+impl NumeracaoArquivo {
+    /// Numeração no diretório informado; é criado na primeira reserva.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Diretório da numeração.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn arquivo(&self, serie: &SerieNumeracao) -> PathBuf {
+        self.dir
+            .join(&serie.emitente)
+            .join(format!("{}-{}-{}.json", serie.tp_amb, serie.modelo, serie.serie))
+    }
+}
+
+impl ArmazemNumeracao for NumeracaoArquivo {
+    fn atualizar(
+        &self,
+        serie: &SerieNumeracao,
+        alterar: &mut dyn FnMut(&mut EstadoNumeracao) -> Result<()>,
+    ) -> Result<EstadoNumeracao> {
+        let arquivo = self.arquivo(serie);
+        if let Some(dir) = arquivo.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // A trava é liberada quando `trava` sai de escopo
+        let trava: File = OpenOptions::new().create(true).truncate(false).write(true).open(arquivo.with_extension("lock"))?;
+        trava.lock()?;
+
+        let mut estado = if arquivo.exists() {
+            serde_json::from_slice(&std::fs::read(&arquivo)?)?
+        } else {
+            EstadoNumeracao::default()
+        };
+        alterar(&mut estado)?;
+
+        let temporario = arquivo.with_extension("json.tmp");
+        std::fs::write(&temporario, serde_json::to_vec_pretty(&estado)?)?;
+        std::fs::rename(&temporario, &arquivo)?;
+        Ok(estado)
+    }
+}
+
+/// Numeração em memória, perdida ao fim do processo.
+#[derive(Debug, Default)]
+pub struct NumeracaoMemoria {
+    series: Mutex<HashMap<SerieNumeracao, EstadoNumeracao>>,
+}
+
+impl NumeracaoMemoria {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ArmazemNumeracao for NumeracaoMemoria {
+    fn atualizar(
+        &self,
+        serie: &SerieNumeracao,
+        alterar: &mut dyn FnMut(&mut EstadoNumeracao) -> Result<()>,
+    ) -> Result<EstadoNumeracao> {
+        let mut series = self.series.lock().map_err(|_| DfeError::Io("numeração em memória envenenada".to_string()))?;
+        let mut estado = series.get(serie).cloned().unwrap_or_default();
+        alterar(&mut estado)?;
+        series.insert(serie.clone(), estado.clone());
+        Ok(estado)
+    }
+}
+
+/// Serviço de numeração sobre um [`ArmazemNumeracao`].
+///
+/// Com [`NFeBuilder::numeracao`](super::NFeBuilder::numeracao), a emissão com `n_nf: 0`
+/// reserva o próximo número da série e registra o resultado da SEFAZ. Números
+/// rejeitados, descartados ou pulados aparecem em [`lacunas`](Self::lacunas), prontos
+/// para a inutilização.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use dfe::{Numeracao, NumeracaoArquivo};
+/// use dfe::emissao::SerieNumeracao;
+///
+/// # async fn example(cert: dfe::Certificado) -> Result<(), dfe::DfeError> {
+/// let numeracao = Numeracao::new(Arc::new(NumeracaoArquivo::new("//servidor/dfe/numeracao")));
+/// let serie = SerieNumeracao { emitente: "11222333000181".into(), tp_amb: 2, modelo: 65, serie: 1 };
+/// for faixa in numeracao.lacunas(&serie)? {
+///     let r = faixa.inutilizacao(&serie)
+///         .certificado(cert.clone()).c_uf(35).justificativa("Numeracao rejeitada pela SEFAZ")
+///         .send().await?;
+///     if r.c_stat == 102 {
+///         numeracao.inutilizada(&serie, faixa)?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Numeracao {
+    armazem: Arc<dyn ArmazemNumeracao>,
+}
+
+impl Numeracao {
+    pub fn new(armazem: Arc<dyn ArmazemNumeracao>) -> Self {
+        Self { armazem }
+    }
+
+    /// Estado atual da série.
+    pub fn estado(&self, serie: &SerieNumeracao) -> Result<EstadoNumeracao> {
+        self.armazem.atualizar(serie, &mut |_| Ok(()))
+    }
+
+    /// Começa a numeração em `proximo` — para séries que já vinham sendo emitidas.
+    /// Só é aceito antes do primeiro número reservado ou registrado.
+    pub fn iniciar(&self, serie: &SerieNumeracao, proximo: u64) -> Result<()> {
+        if proximo == 0 || proximo > MAX_N_NF {
+            return Err(DfeError::Validacao(format!("número inicial inválido: {}", proximo)));
+        }
+        self.armazem.atualizar(serie, &mut |estado| {
+            if !estado.usos.is_empty() {
+                return Err(DfeError::Validacao(format!(
+                    "série {} do emitente {} já tem números registrados", serie.serie, serie.emitente
+                )));
+            }
+            estado.inicio = proximo;
+            estado.proximo = proximo;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Reserva o próximo número livre da série.
+    pub fn reservar(&self, serie: &SerieNumeracao) -> Result<u64> {
+        let mut reservado = 0;
+        self.armazem.atualizar(serie, &mut |estado| {
+            let mut n_nf = estado.proximo.max(1);
+            while estado.usos.contains_key(&n_nf) {
+                n_nf += 1;
+            }
+            if n_nf > MAX_N_NF {
+                return Err(DfeError::Validacao(format!(
+                    "série {} do emitente {} esgotou a numeração", serie.serie, serie.emitente
+                )));
+            }
+            estado.registrar(n_nf, UsoNumero::Reservado);
+            reservado = n_nf;
+            Ok(())
+        })?;
+        Ok(reservado)
+    }
+
+    /// Registra o destino de um número. Autorizado, denegado e inutilizado não voltam atrás;
+    /// um número acima do próximo o avança, e os pulados viram lacunas.
+    pub fn registrar(&self, serie: &SerieNumeracao, n_nf: u64, uso: UsoNumero) -> Result<()> {
+        self.armazem.atualizar(serie, &mut |estado| {
+            estado.registrar(n_nf, uso);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Destino registrado para o número, se houver.
+    pub fn uso(&self, serie: &SerieNumeracao, n_nf: u64) -> Result<Option<UsoNumero>> {
+        Ok(self.estado(serie)?.usos.get(&n_nf).copied())
+    }
+
+    /// Faixas de números abaixo do próximo que não viraram nota — rejeitados, descartados
+    /// ou pulados. Reservados ficam de fora: o resultado deles ainda não é conhecido.
+    pub fn lacunas(&self, serie: &SerieNumeracao) -> Result<Vec<FaixaNumeracao>> {
+        Ok(lacunas(&self.estado(serie)?))
+    }
+
+    /// Marca a faixa como inutilizada depois do `cStat` 102.
+    pub fn inutilizada(&self, serie: &SerieNumeracao, faixa: FaixaNumeracao) -> Result<()> {
+        self.armazem.atualizar(serie, &mut |estado| {
+            for n_nf in faixa.inicio..=faixa.fim {
+                estado.registrar(n_nf, UsoNumero::Inutilizado);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Numeracao {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Numeracao")
+    }
+}
+
+fn lacunas(estado: &EstadoNumeracao) -> Vec<FaixaNumeracao> {
+    let mut faixas: Vec<FaixaNumeracao> = Vec::new();
+    for n_nf in estado.inicio..estado.proximo {
+        if !matches!(estado.usos.get(&n_nf), None | Some(UsoNumero::Rejeitado)) {
+            continue;
+        }
+        match faixas.last_mut() {
+            Some(faixa) if faixa.fim + 1 == n_nf => faixa.fim = n_nf,
+            _ => faixas.push(FaixaNumeracao { inicio: n_nf, fim: n_nf }),
+        }
+    }
+    faixas
+}
+
+// Número reservado para uma emissão em curso; sem numeração configurada nada é registrado
+pub(super) struct Reserva {
+    numeracao: Option<Numeracao>,
+    serie: SerieNumeracao,
+    n_nf: u64,
+    reservado: bool,
+}
+
+impl Reserva {
+    // Reserva o próximo número quando o `ide` vem sem `n_nf`
+    pub(super) fn new(numeracao: Option<Numeracao>, ide: &mut Ide, emit: &Emit) -> Result<Self> {
+        let serie = SerieNumeracao::de(ide, emit);
+        let mut reservado = false;
+        if let Some(numeracao) = &numeracao {
+            if ide.n_nf == 0 {
+                ide.n_nf = numeracao.reservar(&serie)?;
+                reservado = true;
+            } else if let Some(uso) = numeracao.uso(&serie, ide.n_nf)?.filter(|uso| uso.definitivo()) {
+                return Err(DfeError::Validacao(format!(
+                    "número {} da série {} do emitente {} já está {:?}", ide.n_nf, serie.serie, serie.emitente, uso
+                )));
+            }
+        }
+        Ok(Reserva { numeracao, serie, n_nf: ide.n_nf, reservado })
+    }
+
+    pub(super) fn registrar(&self, uso: UsoNumero) -> Result<()> {
+        match &self.numeracao {
+            Some(numeracao) => numeracao.registrar(&self.serie, self.n_nf, uso),
+            None => Ok(()),
+        }
+    }
+
+    // A emissão falhou antes do envio: o número reservado não foi usado
+    pub(super) fn descartar(&self) -> Result<()> {
+        if self.reservado {
+            self.registrar(UsoNumero::Rejeitado)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serie() -> SerieNumeracao {
+        SerieNumeracao { emitente: "11222333000181".to_string(), tp_amb: 2, modelo: 65, serie: 1 }
+    }
+
+    fn verificar_numeracao(armazem: Arc<dyn ArmazemNumeracao>) {
+        let numeracao = Numeracao::new(armazem);
+        let serie = serie();
+        numeracao.iniciar(&serie, 10).unwrap();
+
+        let numeros: Vec<u64> = (0..5).map(|_| numeracao.reservar(&serie).unwrap()).collect();
+        assert_eq!(numeros, vec![10, 11, 12, 13, 14]);
+        assert!(numeracao.iniciar(&serie, 1).is_err());
+
+        numeracao.registrar(&serie, 10, UsoNumero::de_c_stat(100)).unwrap();
+        numeracao.registrar(&serie, 11, UsoNumero::de_c_stat(778)).unwrap();
+        numeracao.registrar(&serie, 12, UsoNumero::de_c_stat(302)).unwrap();
+        numeracao.registrar(&serie, 13, UsoNumero::Rejeitado).unwrap();
+        numeracao.registrar(&serie, 17, UsoNumero::Autorizado).unwrap();
+        numeracao.registrar(&serie, 10, UsoNumero::Rejeitado).unwrap();
+
+        assert_eq!(numeracao.uso(&serie, 10).unwrap(), Some(UsoNumero::Autorizado));
+        assert_eq!(numeracao.uso(&serie, 12).unwrap(), Some(UsoNumero::Denegado));
+        assert_eq!(
+            numeracao.lacunas(&serie).unwrap(),
+            vec![FaixaNumeracao { inicio: 11, fim: 11 }, FaixaNumeracao { inicio: 13, fim: 13 }, FaixaNumeracao { inicio: 15, fim: 16 }]
+        );
+        assert_eq!(numeracao.reservar(&serie).unwrap(), 18);
+
+        numeracao.inutilizada(&serie, FaixaNumeracao { inicio: 15, fim: 16 }).unwrap();
+        assert_eq!(numeracao.lacunas(&serie).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_numeracao_memoria() {
+        verificar_numeracao(Arc::new(NumeracaoMemoria::new()));
+    }
+
+    #[test]
+    fn test_numeracao_arquivo() {
+        let dir = std::env::temp_dir().join(format!("dfe-numeracao-{}", std::process::id()));
+        verificar_numeracao(Arc::new(NumeracaoArquivo::new(&dir)));
+        assert!(dir.join("11222333000181").join("2-65-1.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reserva_concorrente_nao_repete_numero() {
+        let dir = std::env::temp_dir().join(format!("dfe-numeracao-concorrente-{}", std::process::id()));
+        let terminais: Vec<_> = (0..4)
+            .map(|_| {
+                // Cada terminal com seu próprio armazém sobre o mesmo diretório
+                let numeracao = Numeracao::new(Arc::new(NumeracaoArquivo::new(&dir)));
+                std::thread::spawn(move || (0..25).map(|_| numeracao.reservar(&serie()).unwrap()).collect::<Vec<_>>())
+            })
+            .collect();
+        let mut numeros: Vec<u64> = terminais.into_iter().flat_map(|t| t.join().unwrap()).collect();
+        numeros.sort();
+        assert_eq!(numeros, (1..=100).collect::<Vec<_>>());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reserva_da_emissao() {
+        let numeracao = Numeracao::new(Arc::new(NumeracaoMemoria::new()));
+        let emit = Emit { cnpj: Some("11222333000181".to_string()), ..Default::default() };
+        let mut ide = Ide { tp_amb: 2, mod_: 65, serie: 1, n_nf: 0, ..Default::default() };
+
+        let reserva = Reserva::new(Some(numeracao.clone()), &mut ide, &emit).unwrap();
+        assert_eq!(ide.n_nf, 1);
+        reserva.descartar().unwrap();
+        assert_eq!(numeracao.uso(&serie(), 1).unwrap(), Some(UsoNumero::Rejeitado));
+
+        let mut informado = Ide { n_nf: 1, ..ide.clone() };
+        Reserva::new(Some(numeracao.clone()), &mut informado, &emit).unwrap().registrar(UsoNumero::Autorizado).unwrap();
+        assert!(Reserva::new(Some(numeracao), &mut informado, &emit).is_err());
+
+        let mut sem_numeracao = Ide { n_nf: 0, ..ide };
+        Reserva::new(None, &mut sem_numeracao, &emit).unwrap().descartar().unwrap();
+        assert_eq!(sem_numeracao.n_nf, 0);
+    }
+}
@@ -0,0 +1,313 @@
+// Emissão sem autorização prévia — NFC-e offline (tpEmis 9) e NF-e em FS-DA
+// (tpEmis 5): a nota é assinada e circula antes de chegar à SEFAZ; o XML fica numa
+// fila em disco até a conexão voltar, quando é transmitido ao autorizador normal da UF.
+
+use super::diario::DiarioEmissao;
+use super::numeracao::Numeracao;
+use super::{chave_nfe, extract_xml_tag, transmitir_assinada, Response};
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::ArtefatoSink;
+use crate::interno::cert::Certificado;
+use crate::interno::ws::Webservices;
+use chrono::{DateTime, Duration, FixedOffset};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SUFIXO_NFCE: &str = "-nfce.xml";
+const SUFIXO_NFE: &str = "-nfe.xml";
+
+/// Prazo para transmitir a nota emitida sem autorização, contado da emissão.
+pub const PRAZO_TRANSMISSAO_HORAS: i64 = 168;
+
+/// Nota emitida em contingência sem autorização prévia, ainda não transmitida.
+#[derive(Debug, Clone)]
+pub struct NotaOffline {
+    /// Chave de acesso (44 dígitos).
+    pub chave: String,
+    /// NF-e/NFC-e assinada com `tpEmis` 5 ou 9 — imprima o DANFE a partir dela.
+    pub xml: String,
+    /// Arquivo da nota na fila.
+    pub arquivo: PathBuf,
+}
+
+impl NotaOffline {
+    /// Limite para a transmissão: [`PRAZO_TRANSMISSAO_HORAS`] após o `dhEmi`.
+    /// Para a NFC-e algumas UFs exigem prazo menor (ex.: primeiro dia útil seguinte).
+    pub fn prazo(&self) -> Option<DateTime<FixedOffset>> {
+        let dh_emi = extract_xml_tag(&self.xml, "dhEmi")?;
+        let dh_emi = DateTime::parse_from_rfc3339(&dh_emi).ok()?;
+        Some(dh_emi + Duration::hours(PRAZO_TRANSMISSAO_HORAS))
+    }
+
+    /// `true` quando o prazo de transmissão já passou.
+    pub fn vencida(&self) -> bool {
+        self.prazo().is_some_and(|prazo| prazo < chrono::Utc::now())
+    }
+}
+
+/// Fila em disco das notas emitidas sem autorização, uma por arquivo
+/// (`<chave>-nfce.xml` para NFC-e offline, `<chave>-nfe.xml` para NF-e em FS-DA).
+///
+/// A nota sai da fila quando [`TransmissaoOfflineBuilder`] obtém a autorização.
+/// Rejeitadas permanecem até serem tratadas e removidas com [`remover`](Self::remover).
+#[derive(Debug, Clone)]
+pub struct FilaOffline {
+    dir: PathBuf,
+}
+
+impl FilaOffline {
+    /// Fila no diretório informado; é criado na primeira nota guardada.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Diretório da fila.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Guarda a nota assinada na fila. Substitui a nota de mesma chave.
+    pub fn guardar(&self, nfe_xml: &str) -> Result<NotaOffline> {
+        let chave = chave_nfe(nfe_xml)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let arquivo = self.arquivo(&chave);
+        std::fs::write(&arquivo, nfe_xml)?;
+        Ok(NotaOffline { chave, xml: nfe_xml.to_string(), arquivo })
+    }
+
+    /// Notas pendentes de transmissão, em ordem de chave (série e número).
+    pub fn pendentes(&self) -> Result<Vec<NotaOffline>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut notas = Vec::new();
+        for entrada in std::fs::read_dir(&self.dir)? {
+            let arquivo = entrada?.path();
+            let nome = arquivo.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if !nome.ends_with(SUFIXO_NFCE) && !nome.ends_with(SUFIXO_NFE) {
+                continue;
+            }
+            let xml = std::fs::read_to_string(&arquivo)?;
+            notas.push(NotaOffline { chave: chave_nfe(&xml)?, xml, arquivo });
+        }
+        notas.sort_by(|a, b| a.chave.cmp(&b.chave));
+        Ok(notas)
+    }
+
+    /// Retira a nota da fila.
+    pub fn remover(&self, chave: &str) -> Result<()> {
+        let arquivo = self.arquivo(chave);
+        if arquivo.exists() {
+            std::fs::remove_file(arquivo)?;
+        }
+        Ok(())
+    }
+
+    fn arquivo(&self, chave: &str) -> PathBuf {
+        let sufixo = if chave.get(20..22) == Some("55") { SUFIXO_NFE } else { SUFIXO_NFCE };
+        self.dir.join(format!("{}{}", chave, sufixo))
+    }
+}
+
+/// Resultado da transmissão de uma nota da fila.
+#[derive(Debug)]
+pub struct TransmissaoOffline {
+    /// Chave de acesso da nota.
+    pub chave: String,
+    /// Protocolo e XML (`nfeProc` quando autorizada) ou a falha de comunicação.
+    pub resultado: Result<Response>,
+}
+
+impl TransmissaoOffline {
+    /// `true` quando a nota foi autorizada (`cStat` 100 ou 150) e saiu da fila.
+    pub fn autorizada(&self) -> bool {
+        matches!(&self.resultado, Ok(r) if matches!(r.protocolo.inf_prot.c_stat, 100 | 150))
+    }
+}
+
+/// Transmite ao autorizador da UF as notas pendentes de uma [`FilaOffline`].
+///
+/// Cada nota segue num `enviNFe` síncrono, exatamente como foi assinada. Duplicidade
+/// (`cStat` 204) é conciliada pela consulta da chave. Autorizadas saem da fila;
+/// rejeições e falhas de comunicação ficam para nova tentativa ou tratamento.
+///
+/// Com [`numeracao`](Self::numeracao), o número reservado por
+/// [`NFeBuilder::emitir_offline`](super::NFeBuilder::emitir_offline) recebe o destino
+/// dado pela SEFAZ; sem resposta ele segue reservado. Com [`diario`](Self::diario), cada nota
+/// passa por `Requested` e termina em `Autorizado`, `Denegado`, `NaoAutorizado` ou `SemResposta`.
+///
+/// # Exemplo
+///
+/// ```no_run
+/// use dfe::emissao::{FilaOffline, TransmissaoOfflineBuilder};
+///
+/// # async fn example(cert: dfe::Certificado) -> Result<(), dfe::DfeError> {
+/// let fila = FilaOffline::new("./nfce-offline");
+/// for t in TransmissaoOfflineBuilder::new().certificado(cert).fila(fila).send().await? {
+///     match &t.resultado {
+///         Ok(r) if t.autorizada() => std::fs::write(format!("{}-procNFe.xml", t.chave), &r.xml)?,
+///         Ok(r) => eprintln!("{} rejeitada: {} {}", t.chave, r.protocolo.inf_prot.c_stat, r.protocolo.inf_prot.x_motivo),
+///         Err(e) => eprintln!("{} não transmitida: {}", t.chave, e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct TransmissaoOfflineBuilder {
+    certificado: Option<Certificado>,
+    cert_path:   Option<String>,
+    cert_pass:   Option<String>,
+    fila:        Option<FilaOffline>,
+    webservices: Option<Webservices>,
+    artefatos:   Option<Arc<dyn ArtefatoSink>>,
+    numeracao:   Option<Numeracao>,
+    diario:      Option<Arc<dyn DiarioEmissao>>,
+}
+
+impl TransmissaoOfflineBuilder {
+    pub fn new() -> Self {
+        Self {
+            certificado: None, cert_path: None, cert_pass: None, fila: None, webservices: None, artefatos: None,
+            numeracao: None, diario: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+    /// Fila com as notas pendentes
+    pub fn fila(mut self, fila: FilaOffline) -> Self { self.fila = Some(fila); self }
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+    /// Destino ([`ArtefatoSink`]) dos envelopes, retornos e `nfeProc`. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+    /// Numeração ([`Numeracao`]) onde o resultado de cada nota transmitida é registrado
+    pub fn numeracao(mut self, n: Numeracao) -> Self { self.numeracao = Some(n); self }
+    /// Diário ([`DiarioEmissao`]) onde as etapas da transmissão de cada nota são registradas
+    pub fn diario(mut self, d: Arc<dyn DiarioEmissao>) -> Self { self.diario = Some(d); self }
+
+    /// Um [`TransmissaoOffline`] por nota pendente, na ordem da fila.
+    pub async fn send(self) -> Result<Vec<TransmissaoOffline>> {
+        let certificado = Certificado::resolver(self.certificado, self.cert_path, self.cert_pass)?;
+        let fila        = self.fila.ok_or_else(|| DfeError::Validacao("fila não informada".to_string()))?;
+
+        let mut resultados = Vec::new();
+        for nota in fila.pendentes()? {
+            let tp_emis = extract_xml_tag(&nota.xml, "tpEmis").unwrap_or_default();
+            let resultado = if tp_emis != "5" && tp_emis != "9" {
+                Err(DfeError::Validacao(format!("nota {} não foi emitida offline (tpEmis {})", nota.chave, tp_emis)))
+            } else {
+                transmitir_assinada(
+                    certificado.clone(), &nota.xml, self.webservices.clone(), self.artefatos.clone(), self.numeracao.as_ref(),
+                    self.diario.clone(),
+                )
+                .await
+            };
+            let transmissao = TransmissaoOffline { chave: nota.chave, resultado };
+            if transmissao.autorizada() {
+                fila.remover(&transmissao.chave)?;
+            }
+            resultados.push(transmissao);
+        }
+        Ok(resultados)
+    }
+}
+
+impl Default for TransmissaoOfflineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAVE_1: &str = "35250111222333000181650010000000019000000018";
+    const CHAVE_2: &str = "35250111222333000181650010000000029000000023";
+    const CHAVE_FSDA: &str = "35250111222333000181550010000000035000000033";
+
+    fn nfce_xml(chave: &str) -> String {
+        format!(
+            r#"<NFe xmlns="http://www.portalfiscal.inf.br/nfe"><infNFe Id="NFe{}" versao="4.00"><ide><mod>65</mod><tpEmis>9</tpEmis></ide></infNFe></NFe>"#,
+            chave
+        )
+    }
+
+    #[test]
+    fn test_fila_guarda_lista_e_remove() {
+        let dir = std::env::temp_dir().join(format!("dfe-fila-offline-{}", std::process::id()));
+        let fila = FilaOffline::new(&dir);
+        assert!(fila.pendentes().unwrap().is_empty());
+
+        fila.guardar(&nfce_xml(CHAVE_2)).unwrap();
+        let nota = fila.guardar(&nfce_xml(CHAVE_1)).unwrap();
+        assert_eq!(nota.arquivo, dir.join(format!("{}-nfce.xml", CHAVE_1)));
+        std::fs::write(dir.join("outro.txt"), "ignorado").unwrap();
+
+        let chaves: Vec<String> = fila.pendentes().unwrap().into_iter().map(|n| n.chave).collect();
+        assert_eq!(chaves, vec![CHAVE_1, CHAVE_2]);
+
+        fila.remover(CHAVE_1).unwrap();
+        assert_eq!(fila.pendentes().unwrap().len(), 1);
+        assert!(fila.guardar("<NFe/>").is_err());
+
+        let fsda = fila.guardar(&nfce_xml(CHAVE_FSDA)).unwrap();
+        assert_eq!(fsda.arquivo, dir.join(format!("{}-nfe.xml", CHAVE_FSDA)));
+        assert_eq!(fila.pendentes().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transmissao_sem_resposta_fica_pendente() {
+        use crate::emissao::{DiarioMemoria, EstadoEmissao, NotaDiario, NumeracaoMemoria, SerieNumeracao, UsoNumero};
+
+        let dir = std::env::temp_dir().join(format!("dfe-fila-offline-diario-{}", std::process::id()));
+        let fila = FilaOffline::new(&dir);
+        let xml = nfce_xml(CHAVE_1).replace("</ide>", "<tpAmb>2</tpAmb></ide><emit><CNPJ>11222333000181</CNPJ></emit>");
+        fila.guardar(&xml).unwrap();
+
+        let diario = Arc::new(DiarioMemoria::new());
+        let numeracao = Numeracao::new(Arc::new(NumeracaoMemoria::new()));
+        let serie = SerieNumeracao { emitente: "11222333000181".to_string(), tp_amb: 2, modelo: 65, serie: 1 };
+        numeracao.registrar(&serie, 1, UsoNumero::Reservado).unwrap();
+
+        // Porta fechada: a transmissão falha sem resposta da SEFAZ
+        let resultados = TransmissaoOfflineBuilder::new()
+            .cert("testes/certificado_teste_legacy.pfx", "1234")
+            .fila(fila.clone())
+            .webservices(Webservices::new().url("NFeAutorizacao", 2, "*", "http://127.0.0.1:9/autorizacao"))
+            .numeracao(numeracao.clone())
+            .diario(diario.clone())
+            .send()
+            .await
+            .unwrap();
+        assert!(resultados[0].resultado.is_err());
+
+        let nota = NotaDiario { emitente: "11222333000181".to_string(), tp_amb: 2, modelo: 65, serie: 1, n_nf: 1 };
+        let registro = diario.consultar(&nota).unwrap().unwrap();
+        assert_eq!((registro.estado, registro.chave.as_str()), (EstadoEmissao::SemResposta, CHAVE_1));
+        assert_eq!(numeracao.uso(&serie, 1).unwrap(), Some(UsoNumero::Reservado));
+        assert_eq!(fila.pendentes().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prazo_de_transmissao() {
+        let xml = nfce_xml(CHAVE_FSDA).replace("</ide>", "<dhEmi>2025-01-10T09:00:00-03:00</dhEmi></ide>");
+        let nota = NotaOffline { chave: CHAVE_FSDA.to_string(), xml, arquivo: PathBuf::new() };
+        assert_eq!(nota.prazo().unwrap().to_rfc3339(), "2025-01-17T09:00:00-03:00");
+        assert!(nota.vencida());
+
+        let sem_data = NotaOffline { chave: CHAVE_1.to_string(), xml: nfce_xml(CHAVE_1), arquivo: PathBuf::new() };
+        assert!(sem_data.prazo().is_none());
+        assert!(!sem_data.vencida());
+    }
+}
@@ -1,6 +1,6 @@
 use super::NFeInterno;
 use crate::error::Result;
-use rust_decimal::prelude::FromPrimitive;
+use crate::interno::decimal::{arredondar, fmt_casas};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -44,7 +44,7 @@ pub struct Card {
     pub v_troco: Option<Decimal>,
 }
 
-pub(super) fn pag_process(nfe: NFeInterno, v_nf: f64) -> Result<PagProcess> {
+pub(super) fn pag_process(nfe: NFeInterno, v_nf: Decimal) -> Result<PagProcess> {
     let card = if nfe.pag.t_pag == "03" || nfe.pag.t_pag == "04" || nfe.pag.t_pag == "17" {
         if nfe.pag.tp_integra == Some(1) {
             Some(Card {
@@ -67,26 +67,20 @@ pub(super) fn pag_process(nfe: NFeInterno, v_nf: f64) -> Result<PagProcess> {
         None
     };
 
-    let mut troco = Decimal::new(0, 2);
-    let v_nf = Decimal::from_f64(v_nf).unwrap_or_default();
-    let v_pag = Decimal::from_f64(nfe.pag.v_pag).unwrap_or_default();
-    if v_pag > v_nf {
-        troco = v_pag - v_nf;
-    }
+    let v_nf = arredondar(v_nf, 2);
+    let v_pag = arredondar(nfe.pag.v_pag, 2);
+    let mut troco = if v_pag > v_nf { v_pag - v_nf } else { Decimal::ZERO };
+    troco.rescale(2);
 
     let pag_process = PagProcess {
         det_pag: DetPag {
             ind_pag: nfe.pag.ind_pag,
             t_pag: format!("{}", nfe.pag.t_pag),
             x_pag: nfe.pag.x_pag.clone(),
-            v_pag: format!("{:.2}", v_pag),
+            v_pag: fmt_casas(v_pag, 2),
             card,
         },
-        v_troco: Some(
-            format!("{:.2}", troco.round_dp(2))
-                .parse::<Decimal>()
-                .unwrap_or_default(),
-        ),
+        v_troco: Some(troco),
     };
     Ok(pag_process)
 }
@@ -0,0 +1,608 @@
+//! Regras de validação do Anexo I do MOC (Leiaute e Regras de Validação da NF-e e
+//! da NFC-e) conferidas antes da assinatura, com o `cStat` da rejeição.
+//!
+//! O XSD só confere a estrutura do XML; estas são as regras de negócio que mais
+//! derrubam notas na SEFAZ e que podem ser conferidas sem consultar cadastros.
+
+use super::det::det_process;
+use super::total::total_process;
+use crate::interno::decimal::arredondar;
+use crate::tipos::{Dest, Det, Emit, Exporta, Icms, Ide, Pag, Total};
+use chrono::{DateTime, Duration, FixedOffset};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// Tolerância das regras "difere do produto" do Anexo I (nota `*4`): R$ 0,01.
+const TOLERANCIA: Decimal = Decimal::from_parts(1, 0, 0, false, 2);
+
+/// Regra de negócio da SEFAZ violada pela nota, apontada por [`NFeBuilder::validar`](super::NFeBuilder::validar).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violacao {
+    /// Código da rejeição (`cStat`) que a SEFAZ retornaria.
+    pub c_stat: u16,
+    /// Campo da nota, no caminho dos tipos do builder (ex.: `itens[0].cfop`, `ide.id_dest`).
+    pub campo: String,
+    /// Descrição da rejeição.
+    pub mensagem: String,
+}
+
+impl Violacao {
+    fn new(c_stat: u16, campo: impl Into<String>, mensagem: impl Into<String>) -> Self {
+        Violacao { c_stat, campo: campo.into(), mensagem: mensagem.into() }
+    }
+}
+
+impl fmt::Display for Violacao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} — {}: {}", self.c_stat, self.campo, self.mensagem)
+    }
+}
+
+// Dados da nota conferidos pelas regras
+pub(super) struct Nota<'a> {
+    pub ide: &'a Ide,
+    pub emit: &'a Emit,
+    pub dest: Option<&'a Dest>,
+    pub itens: &'a [Det],
+    pub total: &'a Total,
+    pub pag: &'a Pag,
+    pub exporta: Option<&'a Exporta>,
+    pub referencias: &'a [String],
+    pub desconto_rateio: Option<Decimal>,
+}
+
+/// Confere as regras na ordem do leiaute (ide, dest, itens, totais, pagamento, exporta).
+pub(super) fn validar(nota: &Nota, agora: DateTime<FixedOffset>) -> Vec<Violacao> {
+    let mut v = Vec::new();
+    ide(nota, agora, &mut v);
+    dest(nota, &mut v);
+    for (i, item) in nota.itens.iter().enumerate() {
+        det(nota, i, item, &mut v);
+    }
+    totais(nota, &mut v);
+    exporta(nota, &mut v);
+    v
+}
+
+fn ide(nota: &Nota, agora: DateTime<FixedOffset>, v: &mut Vec<Violacao>) {
+    let ide = nota.ide;
+    if ide.mod_ == 65 {
+        if ide.fin_nfe != 1 {
+            v.push(Violacao::new(715, "ide.fin_nfe", "NFC-e com finalidade inválida (somente fin_nfe 1)"));
+        }
+        if ide.id_dest != 1 {
+            v.push(Violacao::new(707, "ide.id_dest", "NFC-e para operação interestadual ou com o exterior"));
+        }
+        if ide.ind_final != 1 {
+            v.push(Violacao::new(716, "ide.ind_final", "NFC-e em operação não destinada a consumidor final"));
+        }
+        if !matches!(ide.ind_pres, 1 | 4) {
+            v.push(Violacao::new(717, "ide.ind_pres", "NFC-e em operação não presencial (somente ind_pres 1 ou 4)"));
+        }
+    }
+
+    // Sem dh_emi a emissão usa o horário atual
+    let Some(dh_emi) = ide.dh_emi.as_deref() else { return };
+    let Ok(dh_emi) = DateTime::parse_from_rfc3339(dh_emi) else {
+        v.push(Violacao::new(225, "ide.dh_emi", "dh_emi fora do formato AAAA-MM-DDThh:mm:ssTZD (falha no schema)"));
+        return;
+    };
+    // Tolerância de 5 minutos para o sincronismo de relógio com a SEFAZ
+    if dh_emi > agora + Duration::minutes(5) {
+        v.push(Violacao::new(703, "ide.dh_emi", "Data-Hora de Emissão posterior ao horário de recebimento"));
+    }
+    if ide.mod_ == 55 && matches!(ide.tp_emis, 1 | 6 | 7) && dh_emi < agora - Duration::days(30) {
+        v.push(Violacao::new(228, "ide.dh_emi", "Data de Emissão muito atrasada (mais de 30 dias)"));
+    }
+    if ide.mod_ == 65 && ide.tp_emis == 1 && dh_emi < agora - Duration::minutes(5) {
+        v.push(Violacao::new(704, "ide.dh_emi", "NFC-e com Data-Hora de emissão atrasada (mais de 5 minutos)"));
+    }
+}
+
+fn dest(nota: &Nota, v: &mut Vec<Violacao>) {
+    let ide = nota.ide;
+    // Na NFC-e o indIEDest vai como 9 quando não informado
+    let ind_ie_dest = match (ide.mod_, nota.dest) {
+        (65, d) => Some(d.and_then(|d| d.ind_ie_dest).unwrap_or(9)),
+        (_, d) => d.and_then(|d| d.ind_ie_dest),
+    };
+
+    if ide.mod_ == 65 {
+        if ind_ie_dest != Some(9) {
+            v.push(Violacao::new(789, "dest.ind_ie_dest", "NFC-e para destinatário contribuinte de ICMS"));
+        }
+        return;
+    }
+    if ind_ie_dest == Some(9) && ide.ind_final != 1 && ide.tp_nf == 1 && ide.id_dest != 3 {
+        v.push(Violacao::new(696, "ide.ind_final", "Operação com não contribuinte deve indicar operação com consumidor final"));
+    }
+    if ide.id_dest == 3 && nota.dest.is_some() && ind_ie_dest != Some(9) {
+        v.push(Violacao::new(790, "dest.ind_ie_dest", "Operação com Exterior para destinatário Contribuinte de ICMS"));
+    }
+    let sem_ie = nota.dest.and_then(|d| d.ie.as_deref()).is_none_or(|ie| ie.trim().is_empty());
+    if ind_ie_dest == Some(1) && sem_ie {
+        v.push(Violacao::new(728, "dest.ie", "NF-e sem informação da IE do destinatário"));
+    }
+}
+
+fn det(nota: &Nota, i: usize, item: &Det, v: &mut Vec<Violacao>) {
+    let ide = nota.ide;
+    let campo = |nome: &str| format!("itens[{}].{}", i, nome);
+    let n_item = i + 1;
+
+    // NCM completo, ou "00" para item sem produto (serviço, crédito etc.)
+    let ncm = item.ncm.trim();
+    if ncm != "00" && !(ncm.len() == 8 && ncm.bytes().all(|b| b.is_ascii_digit())) {
+        v.push(Violacao::new(777, campo("ncm"), format!("Obrigatória a informação do NCM completo [nItem: {}]", n_item)));
+    }
+
+    // CFOP de 4 dígitos iniciado por 1, 2, 3 (entrada) ou 5, 6, 7 (saída), como no XSD
+    let tipo_cfop = item.cfop / 1000;
+    if !matches!(tipo_cfop, 1..=3 | 5..=7) {
+        v.push(Violacao::new(225, campo("cfop"), format!(
+            "CFOP {} inexistente (falha no schema) [nItem: {}]", item.cfop, n_item
+        )));
+    }
+
+    // CFOP x tpNF e idDest (NF-e)
+    if ide.mod_ == 55 {
+        if ide.tp_nf == 1 && (1..=3).contains(&tipo_cfop) {
+            v.push(Violacao::new(518, campo("cfop"), format!("CFOP de entrada para NF-e de saída [nItem: {}]", n_item)));
+        }
+        if ide.tp_nf == 0 && (5..=7).contains(&tipo_cfop) {
+            v.push(Violacao::new(519, campo("cfop"), format!("CFOP de saída para NF-e de entrada [nItem: {}]", n_item)));
+        }
+        let operacao = match tipo_cfop {
+            1 | 5 => Some((733, 1, "interna")),
+            2 | 6 => Some((732, 2, "interestadual")),
+            3 | 7 => Some((731, 3, "com Exterior")),
+            _ => None,
+        };
+        if let Some((c_stat, id_dest, operacao)) = operacao {
+            if ide.id_dest != id_dest {
+                v.push(Violacao::new(c_stat, campo("cfop"), format!(
+                    "CFOP de operação {} e id_dest <> {} [nItem: {}]", operacao, id_dest, n_item
+                )));
+            }
+        }
+    }
+
+    // CST x CSOSN conforme o regime do emitente (CRT 1 e 4 usam CSOSN)
+    let (csosn, cst) = cst_icms(&item.icms);
+    let simples = matches!(nota.emit.crt, 1 | 4);
+    if csosn && !simples {
+        v.push(Violacao::new(591, campo("icms"), format!(
+            "Informado CSOSN para emissor que não é do Simples Nacional (CRT {}) [nItem: {}]", nota.emit.crt, n_item
+        )));
+    }
+    if !csosn && simples {
+        v.push(Violacao::new(590, campo("icms"), format!(
+            "Informado CST para emissor do Simples Nacional (CRT {}) [nItem: {}]", nota.emit.crt, n_item
+        )));
+    }
+
+    if ide.mod_ == 65 {
+        nfce_item(item, &cst, csosn, &campo, n_item, v);
+    }
+    comercio_exterior(nota, item, &cst, &campo, n_item, v);
+
+    // Valores do item: vProd = q × vUn e vICMS = vBC × pICMS, com tolerância de R$ 0,01
+    if ide.fin_nfe == 1 {
+        if difere(item.v_prod, item.q_com * item.v_un_com) {
+            v.push(Violacao::new(629, campo("v_prod"), format!(
+                "Valor do Produto difere do produto Valor Unitário de Comercialização e Quantidade Comercial [nItem: {}]", n_item
+            )));
+        }
+        if difere(item.v_prod, item.q_trib * item.v_un_trib) {
+            v.push(Violacao::new(630, campo("v_prod"), format!(
+                "Valor do Produto difere do produto Valor Unitário de Tributação e Quantidade Tributável [nItem: {}]", n_item
+            )));
+        }
+    }
+    let icms_proprio = match &item.icms {
+        Icms::Icms00 { v_bc, p_icms, v_icms, .. }
+        | Icms::Icms10 { v_bc, p_icms, v_icms, .. }
+        | Icms::Icms20 { v_bc, p_icms, v_icms, .. }
+        | Icms::Icms70 { v_bc, p_icms, v_icms, .. } => Some((*v_bc, *p_icms, *v_icms)),
+        _ => None,
+    };
+    if let Some((v_bc, p_icms, v_icms)) = icms_proprio {
+        if difere(v_icms, v_bc * p_icms / Decimal::ONE_HUNDRED) {
+            v.push(Violacao::new(528, campo("icms"), format!("Valor do ICMS difere do produto BC e Alíquota [nItem: {}]", n_item)));
+        }
+    }
+}
+
+// Restrições do item na NFC-e (CST/CSOSN, CFOP, ST e partilha)
+fn nfce_item(item: &Det, cst: &str, csosn: bool, campo: &dyn Fn(&str) -> String, n_item: usize, v: &mut Vec<Violacao>) {
+    if csosn {
+        if !matches!(cst, "102" | "103" | "300" | "400" | "500" | "900") {
+            v.push(Violacao::new(383, campo("icms"), format!("Item com CSOSN indevido [nItem: {}]", n_item)));
+        }
+    } else if !matches!(cst, "00" | "20" | "40" | "41" | "60" | "90") {
+        v.push(Violacao::new(766, campo("icms"), format!("Item com CST indevido [nItem: {}]", n_item)));
+    }
+
+    match &item.icms {
+        Icms::Icms90 { mod_bcst: Some(_), .. } => v.push(Violacao::new(381, campo("icms"), format!(
+            "Grupo de tributação ICMS90, informando dados do ICMS-ST [nItem: {}]", n_item
+        ))),
+        Icms::Sn900 { mod_bcst: Some(_), .. } => v.push(Violacao::new(385, campo("icms"), format!(
+            "Grupo de tributação ICMSSN900, informando dados do ICMS-ST [nItem: {}]", n_item
+        ))),
+        _ => {}
+    }
+
+    // ST retido anteriormente (CST 60 / CSOSN 500) usa os CFOP de revenda com ST
+    let cfops: &[u16] = if matches!(cst, "60" | "500") {
+        &[5405, 5656, 5667]
+    } else {
+        &[5101, 5102, 5103, 5104, 5115, 5933]
+    };
+    if !cfops.contains(&item.cfop) {
+        let (c_stat, tipo) = if csosn { (386, "CSOSN") } else { (382, "CST") };
+        v.push(Violacao::new(c_stat, campo("cfop"), format!(
+            "CFOP não permitido para o {} informado [nItem: {}]", tipo, n_item
+        )));
+    }
+
+    if item.icms_uf_dest.is_some() {
+        v.push(Violacao::new(807, campo("icms_uf_dest"), format!(
+            "NFC-e com grupo de ICMS para a UF do destinatário [nItem: {}]", n_item
+        )));
+    }
+}
+
+// Importação (CFOP 3xxx: DI, IPI, II) e exportação (CFOP 7xxx: detExport, ICMS)
+fn comercio_exterior(nota: &Nota, item: &Det, cst: &str, campo: &dyn Fn(&str) -> String, n_item: usize, v: &mut Vec<Violacao>) {
+    let ide = nota.ide;
+    if !item.det_export.is_empty() && ide.id_dest != 3 {
+        v.push(Violacao::new(336, campo("det_export"), format!(
+            "Informado o grupo de exportação no item em operação que não é com exterior [nItem: {}]", n_item
+        )));
+    }
+    if ide.mod_ == 65 {
+        if item.ii.is_some() {
+            v.push(Violacao::new(743, campo("ii"), format!("NFC-e com grupo do II [nItem: {}]", n_item)));
+        }
+        return;
+    }
+
+    let cfop = item.cfop;
+    if cfop / 1000 == 3 {
+        if !matches!(cfop, 3201 | 3202 | 3503 | 3553) && item.di.is_empty() {
+            v.push(Violacao::new(525, campo("di"), format!(
+                "CFOP de Importação e não informado dados da DI [nItem: {}]", n_item
+            )));
+        }
+        if !matches!(cfop, 3201 | 3202 | 3211 | 3503 | 3553) {
+            if item.ipi.is_none() {
+                v.push(Violacao::new(597, campo("ipi"), format!("CFOP de Importação e não informado dados de IPI [nItem: {}]", n_item)));
+            }
+            if item.ii.is_none() {
+                v.push(Violacao::new(599, campo("ii"), format!("CFOP de Importação e não informado dados de II [nItem: {}]", n_item)));
+            }
+        }
+    }
+    for (j, di) in item.di.iter().enumerate() {
+        let campo_di = |nome: &str| campo(&format!("di[{}].{}", j, nome));
+        if di.tp_via_transp == 1 && di.v_afrmm.is_none() {
+            v.push(Violacao::new(330, campo_di("v_afrmm"), "Informar o Valor da AFRMM na importação por via marítima"));
+        }
+        if matches!(di.tp_intermedio, 2 | 3) {
+            if di.cnpj.is_none() && di.cpf.is_none() {
+                v.push(Violacao::new(331, campo_di("cnpj"), "Informar o CNPJ do adquirente ou do encomendante nesta forma de importação"));
+            }
+            if di.uf_terceiro.is_none() {
+                v.push(Violacao::new(333, campo_di("uf_terceiro"), "Informar a UF do adquirente ou do encomendante nesta forma de importação"));
+            }
+        }
+        if matches!(cfop, 3127 | 3211) && di.adi.iter().any(|a| a.n_draw.is_none()) {
+            v.push(Violacao::new(334, campo_di("adi"), "Número do processo de drawback não informado na importação"));
+        }
+    }
+
+    if matches!(cfop, 7127 | 7211) && !item.det_export.iter().any(|e| e.n_draw.is_some()) {
+        v.push(Violacao::new(338, campo("det_export"), "Número do processo de drawback não informado na exportação"));
+    }
+    if matches!(cfop, 3503 | 7501) {
+        if ide.fin_nfe != 2 && !item.det_export.iter().any(|e| e.export_ind.is_some()) {
+            v.push(Violacao::new(340, campo("det_export"), format!(
+                "Não informado o grupo de exportação indireta no item [nItem: {}]", n_item
+            )));
+        }
+        if nota.referencias.is_empty() {
+            v.push(Violacao::new(701, campo("cfop"), format!(
+                "Não informado Nota Fiscal referenciada (CFOP de Exportação Indireta) [nItem: {}]", n_item
+            )));
+        }
+    }
+    let exportado: Option<Decimal> = item.det_export.iter()
+        .filter_map(|e| e.export_ind.as_ref().map(|x| x.q_export))
+        .reduce(|a, b| a + b);
+    if exportado.is_some_and(|q| q != item.q_com) {
+        v.push(Violacao::new(346, campo("det_export"), format!(
+            "Somatório das quantidades informadas na Exportação Indireta não corresponde a quantidade total do item [nItem: {}]", n_item
+        )));
+    }
+
+    if cfop / 1000 == 7 && ide.fin_nfe != 4 && !matches!(cst, "41" | "300") {
+        v.push(Violacao::new(527, campo("icms"), format!(
+            "Operação de Exportação com informação de ICMS incompatível (CST 41 ou CSOSN 300) [nItem: {}]", n_item
+        )));
+    }
+}
+
+fn totais(nota: &Nota, v: &mut Vec<Violacao>) {
+    let total = nota.total;
+    let soma = |f: fn(&Det) -> Option<Decimal>| -> Decimal {
+        nota.itens.iter().map(|d| arredondar(f(d).unwrap_or_default(), 2)).sum()
+    };
+    let despesas = [
+        (535, "total.v_frete", "Total do Frete", total.v_frete, soma(|d| d.v_frete)),
+        (536, "total.v_seg", "Total do Seguro", total.v_seg, soma(|d| d.v_seg)),
+        (604, "total.v_outro", "Total do vOutro", total.v_outro, soma(|d| d.v_outro)),
+    ];
+    for (c_stat, campo, nome, informado, itens) in despesas {
+        if arredondar(informado, 2) != itens {
+            v.push(Violacao::new(c_stat, campo, format!(
+                "{} ({}) difere do somatório dos itens ({})", nome, arredondar(informado, 2), itens
+            )));
+        }
+    }
+    // vII é sempre a soma de Det.ii; Total.v_ii, se informado, tem de coincidir com ela
+    let v_ii = arredondar(total.v_ii, 2);
+    let v_ii_itens = soma(|d| d.ii.map(|ii| ii.v_ii));
+    if nota.ide.mod_ == 55 && !v_ii.is_zero() && v_ii != v_ii_itens {
+        v.push(Violacao::new(601, "total.v_ii", format!(
+            "Total do II ({}) difere do somatório dos itens ({})", v_ii, v_ii_itens
+        )));
+    }
+
+    // Pagamento da NFC-e cobre o vNF, salvo ajuste, devolução e "sem pagamento"
+    let ide = nota.ide;
+    if ide.mod_ == 65 && !matches!(ide.fin_nfe, 3 | 4) && nota.pag.t_pag != "90" {
+        let Some(v_nf) = v_nf(nota) else { return };
+        let v_pag = arredondar(nota.pag.v_pag, 2);
+        if v_pag < v_nf {
+            v.push(Violacao::new(865, "pagamento.v_pag", format!(
+                "Total dos pagamentos ({}) menor que o total da nota ({})", v_pag, v_nf
+            )));
+        }
+    }
+}
+
+// Local de embarque só (e sempre) na NF-e de saída para o exterior
+fn exporta(nota: &Nota, v: &mut Vec<Violacao>) {
+    let ide = nota.ide;
+    match (ide.mod_, nota.exporta.is_some()) {
+        (65, true) => v.push(Violacao::new(814, "exporta", "Nota Fiscal com grupo de comércio exterior")),
+        (65, false) => {}
+        (_, false) if ide.tp_nf == 1 && ide.id_dest == 3 => {
+            v.push(Violacao::new(355, "exporta", "Informar o local de saída do Pais no caso da exportação"));
+        }
+        (_, true) if ide.tp_nf == 0 || ide.id_dest != 3 => {
+            v.push(Violacao::new(356, "exporta", "Informar o local de saída do Pais somente no caso da exportação"));
+        }
+        _ => {}
+    }
+}
+
+// vNF como a emissão calcula, a partir dos itens e de Total
+fn v_nf(nota: &Nota) -> Option<Decimal> {
+    let dets = det_process(nota.itens.to_vec(), nota.ide.mod_, nota.ide.tp_amb, nota.desconto_rateio, None).ok()?;
+    let total = total_process(nota.total.clone(), dets, nota.ide.tp_amb, None).ok()?;
+    Some(arredondar(total.v_nf, 2))
+}
+
+fn difere(informado: Decimal, calculado: Decimal) -> bool {
+    (arredondar(informado, 2) - arredondar(calculado, 2)).abs() > TOLERANCIA
+}
+
+// (é CSOSN, código) do grupo de ICMS do item
+fn cst_icms(icms: &Icms) -> (bool, String) {
+    match icms {
+        Icms::Icms00 { .. } => (false, "00".to_string()),
+        Icms::Icms10 { .. } => (false, "10".to_string()),
+        Icms::Icms20 { .. } => (false, "20".to_string()),
+        Icms::Icms30 { .. } => (false, "30".to_string()),
+        Icms::Icms40 { cst, .. } => (false, cst.to_string()),
+        Icms::Icms51 { .. } => (false, "51".to_string()),
+        Icms::Icms60 { .. } => (false, "60".to_string()),
+        Icms::Icms70 { .. } => (false, "70".to_string()),
+        Icms::Icms90 { .. } => (false, "90".to_string()),
+        Icms::Sn101 { .. } => (true, "101".to_string()),
+        Icms::Sn102 { csosn, .. } => (true, csosn.clone()),
+        Icms::Sn500 { .. } => (true, "500".to_string()),
+        Icms::Sn900 { .. } => (true, "900".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tipos::{Adi, Cofins, DetExport, Di, ExportInd, Ii, Ipi, Pis};
+    use rust_decimal_macros::dec;
+
+    fn agora() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2025-03-10T10:00:00-03:00").unwrap()
+    }
+
+    fn item() -> Det {
+        Det {
+            ncm: "22030000".into(),
+            cfop: 5102,
+            q_com: dec!(2),
+            v_un_com: dec!(50.00),
+            v_prod: dec!(100.00),
+            q_trib: dec!(2),
+            v_un_trib: dec!(50.00),
+            icms: Icms::sn102(0, "102"),
+            pis: Pis::Nt { cst: "07".into() },
+            cofins: Cofins::Nt { cst: "07".into() },
+            ..Default::default()
+        }
+    }
+
+    fn cstats(ide: &Ide, emit: &Emit, dest: Option<&Dest>, itens: &[Det], total: &Total, pag: &Pag) -> Vec<u16> {
+        let nota = Nota { ide, emit, dest, itens, total, pag, exporta: None, referencias: &[], desconto_rateio: None };
+        validar(&nota, agora()).into_iter().map(|v| v.c_stat).collect()
+    }
+
+    #[test]
+    fn test_nota_correta_sem_violacoes() {
+        let ide = Ide { dh_emi: Some("2025-03-10T09:58:00-03:00".into()), ..Default::default() };
+        let dest = Dest { ind_ie_dest: Some(9), ..Default::default() };
+        let pag = Pag { v_pag: dec!(100.00), ..Default::default() };
+        assert!(cstats(&ide, &Emit::default(), Some(&dest), &[item()], &Total::default(), &pag).is_empty());
+    }
+
+    #[test]
+    fn test_cfop_id_dest_e_contribuinte() {
+        let ide = Ide { ind_final: 0, ..Default::default() };
+        let dest = Dest { ind_ie_dest: Some(9), ..Default::default() };
+        let mut interestadual = item();
+        interestadual.cfop = 6102;
+        let mut entrada = item();
+        entrada.cfop = 1102;
+        let c = cstats(&ide, &Emit::default(), Some(&dest), &[interestadual, entrada], &Total::default(), &Pag::default());
+        assert_eq!(c, vec![696, 732, 518]);
+    }
+
+    #[test]
+    fn test_cfop_inexistente() {
+        // 4xxx não é operação com Exterior: só a falha de schema, sem 731
+        let mut inexistente = item();
+        inexistente.cfop = 4102;
+        let c = cstats(&Ide::default(), &Emit::default(), None, &[inexistente], &Total::default(), &Pag::default());
+        assert_eq!(c, vec![225]);
+
+        let nfce = Ide { mod_: 65, ..Default::default() };
+        let mut servico = item();
+        servico.cfop = 5933;
+        let mut zero = item();
+        zero.cfop = 102;
+        let pag = Pag { v_pag: dec!(200.00), ..Default::default() };
+        let c = cstats(&nfce, &Emit::default(), None, &[servico, zero], &Total::default(), &pag);
+        assert_eq!(c, vec![225, 386]);
+    }
+
+    #[test]
+    fn test_dh_emi_tolerancia() {
+        let emit = Emit::default();
+        let ide = |mod_: u32, dh: &str| Ide { mod_, dh_emi: Some(dh.into()), ..Default::default() };
+        let pag = Pag { v_pag: dec!(100.00), ..Default::default() };
+        let nfce = |dh: &str| cstats(&ide(65, dh), &emit, None, &[item()], &Total::default(), &pag);
+        assert!(nfce("2025-03-10T10:04:00-03:00").is_empty());
+        assert_eq!(nfce("2025-03-10T10:06:00-03:00"), vec![703]);
+        assert_eq!(nfce("2025-03-10T09:50:00-03:00"), vec![704]);
+        assert_eq!(nfce("10/03/2025 10:00"), vec![225]);
+
+        let nfe = cstats(&ide(55, "2025-01-10T10:00:00-03:00"), &emit, None, &[item()], &Total::default(), &pag);
+        assert_eq!(nfe, vec![228]);
+    }
+
+    #[test]
+    fn test_ncm_e_cst_pelo_crt() {
+        let mut sem_ncm = item();
+        sem_ncm.ncm = "2203".into();
+        let mut cst = item();
+        cst.icms = Icms::icms40(0, 41);
+        let c = cstats(&Ide::default(), &Emit::default(), None, &[sem_ncm, cst.clone()], &Total::default(), &Pag::default());
+        assert_eq!(c, vec![777, 590]);
+
+        let regime_normal = Emit { crt: 3, ..Default::default() };
+        let c = cstats(&Ide::default(), &regime_normal, None, &[item(), cst], &Total::default(), &Pag::default());
+        assert_eq!(c, vec![591]);
+    }
+
+    #[test]
+    fn test_totais_dos_itens() {
+        let mut frete = item();
+        frete.v_frete = Some(dec!(10.00));
+        frete.v_prod = dec!(100.02);
+        let mut icms = item();
+        icms.icms = Icms::icms00(0, 3, dec!(100.00), dec!(18.00), dec!(18.50));
+        let total = Total { v_frete: dec!(15.00), ..Default::default() };
+        let regime_normal = Emit { crt: 3, ..Default::default() };
+        let c = cstats(&Ide::default(), &regime_normal, None, &[frete, icms], &total, &Pag::default());
+        assert_eq!(c, vec![591, 629, 630, 528, 535]);
+    }
+
+    #[test]
+    fn test_restricoes_nfce() {
+        let ide = Ide { mod_: 65, id_dest: 2, ind_pres: 2, ..Default::default() };
+        let dest = Dest { ind_ie_dest: Some(1), ie: Some("111111111111".into()), ..Default::default() };
+        let mut st = item();
+        st.icms = Icms::sn500(0);
+        let mut sn101 = item();
+        sn101.icms = Icms::sn101(0, dec!(2.00), dec!(2.00));
+        let pag = Pag { t_pag: "01".into(), v_pag: dec!(150.00), ..Default::default() };
+        let c = cstats(&ide, &Emit::default(), Some(&dest), &[st, sn101], &Total::default(), &pag);
+        assert_eq!(c, vec![707, 717, 789, 386, 383, 865]);
+    }
+
+    #[test]
+    fn test_importacao_cfop_3xxx() {
+        let ide = Ide { tp_nf: 0, id_dest: 3, ..Default::default() };
+        let mut sem_grupos = item();
+        sem_grupos.cfop = 3102;
+        let di = Di {
+            tp_via_transp: 1,
+            tp_intermedio: 2,
+            adi: vec![Adi { n_seq_adic: 1, c_fabricante: "FAB".into(), ..Default::default() }],
+            ..Default::default()
+        };
+        let drawback = Det {
+            cfop: 3127,
+            di: vec![di],
+            ipi: Some(Ipi::nao_tributado("999", "03")),
+            ii: Some(Ii { v_ii: dec!(10.00), ..Default::default() }),
+            ..item()
+        };
+        let total = Total { v_ii: dec!(10.00), ..Default::default() };
+        let itens = [sem_grupos, drawback];
+        let c = cstats(&ide, &Emit::default(), None, &itens, &total, &Pag::default());
+        assert_eq!(c, vec![525, 597, 599, 330, 331, 333, 334]);
+
+        // Total.v_ii diferente do II dos itens
+        let total = Total { v_ii: dec!(12.00), ..Default::default() };
+        let c = cstats(&ide, &Emit::default(), None, &itens, &total, &Pag::default());
+        assert_eq!(c, vec![525, 597, 599, 330, 331, 333, 334, 601]);
+    }
+
+    #[test]
+    fn test_exportacao_cfop_7xxx_e_exporta() {
+        let ide = Ide { id_dest: 3, ..Default::default() };
+        let mut icms = item();
+        icms.cfop = 7101;
+        let indireta = Det {
+            cfop: 7501,
+            icms: Icms::sn102(0, "300"),
+            det_export: vec![DetExport {
+                n_draw: None,
+                export_ind: Some(ExportInd { n_re: "1".into(), ch_nfe: "3".repeat(44), q_export: dec!(1) }),
+            }],
+            ..item()
+        };
+        let c = cstats(&ide, &Emit::default(), None, &[icms, indireta], &Total::default(), &Pag::default());
+        assert_eq!(c, vec![527, 701, 346, 355]);
+
+        let exporta = Exporta { uf_saida_pais: "SP".into(), x_loc_exporta: "PORTO DE SANTOS".into(), x_loc_despacho: None };
+        let detalhe = Det { det_export: vec![DetExport::default()], ..item() };
+        let c = |ide: &Ide, itens: &[Det]| -> Vec<u16> {
+            let (emit, total, pag) = (Emit::default(), Total::default(), Pag::default());
+            let nota = Nota {
+                ide, emit: &emit, dest: None, itens, total: &total, pag: &pag,
+                exporta: Some(&exporta), referencias: &[], desconto_rateio: None,
+            };
+            validar(&nota, agora()).into_iter().map(|v| v.c_stat).collect()
+        };
+        assert_eq!(c(&Ide::default(), &[detalhe]), vec![336, 356]);
+
+        let nfce = Ide { mod_: 65, ..Default::default() };
+        let ii = Det { ii: Some(Ii::default()), ..item() };
+        let c = c(&nfce, &[ii]);
+        assert_eq!(c, vec![743, 865, 814]);
+    }
+}
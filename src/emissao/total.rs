@@ -1,13 +1,16 @@
 use super::det_process::entity::{COFINSProcess, DetProcess, ICMSProcess, PISProcess};
 use crate::tipos::Total;
 use crate::error::Result;
-use rust_decimal::prelude::ToPrimitive;
+use crate::interno::decimal::{arredondar, fmt_casas};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename = "total")]
 pub struct TotalProcess {
+    /// vNF exato, para o pagamento e o QR Code
+    #[serde(skip)]
+    pub v_nf: Decimal,
     #[serde(rename = "ICMSTot")]
     pub icms_tot: ICMSTot,
     /// Totais da NF-e com IBS e CBS
@@ -154,114 +157,130 @@ pub fn total_process(
     _active_ibscbs: Option<String>,
 ) -> Result<TotalProcess> {
     // ── Totais calculados dos itens ───────────────────────────────────────────
-    let mut v_bc          = 0.0_f64;
-    let mut v_icms        = 0.0_f64;
-    let mut v_icms_deson  = 0.0_f64;
-    let mut v_bc_st_items = 0.0_f64;
-    let mut v_st_items    = 0.0_f64;
-    let mut v_ipi_items   = 0.0_f64;
-    let mut v_prod        = 0.0_f64;
+    let mut v_bc          = Decimal::ZERO;
+    let mut v_icms        = Decimal::ZERO;
+    let mut v_icms_deson  = Decimal::ZERO;
+    let mut v_bc_st_items = Decimal::ZERO;
+    let mut v_st_items    = Decimal::ZERO;
+    let mut v_fcp_items   = Decimal::ZERO;
+    let mut v_fcpst_items = Decimal::ZERO;
+    let mut v_fcpuf_dest_items    = Decimal::ZERO;
+    let mut v_icms_uf_dest_items  = Decimal::ZERO;
+    let mut v_icms_uf_remet_items = Decimal::ZERO;
+    let mut v_ipi_items   = Decimal::ZERO;
+    let mut v_ii_items    = Decimal::ZERO;
+    let mut v_prod        = Decimal::ZERO;
     let mut v_desc        = Decimal::ZERO;
-    let mut v_pis         = 0.0_f64;
-    let mut v_cofins      = 0.0_f64;
-    let mut v_tot_trib    = 0.0_f64;
+    let mut v_pis         = Decimal::ZERO;
+    let mut v_cofins      = Decimal::ZERO;
+    let mut v_tot_trib    = Decimal::ZERO;
 
     // ── Totais IBS/CBS ────────────────────────────────────────────────────────
-    let mut v_bc_ibs_cbs_total    = 0.0_f64;
-    let mut ibs_uf_total          = 0.0_f64;
-    let mut ibs_uf_dif_total      = 0.0_f64;
-    let mut ibs_uf_dev_trib_total = 0.0_f64;
-    let mut ibs_mun_total         = 0.0_f64;
-    let mut ibs_mun_dif_total     = 0.0_f64;
-    let mut ibs_mun_dev_trib_total = 0.0_f64;
-    let mut ibs_total             = 0.0_f64;
-    let mut cbs_total             = 0.0_f64;
-    let mut cbs_dif_total         = 0.0_f64;
-    let mut cbs_dev_trib_total    = 0.0_f64;
+    let mut v_bc_ibs_cbs_total    = Decimal::ZERO;
+    let mut ibs_uf_total          = Decimal::ZERO;
+    let mut ibs_uf_dif_total      = Decimal::ZERO;
+    let mut ibs_uf_dev_trib_total = Decimal::ZERO;
+    let mut ibs_mun_total         = Decimal::ZERO;
+    let mut ibs_mun_dif_total     = Decimal::ZERO;
+    let mut ibs_mun_dev_trib_total = Decimal::ZERO;
+    let mut ibs_total             = Decimal::ZERO;
+    let mut cbs_total             = Decimal::ZERO;
+    let mut cbs_dif_total         = Decimal::ZERO;
+    let mut cbs_dev_trib_total    = Decimal::ZERO;
 
     for det in &dets {
-        v_bc           += icms_v_bc(&det.imposto.icms);
-        v_icms         += icms_v_icms(&det.imposto.icms);
-        v_icms_deson   += icms_v_deson(&det.imposto.icms);
-        v_bc_st_items  += icms_v_bcst(&det.imposto.icms);
-        v_st_items     += icms_v_icmsst(&det.imposto.icms);
+        v_bc           += arredondar(icms_v_bc(&det.imposto.icms), 2);
+        v_icms         += arredondar(icms_v_icms(&det.imposto.icms), 2);
+        v_icms_deson   += arredondar(icms_v_deson(&det.imposto.icms), 2);
+        v_bc_st_items  += arredondar(icms_v_bcst(&det.imposto.icms), 2);
+        v_st_items     += arredondar(icms_v_icmsst(&det.imposto.icms), 2);
+        v_fcp_items    += arredondar(icms_v_fcp(&det.imposto.icms), 2);
+        v_fcpst_items  += arredondar(icms_v_fcpst(&det.imposto.icms), 2);
+        if let Some(uf) = det.imposto.icms_uf_dest.as_ref() {
+            v_fcpuf_dest_items    += arredondar(uf.v_fcp_uf_dest.unwrap_or_default(), 2);
+            v_icms_uf_dest_items  += arredondar(uf.v_icms_uf_dest, 2);
+            v_icms_uf_remet_items += arredondar(uf.v_icms_uf_remet, 2);
+        }
         v_ipi_items    += ipi_v_ipi(&det.imposto.ipi);
-        v_prod       += det.prod.v_prod.parse::<f64>().unwrap_or(0.0);
-        v_desc       += det.prod.v_desc.unwrap_or(Decimal::ZERO);
-        v_pis        += pis_v_pis(&det.imposto.pis);
-        v_cofins     += cofins_v_cofins(&det.imposto.cofins);
-        v_tot_trib   += det.imposto.v_tot_trib.parse::<f64>().unwrap_or(0.0);
+        v_ii_items     += det.imposto.ii.as_ref().map(|ii| arredondar(ii.v_ii, 2)).unwrap_or_default();
+        v_prod       += arredondar(det.prod.v_prod, 2);
+        v_desc       += arredondar(det.prod.v_desc.unwrap_or(Decimal::ZERO), 2);
+        v_pis        += arredondar(pis_v_pis(&det.imposto.pis), 2);
+        v_cofins     += arredondar(cofins_v_cofins(&det.imposto.cofins), 2);
+        v_tot_trib   += arredondar(det.imposto.v_tot_trib, 2);
 
         if let Some(ibs_cbs) = det.imposto.ibs_cbs.as_ref() {
-            v_bc_ibs_cbs_total += ibs_cbs.g_ibscbs.v_bc.parse::<f64>().unwrap_or(0.0);
+            v_bc_ibs_cbs_total += ibs_cbs.g_ibscbs.v_bc.parse::<Decimal>().unwrap_or_default();
 
-            let v_ibs_uf = ibs_cbs.g_ibscbs.g_ibs_uf.v_ibs_uf.parse::<f64>().unwrap_or(0.0);
+            let v_ibs_uf = ibs_cbs.g_ibscbs.g_ibs_uf.v_ibs_uf.parse::<Decimal>().unwrap_or_default();
             ibs_uf_total += v_ibs_uf;
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_ibs_uf.g_dif {
-                ibs_uf_dif_total += g.v_dif.to_f64().unwrap_or(0.0);
+                ibs_uf_dif_total += g.v_dif;
             }
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_ibs_uf.g_dev_trib {
-                ibs_uf_dev_trib_total += g.v_dev_trib.to_f64().unwrap_or(0.0);
+                ibs_uf_dev_trib_total += g.v_dev_trib;
             }
 
-            let v_ibs_mun = ibs_cbs.g_ibscbs.g_ibs_mun.v_ibs_mun.parse::<f64>().unwrap_or(0.0);
+            let v_ibs_mun = ibs_cbs.g_ibscbs.g_ibs_mun.v_ibs_mun.parse::<Decimal>().unwrap_or_default();
             ibs_mun_total += v_ibs_mun;
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_ibs_mun.g_dif {
-                ibs_mun_dif_total += g.v_dif.to_f64().unwrap_or(0.0);
+                ibs_mun_dif_total += g.v_dif;
             }
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_ibs_mun.g_dev_trib {
-                ibs_mun_dev_trib_total += g.v_dev_trib.to_f64().unwrap_or(0.0);
+                ibs_mun_dev_trib_total += g.v_dev_trib;
             }
 
-            ibs_total += ibs_cbs.g_ibscbs.v_ibs.parse::<f64>().unwrap_or(0.0);
-            cbs_total += ibs_cbs.g_ibscbs.g_cbs.v_cbs.parse::<f64>().unwrap_or(0.0);
+            ibs_total += ibs_cbs.g_ibscbs.v_ibs.parse::<Decimal>().unwrap_or_default();
+            cbs_total += ibs_cbs.g_ibscbs.g_cbs.v_cbs.parse::<Decimal>().unwrap_or_default();
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_cbs.g_dif {
-                cbs_dif_total += g.v_dif.to_f64().unwrap_or(0.0);
+                cbs_dif_total += g.v_dif;
             }
             if let Some(ref g) = ibs_cbs.g_ibscbs.g_cbs.g_dev_trib {
-                cbs_dev_trib_total += g.v_dev_trib.to_f64().unwrap_or(0.0);
+                cbs_dev_trib_total += g.v_dev_trib;
             }
         }
     }
 
-    let v_desc_f64 = v_desc.to_f64().unwrap_or(0.0);
+    // Valores globais de Total entram nas somas com as mesmas 2 casas do XML
+    let total = total_2_casas(total);
     // v_bc_st e v_st: auto-calculado dos itens + valor global informado em Total
     let total_v_bc_st = v_bc_st_items + total.v_bc_st;
     let total_v_st    = v_st_items    + total.v_st;
+    // FCP e DIFAL: idem
+    let total_v_fcp   = v_fcp_items   + total.v_fcp;
+    let total_v_fcpst = v_fcpst_items + total.v_fcpst;
     // v_ipi: auto-calculado dos itens + valor global informado em Total
     let total_v_ipi = v_ipi_items + total.v_ipi;
-    let v_nf = v_prod + total.v_frete + total.v_seg - v_desc_f64
-               + total.v_outro + total.v_ii + total_v_ipi - total.v_ipi_devol;
+    // v_ii: só dos itens com II — Total.v_ii é ignorado (rejeição 601)
+    let total_v_ii = v_ii_items;
+    // ST e FCP-ST são cobrados do destinatário e integram o vNF
+    let v_nf = v_prod + total.v_frete + total.v_seg - v_desc
+               + total.v_outro + total_v_ii + total_v_ipi + total.v_ipi_devol
+               + total_v_st + total_v_fcpst;
 
     // Só envia IBSCBSTot se algum item tiver IBS/CBS — enviar zerado causa rejeição 1118
-    let send_ibs_cbs = if v_bc_ibs_cbs_total > 0.0 {
+    let send_ibs_cbs = if v_bc_ibs_cbs_total > Decimal::ZERO {
         Some(IBSCBSTot {
-            v_bc_ibs_cbs: format!("{:.2}", v_bc_ibs_cbs_total),
+            v_bc_ibs_cbs: fmt_casas(v_bc_ibs_cbs_total, 2),
             g_ibs: GIBS {
                 g_ibs_uf: GIBSUF {
-                    v_dif: Decimal::from_str_exact(&format!("{:.2}", ibs_uf_dif_total))
-                        .unwrap_or(Decimal::new(0, 2)),
-                    v_dev_trib: Decimal::from_str_exact(&format!("{:.2}", ibs_uf_dev_trib_total))
-                        .unwrap_or(Decimal::new(0, 2)),
-                    v_ibs_uf: format!("{:.2}", ibs_uf_total),
+                    v_dif: arredondar(ibs_uf_dif_total, 2),
+                    v_dev_trib: arredondar(ibs_uf_dev_trib_total, 2),
+                    v_ibs_uf: fmt_casas(ibs_uf_total, 2),
                 },
                 g_ibs_mun: GIBSMun {
-                    v_dif: Decimal::from_str_exact(&format!("{:.2}", ibs_mun_dif_total))
-                        .unwrap_or(Decimal::new(0, 2)),
-                    v_dev_trib: Decimal::from_str_exact(&format!("{:.2}", ibs_mun_dev_trib_total))
-                        .unwrap_or(Decimal::new(0, 2)),
-                    v_ibs_mun: format!("{:.2}", ibs_mun_total),
+                    v_dif: arredondar(ibs_mun_dif_total, 2),
+                    v_dev_trib: arredondar(ibs_mun_dev_trib_total, 2),
+                    v_ibs_mun: fmt_casas(ibs_mun_total, 2),
                 },
-                v_ibs: format!("{:.2}", ibs_total),
+                v_ibs: fmt_casas(ibs_total, 2),
                 v_cred_pres: None,
                 v_cred_pres_cond_sus: None,
             },
             g_cbs: GCBS {
-                v_dif: Decimal::from_str_exact(&format!("{:.2}", cbs_dif_total))
-                    .unwrap_or(Decimal::new(0, 2)),
-                v_dev_trib: Decimal::from_str_exact(&format!("{:.2}", cbs_dev_trib_total))
-                    .unwrap_or(Decimal::new(0, 2)),
-                v_cbs: format!("{:.2}", cbs_total),
+                v_dif: arredondar(cbs_dif_total, 2),
+                v_dev_trib: arredondar(cbs_dev_trib_total, 2),
+                v_cbs: fmt_casas(cbs_total, 2),
                 v_cred_pres: None,
                 v_cred_pres_cond_sus: None,
             },
@@ -271,122 +290,149 @@ pub fn total_process(
     };
 
     let send_icms_tot = ICMSTot {
-        v_bc:           format!("{:.2}", v_bc),
-        v_icms:         format!("{:.2}", v_icms),
-        v_icms_deson:   format!("{:.2}", v_icms_deson),
-        v_fcpuf_dest:   format!("{:.2}", total.v_fcpuf_dest),
-        v_icms_uf_dest: format!("{:.2}", total.v_icms_uf_dest),
-        v_icms_uf_remet:format!("{:.2}", total.v_icms_uf_remet),
-        v_fcp:          format!("{:.2}", total.v_fcp),
-        v_bc_st:        format!("{:.2}", total_v_bc_st),
-        v_st:           format!("{:.2}", total_v_st),
-        v_fcpst:        format!("{:.2}", total.v_fcpst),
-        v_fcpst_ret:    format!("{:.2}", total.v_fcpst_ret),
-        v_prod:         format!("{:.2}", v_prod),
-        v_frete:        format!("{:.2}", total.v_frete),
-        v_seg:          format!("{:.2}", total.v_seg),
-        v_desc:         format!("{:.2}", v_desc_f64),
-        v_ii:           format!("{:.2}", total.v_ii),
-        v_ipi:          format!("{:.2}", total_v_ipi),
-        v_ipi_devol:    format!("{:.2}", total.v_ipi_devol),
-        v_pis:          format!("{:.2}", v_pis),
-        v_cofins:       format!("{:.2}", v_cofins),
-        v_outro:        format!("{:.2}", total.v_outro),
-        v_nf:           format!("{:.2}", v_nf),
-        v_tot_trib:     format!("{:.2}", v_tot_trib),
+        v_bc:           fmt_casas(v_bc, 2),
+        v_icms:         fmt_casas(v_icms, 2),
+        v_icms_deson:   fmt_casas(v_icms_deson, 2),
+        v_fcpuf_dest:   fmt_casas(v_fcpuf_dest_items + total.v_fcpuf_dest, 2),
+        v_icms_uf_dest: fmt_casas(v_icms_uf_dest_items + total.v_icms_uf_dest, 2),
+        v_icms_uf_remet:fmt_casas(v_icms_uf_remet_items + total.v_icms_uf_remet, 2),
+        v_fcp:          fmt_casas(total_v_fcp, 2),
+        v_bc_st:        fmt_casas(total_v_bc_st, 2),
+        v_st:           fmt_casas(total_v_st, 2),
+        v_fcpst:        fmt_casas(total_v_fcpst, 2),
+        v_fcpst_ret:    fmt_casas(total.v_fcpst_ret, 2),
+        v_prod:         fmt_casas(v_prod, 2),
+        v_frete:        fmt_casas(total.v_frete, 2),
+        v_seg:          fmt_casas(total.v_seg, 2),
+        v_desc:         fmt_casas(v_desc, 2),
+        v_ii:           fmt_casas(total_v_ii, 2),
+        v_ipi:          fmt_casas(total_v_ipi, 2),
+        v_ipi_devol:    fmt_casas(total.v_ipi_devol, 2),
+        v_pis:          fmt_casas(v_pis, 2),
+        v_cofins:       fmt_casas(v_cofins, 2),
+        v_outro:        fmt_casas(total.v_outro, 2),
+        v_nf:           fmt_casas(v_nf, 2),
+        v_tot_trib:     fmt_casas(v_tot_trib, 2),
     };
 
     Ok(TotalProcess {
+        v_nf,
         icms_tot: send_icms_tot,
         ibs_cbs_tot: send_ibs_cbs,
     })
 }
 
+fn total_2_casas(t: Total) -> Total {
+    let c = |v: Decimal| arredondar(v, 2);
+    Total {
+        v_bc_st: c(t.v_bc_st), v_st: c(t.v_st), v_fcp: c(t.v_fcp), v_fcpst: c(t.v_fcpst),
+        v_fcpst_ret: c(t.v_fcpst_ret), v_fcpuf_dest: c(t.v_fcpuf_dest),
+        v_icms_uf_dest: c(t.v_icms_uf_dest), v_icms_uf_remet: c(t.v_icms_uf_remet),
+        v_frete: c(t.v_frete), v_seg: c(t.v_seg), v_outro: c(t.v_outro), v_ii: c(t.v_ii),
+        v_ipi: c(t.v_ipi), v_ipi_devol: c(t.v_ipi_devol),
+    }
+}
+
 // ── Extratores de valores dos itens ──────────────────────────────────────────
 
 use super::det_process::entity::IpiProcess;
 
-fn icms_v_bc(icms: &ICMSProcess) -> f64 {
+fn icms_v_bc(icms: &ICMSProcess) -> Decimal {
     match icms {
         ICMSProcess::ICMS00(v) => v.v_bc,
         ICMSProcess::ICMS10(v) => v.v_bc,
         ICMSProcess::ICMS20(v) => v.v_bc,
-        ICMSProcess::ICMS51(v) => v.v_bc.unwrap_or(0.0),
+        ICMSProcess::ICMS51(v) => v.v_bc.unwrap_or_default(),
         ICMSProcess::ICMS70(v) => v.v_bc,
-        ICMSProcess::ICMS90(v) => v.v_bc.unwrap_or(0.0),
-        ICMSProcess::ICMSSN900(v) => v.vbc.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        _ => 0.0,
+        ICMSProcess::ICMS90(v) => v.v_bc.unwrap_or_default(),
+        ICMSProcess::ICMSSN900(v) => v.vbc.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        _ => Decimal::ZERO,
     }
 }
 
-fn icms_v_icms(icms: &ICMSProcess) -> f64 {
+fn icms_v_icms(icms: &ICMSProcess) -> Decimal {
     match icms {
         ICMSProcess::ICMS00(v) => v.v_icms,
         ICMSProcess::ICMS10(v) => v.v_icms,
         ICMSProcess::ICMS20(v) => v.v_icms,
-        ICMSProcess::ICMS51(v) => v.v_icms.unwrap_or(0.0),
+        ICMSProcess::ICMS51(v) => v.v_icms.unwrap_or_default(),
         ICMSProcess::ICMS70(v) => v.v_icms,
-        ICMSProcess::ICMS90(v) => v.v_icms.unwrap_or(0.0),
-        ICMSProcess::ICMSSN900(v) => v.vicms.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        _ => 0.0,
+        ICMSProcess::ICMS90(v) => v.v_icms.unwrap_or_default(),
+        ICMSProcess::ICMSSN900(v) => v.vicms.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        _ => Decimal::ZERO,
     }
 }
 
-fn icms_v_deson(icms: &ICMSProcess) -> f64 {
+fn icms_v_deson(icms: &ICMSProcess) -> Decimal {
     match icms {
-        ICMSProcess::ICMS40(v)  => v.vicmsdeson.unwrap_or(0.0),
-        ICMSProcess::ICMS20(v)  => v.v_icms_deson.unwrap_or(0.0),
-        ICMSProcess::ICMS30(v)  => v.v_icms_deson.unwrap_or(0.0),
-        ICMSProcess::ICMS70(v)  => v.v_icms_deson.unwrap_or(0.0),
-        ICMSProcess::ICMS90(v)  => v.v_icms_deson.unwrap_or(0.0),
-        _ => 0.0,
+        ICMSProcess::ICMS40(v)  => v.vicmsdeson.unwrap_or_default(),
+        ICMSProcess::ICMS20(v)  => v.v_icms_deson.unwrap_or_default(),
+        ICMSProcess::ICMS30(v)  => v.v_icms_deson.unwrap_or_default(),
+        ICMSProcess::ICMS70(v)  => v.v_icms_deson.unwrap_or_default(),
+        ICMSProcess::ICMS90(v)  => v.v_icms_deson.unwrap_or_default(),
+        _ => Decimal::ZERO,
     }
 }
 
-fn icms_v_bcst(icms: &ICMSProcess) -> f64 {
+fn icms_v_bcst(icms: &ICMSProcess) -> Decimal {
     match icms {
         ICMSProcess::ICMS10(v) => v.v_bcst,
         ICMSProcess::ICMS30(v) => v.v_bcst,
         ICMSProcess::ICMS70(v) => v.v_bcst,
-        ICMSProcess::ICMS90(v) => v.v_bcst.unwrap_or(0.0),
-        ICMSProcess::ICMSSN900(v) => v.vbcst.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        _ => 0.0,
+        ICMSProcess::ICMS90(v) => v.v_bcst.unwrap_or_default(),
+        ICMSProcess::ICMSSN900(v) => v.vbcst.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        _ => Decimal::ZERO,
     }
 }
 
-fn icms_v_icmsst(icms: &ICMSProcess) -> f64 {
+fn icms_v_icmsst(icms: &ICMSProcess) -> Decimal {
     match icms {
         ICMSProcess::ICMS10(v) => v.v_icmsst,
         ICMSProcess::ICMS30(v) => v.v_icmsst,
         ICMSProcess::ICMS70(v) => v.v_icmsst,
-        ICMSProcess::ICMS90(v) => v.v_icmsst.unwrap_or(0.0),
-        ICMSProcess::ICMSSN900(v) => v.vicmsst.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0),
-        _ => 0.0,
+        ICMSProcess::ICMS90(v) => v.v_icmsst.unwrap_or_default(),
+        ICMSProcess::ICMSSN900(v) => v.vicmsst.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        _ => Decimal::ZERO,
     }
 }
 
-fn ipi_v_ipi(ipi: &Option<IpiProcess>) -> f64 {
-    ipi.as_ref().filter(|p| p.tributado)
-        // o XML inner do IPITrib contém vIPI serializado
-        .and_then(|p| {
-            let xml = &p.inner;
-            let start = xml.find("<vIPI>")? + 6;
-            let end   = xml.find("</vIPI>")?;
-            xml[start..end].parse().ok()
-        })
-        .unwrap_or(0.0)
+fn icms_v_fcp(icms: &ICMSProcess) -> Decimal {
+    match icms {
+        ICMSProcess::ICMS00(v) => v.v_fcp.unwrap_or_default(),
+        ICMSProcess::ICMS10(v) => v.v_fcp.unwrap_or_default(),
+        ICMSProcess::ICMS20(v) => v.v_fcp.unwrap_or_default(),
+        ICMSProcess::ICMS51(v) => v.v_fcp.unwrap_or_default(),
+        ICMSProcess::ICMS70(v) => v.v_fcp.unwrap_or_default(),
+        ICMSProcess::ICMS90(v) => v.v_fcp.unwrap_or_default(),
+        _ => Decimal::ZERO,
+    }
+}
+
+fn icms_v_fcpst(icms: &ICMSProcess) -> Decimal {
+    match icms {
+        ICMSProcess::ICMS10(v) => v.v_fcpst.unwrap_or_default(),
+        ICMSProcess::ICMS30(v) => v.v_fcpst.unwrap_or_default(),
+        ICMSProcess::ICMS70(v) => v.v_fcpst.unwrap_or_default(),
+        ICMSProcess::ICMS90(v) => v.v_fcpst.unwrap_or_default(),
+        ICMSProcess::ICMSSN900(v) => v.v_fcpst.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(),
+        _ => Decimal::ZERO,
+    }
+}
+
+fn ipi_v_ipi(ipi: &Option<IpiProcess>) -> Decimal {
+    ipi.as_ref().and_then(|p| p.v_ipi).map(|v| arredondar(v, 2)).unwrap_or_default()
 }
 
-fn pis_v_pis(pis: &PISProcess) -> f64 {
+fn pis_v_pis(pis: &PISProcess) -> Decimal {
     if let Some(v) = &pis.pis_aliq  { return v.v_pis; }
-    if let Some(v) = &pis.pis_qtde  { return v.vpis.parse().unwrap_or(0.0); }
-    if let Some(v) = &pis.pis_st    { return v.vpis.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0); }
-    0.0
+    if let Some(v) = &pis.pis_qtde  { return v.vpis.parse().unwrap_or_default(); }
+    if let Some(v) = &pis.pis_st    { return v.vpis.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(); }
+    Decimal::ZERO
 }
 
-fn cofins_v_cofins(cofins: &COFINSProcess) -> f64 {
+fn cofins_v_cofins(cofins: &COFINSProcess) -> Decimal {
     if let Some(v) = &cofins.cofins_aliq { return v.v_cofins; }
-    if let Some(v) = &cofins.cofins_qtde { return v.vcofins.parse().unwrap_or(0.0); }
-    if let Some(v) = &cofins.cofins_st   { return v.vcofins.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0.0); }
-    0.0
+    if let Some(v) = &cofins.cofins_qtde { return v.vcofins.parse().unwrap_or_default(); }
+    if let Some(v) = &cofins.cofins_st   { return v.vcofins.as_deref().and_then(|s| s.parse().ok()).unwrap_or_default(); }
+    Decimal::ZERO
 }
@@ -1,5 +1,5 @@
 use crate::error::{DfeError, Result};
-use crate::xml_extractor::{XmlExtractor, XmlExtractorSignature};
+use crate::xml_extractor::nfe_proc_ou_offline;
 
 use super::EscPosBuilder;
 
@@ -146,11 +146,12 @@ impl EscPosNFCeBuilder {
             .xml
             .ok_or_else(|| DfeError::Configuracao("XML não informado".to_string()))?;
 
-        let extractor = XmlExtractor::new();
         let nfe_proc = if src.trim_end().ends_with(".xml") {
-            extractor.nfe_proc_from_file(&src)?
+            let xml = std::fs::read_to_string(&src)
+                .map_err(|e| DfeError::Io(format!("Failed to open file: {} [{}]", src, e)))?;
+            nfe_proc_ou_offline(&xml)?
         } else {
-            extractor.nfe_proc_from_string(&src)?
+            nfe_proc_ou_offline(&src)?
         };
 
         let inf = &nfe_proc.nfe.inf_nfe;
@@ -192,6 +193,7 @@ impl EscPosNFCeBuilder {
 
         let ide = &inf.ide;
         let tp_amb = ide.tp_amb.clone().unwrap_or_default();
+        let tp_emis = ide.tp_emis.clone().unwrap_or_default();
         let serie = ide.serie.clone().unwrap_or_default();
         let n_nf = ide.n_nf.clone().unwrap_or_default();
         let dh_emi = ide.dh_emi.clone().unwrap_or_default();
@@ -286,6 +288,7 @@ impl EscPosNFCeBuilder {
             emit_x_bairro,
             emit_x_mun,
             tp_amb,
+            tp_emis,
             serie,
             n_nf,
             dh_emi,
@@ -339,6 +342,7 @@ struct BuildParams {
     emit_x_bairro: String,
     emit_x_mun: String,
     tp_amb: String,
+    tp_emis: String,
     serie: String,
     n_nf: String,
     dh_emi: String,
@@ -384,6 +388,17 @@ fn build_receipt(p: BuildParams) -> Result<Vec<u8>> {
             .line_spacing(SPACING_DIVIDER).divider().line_spacing(SPACING_NORMAL);
     }
 
+    // ── Contingência offline (tpEmis 9) ───────────────────────────────────────
+    if p.tp_emis == "9" {
+        b = b
+            .align_center()
+            .bold(true)
+            .text("EMITIDA EM CONTINGÊNCIA\n")
+            .bold(false)
+            .text("Pendente de autorização\n")
+            .line_spacing(SPACING_DIVIDER).divider().line_spacing(SPACING_NORMAL);
+    }
+
     // ── Título ────────────────────────────────────────────────────────────────
     b = b
         .align_center()
@@ -809,4 +824,16 @@ mod tests {
         let result = EscPosNFCeBuilder::new().xml(xml).build();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn offline_sem_protocolo_imprime_contingencia() {
+        let xml = r#"<NFe><infNFe Id="NFe35250111222333000181650010000000019000000018"><ide><mod>65</mod><serie>1</serie><nNF>1</nNF><tpEmis>9</tpEmis><tpAmb>1</tpAmb></ide><emit><CNPJ>11222333000181</CNPJ><enderEmit/></emit><det nItem="1"><prod><xProd>ITEM</xProd></prod><imposto/></det><total/><transp/><pag/><infAdic/></infNFe></NFe>"#;
+        let bytes = EscPosNFCeBuilder::new().xml(xml).build().unwrap();
+        let texto = String::from_utf8_lossy(&bytes);
+        assert!(texto.contains("EMITIDA EM CONTING"));
+        assert!(texto.contains("Pendente de autoriza"));
+
+        let normal = xml.replace("<tpEmis>9</tpEmis>", "<tpEmis>1</tpEmis>");
+        assert!(EscPosNFCeBuilder::new().xml(normal).build().is_err());
+    }
 }
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Payload de uma operação com a SEFAZ entregue ao [`ArtefatoSink`].
+#[derive(Debug, Clone, Copy)]
+pub struct Artefato<'a> {
+    /// Serviço: `autorizacao`, `ret-autorizacao`, `epec`, `consulta-protocolo`, `cancelamento`,
+    /// `carta-correcao`, `inutilizacao`, `consulta-cadastro`, `status-servico`, `distribuicao`
+    /// ou `manifestacao`.
+    pub servico: &'a str,
+    /// Chave de acesso da nota; sem nota única, o identificador da operação
+    /// (`idLote`, CNPJ da distribuição, UF do status…).
+    pub chave: &'a str,
+    /// Conteúdo: `nfe`, `envelope`, `retorno`, `procNFe`, `inf-evento`, `erro` ou `nsu-<NSU>`.
+    pub nome: &'a str,
+    /// XML (ou texto, em `erro`).
+    pub conteudo: &'a str,
+}
+
+/// Destino dos XML de envio e retorno de todos os serviços.
+///
+/// Sem sink nada é gravado. [`ArtefatosPorChave`] grava um diretório por chave;
+/// [`SemArtefatos`] desliga explicitamente. Qualquer closure `Fn(&Artefato)` também
+/// serve — para mandar os payloads a um bucket, banco ou log estruturado.
+///
+/// `gravar` não devolve erro: uma falha ao guardar o payload não interrompe a
+/// comunicação com a SEFAZ.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use dfe::{Artefato, ArtefatoSink, CancelarBuilder};
+///
+/// let sink: Arc<dyn ArtefatoSink> = Arc::new(|a: &Artefato| {
+///     println!("{} {} {} ({} bytes)", a.servico, a.chave, a.nome, a.conteudo.len());
+/// });
+/// let cancelar = CancelarBuilder::new().artefatos(sink);
+/// # let _ = cancelar;
+/// ```
+pub trait ArtefatoSink: Send + Sync {
+    /// Recebe um payload. Chamado na ordem em que a operação acontece.
+    fn gravar(&self, artefato: &Artefato);
+}
+
+impl std::fmt::Debug for dyn ArtefatoSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ArtefatoSink")
+    }
+}
+
+impl<F> ArtefatoSink for F
+where
+    F: Fn(&Artefato) + Send + Sync,
+{
+    fn gravar(&self, artefato: &Artefato) {
+        self(artefato)
+    }
+}
+
+/// Não grava nada.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemArtefatos;
+
+impl ArtefatoSink for SemArtefatos {
+    fn gravar(&self, _artefato: &Artefato) {}
+}
+
+/// Um diretório por chave: `<dir>/<chave>/<AAAAMMDD-HHMMSS.ffffff>-<servico>-<nome>.xml`
+/// (`.txt` para erros). Falhas de gravação são ignoradas.
+#[derive(Debug, Clone)]
+pub struct ArtefatosPorChave {
+    dir: PathBuf,
+}
+
+impl ArtefatosPorChave {
+    /// Grava sob o diretório informado, criado conforme necessário.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Diretório raiz.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn arquivo(&self, artefato: &Artefato) -> PathBuf {
+        let chave: String = artefato.chave.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        let extensao = if artefato.conteudo.trim_start().starts_with('<') { "xml" } else { "txt" };
+        self.dir.join(if chave.is_empty() { artefato.servico.to_string() } else { chave }).join(format!(
+            "{}-{}-{}.{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S%.6f"),
+            artefato.servico,
+            artefato.nome,
+            extensao
+        ))
+    }
+}
+
+impl ArtefatoSink for ArtefatosPorChave {
+    fn gravar(&self, artefato: &Artefato) {
+        let arquivo = self.arquivo(artefato);
+        if let Some(dir) = arquivo.parent() {
+            if std::fs::create_dir_all(dir).is_err() {
+                return;
+            }
+        }
+        let _ = std::fs::write(arquivo, artefato.conteudo);
+    }
+}
+
+// Sink de uma operação: serviço e chave ficam fixos, cada etapa informa nome e conteúdo
+#[derive(Clone)]
+pub(crate) struct Artefatos {
+    sink: Option<Arc<dyn ArtefatoSink>>,
+    servico: &'static str,
+    chave: String,
+}
+
+impl Artefatos {
+    pub(crate) fn new(sink: Option<Arc<dyn ArtefatoSink>>, servico: &'static str, chave: &str) -> Self {
+        Artefatos { sink, servico, chave: chave.to_string() }
+    }
+
+    // Mesmo sink para outro serviço ou chave
+    pub(crate) fn para(&self, servico: &'static str, chave: &str) -> Self {
+        Artefatos::new(self.sink.clone(), servico, chave)
+    }
+
+    pub(crate) fn gravar(&self, nome: &str, conteudo: &str) {
+        if let Some(sink) = &self.sink {
+            sink.gravar(&Artefato { servico: self.servico, chave: &self.chave, nome, conteudo });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_closure_recebe_os_artefatos() {
+        let recebidos = Arc::new(Mutex::new(Vec::new()));
+        let destino = recebidos.clone();
+        let sink: Arc<dyn ArtefatoSink> = Arc::new(move |a: &Artefato| {
+            destino.lock().unwrap().push(format!("{}/{}/{}", a.servico, a.chave, a.nome));
+        });
+
+        let artefatos = Artefatos::new(Some(sink), "cancelamento", "3525");
+        artefatos.gravar("envelope", "<a/>");
+        artefatos.para("status-servico", "SP").gravar("retorno", "<b/>");
+        Artefatos::new(None, "cancelamento", "3525").gravar("envelope", "<a/>");
+
+        assert_eq!(*recebidos.lock().unwrap(), vec!["cancelamento/3525/envelope", "status-servico/SP/retorno"]);
+    }
+
+    #[test]
+    fn test_diretorio_por_chave() {
+        let dir = std::env::temp_dir().join(format!("dfe-artefatos-{}", std::process::id()));
+        let sink = ArtefatosPorChave::new(&dir);
+        sink.gravar(&Artefato { servico: "autorizacao", chave: "3525", nome: "envelope", conteudo: "<a/>" });
+        sink.gravar(&Artefato { servico: "autorizacao", chave: "3525", nome: "erro", conteudo: "timeout" });
+        sink.gravar(&Artefato { servico: "status-servico", chave: "", nome: "retorno", conteudo: "<b/>" });
+
+        let mut nomes: Vec<String> = std::fs::read_dir(dir.join("3525")).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        nomes.sort();
+        assert_eq!(nomes.len(), 2);
+        assert!(nomes.iter().any(|n| n.ends_with("-autorizacao-envelope.xml")));
+        assert!(nomes.iter().any(|n| n.ends_with("-autorizacao-erro.txt")));
+        assert_eq!(std::fs::read_dir(dir.join("status-servico")).unwrap().count(), 1);
+
+        SemArtefatos.gravar(&Artefato { servico: "autorizacao", chave: "3525", nome: "nfe", conteudo: "<c/>" });
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
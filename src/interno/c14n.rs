@@ -0,0 +1,351 @@
+//! Canonicalização XML inclusiva (`http://www.w3.org/TR/2001/REC-xml-c14n-20010315`).
+//!
+//! É a transformação declarada em `<CanonicalizationMethod>` e `<Transform>` das
+//! assinaturas dos DF-e. O digest e a assinatura são calculados sobre a forma
+//! canônica do elemento referenciado (`infNFe`, `infEvento`, `infInut`) e do
+//! `SignedInfo`, e não sobre o texto gerado — assim qualquer verificador obtém
+//! os mesmos bytes, independentemente de ordem de atributos, declarações de
+//! namespace redundantes, entidades ou elementos vazios (`<a/>`).
+//!
+//! Sem comentários. O elemento canonicalizado herda as declarações de namespace
+//! e os atributos `xml:*` dos ancestrais, como define a seção 2.4 da recomendação.
+
+use crate::error::{DfeError, Result};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+/// Forma canônica do primeiro elemento com nome local `tag` (ex.: `"infNFe"`).
+pub fn canonicalize(xml: &str, tag: &str) -> Result<String> {
+    c14n_subtree(xml, |_, local, _| local == tag, false)
+}
+
+/// Forma canônica do elemento com atributo `Id` igual a `id` (`Reference URI="#id"`).
+///
+/// Com `enveloped = true` aplica também a transformação *enveloped-signature*,
+/// removendo os `<Signature>` contidos no elemento.
+pub fn canonicalize_by_id(xml: &str, id: &str, enveloped: bool) -> Result<String> {
+    c14n_subtree(xml, |_, _, e| attr_value(e, "Id").as_deref() == Some(id), enveloped)
+}
+
+/// Forma canônica do elemento cuja tag de abertura começa no byte `offset` de `xml`.
+///
+/// Usado quando o documento tem vários elementos homônimos (ex.: os `SignedInfo`
+/// de um `procEventoNFe`).
+pub fn canonicalize_at(xml: &str, offset: usize) -> Result<String> {
+    c14n_subtree(xml, |pos, _, _| pos == offset, false)
+}
+
+/// Estado de um elemento aberto: namespaces em escopo e atributos `xml:*` herdáveis.
+struct Frame {
+    ns: BTreeMap<String, String>,
+    xml_attrs: BTreeMap<String, String>,
+}
+
+fn c14n_subtree<F>(xml: &str, is_apex: F, enveloped: bool) -> Result<String>
+where
+    F: Fn(usize, &str, &BytesStart) -> bool,
+{
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(false);
+
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut out = String::new();
+    // Profundidade dentro do elemento canonicalizado (0 = fora dele).
+    let mut depth = 0usize;
+    // Profundidade de um <Signature> sendo descartado (enveloped-signature).
+    let mut skip_depth = 0usize;
+
+    loop {
+        let pos = reader.buffer_position() as usize;
+        let event = reader
+            .read_event()
+            .map_err(|e| DfeError::Xml(format!("C14N: XML inválido: {}", e)))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                let qname = name_of(e);
+                let parent = stack.last();
+                let frame = open_frame(e, parent)?;
+
+                if skip_depth > 0 {
+                    if !empty {
+                        skip_depth += 1;
+                        stack.push(frame);
+                    }
+                    continue;
+                }
+
+                let entering = depth == 0 && is_apex(pos, local_of(&qname), e);
+                if depth > 0 || entering {
+                    if depth > 0
+                        && enveloped
+                        && local_of(&qname) == "Signature"
+                        && resolve_prefix(&frame.ns, prefix_of(&qname)) == DSIG_NS
+                    {
+                        if !empty {
+                            skip_depth = 1;
+                            stack.push(frame);
+                        }
+                        continue;
+                    }
+                    let parent_ns = if entering { None } else { parent.map(|p| &p.ns) };
+                    write_start_tag(&mut out, e, &qname, &frame, parent_ns, entering)?;
+                    if empty {
+                        out.push_str(&format!("</{}>", qname));
+                        if entering {
+                            return Ok(out);
+                        }
+                    } else {
+                        depth += 1;
+                    }
+                }
+                if !empty {
+                    stack.push(frame);
+                }
+            }
+            Event::End(ref e) => {
+                stack.pop();
+                if skip_depth > 0 {
+                    skip_depth -= 1;
+                    continue;
+                }
+                if depth > 0 {
+                    let qname = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    out.push_str(&format!("</{}>", qname));
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(out);
+                    }
+                }
+            }
+            Event::Text(ref t) if depth > 0 && skip_depth == 0 => {
+                let raw = normalize_line_endings(&String::from_utf8_lossy(t.as_ref()));
+                let text = unescape(&raw)
+                    .map_err(|e| DfeError::Xml(format!("C14N: entidade inválida: {}", e)))?;
+                out.push_str(&escape_text(&text));
+            }
+            Event::CData(ref c) if depth > 0 && skip_depth == 0 => {
+                let raw = String::from_utf8_lossy(c.as_ref());
+                out.push_str(&escape_text(&normalize_line_endings(&raw)));
+            }
+            Event::PI(ref p) if depth > 0 && skip_depth == 0 => {
+                out.push_str(&format!("<?{}?>", String::from_utf8_lossy(p.as_ref())));
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Err(DfeError::Xml("C14N: elemento a canonicalizar não encontrado".to_string()))
+}
+
+/// Namespaces e atributos `xml:*` em escopo após abrir `e`.
+fn open_frame(e: &BytesStart, parent: Option<&Frame>) -> Result<Frame> {
+    let mut ns = parent.map(|p| p.ns.clone()).unwrap_or_default();
+    let mut xml_attrs = parent.map(|p| p.xml_attrs.clone()).unwrap_or_default();
+    for (key, value) in attributes(e)? {
+        if key == "xmlns" {
+            ns.insert(String::new(), value);
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            ns.insert(prefix.to_string(), value);
+        } else if key.starts_with("xml:") {
+            xml_attrs.insert(key, value);
+        }
+    }
+    Ok(Frame { ns, xml_attrs })
+}
+
+fn write_start_tag(
+    out: &mut String,
+    e: &BytesStart,
+    qname: &str,
+    frame: &Frame,
+    parent_ns: Option<&BTreeMap<String, String>>,
+    apex: bool,
+) -> Result<()> {
+    out.push('<');
+    out.push_str(qname);
+
+    // Declarações de namespace: só as que mudam em relação ao pai no conjunto de saída.
+    for (prefix, uri) in &frame.ns {
+        let render = match parent_ns {
+            None => !(prefix.is_empty() && uri.is_empty()),
+            Some(parent) => match parent.get(prefix) {
+                Some(p) => p != uri,
+                None => !(prefix.is_empty() && uri.is_empty()),
+            },
+        };
+        if render {
+            if prefix.is_empty() {
+                out.push_str(&format!(" xmlns=\"{}\"", escape_attr(uri)));
+            } else {
+                out.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_attr(uri)));
+            }
+        }
+    }
+
+    let mut attrs: Vec<(String, String, String, String)> = Vec::new();
+    for (key, value) in attributes(e)? {
+        if key == "xmlns" || key.starts_with("xmlns:") {
+            continue;
+        }
+        let uri = match prefix_of(&key) {
+            "" => String::new(),
+            prefix => resolve_prefix(&frame.ns, prefix).to_string(),
+        };
+        attrs.push((uri, local_of(&key).to_string(), key, value));
+    }
+    // O elemento raiz do subconjunto herda os atributos xml:* dos ancestrais.
+    if apex {
+        for (key, value) in &frame.xml_attrs {
+            if !attrs.iter().any(|a| &a.2 == key) {
+                attrs.push((XML_NS.to_string(), local_of(key).to_string(), key.clone(), value.clone()));
+            }
+        }
+    }
+    attrs.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    for (_, _, key, value) in attrs {
+        out.push_str(&format!(" {}=\"{}\"", key, escape_attr(&value)));
+    }
+
+    out.push('>');
+    Ok(())
+}
+
+/// Atributos com o valor normalizado (XML 1.0 §3.3.3) e entidades expandidas.
+fn attributes(e: &BytesStart) -> Result<Vec<(String, String)>> {
+    let mut list = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| DfeError::Xml(format!("C14N: atributo inválido: {}", e)))?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let raw = String::from_utf8_lossy(&attr.value);
+        let normalized = normalize_line_endings(&raw).replace(['\t', '\n'], " ");
+        let value = unescape(&normalized)
+            .map_err(|e| DfeError::Xml(format!("C14N: entidade inválida: {}", e)))?
+            .to_string();
+        list.push((key, value));
+    }
+    Ok(list)
+}
+
+fn attr_value(e: &BytesStart, name: &str) -> Option<String> {
+    attributes(e).ok()?.into_iter().find(|(k, _)| k == name).map(|(_, v)| v)
+}
+
+fn name_of(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.name().as_ref()).to_string()
+}
+
+fn local_of(qname: &str) -> &str {
+    qname.rsplit_once(':').map_or(qname, |(_, local)| local)
+}
+
+fn prefix_of(qname: &str) -> &str {
+    qname.split_once(':').map_or("", |(prefix, _)| prefix)
+}
+
+fn resolve_prefix<'a>(ns: &'a BTreeMap<String, String>, prefix: &str) -> &'a str {
+    if prefix == "xml" {
+        return XML_NS;
+    }
+    ns.get(prefix).map(String::as_str).unwrap_or("")
+}
+
+fn normalize_line_endings(s: &str) -> String {
+    s.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_attr(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push_str("&#x9;"),
+            '\n' => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c14n_ordena_atributos_e_expande_vazios() {
+        let xml = r#"<infNFe versao="4.00" Id="NFe1" xmlns="http://www.portalfiscal.inf.br/nfe"><ide><cUF>35</cUF><vazio/></ide></infNFe>"#;
+        assert_eq!(
+            canonicalize(xml, "infNFe").unwrap(),
+            r#"<infNFe xmlns="http://www.portalfiscal.inf.br/nfe" Id="NFe1" versao="4.00"><ide><cUF>35</cUF><vazio></vazio></ide></infNFe>"#
+        );
+    }
+
+    #[test]
+    fn test_c14n_herda_namespace_e_remove_redundante() {
+        // Dentro do nfeProc, infNFe herda o xmlns do NFe; a redeclaração no filho é descartada.
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><NFe><infNFe Id="NFe1" versao="4.00"><ide xmlns="http://www.portalfiscal.inf.br/nfe"><cUF>35</cUF></ide></infNFe></NFe></nfeProc>"#;
+        assert_eq!(
+            canonicalize(xml, "infNFe").unwrap(),
+            r#"<infNFe xmlns="http://www.portalfiscal.inf.br/nfe" Id="NFe1" versao="4.00"><ide><cUF>35</cUF></ide></infNFe>"#
+        );
+    }
+
+    #[test]
+    fn test_c14n_escapes_e_entidades() {
+        let xml = "<a b='x&apos;y&#9;z'>\r\n&#65;&amp;&gt;&quot;<![CDATA[<c>&]]><!-- fora --></a>";
+        assert_eq!(
+            canonicalize(xml, "a").unwrap(),
+            "<a b=\"x'y&#x9;z\">\nA&amp;&gt;\"&lt;c&gt;&amp;</a>"
+        );
+    }
+
+    #[test]
+    fn test_c14n_namespace_prefixado_e_atributo_qualificado() {
+        let xml = r#"<r xmlns:b="urn:b" xmlns:a="urn:a"><e b:x="1" a:y="2" z="3" xmlns:c="urn:c"/></r>"#;
+        assert_eq!(
+            canonicalize(xml, "e").unwrap(),
+            r#"<e xmlns:a="urn:a" xmlns:b="urn:b" xmlns:c="urn:c" z="3" a:y="2" b:x="1"></e>"#
+        );
+    }
+
+    #[test]
+    fn test_c14n_por_id_com_enveloped_signature() {
+        let xml = r#"<doc Id="X"><v>1</v><Signature xmlns="http://www.w3.org/2000/09/xmldsig#"><SignedInfo/></Signature></doc>"#;
+        assert_eq!(canonicalize_by_id(xml, "X", true).unwrap(), r#"<doc Id="X"><v>1</v></doc>"#);
+    }
+
+    #[test]
+    fn test_c14n_por_posicao() {
+        let xml = r#"<r xmlns="urn:r"><a>1</a><a>2</a></r>"#;
+        let offset = xml.rfind("<a>").unwrap();
+        assert_eq!(canonicalize_at(xml, offset).unwrap(), r#"<a xmlns="urn:r">2</a>"#);
+    }
+
+    #[test]
+    fn test_c14n_elemento_inexistente() {
+        assert!(canonicalize("<a></a>", "infNFe").is_err());
+    }
+}
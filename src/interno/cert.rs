@@ -3,73 +3,151 @@ use crate::error::{DfeError, Result};
 use sha1::{Digest, Sha1};
 use std::fs::File;
 use std::io::Read;
+use std::sync::Arc;
 
-pub struct Cert {
-    pub identity: reqwest::Identity,
+#[cfg(not(all(target_os = "windows", feature = "capi")))]
+use super::pkcs12::Pfx;
+
+/// Certificado digital A1 (PKCS#12 / `.pfx`) carregado **uma única vez**.
+///
+/// O PFX é decifrado na criação do handle; assinatura, extração do certificado e
+/// identidade TLS reutilizam o material já carregado. É barato de clonar (`Arc`) e
+/// pode ser compartilhado entre threads e builders ([`NFeBuilder`](crate::NFeBuilder),
+/// [`CancelarBuilder`](crate::CancelarBuilder), [`NFeService`](crate::status::NFeService)
+/// e os builders de [`distribuicao`](crate::distribuicao)).
+///
+/// ```no_run
+/// use dfe::{Certificado, NFeBuilder};
+///
+/// # fn example() -> Result<(), dfe::DfeError> {
+/// let cert = Certificado::from_pfx("./cert.pfx", "senha")?;
+/// let builder = NFeBuilder::new().certificado(cert.clone());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Certificado {
+    inner: Arc<CertificadoInterno>,
+}
+
+struct CertificadoInterno {
+    cert_der: Vec<u8>,
+    identity: reqwest::Identity,
+    #[cfg(not(all(target_os = "windows", feature = "capi")))]
+    pfx: Pfx,
+    // O CAPI importa o PFX a cada assinatura; guardamos os bytes já lidos do disco.
+    #[cfg(all(target_os = "windows", feature = "capi"))]
+    pfx_bytes: Vec<u8>,
+    #[cfg(all(target_os = "windows", feature = "capi"))]
+    password: String,
 }
 
-/// Certificado digital em formato PKCS12 (PFX)
-/// Uso: Cert::from_pfx("caminho/para/o/certificado.pfx", "senha")
-impl Cert {
-    pub fn from_pfx(path: &str, password: &str) -> Result<Cert> {
+impl Certificado {
+    /// Lê e decifra o `.pfx` em disco.
+    pub fn from_pfx(path: &str, password: &str) -> Result<Certificado> {
         let mut buf = Vec::new();
         let mut pfx = File::open(path)?;
         pfx.read_to_end(&mut buf)?;
+        Self::from_bytes(&buf, password)
+    }
 
-        // reqwest usa native-tls (Windows CAPI) que suporta RC2/3DES dos certificados ICP-Brasil
-        let pkcs2 = reqwest::Identity::from_pkcs12_der(&buf, password).map_err(|e| {
+    /// Decifra o PFX a partir dos bytes (ex.: lido de um banco de dados).
+    pub fn from_bytes(pfx_bytes: &[u8], password: &str) -> Result<Certificado> {
+        let erro = |e: String| {
             DfeError::Certificado(format!(
                 "Erro ao ler certificado: Senha inválida ou PFX corrompido: {}", e
             ))
-        })?;
+        };
+
+        #[cfg(not(all(target_os = "windows", feature = "capi")))]
+        let inner = {
+            let pfx = Pfx::from_der(pfx_bytes, password).map_err(erro)?;
+            CertificadoInterno {
+                cert_der: pfx.cert_der().to_vec(),
+                identity: pfx.identity().map_err(erro)?,
+                pfx,
+            }
+        };
 
-        Ok(Cert { identity: pkcs2 })
+        #[cfg(all(target_os = "windows", feature = "capi"))]
+        let inner = CertificadoInterno {
+            cert_der: pfx_capi_extract_cert_der(pfx_bytes, password).map_err(erro)?,
+            // native-tls (SChannel) suporta RC2/3DES dos certificados ICP-Brasil
+            identity: reqwest::Identity::from_pkcs12_der(pfx_bytes, password)
+                .map_err(|e| erro(e.to_string()))?,
+            pfx_bytes: pfx_bytes.to_vec(),
+            password: password.to_string(),
+        };
+
+        Ok(Certificado { inner: Arc::new(inner) })
     }
-}
 
+    /// Decifra o PFX codificado em base64.
+    pub fn from_base64(pfx_base64: &str, password: &str) -> Result<Certificado> {
+        let bytes = STANDARD.decode(pfx_base64.trim())?;
+        Self::from_bytes(&bytes, password)
+    }
 
-pub struct DigestValue;
+    /// DER do certificado folha (o da empresa).
+    pub fn cert_der(&self) -> &[u8] {
+        &self.inner.cert_der
+    }
 
-impl DigestValue {
-    pub fn sha1(xml: &str) -> Result<String> {
-        let mut hasher = Sha1::new();
-        hasher.update(xml.as_bytes());
-        let result = hasher.finalize();
-        Ok(STANDARD.encode(&result))
+    /// Metadados do certificado folha (titular, emissor, validade e identificação ICP-Brasil).
+    pub fn info(&self) -> Result<CertInfo> {
+        super::x509::info(&self.inner.cert_der).map_err(DfeError::Certificado)
     }
-}
 
-pub struct RawPubKey;
+    /// Assina `data` com RSA PKCS#1 v1.5 + SHA-1.
+    pub(crate) fn sign_rsa_sha1(&self, data: &[u8]) -> Result<Vec<u8>> {
+        #[cfg(not(all(target_os = "windows", feature = "capi")))]
+        let signature: std::result::Result<Vec<u8>, String> = Ok(self.inner.pfx.sign_rsa_sha1(data));
+        #[cfg(all(target_os = "windows", feature = "capi"))]
+        let signature = pfx_capi_sign_rsa_sha1(data, &self.inner.pfx_bytes, &self.inner.password);
 
-impl RawPubKey {
-    pub async fn get_from_file(pfx_path: &str, password: &str) -> Result<String> {
-        let mut file = File::open(pfx_path)?;
-        let mut der = vec![];
-        file.read_to_end(&mut der)?;
+        signature.map_err(|e| DfeError::Assinatura(format!("Erro ao assinar XML: {}", e)))
+    }
 
-        // Windows CAPI: abre o PFX suportando RC2/3DES dos certificados ICP-Brasil
-        // PEM = base64(DER), então encodificamos diretamente sem passar pelo OpenSSL
-        let cert_der = pfx_capi_extract_cert_der(&der, password)
-            .map_err(|e| DfeError::Certificado(format!("Erro ao extrair certificado: {}", e)))?;
+    /// Identidade TLS para a conexão mTLS com a SEFAZ.
+    pub(crate) fn identity(&self) -> reqwest::Identity {
+        self.inner.identity.clone()
+    }
 
-        Ok(STANDARD.encode(&cert_der))
+    /// Usa o handle informado ou, na falta dele, carrega o PFX de `cert(path, pass)`.
+    pub(crate) fn resolver(
+        certificado: Option<Certificado>,
+        cert_path: Option<String>,
+        cert_pass: Option<String>,
+    ) -> Result<Certificado> {
+        if let Some(certificado) = certificado {
+            return Ok(certificado);
+        }
+        let cert_path = cert_path.ok_or_else(|| DfeError::Configuracao("cert_path não informado".to_string()))?;
+        let cert_pass = cert_pass.ok_or_else(|| DfeError::Configuracao("cert_pass não informado".to_string()))?;
+        Self::from_pfx(&cert_path, &cert_pass)
     }
 }
 
-pub struct Sign;
+impl std::fmt::Debug for Certificado {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Nunca expõe chave privada nem senha.
+        f.debug_struct("Certificado").finish_non_exhaustive()
+    }
+}
 
-impl Sign {
-    pub async fn xml_string(data: &str, pfx_path: &str, password: &str) -> Result<String> {
-        let mut buf = Vec::new();
-        let mut pfx_file = File::open(pfx_path)?;
-        pfx_file.read_to_end(&mut buf)?;
+pub struct DigestValue;
 
-        // Windows CAPI: assina RSA-SHA1 diretamente, sem precisar extrair a chave privada
-        // O CAPI suporta RC2/3DES dos certificados ICP-Brasil nativamente
-        let signature = pfx_capi_sign_rsa_sha1(data.as_bytes(), &buf, password)
-            .map_err(|e| DfeError::Assinatura(format!("Erro ao assinar XML: {}", e)))?;
+impl DigestValue {
+    pub fn sha1(xml: &str) -> Result<String> {
+        let mut hasher = Sha1::new();
+        hasher.update(xml.as_bytes());
+        let result = hasher.finalize();
+        Ok(STANDARD.encode(&result))
+    }
 
-        Ok(STANDARD.encode(&signature))
+    /// SHA-1 da forma canônica (C14N) do elemento `tag` — o `<DigestValue>` da `Reference`.
+    pub fn c14n_sha1(xml: &str, tag: &str) -> Result<String> {
+        Self::sha1(&super::c14n::canonicalize(xml, tag)?)
     }
 }
 
@@ -83,21 +161,50 @@ pub struct CertInfo {
     pub issuer: String,
     pub valid_from: String,
     pub valid_to: String,
+    /// CNPJ do titular (e-CNPJ, OID `2.16.76.1.3.3`).
+    pub cnpj: Option<String>,
+    /// CPF do titular (e-CPF, OID `2.16.76.1.3.1`).
+    pub cpf: Option<String>,
+    /// Nome do responsável pelo e-CNPJ (OID `2.16.76.1.3.2`).
+    pub responsible: Option<String>,
+    /// Número de série em hexadecimal maiúsculo.
+    pub serial_number: String,
+    /// Tamanho da chave pública em bits (ex.: 2048).
+    pub key_bits: usize,
+    /// Dias inteiros até o vencimento; negativo se já venceu.
+    pub days_to_expiry: i64,
 }
 
 impl CertInfo {
     /// Lê os metadados do certificado folha de um `.pfx` em disco.
-    ///
-    /// Importa o PFX via Windows CAPI **persistindo** a chave (apagada logo após a
-    /// leitura). Isso é proposital: o import efêmero (`PKCS12_NO_PERSIST_KEY`) falha
-    /// para PFX ICP-Brasil (RC2/3DES) em máquinas onde o certificado não foi instalado
-    /// manualmente — justamente o cenário de um PDV recém-instalado. O caminho
-    /// persistido funciona sem o cert instalado e não deixa keyset órfão em disco.
     pub fn from_pfx(path: &str, password: &str) -> Result<CertInfo> {
-        let mut buf = Vec::new();
-        let mut pfx = File::open(path)?;
-        pfx.read_to_end(&mut buf)?;
-        pfx_capi_read_info(&buf, password).map_err(DfeError::Certificado)
+        Certificado::from_pfx(path, password)?.info()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.days_to_expiry < 0
+    }
+
+    /// Recusa certificado vencido ou de outra empresa antes de transmitir à SEFAZ.
+    ///
+    /// A raiz (8 primeiros dígitos) do CNPJ do certificado deve ser a do emitente —
+    /// a SEFAZ rejeitaria com cStat 213. Sem CNPJ no certificado ou no emitente,
+    /// só a validade é conferida.
+    pub(crate) fn validar_emitente(&self, cnpj_emitente: Option<&str>) -> Result<()> {
+        if self.is_expired() {
+            return Err(DfeError::Certificado(format!(
+                "certificado vencido em {} ({})", self.valid_to, self.subject
+            )));
+        }
+        let emitente = cnpj_emitente.map(super::cnpj_cpf::sanitize_cnpj);
+        if let (Some(cert), Some(emit)) = (self.cnpj.as_deref(), emitente.as_deref()) {
+            if emit.len() == 14 && cert.get(..8) != emit.get(..8) {
+                return Err(DfeError::Certificado(format!(
+                    "raiz do CNPJ do certificado ({}) difere da do emitente ({})", cert, emit
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -109,7 +216,7 @@ impl CertInfo {
 /// `PFXImportCertStore` grava nele: `CERT_KEY_PROV_INFO_PROP_ID` (chave persistida) ou
 /// `CERT_KEY_CONTEXT_PROP_ID` (chave efêmera/NO_PERSIST). Não depende de a chave estar
 /// de fato acessível, então funciona mesmo sem o certificado instalado no Windows.
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "capi"))]
 unsafe fn cert_has_private_key(
     cert_ctx: *const windows_sys::Win32::Security::Cryptography::CERT_CONTEXT,
 ) -> bool {
@@ -131,7 +238,7 @@ unsafe fn cert_has_private_key(
 /// Percorre o store e devolve o contexto do certificado folha (com chave privada).
 /// Retorna ponteiro nulo se nenhum for encontrado. O contexto retornado deve ser
 /// liberado pelo chamador com `CertFreeCertificateContext`.
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "capi"))]
 unsafe fn find_leaf_cert(
     store: *mut std::ffi::c_void,
 ) -> *const windows_sys::Win32::Security::Cryptography::CERT_CONTEXT {
@@ -154,7 +261,7 @@ unsafe fn find_leaf_cert(
 /// Necessário porque assinamos importando com a chave persistida (única forma de
 /// `CryptAcquireCertificatePrivateKey` achar a chave sem o certificado instalado no
 /// Windows); sem apagar, cada emissão deixaria um keyset órfão em disco.
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "capi"))]
 unsafe fn delete_key_container(
     cert_ctx: *const windows_sys::Win32::Security::Cryptography::CERT_CONTEXT,
 ) {
@@ -211,7 +318,7 @@ unsafe fn delete_key_container(
 /// o MESMO certificado folha usado para assinar (o da empresa), e não a CA da cadeia —
 /// senão a SEFAZ rejeita com cStat 290 "Certificado Assinatura inválido", pois a
 /// assinatura não verifica contra o certificado embutido.
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "capi"))]
 fn pfx_capi_extract_cert_der(pfx_bytes: &[u8], password: &str) -> std::result::Result<Vec<u8>, String> {
     use windows_sys::Win32::Security::Cryptography::{
         CertCloseStore, CertFreeCertificateContext, PFXImportCertStore, CRYPT_INTEGER_BLOB,
@@ -264,7 +371,7 @@ fn pfx_capi_extract_cert_der(pfx_bytes: &[u8], password: &str) -> std::result::R
 /// acquire usa `CRYPT_ACQUIRE_PREFER_NCRYPT_KEY_FLAG` e ramificamos:
 /// - chave CNG  → `NCryptSignHash` (retorna assinatura big-endian PKCS#1, sem reverter)
 /// - chave CAPI → `CryptSignHashW` (retorna little-endian, revertemos para big-endian)
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "capi"))]
 fn pfx_capi_sign_rsa_sha1(data: &[u8], pfx_bytes: &[u8], password: &str) -> std::result::Result<Vec<u8>, String> {
     use windows_sys::Win32::Security::Cryptography::{
         CertCloseStore, CertFreeCertificateContext, CryptAcquireCertificatePrivateKey,
@@ -408,93 +515,43 @@ fn pfx_capi_sign_rsa_sha1(data: &[u8], pfx_bytes: &[u8], password: &str) -> std:
     }
 }
 
-/// Lê subject/issuer/validade do certificado folha de um PFX via Windows CAPI.
-///
-/// Importa **persistindo** a chave (`CRYPT_USER_KEYSET`) e apaga o keyset ao final
-/// (`delete_key_container`). Diferente de `pfx_capi_extract_cert_der`, que usa
-/// `PKCS12_NO_PERSIST_KEY`: o import efêmero retorna NULL para PFX ICP-Brasil com
-/// cifragem legada (RC2/3DES) em máquinas onde o certificado não está instalado —
-/// o que fazia esta leitura falhar com "PFX corrompido" num PDV recém-instalado.
-#[cfg(target_os = "windows")]
-fn pfx_capi_read_info(pfx_bytes: &[u8], password: &str) -> std::result::Result<CertInfo, String> {
-    use windows_sys::Win32::Security::Cryptography::{
-        CertCloseStore, CertFreeCertificateContext, CertGetNameStringW, PFXImportCertStore,
-        CERT_NAME_ISSUER_FLAG, CERT_X500_NAME_STR, CRYPT_INTEGER_BLOB,
-    };
-    use windows_sys::Win32::Foundation::SYSTEMTIME;
-    use windows_sys::Win32::System::Time::FileTimeToSystemTime;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    const CRYPT_USER_KEYSET: u32 = 0x00001000;
-    const PKCS12_ALLOW_OVERWRITE_KEY: u32 = 0x00004000;
+    const PFX_LEGACY: &[u8] = include_bytes!("../../testes/certificado_teste_legacy.pfx");
 
-    let pw_wide: Vec<u16> = password.encode_utf16().chain(std::iter::once(0)).collect();
-    let pfx_blob = CRYPT_INTEGER_BLOB {
-        cbData: pfx_bytes.len() as u32,
-        pbData: pfx_bytes.as_ptr() as *mut u8,
-    };
+    #[test]
+    fn test_certificado_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Certificado>();
+    }
 
-    unsafe {
-        // Persiste a chave (apagada ao final) — ver doc da função para o porquê.
-        let store = PFXImportCertStore(
-            &pfx_blob,
-            pw_wide.as_ptr(),
-            CRYPT_USER_KEYSET | PKCS12_ALLOW_OVERWRITE_KEY,
-        );
-        if store.is_null() {
-            return Err("Senha inválida ou PFX corrompido".to_string());
-        }
+    #[test]
+    fn test_certificado_from_bytes_e_base64() {
+        let por_bytes = Certificado::from_bytes(PFX_LEGACY, "1234").unwrap();
+        let por_base64 = Certificado::from_base64(&STANDARD.encode(PFX_LEGACY), "1234").unwrap();
+        assert_eq!(por_bytes.cert_der(), por_base64.cert_der());
+    }
 
-        let cert_ctx = find_leaf_cert(store);
-        if cert_ctx.is_null() {
-            CertCloseStore(store, 0);
-            return Err("Nenhum certificado com chave privada encontrado no PFX".to_string());
+    #[test]
+    fn test_certificado_compartilhado_entre_threads() {
+        let cert = Certificado::from_bytes(PFX_LEGACY, "1234").unwrap();
+        let esperado = cert.sign_rsa_sha1(b"<SignedInfo></SignedInfo>").unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cert = cert.clone();
+                std::thread::spawn(move || cert.sign_rsa_sha1(b"<SignedInfo></SignedInfo>").unwrap())
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.join().unwrap(), esperado);
         }
-
-        // Subject (titular)
-        let mut subj_buf = vec![0u16; 512];
-        CertGetNameStringW(
-            cert_ctx, CERT_X500_NAME_STR, 0, std::ptr::null_mut(),
-            subj_buf.as_mut_ptr(), subj_buf.len() as u32,
-        );
-        let subject = String::from_utf16_lossy(&subj_buf).trim_matches('\0').to_string();
-
-        // Issuer (autoridade certificadora)
-        let mut iss_buf = vec![0u16; 512];
-        CertGetNameStringW(
-            cert_ctx, CERT_X500_NAME_STR, CERT_NAME_ISSUER_FLAG, std::ptr::null_mut(),
-            iss_buf.as_mut_ptr(), iss_buf.len() as u32,
-        );
-        let issuer = String::from_utf16_lossy(&iss_buf).trim_matches('\0').to_string();
-
-        // Validade: CERT_INFO → FILETIME → SYSTEMTIME
-        let cert_info = &*(*cert_ctx).pCertInfo;
-        let mut st_from: SYSTEMTIME = std::mem::zeroed();
-        FileTimeToSystemTime(&cert_info.NotBefore, &mut st_from);
-        let mut st_to: SYSTEMTIME = std::mem::zeroed();
-        FileTimeToSystemTime(&cert_info.NotAfter, &mut st_to);
-        let valid_from = format!("{:04}/{:02}/{:02}", st_from.wYear, st_from.wMonth, st_from.wDay);
-        let valid_to = format!("{:04}/{:02}/{:02}", st_to.wYear, st_to.wMonth, st_to.wDay);
-
-        // Apaga o keyset persistido na importação e libera os handles.
-        delete_key_container(cert_ctx);
-        CertFreeCertificateContext(cert_ctx);
-        CertCloseStore(store, 0);
-
-        Ok(CertInfo { subject, issuer, valid_from, valid_to })
     }
-}
 
-#[cfg(not(target_os = "windows"))]
-fn pfx_capi_read_info(_pfx_bytes: &[u8], _password: &str) -> std::result::Result<CertInfo, String> {
-    Err("Leitura de certificado via CAPI disponível apenas no Windows".to_string())
-}
-
-#[cfg(not(target_os = "windows"))]
-fn pfx_capi_extract_cert_der(_pfx_bytes: &[u8], _password: &str) -> std::result::Result<Vec<u8>, String> {
-    Err("Extração de certificado via CAPI disponível apenas no Windows".to_string())
-}
-
-#[cfg(not(target_os = "windows"))]
-fn pfx_capi_sign_rsa_sha1(_data: &[u8], _pfx_bytes: &[u8], _password: &str) -> std::result::Result<Vec<u8>, String> {
-    Err("Assinatura via CAPI disponível apenas no Windows".to_string())
+    #[test]
+    fn test_resolver_sem_certificado() {
+        let err = Certificado::resolver(None, None, None).unwrap_err();
+        assert!(matches!(err, DfeError::Configuracao(_)));
+    }
 }
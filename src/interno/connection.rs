@@ -1,3 +1,4 @@
+use super::artefatos::Artefatos;
 use crate::error::{DfeError, Result};
 use reqwest::Client;
 use reqwest::Identity;
@@ -20,4 +21,25 @@ impl WebService {
             .await
             .map_err(|e| DfeError::Webservice(format!("Failed to send request: {}", e)))
     }
+
+    /// Envia o envelope e devolve o corpo da resposta; envelope, retorno e falhas
+    /// vão para os artefatos da operação. Status HTTP fora de 2xx vira erro.
+    pub async fn transmitir(client: Client, url: &str, envelope: String, artefatos: &Artefatos) -> Result<String> {
+        artefatos.gravar("envelope", &envelope);
+        let resultado = async {
+            let response = Self::send(client, url, envelope).await?;
+            let status = response.status();
+            let body = response.text().await?;
+            artefatos.gravar("retorno", &body);
+            if !status.is_success() {
+                return Err(DfeError::Webservice(format!("Erro na Requisição: {:?} -> Body: {:?}", status, body)));
+            }
+            Ok(body)
+        }
+        .await;
+        if let Err(e) = &resultado {
+            artefatos.gravar("erro", &e.to_string());
+        }
+        resultado
+    }
 }
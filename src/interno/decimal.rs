@@ -0,0 +1,37 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Arredonda como o leiaute da NF-e: meio para longe do zero (0,005 → 0,01).
+pub(crate) fn arredondar(valor: Decimal, casas: u32) -> Decimal {
+    valor.round_dp_with_strategy(casas, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Valor com exatamente `casas` decimais (`TDec_1302` → 2, `TDec_0302a04` → 4…).
+pub(crate) fn fmt_casas(valor: Decimal, casas: u32) -> String {
+    format!("{:.*}", casas as usize, arredondar(valor, casas))
+}
+
+/// Valor com `min` a `max` decimais, sem zeros à direita além de `min`
+/// (`vUnCom`/`qCom`, que aceitam até 10 e 4 casas).
+pub(crate) fn fmt_casas_var(valor: Decimal, min: u32, max: u32) -> String {
+    let valor = arredondar(valor, max).normalize();
+    if valor.scale() < min { fmt_casas(valor, min) } else { valor.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_arredondamento_e_casas() {
+        assert_eq!(fmt_casas(dec!(0.125), 2), "0.13");
+        assert_eq!(fmt_casas(dec!(-0.125), 2), "-0.13");
+        assert_eq!(fmt_casas(dec!(0.135), 2), "0.14");
+        assert_eq!(fmt_casas(dec!(10), 2), "10.00");
+        assert_eq!(fmt_casas(dec!(18), 4), "18.0000");
+        assert_eq!(fmt_casas_var(dec!(1.2345678), 2, 10), "1.2345678");
+        assert_eq!(fmt_casas_var(dec!(3.5), 2, 10), "3.50");
+        assert_eq!(fmt_casas_var(dec!(2), 3, 4), "2.000");
+        assert_eq!(fmt_casas_var(dec!(0.33335), 3, 4), "0.3334");
+    }
+}
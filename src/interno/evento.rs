@@ -0,0 +1,151 @@
+// Peças comuns aos eventos de NF-e enviados ao NFeRecepcaoEvento4 (CC-e, EPEC…):
+// assinatura do <infEvento>, envelope <envEvento> e montagem do <procEventoNFe>.
+
+use crate::error::{DfeError, Result};
+use crate::interno::c14n;
+use crate::interno::cert::DigestValue;
+use crate::interno::signer::Credencial;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Campos do `<infEvento>` de um pedido de evento.
+pub struct Evento<'a> {
+    pub c_orgao: &'a str,
+    pub tp_amb: u8,
+    pub cnpj: &'a str,
+    pub chave: &'a str,
+    pub dh_evento: &'a str,
+    pub tp_evento: &'a str,
+    pub n_seq_evento: u32,
+    /// Conteúdo de `<detEvento>` (sem a tag externa).
+    pub det_evento: &'a str,
+}
+
+impl Evento<'_> {
+    /// `ID` + tpEvento + chave + nSeqEvento (2 dígitos).
+    pub fn id(&self) -> String {
+        format!("ID{}{}{:02}", self.tp_evento, self.chave, self.n_seq_evento)
+    }
+
+    /// `<evento>` com o `<infEvento>` assinado.
+    pub fn assinar(&self, credencial: &Credencial) -> Result<String> {
+        let id = self.id();
+        let inf_evento = format!(
+            r#"<infEvento xmlns="http://www.portalfiscal.inf.br/nfe" Id="{}"><cOrgao>{}</cOrgao><tpAmb>{}</tpAmb><CNPJ>{}</CNPJ><chNFe>{}</chNFe><dhEvento>{}</dhEvento><tpEvento>{}</tpEvento><nSeqEvento>{}</nSeqEvento><verEvento>1.00</verEvento><detEvento versao="1.00">{}</detEvento></infEvento>"#,
+            id, self.c_orgao, self.tp_amb, self.cnpj, self.chave, self.dh_evento,
+            self.tp_evento, self.n_seq_evento, self.det_evento
+        );
+
+        let digest_value = DigestValue::c14n_sha1(&inf_evento, "infEvento")?;
+        let signed_info  = c14n::canonicalize(&signed_info_xml(&digest_value, &id), "SignedInfo")?;
+        let signature    = format!(
+            r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">{}<SignatureValue>{}</SignatureValue><KeyInfo><X509Data><X509Certificate>{}</X509Certificate></X509Data></KeyInfo></Signature>"#,
+            signed_info, credencial.sign_base64(&signed_info)?, credencial.cert_base64()?
+        );
+        Ok(format!(
+            r#"<evento xmlns="http://www.portalfiscal.inf.br/nfe" versao="1.00">{}{}</evento>"#,
+            inf_evento, signature
+        ))
+    }
+}
+
+fn signed_info_xml(digest: &str, id: &str) -> String {
+    format!(
+        concat!(
+            r#"<SignedInfo xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+            r#"<CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></CanonicalizationMethod>"#,
+            r#"<SignatureMethod Algorithm="http://www.w3.org/2000/09/xmldsig#rsa-sha1"></SignatureMethod>"#,
+            r##"<Reference URI="#{}"><Transforms>"##,
+            r#"<Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></Transform>"#,
+            r#"<Transform Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></Transform>"#,
+            r#"</Transforms><DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"></DigestMethod>"#,
+            r#"<DigestValue>{}</DigestValue></Reference></SignedInfo>"#,
+        ),
+        id, digest
+    )
+}
+
+/// Envelope SOAP do `envEvento` com um único evento.
+pub fn envelope_xml(evento: &str) -> String {
+    let date = chrono::Local::now();
+    let id_lote = format!("{}{}", date.format("%Y%m%d%H%M%S"), rand::random::<u8>() % 10);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeRecepcaoEvento4"><envEvento xmlns="http://www.portalfiscal.inf.br/nfe" versao="1.00"><idLote>{}</idLote>{}</envEvento></nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        id_lote, evento
+    )
+}
+
+/// `<retEvento>` do evento enviado.
+pub struct RetEvento {
+    pub c_stat: i32,
+    pub x_motivo: String,
+    pub n_prot: Option<String>,
+    pub dh_reg_evento: Option<String>,
+    /// XML `<retEvento>` como recebido.
+    pub xml: String,
+}
+
+#[derive(Deserialize)]
+struct TagRetEvento {
+    #[serde(rename = "infEvento")]
+    inf_evento: RetInfEvento,
+}
+
+#[derive(Deserialize)]
+struct RetInfEvento {
+    #[serde(rename = "cStat")]
+    c_stat: i32,
+    #[serde(rename = "xMotivo")]
+    x_motivo: String,
+    #[serde(rename = "dhRegEvento")]
+    dh_reg_evento: Option<String>,
+    #[serde(rename = "nProt")]
+    n_prot: Option<String>,
+}
+
+/// Extrai o `<retEvento>` da resposta; sem ele (lote rejeitado, ex.: cStat 215),
+/// devolve o status do `<retEnvEvento>`.
+pub fn ret_evento(receive_xml: &str) -> Result<RetEvento> {
+    let re = Regex::new(r"(?s)<retEvento\b.*?</retEvento>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?;
+    if let Some(m) = re.find(receive_xml) {
+        let xml = m.as_str().to_string();
+        let ret: TagRetEvento = quick_xml::de::from_str(&xml)
+            .map_err(|e| DfeError::Xml(format!("Erro desserializar retEvento: {} — {}", e, xml)))?;
+        let ret = ret.inf_evento;
+        return Ok(RetEvento {
+            c_stat: ret.c_stat, x_motivo: ret.x_motivo, n_prot: ret.n_prot,
+            dh_reg_evento: ret.dh_reg_evento, xml,
+        });
+    }
+
+    let tag = |nome: &str| {
+        Regex::new(&format!(r"(?s)<{0}>(.*?)</{0}>", nome)).ok()
+            .and_then(|re| re.captures(receive_xml))
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().trim().to_string())
+    };
+    let c_stat = tag("cStat").and_then(|s| s.parse().ok())
+        .ok_or_else(|| DfeError::Xml(format!("retEvento não encontrado: {}", receive_xml)))?;
+    Ok(RetEvento {
+        c_stat, x_motivo: tag("xMotivo").unwrap_or_default(), n_prot: None,
+        dh_reg_evento: None, xml: String::new(),
+    })
+}
+
+impl RetEvento {
+    /// `135` = registrado e vinculado · `136` = registrado, sem vínculo (NF-e ainda não autorizada).
+    pub fn registrado(&self) -> bool {
+        matches!(self.c_stat, 135 | 136)
+    }
+
+    /// `procEventoNFe` com o evento assinado e o retorno, quando o evento foi registrado.
+    pub fn proc_evento_nfe(&self, evento: &str) -> Option<String> {
+        self.registrado().then(|| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><procEventoNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="1.00">{}{}</procEventoNFe>"#,
+                evento, self.xml
+            )
+        })
+    }
+}
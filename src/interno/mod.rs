@@ -1,3 +1,5 @@
+pub mod artefatos;
+pub mod c14n;
 pub mod cert;
 pub mod cnpj_cpf;
 pub mod chave_acesso;
@@ -5,7 +7,13 @@ pub mod chave_acesso_props;
 pub mod cleaner;
 pub mod connection;
 pub mod dates;
+pub(crate) mod decimal;
 pub mod dest_xml;
+pub mod evento;
 pub mod extract;
+#[cfg(not(all(target_os = "windows", feature = "capi")))]
+pub mod pkcs12;
+pub mod signer;
 pub mod validation;
 pub mod ws;
+pub mod x509;
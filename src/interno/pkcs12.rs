@@ -0,0 +1,153 @@
+//! Backend PKCS#12 (`.pfx`) em Rust puro.
+//!
+//! Abre certificados A1 ICP-Brasil em qualquer plataforma, sem OpenSSL nem CAPI.
+//! Suporta a cifragem legada usada pelas ACs (pbeWithSHAAnd40BitRC2-CBC e
+//! pbeWithSHAAnd3-KeyTripleDES-CBC) e a moderna (PBES2/PBKDF2 + AES).
+
+use super::x509::trim_zeros;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use p12_keystore::Pkcs12Archive;
+use rsa::pkcs1v15::SigningKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use sha1::Sha1;
+use x509_parser::prelude::*;
+use x509_parser::public_key::PublicKey;
+
+/// Conteúdo decifrado de um PFX: chave privada RSA, certificado folha e cadeia de CAs.
+pub struct Pfx {
+    key: RsaPrivateKey,
+    cert_der: Vec<u8>,
+    chain_der: Vec<Vec<u8>>,
+}
+
+impl Pfx {
+    /// Decifra o PFX e localiza o certificado folha.
+    ///
+    /// O folha é o certificado cuja chave pública corresponde à chave privada do
+    /// arquivo; a ordem dos bags no PFX não é confiável (a CA pode vir primeiro).
+    pub fn from_der(pfx_bytes: &[u8], password: &str) -> std::result::Result<Pfx, String> {
+        let archive = Pkcs12Archive::from_pkcs12(pfx_bytes, password)
+            .map_err(|e| format!("Senha inválida ou PFX corrompido: {}", e))?;
+
+        let mut certs: Vec<Vec<u8>> = archive
+            .certs
+            .into_iter()
+            .map(|bag| bag.cert.as_der().to_vec())
+            .collect();
+
+        for bag in archive.keys {
+            let Ok(key) = RsaPrivateKey::from_pkcs8_der(bag.key.as_der()) else {
+                continue;
+            };
+            if let Some(pos) = certs.iter().position(|der| cert_matches_key(der, &key)) {
+                let cert_der = certs.remove(pos);
+                return Ok(Pfx { key, cert_der, chain_der: certs });
+            }
+        }
+
+        Err("Nenhum certificado com chave privada RSA encontrado no PFX".to_string())
+    }
+
+    /// DER do certificado folha (o da empresa).
+    pub fn cert_der(&self) -> &[u8] {
+        &self.cert_der
+    }
+
+    /// Assina `data` com RSA PKCS#1 v1.5 + SHA-1 (algoritmo exigido pela SEFAZ).
+    pub fn sign_rsa_sha1(&self, data: &[u8]) -> Vec<u8> {
+        let signing_key = SigningKey::<Sha1>::new(self.key.clone());
+        signing_key.sign(data).to_vec()
+    }
+
+    /// Identidade TLS (mTLS com a SEFAZ) montada a partir da chave e da cadeia em PEM.
+    ///
+    /// Evita `Identity::from_pkcs12_der`, que no OpenSSL 3 falha para PFX com RC2
+    /// quando o provider `legacy` não está carregado.
+    pub fn identity(&self) -> std::result::Result<reqwest::Identity, String> {
+        let key_pem = self
+            .key
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| format!("Erro ao exportar chave privada: {}", e))?;
+        let mut certs_pem = der_to_pem(&self.cert_der);
+        for der in &self.chain_der {
+            certs_pem.push_str(&der_to_pem(der));
+        }
+        reqwest::Identity::from_pkcs8_pem(certs_pem.as_bytes(), key_pem.as_bytes())
+            .map_err(|e| format!("Erro ao montar identidade TLS: {}", e))
+    }
+}
+
+/// Compara o módulo RSA da chave pública do certificado com o da chave privada.
+fn cert_matches_key(cert_der: &[u8], key: &RsaPrivateKey) -> bool {
+    let Ok((_, cert)) = X509Certificate::from_der(cert_der) else {
+        return false;
+    };
+    match cert.public_key().parsed() {
+        Ok(PublicKey::RSA(rsa)) => trim_zeros(rsa.modulus) == trim_zeros(&key.n().to_bytes_be()),
+        _ => false,
+    }
+}
+
+fn der_to_pem(der: &[u8]) -> String {
+    let b64 = STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in b64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PFX_LEGACY: &[u8] = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+    const PFX_AES: &[u8] = include_bytes!("../../testes/certificado_teste_aes.pfx");
+
+    #[test]
+    fn test_abre_pfx_legacy_rc2_3des() {
+        let pfx = Pfx::from_der(PFX_LEGACY, "1234").unwrap();
+        let info = super::super::x509::info(pfx.cert_der()).unwrap();
+        assert_eq!(
+            info.subject,
+            "CN=EMPRESA TESTE LTDA:11222333000181, OU=Certificado PJ A1, O=ICP-Brasil, C=BR"
+        );
+        assert_eq!(info.issuer, "CN=AC TESTE DFE, O=ICP-Brasil, C=BR");
+        assert_eq!(pfx.chain_der.len(), 1);
+    }
+
+    #[test]
+    fn test_abre_pfx_pbes2_aes() {
+        let pfx = Pfx::from_der(PFX_AES, "1234").unwrap();
+        let legacy = Pfx::from_der(PFX_LEGACY, "1234").unwrap();
+        assert_eq!(pfx.cert_der(), legacy.cert_der());
+    }
+
+    #[test]
+    fn test_senha_invalida() {
+        assert!(Pfx::from_der(PFX_LEGACY, "errada").is_err());
+    }
+
+    #[test]
+    fn test_assinatura_rsa_sha1() {
+        // Vetor gerado com `openssl dgst -sha1 -sign` sobre a mesma chave.
+        let pfx = Pfx::from_der(PFX_LEGACY, "1234").unwrap();
+        let data = r#"<SignedInfo xmlns="http://www.w3.org/2000/09/xmldsig#"></SignedInfo>"#;
+        let signature = STANDARD.encode(pfx.sign_rsa_sha1(data.as_bytes()));
+        assert_eq!(
+            signature,
+            "AOJZb4lgtRG2DHvQYFm2buvbiQET1K5pq+vxVOPPM1wh8famr7OwiNcRu0XSXcT9g20PJu193vfufO2QiDcuAPRL29dmMl8VgmqqQR0xmsJBGAuohBv53nzwtr1+PnWCPCFFIXPtiF10kFsPNpRdhHbuutOR1/4W4wGvwOO1GefNgPXFbJiTP09SQlZr0Z8smmkbG4+oNRSdDiUeERpcwzzmO/tq+Z0go7C8JkBvehQclEevkNkrMfnmzl27YDV7HXRKPBxFf+hcWoo5s66GTXKvsl5N6T+uNVRWRpX4gSv2Cfwxdf1I/5LiEqN8HFjpW6zEXSdqDzjTOO6YbR+sqw=="
+        );
+    }
+
+    #[test]
+    fn test_identidade_tls() {
+        let pfx = Pfx::from_der(PFX_LEGACY, "1234").unwrap();
+        assert!(pfx.identity().is_ok());
+    }
+}
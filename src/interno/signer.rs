@@ -0,0 +1,193 @@
+use super::cert::Certificado;
+use crate::error::{DfeError, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::sync::Arc;
+
+/// Origem da assinatura XMLDSig (RSA-SHA1) dos documentos fiscais.
+///
+/// Permite assinar com uma chave que não está num `.pfx` local — um serviço de
+/// assinatura remoto, um token PKCS#11, um HSM. [`Certificado`] é a implementação
+/// padrão (A1 em arquivo).
+///
+/// As chamadas são síncronas; uma implementação remota pode bloquear a thread
+/// (ex.: `tokio::task::block_in_place`).
+///
+/// ```no_run
+/// use dfe::{DfeError, Signer};
+///
+/// /// Cliente do HSM/token (PKCS#11, serviço remoto...).
+/// struct Sessao;
+///
+/// impl Sessao {
+///     fn assinar_rsa_sha1(&self, _data: &[u8]) -> Option<Vec<u8>> {
+///         None
+///     }
+/// }
+///
+/// struct Hsm {
+///     sessao: Sessao,
+/// }
+///
+/// impl Signer for Hsm {
+///     fn cert_der(&self) -> Result<Vec<u8>, DfeError> {
+///         Ok(std::fs::read("./empresa.cer")?)
+///     }
+///     fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DfeError> {
+///         // assinatura PKCS#1 v1.5 / SHA-1 feita pelo HSM
+///         self.sessao
+///             .assinar_rsa_sha1(data)
+///             .ok_or_else(|| DfeError::Assinatura("HSM recusou a assinatura".to_string()))
+///     }
+/// }
+/// ```
+pub trait Signer: Send + Sync {
+    /// DER do certificado X.509 do signatário (vai em `<X509Certificate>`).
+    fn cert_der(&self) -> Result<Vec<u8>>;
+
+    /// Assina `data` com RSA PKCS#1 v1.5 + SHA-1 e devolve a assinatura bruta.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl Signer for Certificado {
+    fn cert_der(&self) -> Result<Vec<u8>> {
+        Ok(Certificado::cert_der(self).to_vec())
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.sign_rsa_sha1(data)
+    }
+}
+
+/// Assinante + certificado da conexão mTLS, resolvidos a partir de um builder.
+#[derive(Clone)]
+pub struct Credencial {
+    signer: Arc<dyn Signer>,
+    certificado: Option<Certificado>,
+}
+
+impl Credencial {
+    /// Resolve as credenciais informadas no builder.
+    ///
+    /// Sem `signer`, o certificado (handle ou `cert_path`/`cert_pass`) assina e conecta.
+    /// Com `signer`, o certificado é opcional e só é usado na conexão com a SEFAZ.
+    pub fn resolver(
+        signer: Option<Arc<dyn Signer>>,
+        certificado: Option<Certificado>,
+        cert_path: Option<String>,
+        cert_pass: Option<String>,
+    ) -> Result<Credencial> {
+        match signer {
+            Some(signer) => {
+                let certificado = match (certificado, cert_path, cert_pass) {
+                    (Some(c), _, _) => Some(c),
+                    (None, Some(path), Some(pass)) => Some(Certificado::from_pfx(&path, &pass)?),
+                    _ => None,
+                };
+                Ok(Credencial { signer, certificado })
+            }
+            None => {
+                let certificado = Certificado::resolver(certificado, cert_path, cert_pass)?;
+                Ok(Credencial { signer: Arc::new(certificado.clone()), certificado: Some(certificado) })
+            }
+        }
+    }
+
+    /// Conteúdo de `<X509Certificate>`: base64 do DER do signatário.
+    pub fn cert_base64(&self) -> Result<String> {
+        Ok(STANDARD.encode(self.signer.cert_der()?))
+    }
+
+    /// `<SignatureValue>` em base64.
+    pub fn sign_base64(&self, data: &str) -> Result<String> {
+        Ok(STANDARD.encode(self.signer.sign(data.as_bytes())?))
+    }
+
+    /// Recusa, antes da transmissão, certificado do signatário vencido ou com raiz de
+    /// CNPJ diferente da do emitente.
+    pub fn validar(&self, cnpj_emitente: Option<&str>) -> Result<()> {
+        let cert_der = self.signer.cert_der()?;
+        super::x509::info(&cert_der)
+            .map_err(DfeError::Certificado)?
+            .validar_emitente(cnpj_emitente)
+    }
+
+    /// Identidade TLS para a SEFAZ; exige um [`Certificado`].
+    pub fn identity(&self) -> Result<reqwest::Identity> {
+        Ok(self.certificado()?.identity())
+    }
+
+    /// Certificado da conexão com a SEFAZ.
+    pub(crate) fn certificado(&self) -> Result<&Certificado> {
+        self.certificado.as_ref().ok_or_else(|| {
+            DfeError::Configuracao(
+                "certificado para a conexão com a SEFAZ não informado (signer externo exige também cert ou certificado)".to_string(),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Assinante local de teste: devolve bytes fixos e conta as chamadas.
+    struct SignerFake {
+        chamadas: AtomicUsize,
+    }
+
+    impl Signer for SignerFake {
+        fn cert_der(&self) -> Result<Vec<u8>> {
+            Ok(vec![0x30, 0x00])
+        }
+
+        fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            self.chamadas.fetch_add(1, Ordering::SeqCst);
+            Ok(data.iter().rev().copied().collect())
+        }
+    }
+
+    #[test]
+    fn test_credencial_usa_signer_externo() {
+        let fake = Arc::new(SignerFake { chamadas: AtomicUsize::new(0) });
+        let cred = Credencial::resolver(Some(fake.clone()), None, None, None).unwrap();
+
+        assert_eq!(cred.sign_base64("abc").unwrap(), STANDARD.encode("cba"));
+        assert_eq!(cred.cert_base64().unwrap(), STANDARD.encode([0x30, 0x00]));
+        assert_eq!(fake.chamadas.load(Ordering::SeqCst), 1);
+        // Sem certificado não há identidade TLS para transmitir.
+        assert!(matches!(cred.identity(), Err(DfeError::Configuracao(_))));
+    }
+
+    #[test]
+    fn test_credencial_sem_signer_usa_certificado() {
+        let pfx = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+        let cert = Certificado::from_bytes(pfx, "1234").unwrap();
+        let cred = Credencial::resolver(None, Some(cert.clone()), None, None).unwrap();
+
+        assert_eq!(cred.cert_base64().unwrap(), STANDARD.encode(cert.cert_der()));
+        assert!(cred.identity().is_ok());
+    }
+
+    #[test]
+    fn test_credencial_valida_emitente() {
+        let pfx = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+        let cert = Certificado::from_bytes(pfx, "1234").unwrap();
+        let cred = Credencial::resolver(None, Some(cert), None, None).unwrap();
+
+        // Mesma raiz (filial 0002) e emitente sem CNPJ passam.
+        assert!(cred.validar(Some("11.222.333/0002-62")).is_ok());
+        assert!(cred.validar(None).is_ok());
+        assert!(matches!(cred.validar(Some("99888777000100")), Err(DfeError::Certificado(_))));
+    }
+
+    #[test]
+    fn test_credencial_recusa_certificado_vencido() {
+        let pfx = include_bytes!("../../testes/certificado_teste_expirado.pfx");
+        let cert = Certificado::from_bytes(pfx, "1234").unwrap();
+        let cred = Credencial::resolver(None, Some(cert), None, None).unwrap();
+
+        let erro = cred.validar(None).unwrap_err().to_string();
+        assert!(erro.contains("vencido em 2021/01/01"), "{}", erro);
+    }
+}
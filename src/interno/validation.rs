@@ -14,6 +14,8 @@ static XSD_FILES: &[(&str, &[u8])] = &[
     ("leiauteNFe_v4.00.xsd",          include_bytes!("../schemas_nt2026_004/leiauteNFe_v4.00.xsd")),
     ("xmldsig-core-schema_v1.01.xsd", include_bytes!("../schemas_nt2026_004/xmldsig-core-schema_v1.01.xsd")),
     ("DFeTiposBasicos_v1.00.xsd",     include_bytes!("../schemas_nt2026_004/DFeTiposBasicos_v1.00.xsd")),
+    ("inutNFe_v4.00.xsd",             include_bytes!("../schemas_nt2026_004/inutNFe_v4.00.xsd")),
+    ("leiauteInutNFe_v4.00.xsd",      include_bytes!("../schemas_nt2026_004/leiauteInutNFe_v4.00.xsd")),
 ];
 
 static SCHEMA_DIR: OnceLock<std::result::Result<PathBuf, String>> = OnceLock::new();
@@ -36,8 +38,13 @@ fn extract_schemas() -> Result<PathBuf> {
 }
 
 pub fn is_xml_valid(xml: &str) -> Result<String> {
+    validar_xsd(xml, "nfe_v4.00.xsd")
+}
+
+/// Valida `xml` contra um dos XSDs embutidos (ex.: `"inutNFe_v4.00.xsd"`).
+pub fn validar_xsd(xml: &str, xsd: &str) -> Result<String> {
     let dir = schema_dir()?;
-    let nfe_xsd = dir.join("nfe_v4.00.xsd");
+    let nfe_xsd = dir.join(xsd);
     let nfe_xsd_str = nfe_xsd.to_string_lossy();
 
     let doc = Parser::default()
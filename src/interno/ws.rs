@@ -1,20 +1,156 @@
 use crate::error::{DfeError, Result};
 use serde::Deserialize;
+use std::path::Path;
 use std::sync::OnceLock;
 
 const WEBSERVICES_JSON: &str = include_str!("../data/webservices.json");
 
-#[derive(Deserialize)]
+/// Variável de ambiente com o caminho de um JSON de endpoints (ou o próprio JSON) que
+/// sobrepõe a tabela embutida.
+pub const WEBSERVICES_ENV: &str = "DFE_WEBSERVICES";
+
+#[derive(Debug, Clone, Deserialize)]
 struct Endpoint {
     servico: String,
     ambiente: u8,
-    uf: String,
+    autorizador: String,
+    #[serde(default)]
     modelo: u32,
-    svn: bool,
     url: String,
 }
 
+impl Endpoint {
+    /// `autorizador = "*"` e `modelo = 0` (ou ausente) valem para qualquer valor.
+    fn atende(&self, servico: &str, ambiente: u8, autorizador: &str, modelo: u32) -> bool {
+        self.servico == servico
+            && self.ambiente == ambiente
+            && (self.autorizador == "*" || self.autorizador == autorizador)
+            && (self.modelo == 0 || self.modelo == modelo)
+    }
+}
+
+/// Endpoints que substituem os da tabela embutida (`data/webservices.json`).
+///
+/// Cada linha segue o formato da tabela embutida — `servico`, `ambiente`, `autorizador`,
+/// `modelo`, `url` —, com `"autorizador": "*"` e `modelo` omitido valendo para qualquer
+/// autorizador/modelo. Serviços não sobrepostos continuam resolvidos pela tabela embutida.
+///
+/// A URL é resolvida, em ordem, pelo registro passado ao builder (`.webservices(...)`),
+/// pela variável de ambiente `DFE_WEBSERVICES` (caminho do arquivo ou o próprio JSON) e pela
+/// tabela embutida.
+///
+/// ```no_run
+/// use dfe::Webservices;
+///
+/// # fn example() -> Result<(), dfe::DfeError> {
+/// // todos os serviços de homologação apontando para um mock local
+/// let mock = Webservices::new()
+///     .url("NFeAutorizacao", 2, "*", "http://127.0.0.1:8080/autorizacao")
+///     .url("RecepcaoEvento", 2, "*", "http://127.0.0.1:8080/evento")
+///     .url("NfeStatusServico", 2, "*", "http://127.0.0.1:8080/status")
+///     .url("NFeDistribuicaoDFe", 2, "AN", "http://127.0.0.1:8080/distribuicao");
+///
+/// // ou a partir de um arquivo no formato de `webservices.json`
+/// let staging = Webservices::from_file("./webservices-staging.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Webservices {
+    endpoints: Vec<Endpoint>,
+}
+
+impl Webservices {
+    /// Registro vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lê um array JSON no formato de `webservices.json`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let endpoints = serde_json::from_str(json)
+            .map_err(|e| DfeError::Configuracao(format!("JSON de webservices inválido: {}", e)))?;
+        Ok(Self { endpoints })
+    }
+
+    /// Lê o JSON de endpoints de um arquivo.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            DfeError::Configuracao(format!("Erro ao ler {}: {}", path.display(), e))
+        })?;
+        Self::from_json(&json)
+    }
+
+    /// Sobrepõe a URL de um serviço (`"NFeAutorizacao"`, `"RecepcaoEvento"`, …) em todos os modelos.
+    /// `autorizador` aceita `"*"` para qualquer autorizador.
+    pub fn url(self, servico: &str, ambiente: u8, autorizador: &str, url: &str) -> Self {
+        self.url_modelo(servico, ambiente, autorizador, 0, url)
+    }
+
+    /// Sobrepõe a URL de um serviço só no modelo informado (`55` ou `65`).
+    pub fn url_modelo(mut self, servico: &str, ambiente: u8, autorizador: &str, modelo: u32, url: &str) -> Self {
+        self.endpoints.push(Endpoint {
+            servico: servico.to_string(),
+            ambiente,
+            autorizador: autorizador.to_string(),
+            modelo,
+            url: url.to_string(),
+        });
+        self
+    }
+
+    /// Acrescenta os endpoints de `outro`; em caso de conflito prevalecem os de `outro`.
+    pub fn merge(mut self, outro: Webservices) -> Self {
+        let mut endpoints = outro.endpoints;
+        endpoints.append(&mut self.endpoints);
+        self.endpoints = endpoints;
+        self
+    }
+
+    fn buscar(&self, servico: &str, ambiente: u8, autorizador: &str, modelo: u32) -> Option<&str> {
+        self.endpoints
+            .iter()
+            .find(|e| e.atende(servico, ambiente, autorizador, modelo))
+            .map(|e| e.url.as_str())
+    }
+}
+
+/// Código IBGE, sigla, autorizador NF-e (55), autorizador NFC-e (65) e SVC de cada UF.
+///
+/// Fonte: Portal Nacional da NF-e (relação de autorizadores) e NT 2013/007 (SVC).
+const UFS: &[(u16, &str, &str, &str, &str)] = &[
+    (11, "RO", "SVRS", "SVRS", "SVC-AN"),
+    (12, "AC", "SVRS", "SVRS", "SVC-AN"),
+    (13, "AM", "AM", "AM", "SVC-RS"),
+    (14, "RR", "SVRS", "SVRS", "SVC-AN"),
+    (15, "PA", "SVRS", "SVRS", "SVC-AN"),
+    (16, "AP", "SVRS", "SVRS", "SVC-AN"),
+    (17, "TO", "SVRS", "SVRS", "SVC-AN"),
+    (21, "MA", "SVAN", "SVRS", "SVC-RS"),
+    (22, "PI", "SVRS", "SVRS", "SVC-RS"),
+    (23, "CE", "CE", "CE", "SVC-RS"),
+    (24, "RN", "SVRS", "SVRS", "SVC-AN"),
+    (25, "PB", "SVRS", "SVRS", "SVC-AN"),
+    (26, "PE", "PE", "SVRS", "SVC-RS"),
+    (27, "AL", "SVRS", "SVRS", "SVC-AN"),
+    (28, "SE", "SVRS", "SVRS", "SVC-AN"),
+    (29, "BA", "BA", "SVRS", "SVC-RS"),
+    (31, "MG", "MG", "MG", "SVC-AN"),
+    (32, "ES", "SVRS", "SVRS", "SVC-AN"),
+    (33, "RJ", "SVRS", "SVRS", "SVC-AN"),
+    (35, "SP", "SP", "SP", "SVC-AN"),
+    (41, "PR", "PR", "PR", "SVC-RS"),
+    (42, "SC", "SVRS", "SVRS", "SVC-AN"),
+    (43, "RS", "RS", "RS", "SVC-AN"),
+    (50, "MS", "MS", "MS", "SVC-RS"),
+    (51, "MT", "MT", "MT", "SVC-RS"),
+    (52, "GO", "GO", "GO", "SVC-RS"),
+    (53, "DF", "SVRS", "SVRS", "SVC-AN"),
+];
+
 static ENDPOINTS: OnceLock<Vec<Endpoint>> = OnceLock::new();
+static ENDPOINTS_ENV: OnceLock<std::result::Result<Option<Webservices>, String>> = OnceLock::new();
 
 fn endpoints() -> &'static Vec<Endpoint> {
     ENDPOINTS.get_or_init(|| {
@@ -23,35 +159,187 @@ fn endpoints() -> &'static Vec<Endpoint> {
     })
 }
 
-fn lookup(servico: &str, ambiente: u8, uf: &str, modelo: u32, svn: bool) -> Result<&'static str> {
+/// Endpoints de `DFE_WEBSERVICES`, lidos na primeira consulta.
+fn endpoints_env() -> Result<Option<&'static Webservices>> {
+    ENDPOINTS_ENV
+        .get_or_init(|| match std::env::var(WEBSERVICES_ENV) {
+            Ok(v) if v.trim_start().starts_with('[') => Webservices::from_json(&v).map(Some).map_err(|e| e.to_string()),
+            Ok(v) if !v.trim().is_empty() => Webservices::from_file(v.trim()).map(Some).map_err(|e| e.to_string()),
+            _ => Ok(None),
+        })
+        .as_ref()
+        .map(Option::as_ref)
+        .map_err(|e| DfeError::Configuracao(format!("{}: {}", WEBSERVICES_ENV, e)))
+}
+
+/// Sigla da UF a partir do código IBGE (`Ide.c_uf`), ex.: `35` → `"SP"`.
+pub fn uf_sigla(c_uf: u16) -> Result<&'static str> {
+    UFS.iter()
+        .find(|u| u.0 == c_uf)
+        .map(|u| u.1)
+        .ok_or_else(|| DfeError::Validacao(format!("cUF inválido: {}", c_uf)))
+}
+
+/// Código IBGE da UF a partir da sigla, ex.: `"SP"` → `35`.
+pub fn uf_codigo(uf: &str) -> Result<u16> {
+    UFS.iter()
+        .find(|u| u.1.eq_ignore_ascii_case(uf))
+        .map(|u| u.0)
+        .ok_or_else(|| DfeError::Validacao(format!("UF inválida: {}", uf)))
+}
+
+/// Autorizador que atende a UF no modelo informado (`svn` = contingência SVC).
+fn autorizador(uf: &str, modelo: u32, svn: bool) -> Result<&'static str> {
+    let (_, _, nfe, nfce, svc) = UFS
+        .iter()
+        .find(|u| u.1.eq_ignore_ascii_case(uf))
+        .ok_or_else(|| DfeError::Webservice(format!("UF sem autorizador cadastrado: {}", uf)))?;
+    match (modelo, svn) {
+        (55, false) => Ok(nfe),
+        (55, true) => Ok(svc),
+        (65, false) => Ok(nfce),
+        (65, true) => Err(DfeError::Webservice(
+            "NFC-e (modelo 65) não tem SVC; use a contingência offline".to_string(),
+        )),
+        _ => Err(DfeError::Webservice(format!("modelo sem webservice: {}", modelo))),
+    }
+}
+
+/// `tpEmis` da contingência SVC que atende a UF: `6` = SVC-AN · `7` = SVC-RS.
+pub fn svc_tp_emis(uf: &str) -> Result<u8> {
+    match autorizador(uf, 55, true)? {
+        "SVC-AN" => Ok(6),
+        _ => Ok(7),
+    }
+}
+
+fn lookup_autorizador(
+    servico: &str,
+    ambiente: u8,
+    autorizador: &str,
+    modelo: u32,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    if let Some(url) = webservices.and_then(|w| w.buscar(servico, ambiente, autorizador, modelo)) {
+        return Ok(url.to_string());
+    }
+    if let Some(url) = endpoints_env()?.and_then(|w| w.buscar(servico, ambiente, autorizador, modelo)) {
+        return Ok(url.to_string());
+    }
     endpoints()
         .iter()
         .find(|e| {
             e.servico == servico
                 && e.ambiente == ambiente
-                && e.uf == uf
+                && e.autorizador == autorizador
                 && e.modelo == modelo
-                && e.svn == svn
         })
-        .map(|e| e.url.as_str())
+        .map(|e| e.url.clone())
         .ok_or_else(|| {
             DfeError::Webservice(format!(
-                "Endpoint não encontrado: servico={} ambiente={} uf={} modelo={} svn={}",
-                servico, ambiente, uf, modelo, svn
+                "Endpoint não encontrado: servico={} ambiente={} autorizador={} modelo={}",
+                servico, ambiente, autorizador, modelo
             ))
         })
 }
 
-pub fn nfe_status_servico(ambiente: u8, uf: &str, modelo: u32, svn: bool) -> Result<&'static str> {
-    lookup("NfeStatusServico", ambiente, uf, modelo, svn)
+fn lookup(
+    servico: &str,
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup_autorizador(servico, ambiente, autorizador(uf, modelo, svn)?, modelo, webservices)
+}
+
+pub fn nfe_status_servico(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("NfeStatusServico", ambiente, uf, modelo, svn, webservices)
+}
+
+pub fn nfe_autorizacao(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("NFeAutorizacao", ambiente, uf, modelo, svn, webservices)
+}
+
+pub fn nfe_ret_autorizacao(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("NFeRetAutorizacao", ambiente, uf, modelo, svn, webservices)
+}
+
+pub fn nfe_recepcao_evento(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("RecepcaoEvento", ambiente, uf, modelo, svn, webservices)
+}
+
+pub fn nfe_consulta_protocolo(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("NfeConsultaProtocolo", ambiente, uf, modelo, svn, webservices)
+}
+
+pub fn nfe_inutilizacao(
+    ambiente: u8,
+    uf: &str,
+    modelo: u32,
+    svn: bool,
+    webservices: Option<&Webservices>,
+) -> Result<String> {
+    lookup("NfeInutilizacao", ambiente, uf, modelo, svn, webservices)
+}
+
+/// NfeConsultaCadastro do autorizador de NF-e da UF. Nem toda UF oferece o serviço.
+pub fn nfe_consulta_cadastro(ambiente: u8, uf: &str, webservices: Option<&Webservices>) -> Result<String> {
+    lookup("NfeConsultaCadastro", ambiente, uf, 55, false, webservices)
 }
 
-pub fn nfe_autorizacao(ambiente: u8, uf: &str, modelo: u32, svn: bool) -> Result<&'static str> {
-    lookup("NFeAutorizacao", ambiente, uf, modelo, svn)
+/// RecepcaoEvento do Ambiente Nacional (manifestação do destinatário, EPEC).
+pub fn nfe_recepcao_evento_an(ambiente: u8, webservices: Option<&Webservices>) -> Result<String> {
+    lookup_autorizador("RecepcaoEvento", ambiente, "AN", 55, webservices)
 }
 
-pub fn nfe_recepcao_evento(ambiente: u8, uf: &str, modelo: u32, svn: bool) -> Result<&'static str> {
-    lookup("RecepcaoEvento", ambiente, uf, modelo, svn)
+/// NFeDistribuicaoDFe (Ambiente Nacional).
+pub fn nfe_distribuicao_dfe(ambiente: u8, webservices: Option<&Webservices>) -> Result<String> {
+    lookup_autorizador("NFeDistribuicaoDFe", ambiente, "AN", 55, webservices)
+}
+
+/// URL base do QR Code da NFC-e (`infNFeSupl/qrCode`) da UF.
+///
+/// As URLs de consulta são definidas por UF, não por autorizador: na tabela o campo
+/// `autorizador` traz a sigla da UF (serviço `NfceConsultaQR`).
+pub fn nfce_consulta_qr(ambiente: u8, uf: &str, webservices: Option<&Webservices>) -> Result<String> {
+    lookup_autorizador("NfceConsultaQR", ambiente, &uf.to_ascii_uppercase(), 65, webservices)
+}
+
+/// URL de consulta da NFC-e pela chave de acesso (`infNFeSupl/urlChave`) da UF (serviço `NfceUrlChave`).
+pub fn nfce_url_chave(ambiente: u8, uf: &str, webservices: Option<&Webservices>) -> Result<String> {
+    lookup_autorizador("NfceUrlChave", ambiente, &uf.to_ascii_uppercase(), 65, webservices)
 }
 
 #[cfg(test)]
@@ -65,7 +353,7 @@ mod tests {
 
     #[test]
     fn test_get_ws_url() {
-        let url = nfe_status_servico(2, "SP", 55, false);
+        let url = nfe_status_servico(2, "SP", 55, false, None);
         assert_eq!(
             url.unwrap(),
             "https://homologacao.nfe.fazenda.sp.gov.br/ws/nfestatusservico4.asmx"
@@ -74,7 +362,106 @@ mod tests {
 
     #[test]
     fn test_endpoint_nao_encontrado() {
-        let url = nfe_status_servico(1, "XX", 55, false);
+        let url = nfe_status_servico(1, "XX", 55, false, None);
         assert!(url.is_err());
     }
+
+    #[test]
+    fn test_todas_as_ufs_tem_autorizacao_e_evento() {
+        for (_, uf, _, _, _) in UFS {
+            for ambiente in [1, 2] {
+                for modelo in [55, 65] {
+                    assert!(nfe_autorizacao(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                    assert!(nfe_ret_autorizacao(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                    assert!(nfe_recepcao_evento(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                    assert!(nfe_status_servico(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                    assert!(nfe_consulta_protocolo(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                    assert!(nfe_inutilizacao(ambiente, uf, modelo, false, None).is_ok(), "{} {} {}", uf, ambiente, modelo);
+                }
+                assert!(nfe_autorizacao(ambiente, uf, 55, true, None).is_ok(), "SVC {} {}", uf, ambiente);
+                assert!(nfe_ret_autorizacao(ambiente, uf, 55, true, None).is_ok(), "SVC {} {}", uf, ambiente);
+            }
+        }
+    }
+
+    #[test]
+    fn test_uf_resolve_autorizador() {
+        assert_eq!(
+            nfe_autorizacao(1, uf_sigla(31).unwrap(), 55, false, None).unwrap(),
+            "https://nfe.fazenda.mg.gov.br/nfe2/services/NFeAutorizacao4"
+        );
+        // BA autoriza NF-e, mas a NFC-e baiana vai para a SVRS.
+        assert_eq!(
+            nfe_autorizacao(1, "BA", 65, false, None).unwrap(),
+            "https://nfce.svrs.rs.gov.br/ws/NfeAutorizacao/NFeAutorizacao4.asmx"
+        );
+        assert_eq!(
+            nfe_autorizacao(2, "MA", 55, false, None).unwrap(),
+            "https://hom.sefazvirtual.fazenda.gov.br/NFeAutorizacao4/NFeAutorizacao4.asmx"
+        );
+        assert_eq!(
+            nfe_consulta_cadastro(2, "SP", None).unwrap(),
+            "https://homologacao.nfe.fazenda.sp.gov.br/ws/cadconsultacadastro4.asmx"
+        );
+        assert!(nfe_consulta_cadastro(2, "MA", None).is_err());
+        assert_eq!(uf_codigo("pr").unwrap(), 41);
+        assert_eq!(svc_tp_emis("SP").unwrap(), 6);
+        assert_eq!(svc_tp_emis("PR").unwrap(), 7);
+        assert!(uf_sigla(99).is_err());
+    }
+
+    #[test]
+    fn test_webservices_sobrepoe_tabela() {
+        let mock = Webservices::from_json(
+            r#"[{"servico":"NFeAutorizacao","ambiente":2,"autorizador":"*","url":"http://127.0.0.1:8080/autorizacao"}]"#,
+        )
+        .unwrap()
+        .url("NFeDistribuicaoDFe", 2, "AN", "http://127.0.0.1:8080/distribuicao")
+        .url_modelo("RecepcaoEvento", 2, "SVRS", 65, "http://127.0.0.1:8080/evento");
+        let mock = Some(&mock);
+
+        assert_eq!(nfe_autorizacao(2, "SP", 55, false, mock).unwrap(), "http://127.0.0.1:8080/autorizacao");
+        assert_eq!(nfe_autorizacao(2, "RJ", 65, false, mock).unwrap(), "http://127.0.0.1:8080/autorizacao");
+        assert_eq!(nfe_distribuicao_dfe(2, mock).unwrap(), "http://127.0.0.1:8080/distribuicao");
+        assert_eq!(nfe_recepcao_evento(2, "RJ", 65, false, mock).unwrap(), "http://127.0.0.1:8080/evento");
+        // Fora do registro: tabela embutida.
+        assert!(nfe_recepcao_evento(2, "RJ", 55, false, mock).unwrap().starts_with("https://"));
+        assert!(nfe_autorizacao(1, "SP", 55, false, mock).unwrap().starts_with("https://"));
+    }
+
+    #[test]
+    fn test_webservices_merge_prevalece_o_ultimo() {
+        let base = Webservices::new().url("NfeStatusServico", 2, "*", "http://a");
+        let merged = base.merge(Webservices::new().url("NfeStatusServico", 2, "SP", "http://b"));
+        assert_eq!(nfe_status_servico(2, "SP", 55, false, Some(&merged)).unwrap(), "http://b");
+        assert_eq!(nfe_status_servico(2, "MG", 55, false, Some(&merged)).unwrap(), "http://a");
+        assert!(Webservices::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_urls_da_nfce_por_uf() {
+        for (_, uf, _, _, _) in UFS {
+            for ambiente in [1, 2] {
+                assert!(nfce_consulta_qr(ambiente, uf, None).is_ok(), "{} {}", uf, ambiente);
+                assert!(nfce_url_chave(ambiente, uf, None).is_ok(), "{} {}", uf, ambiente);
+            }
+        }
+        assert_eq!(nfce_consulta_qr(1, "rs", None).unwrap(), "https://www.sefaz.rs.gov.br/NFCE/NFCE-COM.aspx");
+        assert_eq!(nfce_url_chave(2, "SP", None).unwrap(), "https://www.homologacao.nfce.fazenda.sp.gov.br/consulta");
+        // SVRS autoriza a NFC-e do RJ, mas a consulta pública é a da SEFAZ-RJ.
+        assert!(nfce_consulta_qr(1, "RJ", None).unwrap().contains("fazenda.rj.gov.br"));
+
+        let mock = Webservices::new().url("NfceConsultaQR", 2, "*", "http://127.0.0.1:8080/qrcode");
+        assert_eq!(nfce_consulta_qr(2, "MG", Some(&mock)).unwrap(), "http://127.0.0.1:8080/qrcode");
+        assert!(nfce_url_chave(2, "MG", Some(&mock)).unwrap().contains("mg.gov.br"));
+    }
+
+    #[test]
+    fn test_ambiente_nacional() {
+        assert_eq!(
+            nfe_distribuicao_dfe(2, None).unwrap(),
+            "https://hom1.nfe.fazenda.gov.br/NFeDistribuicaoDFe/NFeDistribuicaoDFe.asmx"
+        );
+        assert!(nfe_recepcao_evento_an(1, None).unwrap().starts_with("https://www1.nfe.fazenda.gov.br/"));
+    }
 }
@@ -0,0 +1,186 @@
+//! Leitura de certificados X.509 (DER) independente do backend do PFX.
+//!
+//! Usado tanto para os metadados do certificado carregado quanto para o
+//! certificado embutido em `<X509Certificate>` de um documento recebido.
+
+use super::cert::CertInfo;
+use super::cnpj_cpf::sanitize_cnpj;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use sha1::Sha1;
+use x509_parser::der_parser::asn1_rs::{Any, FromDer as _};
+use x509_parser::prelude::*;
+use x509_parser::public_key::PublicKey;
+
+// otherNames ICP-Brasil do SubjectAltName (DOC-ICP-04).
+/// Dados da pessoa física titular: nascimento (8) + CPF (11) + NIS + RG + órgão/UF.
+const OID_DADOS_PF: &str = "2.16.76.1.3.1";
+/// Nome do responsável pelo certificado de pessoa jurídica.
+const OID_RESPONSAVEL: &str = "2.16.76.1.3.2";
+/// CNPJ da pessoa jurídica titular.
+const OID_CNPJ: &str = "2.16.76.1.3.3";
+
+/// Metadados do certificado (subject/issuer do mais específico ao mais geral).
+pub fn info(cert_der: &[u8]) -> Result<CertInfo, String> {
+    let cert = parse(cert_der)?;
+    let validity = cert.validity();
+    let key_bits = match cert.public_key().parsed() {
+        Ok(PublicKey::RSA(rsa)) => bit_len(rsa.modulus),
+        Ok(key) => key.key_size(),
+        Err(_) => 0,
+    };
+    let now = chrono::Utc::now().timestamp();
+    Ok(CertInfo {
+        subject: name_to_string(cert.subject()),
+        issuer: name_to_string(cert.issuer()),
+        valid_from: format_date(&validity.not_before),
+        valid_to: format_date(&validity.not_after),
+        cnpj: cnpj_of(&cert),
+        cpf: other_name(&cert, OID_DADOS_PF)
+            .and_then(|dados| dados.get(8..19).map(str::to_string))
+            .filter(|cpf| digits(cpf, 11) && cpf.bytes().any(|b| b != b'0')),
+        responsible: other_name(&cert, OID_RESPONSAVEL).filter(|nome| !nome.is_empty()),
+        serial_number: hex::encode_upper(trim_zeros(cert.raw_serial())),
+        key_bits,
+        days_to_expiry: (validity.not_after.timestamp() - now).div_euclid(86_400),
+    })
+}
+
+/// CNPJ do titular, lido do otherName `2.16.76.1.3.3` ou, na falta dele, do sufixo
+/// `:CNPJ` do CN (`EMPRESA LTDA:11222333000181`).
+pub fn cnpj(cert_der: &[u8]) -> Option<String> {
+    cnpj_of(&parse(cert_der).ok()?)
+}
+
+/// Confere uma assinatura RSA PKCS#1 v1.5 + SHA-1 com a chave pública do certificado.
+pub fn verify_rsa_sha1(cert_der: &[u8], data: &[u8], signature: &[u8]) -> Result<bool, String> {
+    let cert = parse(cert_der)?;
+    let Ok(PublicKey::RSA(rsa)) = cert.public_key().parsed() else {
+        return Err("Chave pública do certificado não é RSA".to_string());
+    };
+    let public_key = RsaPublicKey::new(
+        BigUint::from_bytes_be(rsa.modulus),
+        BigUint::from_bytes_be(rsa.exponent),
+    )
+    .map_err(|e| format!("Chave pública RSA inválida: {}", e))?;
+    let Ok(signature) = Signature::try_from(signature) else {
+        return Ok(false);
+    };
+    Ok(VerifyingKey::<Sha1>::new(public_key).verify(data, &signature).is_ok())
+}
+
+/// CNPJ numérico ou alfanumérico (14 posições), normalizado por [`sanitize_cnpj`].
+fn cnpj_of(cert: &X509Certificate) -> Option<String> {
+    let cnpj = |v: &str| Some(sanitize_cnpj(v)).filter(|c| c.len() == 14 && c.bytes().any(|b| b != b'0'));
+    other_name(cert, OID_CNPJ).and_then(|v| cnpj(&v)).or_else(|| {
+        let cn = cert.subject().iter_common_name().next()?.as_str().ok()?;
+        let (_, sufixo) = cn.rsplit_once(':')?;
+        cnpj(sufixo)
+    })
+}
+
+fn digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Remove os zeros à esquerda de um inteiro big-endian (módulo RSA, serial).
+pub fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn bit_len(bytes: &[u8]) -> usize {
+    match trim_zeros(bytes) {
+        [] => 0,
+        [first, rest @ ..] => rest.len() * 8 + (8 - first.leading_zeros() as usize),
+    }
+}
+
+fn parse(cert_der: &[u8]) -> Result<X509Certificate<'_>, String> {
+    X509Certificate::from_der(cert_der)
+        .map(|(_, cert)| cert)
+        .map_err(|e| format!("Certificado X.509 inválido: {}", e))
+}
+
+/// Valor textual de um otherName ICP-Brasil (`[0] EXPLICIT` com uma string DER).
+fn other_name(cert: &X509Certificate, oid: &str) -> Option<String> {
+    let san = cert.subject_alternative_name().ok()??;
+    san.value.general_names.iter().find_map(|name| match name {
+        GeneralName::OtherName(id, value) if id.to_id_string() == oid => {
+            let (_, explicit) = Any::from_der(value).ok()?;
+            let (_, inner) = Any::from_der(explicit.data).ok()?;
+            std::str::from_utf8(inner.data).ok().map(|s| s.trim().to_string())
+        }
+        _ => None,
+    })
+}
+
+/// Nome X.500 do mais específico para o mais geral (`CN=..., OU=..., O=..., C=BR`),
+/// como o `CertGetNameStringW` do CAPI devolve.
+fn name_to_string(name: &X509Name) -> String {
+    let attrs: Vec<_> = name.iter_rdn().flat_map(|rdn| rdn.iter()).collect();
+    attrs
+        .iter()
+        .rev()
+        .map(|attr| {
+            let key = oid2abbrev(attr.attr_type(), oid_registry())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| attr.attr_type().to_id_string());
+            let value = attr.as_str().unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_date(time: &ASN1Time) -> String {
+    let dt = time.to_datetime();
+    format!("{:04}/{:02}/{:02}", dt.year(), u8::from(dt.month()), dt.day())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cert_der() -> Vec<u8> {
+        let pfx = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+        crate::Certificado::from_bytes(pfx, "1234").unwrap().cert_der().to_vec()
+    }
+
+    #[test]
+    fn test_info_e_cnpj() {
+        let der = cert_der();
+        let info = info(&der).unwrap();
+        assert_eq!(info.issuer, "CN=AC TESTE DFE, O=ICP-Brasil, C=BR");
+        assert_eq!(info.valid_to, "2046/10/13");
+        assert_eq!(info.cnpj.as_deref(), Some("11222333000181"));
+        assert_eq!(info.cpf, None);
+        assert_eq!(info.responsible.as_deref(), Some("FULANO DE TAL"));
+        assert_eq!(info.serial_number, "1A2B3C4D");
+        assert_eq!(info.key_bits, 2048);
+        assert!(info.days_to_expiry > 0);
+        assert_eq!(cnpj(&der).as_deref(), Some("11222333000181"));
+    }
+
+    #[test]
+    fn test_info_e_cpf_certificado_expirado() {
+        let pfx = include_bytes!("../../testes/certificado_teste_expirado.pfx");
+        let cert = crate::Certificado::from_bytes(pfx, "1234").unwrap();
+        let info = info(cert.cert_der()).unwrap();
+        assert_eq!(info.cpf.as_deref(), Some("11144477735"));
+        assert_eq!(info.cnpj, None);
+        assert_eq!(info.serial_number, "0BADCAFE");
+        assert_eq!(info.valid_to, "2021/01/01");
+        assert!(info.days_to_expiry < 0);
+    }
+
+    #[test]
+    fn test_verifica_rsa_sha1() {
+        let pfx = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+        let cert = crate::Certificado::from_bytes(pfx, "1234").unwrap();
+        let assinatura = cert.sign_rsa_sha1(b"dados").unwrap();
+        assert!(verify_rsa_sha1(cert.cert_der(), b"dados", &assinatura).unwrap());
+        assert!(!verify_rsa_sha1(cert.cert_der(), b"outros", &assinatura).unwrap());
+    }
+}
@@ -0,0 +1,407 @@
+//! Inutilização de numeração de NF-e/NFC-e (`NfeInutilizacao4`).
+//!
+//! Números pulados (rejeições, falhas de sequência) precisam ser inutilizados na
+//! SEFAZ até o dia 10 do mês seguinte. O pedido `infInut` é assinado, validado
+//! contra o XSD oficial e, com o `cStat` 102, a resposta traz o `procInutNFe`
+//! pronto para ser guardado.
+//!
+//! ```no_run
+//! use dfe::InutilizacaoBuilder;
+//!
+//! # async fn example() -> Result<(), dfe::DfeError> {
+//! let r = InutilizacaoBuilder::new()
+//!     .cert("./cert.pfx", "senha")
+//!     .tp_amb(2)
+//!     .c_uf(35)
+//!     .cnpj("11222333000181")
+//!     .modelo(55)
+//!     .serie(1)
+//!     .n_nf_ini(10)
+//!     .n_nf_fin(12)
+//!     .justificativa("Numeracao pulada por falha no sistema")
+//!     .send()
+//!     .await?;
+//!
+//! println!("{} — {}", r.c_stat, r.x_motivo);
+//! if let Some(xml) = r.proc_inut_nfe {
+//!     std::fs::write("./inutilizacao-procInutNFe.xml", xml)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{DfeError, Result};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::c14n;
+use crate::interno::cert::{Certificado, DigestValue};
+use crate::interno::cleaner::Strings;
+use crate::interno::cnpj_cpf::{sanitize_cnpj, validate_cnpj};
+use crate::interno::connection::WebService;
+use crate::interno::dates::get_current_year;
+use crate::interno::signer::{Credencial, Signer};
+use crate::interno::validation::validar_xsd;
+use crate::interno::ws::{nfe_inutilizacao, uf_sigla, Webservices};
+use quick_xml::events::BytesText;
+use quick_xml::writer::Writer;
+use regex::Regex;
+use serde::Deserialize;
+use std::io::Cursor;
+use std::sync::Arc;
+
+const XSD_INUT_NFE: &str = "inutNFe_v4.00.xsd";
+
+/// Resposta de [`InutilizacaoBuilder::send`].
+#[derive(Debug, Clone)]
+pub struct InutilizacaoResposta {
+    /// `cStat` do pedido. `102` = inutilização homologada.
+    pub c_stat: i32,
+    /// Descrição do status retornado pela SEFAZ.
+    pub x_motivo: String,
+    /// Protocolo da inutilização. Presente somente quando `c_stat == 102`.
+    pub n_prot: Option<String>,
+    /// Data e hora do processamento pela SEFAZ.
+    pub dh_recbto: Option<String>,
+    /// XML `procInutNFe` (pedido assinado + retorno) — deve ser persistido em disco.
+    pub proc_inut_nfe: Option<String>,
+    /// Envelope SOAP enviado.
+    pub send_xml: String,
+    /// Resposta SOAP recebida.
+    pub receive_xml: String,
+}
+
+#[derive(Deserialize)]
+struct RetInutNFe {
+    #[serde(rename = "infInut")]
+    inf_inut: RetInfInut,
+}
+
+#[derive(Deserialize)]
+struct RetInfInut {
+    #[serde(rename = "cStat")]
+    c_stat: i32,
+    #[serde(rename = "xMotivo")]
+    x_motivo: String,
+    #[serde(rename = "dhRecbto")]
+    dh_recbto: Option<String>,
+    #[serde(rename = "nProt")]
+    n_prot: Option<String>,
+}
+
+// ─── Builder público ──────────────────────────────────────────────────────────
+
+/// Builder do pedido de inutilização de uma faixa de numeração.
+pub struct InutilizacaoBuilder {
+    signer:        Option<Arc<dyn Signer>>,
+    certificado:   Option<Certificado>,
+    cert_path:     Option<String>,
+    cert_pass:     Option<String>,
+    tp_amb:        Option<u8>,
+    c_uf:          Option<u16>,
+    ano:           Option<u16>,
+    cnpj:          Option<String>,
+    modelo:        Option<u32>,
+    serie:         Option<u32>,
+    n_nf_ini:      Option<u32>,
+    n_nf_fin:      Option<u32>,
+    justificativa: Option<String>,
+    webservices:   Option<Webservices>,
+    artefatos:     Option<Arc<dyn ArtefatoSink>>,
+}
+
+impl InutilizacaoBuilder {
+    pub fn new() -> Self {
+        Self {
+            signer: None, certificado: None, cert_path: None, cert_pass: None, tp_amb: None,
+            c_uf: None, ano: None, cnpj: None, modelo: None, serie: None, n_nf_ini: None,
+            n_nf_fin: None, justificativa: None, webservices: None, artefatos: None,
+        }
+    }
+
+    pub fn cert(mut self, path: &str, pass: &str) -> Self {
+        self.cert_path = Some(path.to_string());
+        self.cert_pass = Some(pass.to_string());
+        self
+    }
+
+    /// Certificado já carregado; tem precedência sobre `cert(path, pass)`
+    pub fn certificado(mut self, c: Certificado) -> Self { self.certificado = Some(c); self }
+
+    /// Assinante externo do pedido; a conexão com a SEFAZ ainda usa `cert` ou `certificado`
+    pub fn signer(mut self, s: Arc<dyn Signer>) -> Self { self.signer = Some(s); self }
+
+    /// 1 = Produção | 2 = Homologação
+    pub fn tp_amb(mut self, v: u8) -> Self { self.tp_amb = Some(v); self }
+
+    /// Código IBGE da UF do emitente (ex.: 35 = SP)
+    pub fn c_uf(mut self, v: u16) -> Self { self.c_uf = Some(v); self }
+
+    /// Ano da numeração, com 2 dígitos (padrão: ano corrente)
+    pub fn ano(mut self, v: u16) -> Self { self.ano = Some(v); self }
+
+    /// CNPJ do emitente
+    pub fn cnpj(mut self, v: &str) -> Self { self.cnpj = Some(v.to_string()); self }
+
+    /// Modelo do documento: 55 = NF-e | 65 = NFC-e
+    pub fn modelo(mut self, v: u32) -> Self { self.modelo = Some(v); self }
+
+    /// Série da numeração (0 a 999)
+    pub fn serie(mut self, v: u32) -> Self { self.serie = Some(v); self }
+
+    /// Primeiro número da faixa a inutilizar
+    pub fn n_nf_ini(mut self, v: u32) -> Self { self.n_nf_ini = Some(v); self }
+
+    /// Último número da faixa (igual a `n_nf_ini` para um número só)
+    pub fn n_nf_fin(mut self, v: u32) -> Self { self.n_nf_fin = Some(v); self }
+
+    /// Justificativa (15 a 255 caracteres)
+    pub fn justificativa(mut self, v: &str) -> Self { self.justificativa = Some(v.to_string()); self }
+
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local)
+    pub fn webservices(mut self, w: Webservices) -> Self { self.webservices = Some(w); self }
+
+    /// Destino ([`ArtefatoSink`]) do envelope e do retorno. Sem ele nada é gravado
+    pub fn artefatos(mut self, a: Arc<dyn ArtefatoSink>) -> Self { self.artefatos = Some(a); self }
+
+    /// Monta, assina e valida o `inutNFe` sem enviar à SEFAZ.
+    pub fn gerar_xml(self) -> Result<String> {
+        let pedido     = self.pedido()?;
+        let credencial = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
+        inut_nfe_xml(&pedido, &credencial)
+    }
+
+    pub async fn send(self) -> Result<InutilizacaoResposta> {
+        let pedido     = self.pedido()?;
+        let credencial = Credencial::resolver(self.signer, self.certificado, self.cert_path, self.cert_pass)?;
+        credencial.validar(Some(&pedido.cnpj))?;
+
+        let inut_nfe = inut_nfe_xml(&pedido, &credencial)?;
+        let url      = nfe_inutilizacao(pedido.tp_amb, uf_sigla(pedido.c_uf)?, pedido.modelo, false, self.webservices.as_ref())?;
+        let envelope = envelope_xml(&inut_nfe);
+        let client   = WebService::client(credencial.identity()?)?;
+        let artefatos = Artefatos::new(self.artefatos, "inutilizacao", &pedido.id());
+        let body     = WebService::transmitir(client, &url, envelope.clone(), &artefatos).await?;
+        resposta(&inut_nfe, envelope, body)
+    }
+
+    fn pedido(&self) -> Result<Pedido> {
+        let tp_amb        = self.tp_amb.ok_or_else(|| DfeError::Configuracao("tp_amb não informado".to_string()))?;
+        let c_uf          = self.c_uf.ok_or_else(|| DfeError::Validacao("c_uf não informado".to_string()))?;
+        let cnpj          = self.cnpj.as_deref().ok_or_else(|| DfeError::Validacao("cnpj não informado".to_string()))?;
+        let modelo        = self.modelo.ok_or_else(|| DfeError::Validacao("modelo não informado".to_string()))?;
+        let serie         = self.serie.ok_or_else(|| DfeError::Validacao("serie não informada".to_string()))?;
+        let n_nf_ini      = self.n_nf_ini.ok_or_else(|| DfeError::Validacao("n_nf_ini não informado".to_string()))?;
+        let n_nf_fin      = self.n_nf_fin.ok_or_else(|| DfeError::Validacao("n_nf_fin não informado".to_string()))?;
+        let justificativa = self.justificativa.as_deref().map(str::trim)
+            .ok_or_else(|| DfeError::Validacao("justificativa não informada".to_string()))?;
+        let ano = match self.ano {
+            Some(ano) => format!("{:02}", ano % 100),
+            None => get_current_year(2),
+        };
+
+        uf_sigla(c_uf)?;
+        if !validate_cnpj(cnpj) {
+            return Err(DfeError::Validacao(format!("CNPJ inválido: {}", cnpj)));
+        }
+        if !matches!(modelo, 55 | 65) {
+            return Err(DfeError::Validacao(format!("modelo inválido: {}", modelo)));
+        }
+        if serie > 999 {
+            return Err(DfeError::Validacao(format!("série inválida: {}", serie)));
+        }
+        if n_nf_ini == 0 || n_nf_fin > 999_999_999 || n_nf_ini > n_nf_fin {
+            return Err(DfeError::Validacao(format!(
+                "faixa de numeração inválida: {} a {}", n_nf_ini, n_nf_fin
+            )));
+        }
+        let len = justificativa.chars().count();
+        if !(15..=255).contains(&len) {
+            return Err(DfeError::Validacao("justificativa deve ter de 15 a 255 caracteres".to_string()));
+        }
+
+        Ok(Pedido {
+            tp_amb, c_uf, ano, cnpj: sanitize_cnpj(cnpj), modelo, serie, n_nf_ini, n_nf_fin,
+            justificativa: justificativa.to_string(),
+        })
+    }
+}
+
+impl Default for InutilizacaoBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ─── Lógica interna ───────────────────────────────────────────────────────────
+
+struct Pedido {
+    tp_amb: u8,
+    c_uf: u16,
+    ano: String,
+    cnpj: String,
+    modelo: u32,
+    serie: u32,
+    n_nf_ini: u32,
+    n_nf_fin: u32,
+    justificativa: String,
+}
+
+impl Pedido {
+    /// `ID` + cUF + ano + CNPJ + modelo + série + nNFIni + nNFFin.
+    fn id(&self) -> String {
+        format!(
+            "ID{:02}{}{}{:02}{:03}{:09}{:09}",
+            self.c_uf, self.ano, self.cnpj, self.modelo, self.serie, self.n_nf_ini, self.n_nf_fin
+        )
+    }
+}
+
+/// `inutNFe` assinado e validado pelo XSD.
+fn inut_nfe_xml(pedido: &Pedido, credencial: &Credencial) -> Result<String> {
+    let id = pedido.id();
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer.create_element("inutNFe")
+        .with_attribute(("xmlns", "http://www.portalfiscal.inf.br/nfe"))
+        .with_attribute(("versao", "4.00"))
+        .write_inner_content(|w| {
+            w.create_element("infInut")
+                .with_attribute(("Id", id.as_str()))
+                .write_inner_content(|w| {
+                    w.create_element("tpAmb") .write_text_content(BytesText::new(&pedido.tp_amb.to_string()))?;
+                    w.create_element("xServ") .write_text_content(BytesText::new("INUTILIZAR"))?;
+                    w.create_element("cUF")   .write_text_content(BytesText::new(&pedido.c_uf.to_string()))?;
+                    w.create_element("ano")   .write_text_content(BytesText::new(&pedido.ano))?;
+                    w.create_element("CNPJ")  .write_text_content(BytesText::new(&pedido.cnpj))?;
+                    w.create_element("mod")   .write_text_content(BytesText::new(&pedido.modelo.to_string()))?;
+                    w.create_element("serie") .write_text_content(BytesText::new(&pedido.serie.to_string()))?;
+                    w.create_element("nNFIni").write_text_content(BytesText::new(&pedido.n_nf_ini.to_string()))?;
+                    w.create_element("nNFFin").write_text_content(BytesText::new(&pedido.n_nf_fin.to_string()))?;
+                    w.create_element("xJust") .write_text_content(BytesText::new(&pedido.justificativa))?;
+                    Ok(())
+                })?;
+            Ok(())
+        })?;
+    let xml = String::from_utf8(writer.into_inner().into_inner())?;
+
+    let digest_value = DigestValue::c14n_sha1(&xml, "infInut")?;
+    let signed_info  = c14n::canonicalize(&signed_info_xml(&digest_value, &id), "SignedInfo")?;
+    let signature    = signature_xml(
+        &signed_info,
+        &credencial.sign_base64(&signed_info)?,
+        &credencial.cert_base64()?,
+    );
+    let xml = xml.replace("</inutNFe>", &format!("{}</inutNFe>", signature));
+    validar_xsd(&xml, XSD_INUT_NFE)
+}
+
+fn signed_info_xml(digest: &str, id: &str) -> String {
+    format!(
+        concat!(
+            r#"<SignedInfo xmlns="http://www.w3.org/2000/09/xmldsig#">"#,
+            r#"<CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></CanonicalizationMethod>"#,
+            r#"<SignatureMethod Algorithm="http://www.w3.org/2000/09/xmldsig#rsa-sha1"></SignatureMethod>"#,
+            r##"<Reference URI="#{}"><Transforms>"##,
+            r#"<Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></Transform>"#,
+            r#"<Transform Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></Transform>"#,
+            r#"</Transforms><DigestMethod Algorithm="http://www.w3.org/2000/09/xmldsig#sha1"></DigestMethod>"#,
+            r#"<DigestValue>{}</DigestValue></Reference></SignedInfo>"#,
+        ),
+        id, digest
+    )
+}
+
+fn signature_xml(signed_info: &str, signed_value: &str, certificate: &str) -> String {
+    format!(
+        r#"<Signature xmlns="http://www.w3.org/2000/09/xmldsig#">{}<SignatureValue>{}</SignatureValue><KeyInfo><X509Data><X509Certificate>{}</X509Certificate></X509Data></KeyInfo></Signature>"#,
+        signed_info, signed_value, certificate
+    )
+}
+
+fn envelope_xml(inut_nfe: &str) -> String {
+    let envelope = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><soap12:Envelope xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:soap12="http://www.w3.org/2003/05/soap-envelope"><soap12:Body><nfeDadosMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeInutilizacao4">{}</nfeDadosMsg></soap12:Body></soap12:Envelope>"#,
+        inut_nfe
+    );
+    Strings::clear_xml_string(&envelope)
+}
+
+fn resposta(inut_nfe: &str, send_xml: String, receive_xml: String) -> Result<InutilizacaoResposta> {
+    let ret_inut_nfe = Regex::new(r"(?s)<retInutNFe\b.*?</retInutNFe>")
+        .map_err(|e| DfeError::Xml(format!("Erro regex: {}", e)))?
+        .find(&receive_xml)
+        .map(|m| m.as_str().to_string())
+        .ok_or_else(|| DfeError::Xml(format!("retInutNFe não encontrado: {}", receive_xml)))?;
+    let ret: RetInutNFe = quick_xml::de::from_str(&ret_inut_nfe)
+        .map_err(|e| DfeError::Xml(format!("Erro desserializar retInutNFe: {} — {}", e, ret_inut_nfe)))?;
+    let ret = ret.inf_inut;
+
+    let proc_inut_nfe = (ret.c_stat == 102).then(|| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><procInutNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">{}{}</procInutNFe>"#,
+            inut_nfe, ret_inut_nfe
+        )
+    });
+
+    Ok(InutilizacaoResposta {
+        c_stat: ret.c_stat,
+        x_motivo: ret.x_motivo,
+        n_prot: ret.n_prot,
+        dh_recbto: ret.dh_recbto,
+        proc_inut_nfe,
+        send_xml,
+        receive_xml,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PFX: &[u8] = include_bytes!("../../testes/certificado_teste_legacy.pfx");
+
+    fn builder() -> InutilizacaoBuilder {
+        InutilizacaoBuilder::new()
+            .certificado(Certificado::from_bytes(PFX, "1234").unwrap())
+            .tp_amb(2)
+            .c_uf(35)
+            .ano(25)
+            .cnpj("11.222.333/0001-81")
+            .modelo(55)
+            .serie(1)
+            .n_nf_ini(10)
+            .n_nf_fin(12)
+            .justificativa("Numeracao pulada por falha no sistema")
+    }
+
+    #[test]
+    fn test_gera_inut_nfe_assinado_e_valido() {
+        let xml = builder().gerar_xml().unwrap();
+        assert!(xml.starts_with(r#"<inutNFe xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00"><infInut Id="ID35251122233300018155001000000010000000012">"#));
+        assert!(xml.contains("<CNPJ>11222333000181</CNPJ><mod>55</mod><serie>1</serie><nNFIni>10</nNFIni><nNFFin>12</nNFFin>"));
+
+        let verificacao = crate::assinatura::verificar(&xml).unwrap();
+        assert_eq!(verificacao.assinaturas.len(), 1);
+        assert!(verificacao.valida());
+    }
+
+    #[test]
+    fn test_recusa_pedido_invalido() {
+        assert!(builder().n_nf_ini(13).gerar_xml().is_err());
+        assert!(builder().justificativa("curta").gerar_xml().is_err());
+        assert!(builder().cnpj("11222333000180").gerar_xml().is_err());
+        assert!(builder().modelo(57).gerar_xml().is_err());
+    }
+
+    #[test]
+    fn test_resposta_homologada_monta_proc_inut() {
+        let body = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><nfeResultMsg xmlns="http://www.portalfiscal.inf.br/nfe/wsdl/NFeInutilizacao4"><retInutNFe versao="4.00" xmlns="http://www.portalfiscal.inf.br/nfe"><infInut><tpAmb>2</tpAmb><verAplic>SP_NFE_PL009_V4</verAplic><cStat>102</cStat><xMotivo>Inutilizacao de numero homologado</xMotivo><cUF>35</cUF><ano>25</ano><CNPJ>11222333000181</CNPJ><mod>55</mod><serie>1</serie><nNFIni>10</nNFIni><nNFFin>12</nNFFin><dhRecbto>2025-01-10T10:00:00-03:00</dhRecbto><nProt>135250000000003</nProt></infInut></retInutNFe></nfeResultMsg></soap:Body></soap:Envelope>"#;
+        let r = resposta("<inutNFe/>", String::new(), body.to_string()).unwrap();
+        assert_eq!(r.c_stat, 102);
+        assert_eq!(r.n_prot.as_deref(), Some("135250000000003"));
+        let proc_inut = r.proc_inut_nfe.unwrap();
+        assert!(proc_inut.contains(r#"versao="4.00"><inutNFe/><retInutNFe versao="4.00""#));
+        assert!(proc_inut.ends_with("</retInutNFe></procInutNFe>"));
+
+        let rejeitada = body.replace("<cStat>102</cStat>", "<cStat>563</cStat>");
+        assert!(resposta("<inutNFe/>", String::new(), rejeitada).unwrap().proc_inut_nfe.is_none());
+    }
+}
@@ -14,14 +14,19 @@
 //!
 //! | Módulo | Responsabilidade |
 //! |---|---|
-//! | [`emissao`] | Emissão de NF-e e NFC-e via [`NFeBuilder`] |
+//! | [`emissao`] | Emissão de NF-e e NFC-e via [`NFeBuilder`], em lote via [`LoteNFeBuilder`], contingência EPEC via [`EpecBuilder`], NFC-e offline / FS-DA via [`TransmissaoOfflineBuilder`], diário de emissão com recuperação via [`RecuperacaoEmissaoBuilder`] e numeração de série/nNF via [`Numeracao`] |
 //! | [`cancelar`] | Cancelamento via [`CancelarBuilder`] |
+//! | [`carta_correcao`] | Carta de Correção Eletrônica via [`CartaCorrecaoBuilder`] |
+//! | [`inutilizacao`] | Inutilização de numeração via [`InutilizacaoBuilder`] |
+//! | [`cadastro`] | Cadastro de contribuintes do ICMS via [`ConsultaCadastroBuilder`] |
+//! | [`consulta`] | Situação, protocolo e eventos de uma NF-e via [`ConsultaProtocoloBuilder`] |
 //! | [`danfe`] | Geração de DANFE em PDF via [`DanfeBuilder`] |
 //! | [`escpos`] | Impressão ESC/POS via [`EscPosBuilder`] e [`EscPosNFCeBuilder`] |
 //! | [`distribuicao`] | Distribuição de DF-e (Ambiente Nacional) |
 //! | [`status`] | Status do webservice SEFAZ via [`NFeService`] |
 //! | [`manifestacao`] | Manifestação do destinatário |
 //! | [`xml_extractor`] | Extração de campos de XML autorizado |
+//! | [`assinatura`] | Verificação das assinaturas XMLDSig de DF-e recebidos |
 //! | [`tipos`] | Structs e enums de domínio (`Icms`, `Det`, `Ide`, …) |
 //!
 //! ## Exemplo — Emissão de NF-e
@@ -29,6 +34,7 @@
 //! ```no_run
 //! use dfe::{NFeBuilder, DfeError};
 //! use dfe::tipos::{Det, Emit, Icms, Ide, Pag, Pis, Cofins, Total, Transp};
+//! use rust_decimal_macros::dec;
 //!
 //! # async fn example() -> Result<(), DfeError> {
 //! let resp = NFeBuilder::new()
@@ -37,7 +43,7 @@
 //!     .emitente(Emit { cnpj: Some("11111111111111".into()), ..Default::default() })
 //!     .itens(vec![Det {
 //!         c_prod: "001".into(), x_prod: "PRODUTO".into(), ncm: "22030000".into(),
-//!         cfop: 5102, q_com: 1.0, v_un_com: 10.0, v_prod: 10.0,
+//!         cfop: 5102, q_com: dec!(1), v_un_com: dec!(10.00), v_prod: dec!(10.00),
 //!         icms: Icms::sn102(0, "400"),
 //!         pis: Pis::Nt { cst: "07".into() },
 //!         cofins: Cofins::Nt { cst: "07".into() },
@@ -71,12 +77,17 @@
 //! }
 //! # }
 //! ```
+pub mod assinatura;
+pub mod cadastro;
 pub mod cancelar;
+pub mod carta_correcao;
+pub mod consulta;
 pub mod danfe;
 pub mod distribuicao;
 pub mod emissao;
 pub mod error;
 pub mod escpos;
+pub mod inutilizacao;
 pub mod manifestacao;
 pub mod status;
 pub mod tipos;
@@ -84,14 +95,26 @@ pub mod xml_extractor;
 
 mod interno;
 
+pub use cadastro::ConsultaCadastroBuilder;
 pub use cancelar::CancelarBuilder;
+pub use carta_correcao::CartaCorrecaoBuilder;
+pub use consulta::ConsultaProtocoloBuilder;
 pub use danfe::DanfeBuilder;
 pub use escpos::EscPosBuilder;
 pub use escpos::EscPosNFCeBuilder;
-pub use interno::cert::CertInfo;
+pub use inutilizacao::InutilizacaoBuilder;
+pub use interno::cert::{CertInfo, Certificado};
+pub use interno::artefatos::{Artefato, ArtefatoSink, ArtefatosPorChave, SemArtefatos};
+pub use interno::signer::Signer;
 pub use interno::cnpj_cpf::{format_cnpj, sanitize_cnpj, validate_cnpj, validate_cpf};
 pub use interno::validation::is_xml_valid;
-pub use emissao::NFeBuilder;
+pub use interno::ws::Webservices;
+pub use emissao::{NFeBuilder, Violacao};
+pub use emissao::LoteNFeBuilder;
+pub use emissao::{EpecBuilder, RegularizacaoEpecBuilder};
+pub use emissao::{FilaOffline, TransmissaoOfflineBuilder};
+pub use emissao::{DiarioArquivo, DiarioEmissao, DiarioMemoria, RecuperacaoEmissaoBuilder};
+pub use emissao::{Numeracao, NumeracaoArquivo, NumeracaoMemoria};
 pub use emissao::Response as EmissaoResponse;
 pub use error::DfeError;
 pub use status::NFeService;
@@ -1,88 +1,41 @@
 use crate::error::{DfeError, Result};
-use crate::interno::cert::{Cert, DigestValue, Sign};
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::c14n;
+use crate::interno::cert::DigestValue;
+use crate::interno::signer::Credencial;
 use crate::interno::connection::WebService;
+use crate::interno::ws::{nfe_recepcao_evento_an, Webservices};
 use crate::tipos::manifestacao::{InfEvento, Manifestacao, OperacaoNaoRealizada, Response};
-use chrono::Local;
 use quick_xml::de;
 use quick_xml::events::BytesText;
 use quick_xml::writer::Writer;
-use std::fs::{self, File};
 use std::io::Cursor;
-use std::io::Write;
-use std::path::PathBuf;
+use std::sync::Arc;
 
-const TP_EVENTO_CONFIRMACAO_OPERACAO: &str = "210200";
-const TP_EVENTO_CIENCIA_OPERACAO: &str = "210210";
-const TP_EVENTO_DESCONHECIMENTO_OPERACAO: &str = "210220";
-const TP_EVENTO_OPERACAO_NAO_REALIZADA: &str = "210240";
+pub(crate) const TP_EVENTO_CONFIRMACAO_OPERACAO: &str = "210200";
+pub(crate) const TP_EVENTO_CIENCIA_OPERACAO: &str = "210210";
+pub(crate) const TP_EVENTO_DESCONHECIMENTO_OPERACAO: &str = "210220";
+pub(crate) const TP_EVENTO_OPERACAO_NAO_REALIZADA: &str = "210240";
 const VER_EVENTO: &str = "1.00";
 // Manifestacao do destinatario e processada pelo Ambiente Nacional.
 const C_ORGAO_AMBIENTE_NACIONAL: &str = "91";
-const MANIFESTACAO_LOG_REQUESTS_DIR: &str = "./distribuicao-logs/requests";
-const MANIFESTACAO_LOG_RESPONSES_DIR: &str = "./distribuicao-logs/responses";
-const MANIFESTACAO_LOG_ERRORS_DIR: &str = "./distribuicao-logs/errors";
-
-struct ManifestacaoLogPaths {
-    dir_requests: PathBuf,
-    dir_responses: PathBuf,
-    dir_errors: PathBuf,
-    base_name: String,
-}
-
-fn manifestacao_log_paths(cnpj: &str, desc_evento: &str) -> ManifestacaoLogPaths {
-    let now = Local::now();
-    let cnpj_clean: String = cnpj.chars().filter(|c| c.is_alphanumeric()).collect();
-    let ano = now.format("%Y").to_string();
-    let mes = now.format("%m").to_string();
-    let op_name = desc_evento.to_lowercase().replace(' ', "-");
-    let time = now.format("%H-%M-%S").to_string();
-    let suffix = [cnpj_clean.as_str(), ano.as_str(), mes.as_str()];
-    let build = |base: &str| suffix.iter().fold(PathBuf::from(base), |p, s| p.join(s));
-    ManifestacaoLogPaths {
-        dir_requests: build(MANIFESTACAO_LOG_REQUESTS_DIR),
-        dir_responses: build(MANIFESTACAO_LOG_RESPONSES_DIR),
-        dir_errors: build(MANIFESTACAO_LOG_ERRORS_DIR),
-        base_name: format!("{}-{}", op_name, time),
-    }
-}
-
 pub async fn nfe_confirmacao_operacao(params: Manifestacao) -> Result<Response> {
-    enviar_manifestacao(
-        &params,
-        TP_EVENTO_CONFIRMACAO_OPERACAO,
-        "Confirmacao da Operacao",
-        None,
-    )
-    .await
+    let credencial = Credencial::resolver(None, None, Some(params.cert_path.clone()), Some(params.cert_pass.clone()))?;
+    manifestar(&params, &credencial, TP_EVENTO_CONFIRMACAO_OPERACAO, None, None, None).await
 }
 
 pub async fn nfe_ciencia_operacao(params: Manifestacao) -> Result<Response> {
-    enviar_manifestacao(
-        &params,
-        TP_EVENTO_CIENCIA_OPERACAO,
-        "Ciencia da Operacao",
-        None,
-    )
-    .await
+    let credencial = Credencial::resolver(None, None, Some(params.cert_path.clone()), Some(params.cert_pass.clone()))?;
+    manifestar(&params, &credencial, TP_EVENTO_CIENCIA_OPERACAO, None, None, None).await
 }
 
 pub async fn nfe_desconhecimento_operacao(params: Manifestacao) -> Result<Response> {
-    enviar_manifestacao(
-        &params,
-        TP_EVENTO_DESCONHECIMENTO_OPERACAO,
-        "Desconhecimento da Operacao",
-        None,
-    )
-    .await
+    let credencial = Credencial::resolver(None, None, Some(params.cert_path.clone()), Some(params.cert_pass.clone()))?;
+    manifestar(&params, &credencial, TP_EVENTO_DESCONHECIMENTO_OPERACAO, None, None, None).await
 }
 
 pub async fn nfe_operacao_nao_realizada(params: OperacaoNaoRealizada) -> Result<Response> {
-    if params.justificativa.trim().is_empty() {
-        return Err(DfeError::Validacao(
-            "A justificativa e obrigatoria para Operacao nao Realizada".to_string(),
-        ));
-    }
-
+    let credencial = Credencial::resolver(None, None, Some(params.cert_path.clone()), Some(params.cert_pass.clone()))?;
     let base = Manifestacao {
         cert_path: params.cert_path.clone(),
         cert_pass: params.cert_pass.clone(),
@@ -92,45 +45,68 @@ pub async fn nfe_operacao_nao_realizada(params: OperacaoNaoRealizada) -> Result<
         chave: params.chave.clone(),
     };
 
-    enviar_manifestacao(
+    manifestar(
         &base,
+        &credencial,
         TP_EVENTO_OPERACAO_NAO_REALIZADA,
-        "Operacao nao Realizada",
         Some(params.justificativa.as_str()),
+        None,
+        None,
     )
     .await
 }
 
+/// Envia o evento de manifestação `tp_evento` com credenciais já resolvidas.
+pub(crate) async fn manifestar(
+    params: &Manifestacao,
+    credencial: &Credencial,
+    tp_evento: &str,
+    justificativa: Option<&str>,
+    webservices: Option<&Webservices>,
+    artefatos: Option<Arc<dyn ArtefatoSink>>,
+) -> Result<Response> {
+    let desc_evento = match tp_evento {
+        TP_EVENTO_CONFIRMACAO_OPERACAO => "Confirmacao da Operacao",
+        TP_EVENTO_CIENCIA_OPERACAO => "Ciencia da Operacao",
+        TP_EVENTO_DESCONHECIMENTO_OPERACAO => "Desconhecimento da Operacao",
+        TP_EVENTO_OPERACAO_NAO_REALIZADA => "Operacao nao Realizada",
+        _ => {
+            return Err(DfeError::Validacao(format!(
+                "Evento de manifestacao desconhecido: {}",
+                tp_evento
+            )))
+        }
+    };
+
+    if tp_evento == TP_EVENTO_OPERACAO_NAO_REALIZADA
+        && justificativa.is_none_or(|j| j.trim().is_empty())
+    {
+        return Err(DfeError::Validacao(
+            "A justificativa e obrigatoria para Operacao nao Realizada".to_string(),
+        ));
+    }
+    credencial.validar(Some(&params.cnpj))?;
+
+    let artefatos = Artefatos::new(artefatos, "manifestacao", &params.chave);
+    enviar_manifestacao(params, credencial, tp_evento, desc_evento, justificativa, webservices, &artefatos).await
+}
+
 async fn enviar_manifestacao(
     params: &Manifestacao,
+    credencial: &Credencial,
     tp_evento: &str,
     desc_evento: &str,
     justificativa: Option<&str>,
+    webservices: Option<&Webservices>,
+    artefatos: &Artefatos,
 ) -> Result<Response> {
     let lote_seq = lote_seq_generate();
 
-    let log_paths = manifestacao_log_paths(&params.cnpj, desc_evento);
-    fs::create_dir_all(&log_paths.dir_requests)?;
-    fs::create_dir_all(&log_paths.dir_responses)?;
-    fs::create_dir_all(&log_paths.dir_errors)?;
-    let path_inf_evento = log_paths
-        .dir_requests
-        .join(format!("{}-inf-evento.xml", log_paths.base_name));
-    let path_envio = log_paths
-        .dir_requests
-        .join(format!("{}-envio.xml", log_paths.base_name));
-    let path_resposta = log_paths
-        .dir_responses
-        .join(format!("{}-resposta.xml", log_paths.base_name));
-    let path_erro = log_paths
-        .dir_errors
-        .join(format!("{}-erro.txt", log_paths.base_name));
-
     macro_rules! log_err {
         ($expr:expr, $msg:literal) => {
             $expr.map_err(|e| {
                 let msg = format!("{}: {}", $msg, e);
-                let _ = fs::write(&path_erro, &msg);
+                artefatos.gravar("erro", &msg);
                 DfeError::Webservice(msg)
             })?
         };
@@ -143,35 +119,30 @@ async fn enviar_manifestacao(
             desc_evento,
             justificativa,
             lote_seq,
-            &path_inf_evento
         ),
         "Erro ao gerar XML do infEvento"
     );
+    artefatos.gravar("inf-evento", &inf_evento_xml);
     let inf_evento_xml =
         crate::interno::cleaner::Strings::clear_xml_string(inf_evento_xml.as_str());
 
     let digest_value = log_err!(
-        DigestValue::sha1(&inf_evento_xml),
+        DigestValue::c14n_sha1(&inf_evento_xml, "infEvento"),
         "Erro ao calcular DigestValue SHA1"
     );
     let signed_info = log_err!(
-        signed_info_xml(&digest_value, tp_evento, &params.chave, lote_seq),
+        signed_info_xml(&digest_value, tp_evento, &params.chave, lote_seq)
+            .and_then(|xml| c14n::canonicalize(&xml, "SignedInfo")),
         "Erro ao gerar XML do SignedInfo"
     );
 
     let signature_base64 = log_err!(
-        Sign::xml_string(
-            &signed_info,
-            &params.cert_path,
-            &params.cert_pass
-        )
-        .await,
+        credencial.sign_base64(&signed_info),
         "Erro ao assinar XML"
     );
 
     let x509_cert = log_err!(
-        crate::interno::cert::RawPubKey::get_from_file(&params.cert_path, &params.cert_pass)
-            .await,
+        credencial.cert_base64(),
         "Erro ao extrair chave publica do certificado"
     );
 
@@ -186,29 +157,22 @@ async fn enviar_manifestacao(
     let envelope_xml =
         crate::interno::cleaner::Strings::clear_xml_string(envelope_xml.as_str());
 
-    log_err!(
-        File::create(&path_envio).and_then(|mut f| f.write_all(envelope_xml.as_bytes())),
-        "Erro ao salvar XML de envio"
-    );
+    artefatos.gravar("envelope", &envelope_xml);
 
     // Usa sempre o endpoint de RecepcaoEvento do Ambiente Nacional para tpEvento de manifestacao.
     let url = log_err!(
-        recepcao_evento_ambiente_nacional(params.tp_amb),
+        nfe_recepcao_evento_an(params.tp_amb, webservices),
         "Erro ao resolver URL do webservice"
     );
 
-    let cert = log_err!(
-        Cert::from_pfx(&params.cert_path, &params.cert_pass),
-        "Erro ao carregar certificado PKCS12"
-    );
     let client = log_err!(
-        WebService::client(cert.identity),
+        credencial.identity().and_then(WebService::client),
         "Erro ao construir cliente HTTP"
     );
 
     let response = log_err!(
         client
-            .post(url)
+            .post(&url)
             .header("Content-Type", "application/soap+xml; charset=utf-8")
             .header("Content-Length", envelope_xml.len().to_string())
             .body(envelope_xml.clone())
@@ -219,6 +183,7 @@ async fn enviar_manifestacao(
 
     let status = response.status();
     let response = log_err!(response.text().await, "Erro ao ler corpo da resposta HTTP");
+    artefatos.gravar("retorno", &response);
 
     if !status.is_success() {
         let msg = format!(
@@ -227,15 +192,10 @@ async fn enviar_manifestacao(
             status,
             response.chars().take(220).collect::<String>()
         );
-        let _ = fs::write(&path_erro, &msg);
+        artefatos.gravar("erro", &msg);
         return Err(DfeError::Webservice(msg));
     }
 
-    log_err!(
-        File::create(&path_resposta).and_then(|mut f| f.write_all(response.as_bytes())),
-        "Erro ao salvar XML de resposta"
-    );
-
     let re = regex::bytes::Regex::new(r"(?s)<infEvento.*?</infEvento>").unwrap();
     let ret_evento = re.captures(response.as_bytes());
     if let Some(captures) = ret_evento {
@@ -254,7 +214,7 @@ async fn enviar_manifestacao(
         }
 
         let msg = "Erro ao converter xml para struct".to_string();
-        let _ = fs::write(&path_erro, &msg);
+        artefatos.gravar("erro", &msg);
         return Err(DfeError::Xml(msg));
     }
 
@@ -262,7 +222,7 @@ async fn enviar_manifestacao(
         "Erro ao capturar infEvento. Resposta_inicio={}",
         response.chars().take(220).collect::<String>()
     );
-    let _ = fs::write(&path_erro, &msg);
+    artefatos.gravar("erro", &msg);
     Err(DfeError::Xml(msg))
 }
 
@@ -272,7 +232,6 @@ fn inf_evento_xml(
     desc_evento: &str,
     justificativa: Option<&str>,
     lote_seq: u32,
-    log_path: &PathBuf,
 ) -> Result<String> {
     let inf_evento_id = format!("ID{}{}{:>02}", tp_evento, params.chave, lote_seq);
 
@@ -339,22 +298,9 @@ fn inf_evento_xml(
     let string = writer.into_inner().into_inner();
     let string = String::from_utf8(string)?;
 
-    let mut file = File::create(log_path)?;
-    file.write_all(string.as_bytes())?;
-
     Ok(string)
 }
 
-fn recepcao_evento_ambiente_nacional(tp_amb: u8) -> Result<&'static str> {
-    match tp_amb {
-        1 => Ok("https://www1.nfe.fazenda.gov.br/NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx"),
-        2 => Ok("https://hom1.nfe.fazenda.gov.br/NFeRecepcaoEvento4/NFeRecepcaoEvento4.asmx"),
-        _ => Err(DfeError::Validacao(
-            "tpAmb invalido para RecepcaoEvento no Ambiente Nacional".to_string(),
-        )),
-    }
-}
-
 fn signed_info_xml(
     digest_: &str,
     tp_evento: &str,
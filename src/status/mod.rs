@@ -6,6 +6,12 @@ mod xml;
 
 use serde::Serialize;
 
+use std::sync::Arc;
+
+use crate::interno::artefatos::{ArtefatoSink, Artefatos};
+use crate::interno::cert::Certificado;
+use crate::interno::ws::Webservices;
+
 /// Resposta da consulta de status do webservice SEFAZ.
 #[derive(Debug, Serialize)]
 pub struct NFeServiceResponse {
@@ -47,10 +53,16 @@ pub struct NFeService {
     pub cert_path: String,
     /// Senha do certificado.
     pub cert_pass: String,
+    /// Certificado já carregado; dispensa `cert_path`/`cert_pass`.
+    pub certificado: Option<Certificado>,
     /// Sigla da UF (ex.: `"SP"`, `"RJ"`).
     pub uf: String,
     /// Ambiente: `1` = Produção · `2` = Homologação.
     pub environment: u8,
+    /// Endpoints que substituem os da tabela embutida.
+    pub webservices: Option<Webservices>,
+    /// Destino do envelope e do retorno; `None` não grava nada.
+    pub artefatos: Option<Arc<dyn ArtefatoSink>>,
 }
 
 impl NFeService {
@@ -59,8 +71,11 @@ impl NFeService {
         Self {
             cert_path: String::new(),
             cert_pass: String::new(),
+            certificado: None,
             uf: String::new(),
             environment: 0,
+            webservices: None,
+            artefatos: None,
         }
     }
 
@@ -76,6 +91,12 @@ impl NFeService {
         self
     }
 
+    /// Certificado já carregado ([`Certificado`]), reutilizável entre consultas.
+    pub fn certificado(mut self, certificado: Certificado) -> Self {
+        self.certificado = Some(certificado);
+        self
+    }
+
     /// Sigla da UF (ex.: `"SP"`, `"RJ"`).
     pub fn uf(mut self, uf: &str) -> Self {
         self.uf = uf.to_string();
@@ -88,6 +109,18 @@ impl NFeService {
         self
     }
 
+    /// Endpoints que substituem os da tabela embutida (ex.: mock local em testes de integração).
+    pub fn webservices(mut self, webservices: Webservices) -> Self {
+        self.webservices = Some(webservices);
+        self
+    }
+
+    /// Destino ([`ArtefatoSink`]) do envelope e do retorno. Sem ele nada é gravado.
+    pub fn artefatos(mut self, artefatos: Arc<dyn ArtefatoSink>) -> Self {
+        self.artefatos = Some(artefatos);
+        self
+    }
+
     /// Valida os campos sem enviar a requisição. Retorna `Err` se algum campo obrigatório estiver vazio.
     pub fn build(self) -> Result<Self, String> {
         validation::validate_nfe_service(&self)?;
@@ -103,10 +136,14 @@ impl NFeService {
     pub async fn send(self) -> Result<NFeServiceResponse, String> {
         validation::validate_nfe_service(&self)?;
 
-        let url = endpoint::status_url(self.environment, &self.uf)?;
+        let url = endpoint::status_url(self.environment, &self.uf, self.webservices.as_ref())?;
         let xml = xml::status_request_xml(self.environment, &self.uf)?;
-        let body =
-            service::send_status_request(&self.cert_path, &self.cert_pass, &url, &xml).await?;
+        let certificado = match self.certificado {
+            Some(certificado) => certificado,
+            None => Certificado::from_pfx(&self.cert_path, &self.cert_pass).map_err(|e| e.to_string())?,
+        };
+        let artefatos = Artefatos::new(self.artefatos, "status-servico", &self.uf);
+        let body = service::send_status_request(&certificado, &url, &xml, &artefatos).await?;
         let status = parser::parse_status_response(&body)?;
 
         Ok(NFeServiceResponse {
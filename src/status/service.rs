@@ -1,18 +1,23 @@
-use crate::interno::cert;
+use crate::interno::artefatos::Artefatos;
+use crate::interno::cert::Certificado;
 use crate::interno::connection;
 
 pub async fn send_status_request(
-    cert_path: &str,
-    cert_pass: &str,
+    certificado: &Certificado,
     url: &str,
     xml: &str,
+    artefatos: &Artefatos,
 ) -> Result<String, String> {
-    let cert = cert::Cert::from_pfx(cert_path, cert_pass).map_err(|e| e.to_string())?;
-    let client = connection::WebService::client(cert.identity).map_err(|e| e.to_string())?;
+    let client = connection::WebService::client(certificado.identity()).map_err(|e| e.to_string())?;
 
-    let response = connection::WebService::send(client, url, xml.to_string())
-        .await
-        .map_err(|e| e.to_string())?;
-
-    response.text().await.map_err(|e| e.to_string())
+    artefatos.gravar("envelope", xml);
+    let body = match connection::WebService::send(client, url, xml.to_string()).await {
+        Ok(response) => response.text().await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match &body {
+        Ok(body) => artefatos.gravar("retorno", body),
+        Err(e) => artefatos.gravar("erro", e),
+    }
+    body
 }
@@ -0,0 +1,478 @@
+use rust_decimal::Decimal;
+
+use super::emissao::{Det, Fcp, Icms, IcmsUfDest};
+use crate::error::{DfeError, Result};
+use crate::interno::decimal::arredondar;
+
+// ─── Difal ────────────────────────────────────────────────────────────────────
+
+/// Alíquotas do DIFAL para consumidor final não contribuinte em outra UF (`<ICMSUFDest>`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difal {
+    /// Alíquota interna da UF de destino.
+    pub p_icms_uf_dest: Decimal,
+    /// Alíquota interestadual: `4`, `7` ou `12`.
+    pub p_icms_inter: Decimal,
+    /// Percentual de partilha para a UF de destino. Padrão `100`.
+    pub p_icms_inter_part: Decimal,
+    /// Percentual do FCP na UF de destino.
+    pub p_fcp_uf_dest: Option<Decimal>,
+    /// Base dupla: a base é recomposta "por dentro" com a alíquota interna do destino.
+    pub base_dupla: bool,
+}
+
+impl Difal {
+    /// Base única, partilha de 100% para o destino.
+    pub fn new(p_icms_uf_dest: Decimal, p_icms_inter: Decimal) -> Self {
+        Difal { p_icms_uf_dest, p_icms_inter, p_icms_inter_part: Decimal::ONE_HUNDRED, p_fcp_uf_dest: None, base_dupla: false }
+    }
+
+    pub fn p_fcp_uf_dest(mut self, p: Decimal) -> Self { self.p_fcp_uf_dest = Some(p); self }
+    pub fn p_icms_inter_part(mut self, p: Decimal) -> Self { self.p_icms_inter_part = p; self }
+    pub fn base_dupla(mut self) -> Self { self.base_dupla = true; self }
+}
+
+// ─── CalculoIcms ──────────────────────────────────────────────────────────────
+
+/// Calcula o ICMS de um item a partir de alíquotas e bases e preenche `icms`,
+/// `fcp`, `fcp_st` e `icms_uf_dest` do [`Det`].
+///
+/// A base da operação é `v_prod + v_frete + v_seg + v_outro − v_desc` do próprio item;
+/// o IPI entra na base do ICMS-ST e do DIFAL, e na base própria só com [`ipi_na_base`](Self::ipi_na_base).
+/// Valores arredondados em 2 casas (meio para longe do zero), o pMVAST ajustado em 4.
+///
+/// | CST/CSOSN | Obrigatórios | Opcionais |
+/// |---|---|---|
+/// | 00 | `p_icms` | `p_fcp` |
+/// | 10 | `p_icms`, `p_icms_st` | `mva`, `p_red_bcst`, `p_fcp`, `p_fcp_st` |
+/// | 20 | `p_icms`, `p_red_bc` | `p_fcp` |
+/// | 30 | `p_icms_st` | `p_icms` (dedução), `mva`, `p_red_bcst`, `p_fcp_st` |
+/// | 51 | `p_icms` | `p_red_bc`, `p_dif`, `p_fcp` |
+/// | 70 | `p_icms`, `p_icms_st` | `p_red_bc`, `mva`, `p_red_bcst`, `p_fcp`, `p_fcp_st` |
+/// | 90 | — | todos; calcula só os grupos com alíquota informada |
+/// | 101 | `p_cred_sn` | — |
+/// | 900 | — | `p_icms`, `p_cred_sn`, grupo ST |
+///
+/// ```
+/// use dfe::tipos::{CalculoIcms, Det, Difal};
+/// use rust_decimal_macros::dec;
+///
+/// let mut item = Det { v_prod: dec!(1000.00), v_frete: Some(dec!(50.00)), ..Default::default() };
+/// CalculoIcms::cst(0, "10")
+///     .p_icms(dec!(12))
+///     .mva(dec!(40))
+///     .mva_ajustada(true)
+///     .p_icms_st(dec!(18))
+///     .p_fcp_st(dec!(2))
+///     .aplicar(&mut item)
+///     .unwrap();
+///
+/// let mut venda = Det { v_prod: dec!(500.00), ..Default::default() };
+/// CalculoIcms::cst(0, "00")
+///     .p_icms(dec!(12))
+///     .difal(Difal::new(dec!(18), dec!(12)).p_fcp_uf_dest(dec!(2)))
+///     .aplicar(&mut venda)
+///     .unwrap();
+/// assert_eq!(venda.icms_uf_dest.unwrap().v_icms_uf_dest, dec!(30.00));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CalculoIcms {
+    orig: u8,
+    cst: String,
+    mod_bc: Option<u8>,
+    p_icms: Option<Decimal>,
+    p_red_bc: Option<Decimal>,
+    p_fcp: Option<Decimal>,
+    ipi_na_base: Option<bool>,
+    mod_bcst: Option<u8>,
+    p_mvast: Option<Decimal>,
+    mva_ajustada: Option<bool>,
+    p_red_bcst: Option<Decimal>,
+    p_icmsst: Option<Decimal>,
+    p_fcpst: Option<Decimal>,
+    p_dif: Option<Decimal>,
+    p_cred_sn: Option<Decimal>,
+    difal: Option<Difal>,
+}
+
+impl CalculoIcms {
+    /// CST (regime normal) ou CSOSN (Simples Nacional) e origem da mercadoria.
+    pub fn cst(orig: u8, cst: &str) -> Self {
+        CalculoIcms {
+            orig,
+            cst: cst.to_string(),
+            mod_bc: None,
+            p_icms: None,
+            p_red_bc: None,
+            p_fcp: None,
+            ipi_na_base: None,
+            mod_bcst: None,
+            p_mvast: None,
+            mva_ajustada: None,
+            p_red_bcst: None,
+            p_icmsst: None,
+            p_fcpst: None,
+            p_dif: None,
+            p_cred_sn: None,
+            difal: None,
+        }
+    }
+
+    /// Modalidade da BC. Padrão `3` (valor da operação).
+    pub fn mod_bc(mut self, v: u8) -> Self { self.mod_bc = Some(v); self }
+    pub fn p_icms(mut self, p: Decimal) -> Self { self.p_icms = Some(p); self }
+    pub fn p_red_bc(mut self, p: Decimal) -> Self { self.p_red_bc = Some(p); self }
+    pub fn p_fcp(mut self, p: Decimal) -> Self { self.p_fcp = Some(p); self }
+    /// Soma o IPI do item à base do ICMS próprio (venda a consumidor final/uso e consumo).
+    pub fn ipi_na_base(mut self, v: bool) -> Self { self.ipi_na_base = Some(v); self }
+    /// Modalidade da BC ST. Padrão `4` (margem de valor agregado).
+    pub fn mod_bcst(mut self, v: u8) -> Self { self.mod_bcst = Some(v); self }
+    /// MVA original (%).
+    pub fn mva(mut self, p: Decimal) -> Self { self.p_mvast = Some(p); self }
+    /// Ajusta a MVA: `[(1 + MVA) × (1 − pICMS) / (1 − pICMSST)] − 1`.
+    pub fn mva_ajustada(mut self, v: bool) -> Self { self.mva_ajustada = Some(v); self }
+    pub fn p_red_bcst(mut self, p: Decimal) -> Self { self.p_red_bcst = Some(p); self }
+    pub fn p_icms_st(mut self, p: Decimal) -> Self { self.p_icmsst = Some(p); self }
+    pub fn p_fcp_st(mut self, p: Decimal) -> Self { self.p_fcpst = Some(p); self }
+    /// Percentual do diferimento (CST 51).
+    pub fn p_dif(mut self, p: Decimal) -> Self { self.p_dif = Some(p); self }
+    /// Alíquota do crédito do Simples Nacional (CSOSN 101/900).
+    pub fn p_cred_sn(mut self, p: Decimal) -> Self { self.p_cred_sn = Some(p); self }
+    pub fn difal(mut self, difal: Difal) -> Self { self.difal = Some(difal); self }
+
+    /// Calcula e grava `icms`, `fcp`, `fcp_st` e `icms_uf_dest` no item.
+    ///
+    /// Retorna [`DfeError::Validacao`] para CST sem valores a calcular (40, 60, 102…)
+    /// ou quando falta uma alíquota obrigatória; o item não é alterado nesse caso.
+    pub fn aplicar(&self, det: &mut Det) -> Result<()> {
+        let ipi = det.ipi.as_ref().and_then(|i| i.v_ipi).unwrap_or_default();
+        let operacao = det.v_prod + det.v_frete.unwrap_or_default() + det.v_seg.unwrap_or_default()
+            + det.v_outro.unwrap_or_default() - det.v_desc.unwrap_or_default();
+        let base = if self.ipi_na_base.unwrap_or(false) { operacao + ipi } else { operacao };
+
+        let orig = self.orig;
+        let mod_bc = self.mod_bc.unwrap_or(3);
+        let mod_bcst = self.mod_bcst.unwrap_or(4);
+
+        let (icms, fcp, fcp_st) = match self.cst.as_str() {
+            "00" => {
+                let p_icms = self.exigir(self.p_icms, "p_icms")?;
+                let propria = Propria::calcular(base, None, p_icms);
+                let fcp = self.fcp(&propria);
+                (Icms::Icms00 { orig, mod_bc, v_bc: propria.v_bc, p_icms, v_icms: propria.v_icms }, fcp, None)
+            }
+            "10" => {
+                let p_icms = self.exigir(self.p_icms, "p_icms")?;
+                let p_icmsst = self.exigir(self.p_icmsst, "p_icms_st")?;
+                let propria = Propria::calcular(base, None, p_icms);
+                let fcp = self.fcp(&propria);
+                let st = self.st(operacao + ipi, Some(p_icms), p_icmsst, propria.v_icms);
+                let fcp_st = self.fcp_st(&st, &fcp);
+                (Icms::Icms10 {
+                    orig, mod_bc, v_bc: propria.v_bc, p_icms, v_icms: propria.v_icms,
+                    mod_bcst, p_mvast: st.p_mvast, p_red_bcst: self.p_red_bcst,
+                    v_bcst: st.v_bcst, p_icmsst, v_icmsst: st.v_icmsst,
+                }, fcp, fcp_st)
+            }
+            "20" => {
+                let p_icms = self.exigir(self.p_icms, "p_icms")?;
+                let p_red_bc = self.exigir(self.p_red_bc, "p_red_bc")?;
+                let propria = Propria::calcular(base, Some(p_red_bc), p_icms);
+                let fcp = self.fcp(&propria);
+                (Icms::Icms20 {
+                    orig, mod_bc, p_red_bc, v_bc: propria.v_bc, p_icms, v_icms: propria.v_icms,
+                    v_icms_deson: None, mot_des_icms: None,
+                }, fcp, None)
+            }
+            "30" => {
+                let p_icmsst = self.exigir(self.p_icmsst, "p_icms_st")?;
+                // Operação própria isenta: a dedução usa a alíquota que seria aplicada, se informada
+                let deducao = self.p_icms.map(|p| Propria::calcular(base, None, p).v_icms).unwrap_or(Decimal::ZERO);
+                let st = self.st(operacao + ipi, self.p_icms, p_icmsst, deducao);
+                let fcp_st = self.fcp_st(&st, &None);
+                (Icms::Icms30 {
+                    orig, mod_bcst, p_mvast: st.p_mvast, p_red_bcst: self.p_red_bcst,
+                    v_bcst: st.v_bcst, p_icmsst, v_icmsst: st.v_icmsst,
+                    v_icms_deson: None, mot_des_icms: None,
+                }, None, fcp_st)
+            }
+            "51" => {
+                let p_icms = self.exigir(self.p_icms, "p_icms")?;
+                let propria = Propria::calcular(base, self.p_red_bc, p_icms);
+                let p_dif = self.p_dif.unwrap_or_default();
+                let v_icms_dif = centavos(propria.v_icms * p_dif / CEM);
+                let fcp = self.fcp(&propria);
+                (Icms::Icms51 {
+                    orig, mod_bc: Some(mod_bc), p_red_bc: self.p_red_bc, v_bc: Some(propria.v_bc),
+                    p_icms: Some(p_icms), v_icms_op: Some(propria.v_icms), p_dif: Some(p_dif),
+                    v_icms_dif: Some(v_icms_dif), v_icms: Some(propria.v_icms - v_icms_dif),
+                }, fcp, None)
+            }
+            "70" => {
+                let p_icms = self.exigir(self.p_icms, "p_icms")?;
+                let p_icmsst = self.exigir(self.p_icmsst, "p_icms_st")?;
+                let propria = Propria::calcular(base, self.p_red_bc, p_icms);
+                let fcp = self.fcp(&propria);
+                let st = self.st(operacao + ipi, Some(p_icms), p_icmsst, propria.v_icms);
+                let fcp_st = self.fcp_st(&st, &fcp);
+                (Icms::Icms70 {
+                    orig, mod_bc, p_red_bc: self.p_red_bc, v_bc: propria.v_bc, p_icms, v_icms: propria.v_icms,
+                    mod_bcst, p_mvast: st.p_mvast, p_red_bcst: self.p_red_bcst,
+                    v_bcst: st.v_bcst, p_icmsst, v_icmsst: st.v_icmsst,
+                    v_icms_deson: None, mot_des_icms: None,
+                }, fcp, fcp_st)
+            }
+            "90" => {
+                let propria = self.p_icms.map(|p| Propria::calcular(base, self.p_red_bc, p));
+                let fcp = propria.as_ref().and_then(|p| self.fcp(p));
+                let deducao = propria.as_ref().map(|p| p.v_icms).unwrap_or(Decimal::ZERO);
+                let st = self.p_icmsst.map(|p| self.st(operacao + ipi, self.p_icms, p, deducao));
+                let fcp_st = st.as_ref().and_then(|s| self.fcp_st(s, &fcp));
+                (Icms::Icms90 {
+                    orig,
+                    mod_bc: propria.as_ref().map(|_| mod_bc),
+                    p_red_bc: propria.as_ref().and(self.p_red_bc),
+                    v_bc: propria.as_ref().map(|p| p.v_bc),
+                    p_icms: self.p_icms,
+                    v_icms: propria.as_ref().map(|p| p.v_icms),
+                    mod_bcst: st.as_ref().map(|_| mod_bcst),
+                    p_mvast: st.as_ref().map(|s| s.p_mvast),
+                    p_red_bcst: st.as_ref().and(self.p_red_bcst),
+                    v_bcst: st.as_ref().map(|s| s.v_bcst),
+                    p_icmsst: self.p_icmsst,
+                    v_icmsst: st.as_ref().map(|s| s.v_icmsst),
+                    v_icms_deson: None, mot_des_icms: None,
+                }, fcp, fcp_st)
+            }
+            "101" => {
+                let p_cred_sn = self.exigir(self.p_cred_sn, "p_cred_sn")?;
+                let credito = centavos(base * p_cred_sn / CEM);
+                (Icms::Sn101 { orig, p_cred_sn, v_cred_icmssn: credito }, None, None)
+            }
+            "900" => {
+                let propria = self.p_icms.map(|p| Propria::calcular(base, self.p_red_bc, p));
+                let deducao = propria.as_ref().map(|p| p.v_icms).unwrap_or(Decimal::ZERO);
+                let st = self.p_icmsst.map(|p| self.st(operacao + ipi, self.p_icms, p, deducao));
+                let fcp_st = st.as_ref().and_then(|s| self.fcp_st(s, &None));
+                (Icms::Sn900 {
+                    orig,
+                    mod_bc: propria.as_ref().map(|_| mod_bc),
+                    v_bc: propria.as_ref().map(|p| p.v_bc),
+                    p_red_bc: propria.as_ref().and(self.p_red_bc),
+                    p_icms: self.p_icms,
+                    v_icms: propria.as_ref().map(|p| p.v_icms),
+                    p_cred_sn: self.p_cred_sn,
+                    v_cred_icmssn: self.p_cred_sn.map(|p| centavos(base * p / CEM)),
+                    mod_bcst: st.as_ref().map(|_| mod_bcst),
+                    p_mvast: st.as_ref().map(|s| s.p_mvast),
+                    p_red_bcst: st.as_ref().and(self.p_red_bcst),
+                    v_bcst: st.as_ref().map(|s| s.v_bcst),
+                    p_icmsst: self.p_icmsst,
+                    v_icmsst: st.as_ref().map(|s| s.v_icmsst),
+                }, None, fcp_st)
+            }
+            outro => {
+                return Err(DfeError::Validacao(format!("CST/CSOSN {} não tem valores de ICMS a calcular", outro)));
+            }
+        };
+
+        det.icms = icms;
+        det.fcp = fcp;
+        det.fcp_st = fcp_st;
+        det.icms_uf_dest = self.difal.as_ref().map(|d| calcular_difal(d, operacao + ipi));
+        Ok(())
+    }
+
+    fn exigir(&self, valor: Option<Decimal>, campo: &str) -> Result<Decimal> {
+        valor.ok_or_else(|| DfeError::Validacao(format!("CST {}: {} é obrigatório", self.cst, campo)))
+    }
+
+    // FCP próprio sobre a BC do ICMS
+    fn fcp(&self, propria: &Propria) -> Option<Fcp> {
+        self.p_fcp.map(|p| Fcp { v_bc: propria.v_bc, p_fcp: p, v_fcp: centavos(propria.v_bc * p / CEM) })
+    }
+
+    // FCP-ST: sobre a BC ST, deduzido o FCP próprio
+    fn fcp_st(&self, st: &St, fcp: &Option<Fcp>) -> Option<Fcp> {
+        self.p_fcpst.map(|p| {
+            let proprio = fcp.map(|f| f.v_fcp).unwrap_or_default();
+            let v_fcp = (centavos(st.v_bcst * p / CEM) - proprio).max(Decimal::ZERO);
+            Fcp { v_bc: st.v_bcst, p_fcp: p, v_fcp }
+        })
+    }
+
+    fn st(&self, base: Decimal, p_icms: Option<Decimal>, p_icmsst: Decimal, deducao: Decimal) -> St {
+        let mut mva = self.p_mvast.unwrap_or_default() / CEM;
+        if self.mva_ajustada.unwrap_or(false) {
+            if let Some(p_icms) = p_icms {
+                mva = (Decimal::ONE + mva) * (Decimal::ONE - p_icms / CEM)
+                    / (Decimal::ONE - p_icmsst / CEM) - Decimal::ONE;
+            }
+        }
+        let mva = arredondar(mva * CEM, 4);
+        let reducao = Decimal::ONE - self.p_red_bcst.unwrap_or_default() / CEM;
+        let v_bcst = centavos(base * (Decimal::ONE + mva / CEM) * reducao);
+        let v_icmsst = (centavos(v_bcst * p_icmsst / CEM) - deducao).max(Decimal::ZERO);
+        St { p_mvast: mva, v_bcst, v_icmsst }
+    }
+}
+
+// ─── Partes do cálculo ───────────────────────────────────────────────────────
+
+const CEM: Decimal = Decimal::ONE_HUNDRED;
+
+struct Propria {
+    v_bc: Decimal,
+    v_icms: Decimal,
+}
+
+impl Propria {
+    fn calcular(base: Decimal, p_red_bc: Option<Decimal>, p_icms: Decimal) -> Self {
+        let v_bc = centavos(base * (Decimal::ONE - p_red_bc.unwrap_or_default() / CEM));
+        Propria { v_bc, v_icms: centavos(v_bc * p_icms / CEM) }
+    }
+}
+
+struct St {
+    p_mvast: Decimal,
+    v_bcst: Decimal,
+    v_icmsst: Decimal,
+}
+
+fn calcular_difal(d: &Difal, base: Decimal) -> IcmsUfDest {
+    let p_dest = d.p_icms_uf_dest;
+    let icms_origem = centavos(base * d.p_icms_inter / CEM);
+    let (v_bc_uf_dest, difal) = if d.base_dupla {
+        // Base "por dentro": tira o ICMS de origem e inclui o do destino
+        let v_bc = centavos((base - icms_origem) / (Decimal::ONE - p_dest / CEM));
+        (v_bc, centavos(v_bc * p_dest / CEM) - icms_origem)
+    } else {
+        (base, centavos(base * (p_dest - d.p_icms_inter) / CEM))
+    };
+    let difal = difal.max(Decimal::ZERO);
+    let v_icms_uf_dest = centavos(difal * d.p_icms_inter_part / CEM);
+    IcmsUfDest {
+        v_bc_uf_dest,
+        v_bc_fcp_uf_dest: d.p_fcp_uf_dest.map(|_| v_bc_uf_dest),
+        p_fcp_uf_dest: d.p_fcp_uf_dest,
+        p_icms_uf_dest: d.p_icms_uf_dest,
+        p_icms_inter: d.p_icms_inter,
+        p_icms_inter_part: d.p_icms_inter_part,
+        v_fcp_uf_dest: d.p_fcp_uf_dest.map(|p| centavos(v_bc_uf_dest * p / CEM)),
+        v_icms_uf_dest,
+        v_icms_uf_remet: difal - v_icms_uf_dest,
+    }
+}
+
+fn centavos(v: Decimal) -> Decimal {
+    arredondar(v, 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tipos::Ipi;
+    use rust_decimal_macros::dec;
+
+    fn item(v_prod: Decimal) -> Det {
+        Det { v_prod, ..Default::default() }
+    }
+
+    #[test]
+    fn test_cst00_com_frete_desconto_e_fcp() {
+        let mut det = Det { v_frete: Some(dec!(10.0)), v_desc: Some(dec!(5.0)), ..item(dec!(100.0)) };
+        CalculoIcms::cst(0, "00").p_icms(dec!(18.0)).p_fcp(dec!(2.0)).aplicar(&mut det).unwrap();
+
+        match det.icms {
+            Icms::Icms00 { v_bc, v_icms, mod_bc, .. } => {
+                assert_eq!((v_bc, v_icms, mod_bc), (dec!(105.0), dec!(18.9), 3));
+            }
+            ref outro => panic!("esperado Icms00: {:?}", outro),
+        }
+        assert_eq!(det.fcp, Some(Fcp { v_bc: dec!(105.0), p_fcp: dec!(2.0), v_fcp: dec!(2.1) }));
+    }
+
+    #[test]
+    fn test_cst20_reducao_de_base() {
+        let mut det = item(dec!(200.0));
+        CalculoIcms::cst(1, "20").p_icms(dec!(18.0)).p_red_bc(dec!(33.33)).aplicar(&mut det).unwrap();
+
+        match det.icms {
+            Icms::Icms20 { orig, v_bc, v_icms, p_red_bc, .. } => {
+                assert_eq!((orig, v_bc, v_icms, p_red_bc), (1, dec!(133.34), dec!(24.0), dec!(33.33)));
+            }
+            ref outro => panic!("esperado Icms20: {:?}", outro),
+        }
+    }
+
+    #[test]
+    fn test_cst10_mva_ajustada_ipi_e_fcp_st() {
+        let mut det = Det { ipi: Some(Ipi::tributado("999", dec!(1000.0), dec!(10.0), dec!(100.0))), ..item(dec!(1000.0)) };
+        CalculoIcms::cst(0, "10")
+            .p_icms(dec!(12.0))
+            .mva(dec!(40.0))
+            .mva_ajustada(true)
+            .p_icms_st(dec!(18.0))
+            .p_fcp_st(dec!(2.0))
+            .aplicar(&mut det)
+            .unwrap();
+
+        match det.icms {
+            Icms::Icms10 { v_bc, v_icms, p_mvast, v_bcst, v_icmsst, mod_bcst, .. } => {
+                // IPI fora da base própria, dentro da base ST
+                assert_eq!((v_bc, v_icms), (dec!(1000.0), dec!(120.0)));
+                // [(1,40 × 0,88) / 0,82] − 1 = 50,2439%
+                assert_eq!(p_mvast, dec!(50.2439));
+                assert_eq!(v_bcst, dec!(1652.68));
+                assert_eq!(v_icmsst, dec!(177.48));
+                assert_eq!(mod_bcst, 4);
+            }
+            ref outro => panic!("esperado Icms10: {:?}", outro),
+        }
+        assert_eq!(det.fcp_st, Some(Fcp { v_bc: dec!(1652.68), p_fcp: dec!(2.0), v_fcp: dec!(33.05) }));
+    }
+
+    #[test]
+    fn test_cst51_diferimento() {
+        let mut det = item(dec!(1000.0));
+        CalculoIcms::cst(0, "51").p_icms(dec!(18.0)).p_dif(dec!(33.33)).aplicar(&mut det).unwrap();
+
+        match det.icms {
+            Icms::Icms51 { v_icms_op, v_icms_dif, v_icms, .. } => {
+                assert_eq!((v_icms_op, v_icms_dif, v_icms), (Some(dec!(180.0)), Some(dec!(59.99)), Some(dec!(120.01))));
+            }
+            ref outro => panic!("esperado Icms51: {:?}", outro),
+        }
+    }
+
+    #[test]
+    fn test_difal_base_unica_e_dupla() {
+        let mut det = item(dec!(1000.0));
+        CalculoIcms::cst(0, "00")
+            .p_icms(dec!(12.0))
+            .difal(Difal::new(dec!(18.0), dec!(12.0)).p_fcp_uf_dest(dec!(2.0)))
+            .aplicar(&mut det)
+            .unwrap();
+        let uf = det.icms_uf_dest.unwrap();
+        assert_eq!((uf.v_bc_uf_dest, uf.v_icms_uf_dest, uf.v_icms_uf_remet), (dec!(1000.0), dec!(60.0), dec!(0.0)));
+        assert_eq!((uf.v_bc_fcp_uf_dest, uf.v_fcp_uf_dest), (Some(dec!(1000.0)), Some(dec!(20.0))));
+
+        let mut det = item(dec!(1000.0));
+        CalculoIcms::cst(0, "00")
+            .p_icms(dec!(12.0))
+            .difal(Difal::new(dec!(18.0), dec!(12.0)).base_dupla())
+            .aplicar(&mut det)
+            .unwrap();
+        let uf = det.icms_uf_dest.unwrap();
+        // (1000 − 120) / 0,82 = 1073,17 → 193,17 − 120
+        assert_eq!((uf.v_bc_uf_dest, uf.v_icms_uf_dest), (dec!(1073.17), dec!(73.17)));
+    }
+
+    #[test]
+    fn test_cst_sem_calculo_ou_aliquota_faltando() {
+        let mut det = item(dec!(100.0));
+        assert!(matches!(CalculoIcms::cst(0, "40").aplicar(&mut det), Err(DfeError::Validacao(_))));
+        assert!(matches!(CalculoIcms::cst(0, "10").p_icms(dec!(18.0)).aplicar(&mut det), Err(DfeError::Validacao(_))));
+        assert!(matches!(det.icms, Icms::Icms40 { .. }));
+    }
+}
@@ -32,7 +32,8 @@ pub struct Ide {
     pub c_mun_fg: String,
     /// Tipo de impressão: `1` = DANFE NF-e normal · `4` = DANFE NFC-e.
     pub tp_imp: u8,
-    /// Forma de emissão: `1` = Normal · `5` = Contingência EPEC.
+    /// Forma de emissão: `1` = Normal · `4` = Contingência EPEC · `5` = Contingência FS-DA ·
+    /// `6` = Contingência SVC-AN · `7` = Contingência SVC-RS · `9` = Contingência offline NFC-e.
     pub tp_emis: u8,
     /// Ambiente: `1` = Produção · `2` = Homologação.
     pub tp_amb: u8,
@@ -46,6 +47,10 @@ pub struct Ide {
     pub proc_emi: u8,
     /// Versão do processo de emissão (ex.: `"1.0.0"`).
     pub ver_proc: String,
+    /// Data e hora de entrada em contingência (ISO 8601). Obrigatória se `tp_emis != 1`.
+    pub dh_cont: Option<String>,
+    /// Justificativa da contingência (15–256 caracteres). Obrigatória se `tp_emis != 1`.
+    pub x_just: Option<String>,
 }
 
 impl Default for Ide {
@@ -71,6 +76,8 @@ impl Default for Ide {
             ind_pres: 1,
             proc_emi: 0,
             ver_proc: "1.0.0".to_string(),
+            dh_cont: None,
+            x_just: None,
         }
     }
 }
@@ -188,32 +195,32 @@ impl Default for Emit {
 pub enum Icms {
     // ── Regime Normal (CRT=3) ─────────────────────────────────────────────────
     /// CST 00 — Tributada integralmente
-    Icms00 { orig: u8, mod_bc: u8, v_bc: f64, p_icms: f64, v_icms: f64 },
+    Icms00 { orig: u8, mod_bc: u8, v_bc: Decimal, p_icms: Decimal, v_icms: Decimal },
 
     /// CST 10 — Tributada e com cobrança do ICMS por substituição tributária
     Icms10 {
         orig: u8,
         mod_bc: u8,
-        v_bc: f64,
-        p_icms: f64,
-        v_icms: f64,
+        v_bc: Decimal,
+        p_icms: Decimal,
+        v_icms: Decimal,
         mod_bcst: u8,
-        p_mvast: f64,
-        p_red_bcst: Option<f64>,
-        v_bcst: f64,
-        p_icmsst: f64,
-        v_icmsst: f64,
+        p_mvast: Decimal,
+        p_red_bcst: Option<Decimal>,
+        v_bcst: Decimal,
+        p_icmsst: Decimal,
+        v_icmsst: Decimal,
     },
 
     /// CST 20 — Com redução de base de cálculo
     Icms20 {
         orig: u8,
         mod_bc: u8,
-        p_red_bc: f64,
-        v_bc: f64,
-        p_icms: f64,
-        v_icms: f64,
-        v_icms_deson: Option<f64>,
+        p_red_bc: Decimal,
+        v_bc: Decimal,
+        p_icms: Decimal,
+        v_icms: Decimal,
+        v_icms_deson: Option<Decimal>,
         mot_des_icms: Option<u16>,
     },
 
@@ -221,55 +228,55 @@ pub enum Icms {
     Icms30 {
         orig: u8,
         mod_bcst: u8,
-        p_mvast: f64,
-        p_red_bcst: Option<f64>,
-        v_bcst: f64,
-        p_icmsst: f64,
-        v_icmsst: f64,
-        v_icms_deson: Option<f64>,
+        p_mvast: Decimal,
+        p_red_bcst: Option<Decimal>,
+        v_bcst: Decimal,
+        p_icmsst: Decimal,
+        v_icmsst: Decimal,
+        v_icms_deson: Option<Decimal>,
         mot_des_icms: Option<u16>,
     },
 
     /// CST 40=Isenta | 41=Não tributada | 50=Suspensão
-    Icms40 { orig: u8, cst: u16, v_icms_deson: Option<f64>, mot_des_icms: Option<u16> },
+    Icms40 { orig: u8, cst: u16, v_icms_deson: Option<Decimal>, mot_des_icms: Option<u16> },
 
     /// CST 51 — Diferimento total ou parcial (todos os campos opcionais)
     Icms51 {
         orig: u8,
         mod_bc: Option<u8>,
-        p_red_bc: Option<f64>,
-        v_bc: Option<f64>,
-        p_icms: Option<f64>,
-        v_icms_op: Option<f64>,
-        p_dif: Option<f64>,
-        v_icms_dif: Option<f64>,
-        v_icms: Option<f64>,
+        p_red_bc: Option<Decimal>,
+        v_bc: Option<Decimal>,
+        p_icms: Option<Decimal>,
+        v_icms_op: Option<Decimal>,
+        p_dif: Option<Decimal>,
+        v_icms_dif: Option<Decimal>,
+        v_icms: Option<Decimal>,
     },
 
     /// CST 60 — ICMS-ST retido anteriormente
     Icms60 {
         orig: u8,
-        v_bcst_ret: Option<f64>,
-        p_st: Option<f64>,
-        v_icms_substituto: Option<f64>,
-        v_icmsst_ret: Option<f64>,
+        v_bcst_ret: Option<Decimal>,
+        p_st: Option<Decimal>,
+        v_icms_substituto: Option<Decimal>,
+        v_icmsst_ret: Option<Decimal>,
     },
 
     /// CST 70 — Com redução de BC e cobrança do ICMS por ST
     Icms70 {
         orig: u8,
         mod_bc: u8,
-        p_red_bc: Option<f64>,
-        v_bc: f64,
-        p_icms: f64,
-        v_icms: f64,
+        p_red_bc: Option<Decimal>,
+        v_bc: Decimal,
+        p_icms: Decimal,
+        v_icms: Decimal,
         mod_bcst: u8,
-        p_mvast: f64,
-        p_red_bcst: Option<f64>,
-        v_bcst: f64,
-        p_icmsst: f64,
-        v_icmsst: f64,
-        v_icms_deson: Option<f64>,
+        p_mvast: Decimal,
+        p_red_bcst: Option<Decimal>,
+        v_bcst: Decimal,
+        p_icmsst: Decimal,
+        v_icmsst: Decimal,
+        v_icms_deson: Option<Decimal>,
         mot_des_icms: Option<u16>,
     },
 
@@ -277,70 +284,70 @@ pub enum Icms {
     Icms90 {
         orig: u8,
         mod_bc: Option<u8>,
-        p_red_bc: Option<f64>,
-        v_bc: Option<f64>,
-        p_icms: Option<f64>,
-        v_icms: Option<f64>,
+        p_red_bc: Option<Decimal>,
+        v_bc: Option<Decimal>,
+        p_icms: Option<Decimal>,
+        v_icms: Option<Decimal>,
         mod_bcst: Option<u8>,
-        p_mvast: Option<f64>,
-        p_red_bcst: Option<f64>,
-        v_bcst: Option<f64>,
-        p_icmsst: Option<f64>,
-        v_icmsst: Option<f64>,
-        v_icms_deson: Option<f64>,
+        p_mvast: Option<Decimal>,
+        p_red_bcst: Option<Decimal>,
+        v_bcst: Option<Decimal>,
+        p_icmsst: Option<Decimal>,
+        v_icmsst: Option<Decimal>,
+        v_icms_deson: Option<Decimal>,
         mot_des_icms: Option<u16>,
     },
 
     // ── Simples Nacional (CRT=1) ──────────────────────────────────────────────
     /// CSOSN 101 — tributada com crédito
-    Sn101 { orig: u8, p_cred_sn: f64, v_cred_icmssn: f64 },
+    Sn101 { orig: u8, p_cred_sn: Decimal, v_cred_icmssn: Decimal },
     /// CSOSN 102/103/300/400
     Sn102 { orig: u8, csosn: String },
     /// CSOSN 500 — ST retido anteriormente
-    Sn500 { orig: u8, v_bcst_ret: Option<f64>, v_icmsst_ret: Option<f64> },
+    Sn500 { orig: u8, v_bcst_ret: Option<Decimal>, v_icmsst_ret: Option<Decimal> },
     /// CSOSN 900 — outros; inclui campos opcionais de cálculo e ST
     Sn900 {
         orig: u8,
         mod_bc: Option<u8>,
-        v_bc: Option<f64>,
-        p_red_bc: Option<f64>,
-        p_icms: Option<f64>,
-        v_icms: Option<f64>,
-        p_cred_sn: Option<f64>,
-        v_cred_icmssn: Option<f64>,
+        v_bc: Option<Decimal>,
+        p_red_bc: Option<Decimal>,
+        p_icms: Option<Decimal>,
+        v_icms: Option<Decimal>,
+        p_cred_sn: Option<Decimal>,
+        v_cred_icmssn: Option<Decimal>,
         // campos ST opcionais
         mod_bcst: Option<u8>,
-        p_mvast: Option<f64>,
-        p_red_bcst: Option<f64>,
-        v_bcst: Option<f64>,
-        p_icmsst: Option<f64>,
-        v_icmsst: Option<f64>,
+        p_mvast: Option<Decimal>,
+        p_red_bcst: Option<Decimal>,
+        v_bcst: Option<Decimal>,
+        p_icmsst: Option<Decimal>,
+        v_icmsst: Option<Decimal>,
     },
 }
 
 impl Icms {
     // ── Regime Normal (CRT=3) ────────────────────────────────────────────────
 
-    pub fn icms00(orig: u8, mod_bc: u8, v_bc: f64, p_icms: f64, v_icms: f64) -> Self {
+    pub fn icms00(orig: u8, mod_bc: u8, v_bc: Decimal, p_icms: Decimal, v_icms: Decimal) -> Self {
         Icms::Icms00 { orig, mod_bc, v_bc, p_icms, v_icms }
     }
 
     /// CST 10 — ICMS próprio + ST; `p_red_bcst` opcional
-    pub fn icms10(orig: u8, mod_bc: u8, v_bc: f64, p_icms: f64, v_icms: f64,
-                  mod_bcst: u8, p_mvast: f64, v_bcst: f64, p_icmsst: f64, v_icmsst: f64) -> Self {
+    pub fn icms10(orig: u8, mod_bc: u8, v_bc: Decimal, p_icms: Decimal, v_icms: Decimal,
+                  mod_bcst: u8, p_mvast: Decimal, v_bcst: Decimal, p_icmsst: Decimal, v_icmsst: Decimal) -> Self {
         Icms::Icms10 { orig, mod_bc, v_bc, p_icms, v_icms,
                        mod_bcst, p_mvast, p_red_bcst: None, v_bcst, p_icmsst, v_icmsst }
     }
 
     /// CST 20 — BC reduzida; campos de desoneração opcionais
-    pub fn icms20(orig: u8, mod_bc: u8, p_red_bc: f64, v_bc: f64, p_icms: f64, v_icms: f64) -> Self {
+    pub fn icms20(orig: u8, mod_bc: u8, p_red_bc: Decimal, v_bc: Decimal, p_icms: Decimal, v_icms: Decimal) -> Self {
         Icms::Icms20 { orig, mod_bc, p_red_bc, v_bc, p_icms, v_icms,
                        v_icms_deson: None, mot_des_icms: None }
     }
 
     /// CST 30 — Isenta/NT + ST; campos de desoneração opcionais
-    pub fn icms30(orig: u8, mod_bcst: u8, p_mvast: f64, v_bcst: f64,
-                  p_icmsst: f64, v_icmsst: f64) -> Self {
+    pub fn icms30(orig: u8, mod_bcst: u8, p_mvast: Decimal, v_bcst: Decimal,
+                  p_icmsst: Decimal, v_icmsst: Decimal) -> Self {
         Icms::Icms30 { orig, mod_bcst, p_mvast, p_red_bcst: None,
                        v_bcst, p_icmsst, v_icmsst, v_icms_deson: None, mot_des_icms: None }
     }
@@ -362,8 +369,8 @@ impl Icms {
     }
 
     /// CST 70 — BC reduzida + ST; `p_red_bc` e `p_red_bcst` opcionais
-    pub fn icms70(orig: u8, mod_bc: u8, v_bc: f64, p_icms: f64, v_icms: f64,
-                  mod_bcst: u8, p_mvast: f64, v_bcst: f64, p_icmsst: f64, v_icmsst: f64) -> Self {
+    pub fn icms70(orig: u8, mod_bc: u8, v_bc: Decimal, p_icms: Decimal, v_icms: Decimal,
+                  mod_bcst: u8, p_mvast: Decimal, v_bcst: Decimal, p_icmsst: Decimal, v_icmsst: Decimal) -> Self {
         Icms::Icms70 { orig, mod_bc, p_red_bc: None, v_bc, p_icms, v_icms,
                        mod_bcst, p_mvast, p_red_bcst: None, v_bcst, p_icmsst, v_icmsst,
                        v_icms_deson: None, mot_des_icms: None }
@@ -380,7 +387,7 @@ impl Icms {
     // ── Simples Nacional (CRT=1) ─────────────────────────────────────────────
 
     /// CSOSN 101 — tributada com crédito
-    pub fn sn101(orig: u8, p_cred_sn: f64, v_cred_icmssn: f64) -> Self {
+    pub fn sn101(orig: u8, p_cred_sn: Decimal, v_cred_icmssn: Decimal) -> Self {
         Icms::Sn101 { orig, p_cred_sn, v_cred_icmssn }
     }
 
@@ -410,16 +417,16 @@ impl Icms {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Pis {
     /// CST 01/02 — tributada por alíquota
-    Aliq { cst: String, v_bc: f64, p_pis: f64, v_pis: f64 },
+    Aliq { cst: String, v_bc: Decimal, p_pis: Decimal, v_pis: Decimal },
     /// CST 99 — outros (zeros automáticos)
     Outr,
     /// CST 04-09 — não tributado/isento/suspenso
     Nt { cst: String },
     /// CST 03 — tributada por quantidade
-    Qtde { cst: String, q_bc_prod: f64, v_aliq_prod: f64, v_pis: f64 },
+    Qtde { cst: String, q_bc_prod: Decimal, v_aliq_prod: Decimal, v_pis: Decimal },
     /// CST 05 — substituição tributária
     /// Use `v_bc + p_pis` OU `q_bc_prod + v_aliq_prod` (os outros ficam `None`)
-    St { v_bc: Option<f64>, p_pis: Option<f64>, q_bc_prod: Option<f64>, v_aliq_prod: Option<f64>, v_pis: f64 },
+    St { v_bc: Option<Decimal>, p_pis: Option<Decimal>, q_bc_prod: Option<Decimal>, v_aliq_prod: Option<Decimal>, v_pis: Decimal },
 }
 
 // ─── Cofins ───────────────────────────────────────────────────────────────────
@@ -427,15 +434,15 @@ pub enum Pis {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Cofins {
     /// CST 01/02 — tributada por alíquota
-    Aliq { cst: String, v_bc: f64, p_cofins: f64, v_cofins: f64 },
+    Aliq { cst: String, v_bc: Decimal, p_cofins: Decimal, v_cofins: Decimal },
     /// CST 99 — outros
     Outr { cst: String },
     /// CST 04-09 — não tributado/isento/suspenso
     Nt { cst: String },
     /// CST 03 — tributada por quantidade
-    Qtde { cst: String, q_bc_prod: f64, v_aliq_prod: f64, v_cofins: f64 },
+    Qtde { cst: String, q_bc_prod: Decimal, v_aliq_prod: Decimal, v_cofins: Decimal },
     /// CST 05 — substituição tributária
-    St { v_bc: Option<f64>, p_cofins: Option<f64>, q_bc_prod: Option<f64>, v_aliq_prod: Option<f64>, v_cofins: f64 },
+    St { v_bc: Option<Decimal>, p_cofins: Option<Decimal>, q_bc_prod: Option<Decimal>, v_aliq_prod: Option<Decimal>, v_cofins: Decimal },
 }
 
 // ─── Ipi ──────────────────────────────────────────────────────────────────────
@@ -447,15 +454,15 @@ pub struct Ipi {
     /// CST IPI: "50"=saída tributada, "53"=saída NT, etc.
     pub cst: String,
     /// Base de cálculo por valor (exclusivo com q_bc_prod)
-    pub v_bc: Option<f64>,
+    pub v_bc: Option<Decimal>,
     /// Alíquota ad valorem (usado junto com v_bc)
-    pub p_ipi: Option<f64>,
+    pub p_ipi: Option<Decimal>,
     /// Base de cálculo por quantidade (exclusivo com v_bc)
-    pub q_bc_prod: Option<f64>,
+    pub q_bc_prod: Option<Decimal>,
     /// Alíquota por unidade de medida (usado junto com q_bc_prod)
-    pub v_aliq_prod: Option<f64>,
+    pub v_aliq_prod: Option<Decimal>,
     /// Valor do IPI; obrigatório para CST de saída tributada ("50", "99")
-    pub v_ipi: Option<f64>,
+    pub v_ipi: Option<Decimal>,
     /// Código do selo de controle IPI
     pub c_selo: Option<String>,
     /// Quantidade de selos
@@ -464,7 +471,7 @@ pub struct Ipi {
 
 impl Ipi {
     /// CST 50 — saída tributada por alíquota ad valorem
-    pub fn tributado(c_enq: &str, v_bc: f64, p_ipi: f64, v_ipi: f64) -> Self {
+    pub fn tributado(c_enq: &str, v_bc: Decimal, p_ipi: Decimal, v_ipi: Decimal) -> Self {
         Ipi { c_enq: c_enq.to_string(), cst: "50".to_string(),
               v_bc: Some(v_bc), p_ipi: Some(p_ipi), v_ipi: Some(v_ipi),
               q_bc_prod: None, v_aliq_prod: None, c_selo: None, q_selo: None }
@@ -493,18 +500,356 @@ pub struct IbsCbs {
     pub v_cbs: Decimal,
 }
 
+// ─── Fcp ──────────────────────────────────────────────────────────────────────
+
+/// Fundo de Combate à Pobreza de um item: próprio (`vBCFCP`/`pFCP`/`vFCP`)
+/// ou retido por ST (`vBCFCPST`/`pFCPST`/`vFCPST`).
+///
+/// Vai dentro do grupo ICMS do item (CST 00, 10, 20, 30, 51, 70, 90 e CSOSN 900);
+/// nos demais é ignorado. No CST 00 só `p_fcp` e `v_fcp` são enviados.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fcp {
+    /// Base de cálculo do FCP.
+    pub v_bc: Decimal,
+    /// Percentual do FCP.
+    pub p_fcp: Decimal,
+    /// Valor do FCP.
+    pub v_fcp: Decimal,
+}
+
+// ─── IcmsUfDest ───────────────────────────────────────────────────────────────
+
+/// Partilha do ICMS interestadual para consumidor final não contribuinte (`<ICMSUFDest>`, DIFAL).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IcmsUfDest {
+    /// BC do ICMS na UF de destino.
+    pub v_bc_uf_dest: Decimal,
+    /// BC do FCP na UF de destino.
+    pub v_bc_fcp_uf_dest: Option<Decimal>,
+    /// Percentual do FCP na UF de destino.
+    pub p_fcp_uf_dest: Option<Decimal>,
+    /// Alíquota interna da UF de destino.
+    pub p_icms_uf_dest: Decimal,
+    /// Alíquota interestadual: `4`, `7` ou `12`.
+    pub p_icms_inter: Decimal,
+    /// Percentual de partilha para a UF de destino (`100` a partir de 2019).
+    pub p_icms_inter_part: Decimal,
+    /// Valor do FCP da UF de destino.
+    pub v_fcp_uf_dest: Option<Decimal>,
+    /// ICMS de partilha para a UF de destino.
+    pub v_icms_uf_dest: Decimal,
+    /// ICMS de partilha para a UF do remetente.
+    pub v_icms_uf_remet: Decimal,
+}
+
+// ─── Grupos específicos do produto ────────────────────────────────────────────
+
+/// Rastreabilidade do produto (`<rastro>`), obrigatória para produtos sujeitos
+/// a regulações sanitárias (medicamentos, alimentos, bebidas...).
+///
+/// Um item aceita até 500 lotes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rastro {
+    /// Número do lote (1–20 caracteres).
+    pub n_lote: String,
+    /// Quantidade de produto no lote (até 3 casas decimais).
+    pub q_lote: Decimal,
+    /// Data de fabricação/produção (`AAAA-MM-DD`).
+    pub d_fab: String,
+    /// Data de validade (`AAAA-MM-DD`).
+    pub d_val: String,
+    /// Código de agregação. Opcional.
+    pub c_agreg: Option<String>,
+}
+
+/// Veículo novo (`<veicProd>`), conforme tabelas do RENAVAM/DENATRAN.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VeicProd {
+    /// Tipo da operação: `0` = Outros · `1` = Venda concessionária ·
+    /// `2` = Faturamento direto · `3` = Venda direta.
+    pub tp_op: u8,
+    /// Chassi do veículo (VIN, 17 caracteres).
+    pub chassi: String,
+    /// Código da cor da montadora (até 4 caracteres).
+    pub c_cor: String,
+    /// Descrição da cor (até 40 caracteres).
+    pub x_cor: String,
+    /// Potência do motor em CV.
+    pub pot: String,
+    /// Cilindradas.
+    pub cilin: String,
+    /// Peso líquido.
+    pub peso_l: String,
+    /// Peso bruto.
+    pub peso_b: String,
+    /// Número de série.
+    pub n_serie: String,
+    /// Tipo de combustível (tabela RENAVAM, ex.: `"16"` = Álcool/Gasolina).
+    pub tp_comb: String,
+    /// Número do motor.
+    pub n_motor: String,
+    /// Capacidade máxima de tração, em toneladas.
+    pub cmt: String,
+    /// Distância entre eixos.
+    pub dist: String,
+    /// Ano modelo.
+    pub ano_mod: u16,
+    /// Ano de fabricação.
+    pub ano_fab: u16,
+    /// Tipo de pintura (1 caractere).
+    pub tp_pint: String,
+    /// Tipo de veículo (tabela RENAVAM).
+    pub tp_veic: u8,
+    /// Espécie de veículo (tabela RENAVAM).
+    pub esp_veic: u8,
+    /// Condição do VIN: `"R"` = Remarcado · `"N"` = Normal.
+    pub vin: String,
+    /// Condição do veículo: `1` = Acabado · `2` = Inacabado · `3` = Semiacabado.
+    pub cond_veic: u8,
+    /// Código marca/modelo (tabela RENAVAM).
+    pub c_mod: String,
+    /// Código da cor DENATRAN (`01` a `16`).
+    pub c_cor_denatran: String,
+    /// Capacidade máxima de lotação (passageiros).
+    pub lota: u16,
+    /// Restrição: `0` = Não há · `1` = Alienação fiduciária · `2` = Arrendamento
+    /// mercantil · `3` = Reserva de domínio · `4` = Penhor · `9` = Outras.
+    pub tp_rest: u8,
+}
+
+/// Medicamento ou matéria-prima farmacêutica (`<med>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Med {
+    /// Código de registro na ANVISA (11 ou 13 dígitos) ou `"ISENTO"`.
+    pub c_prod_anvisa: String,
+    /// Motivo da isenção do registro, quando `c_prod_anvisa` é `"ISENTO"`.
+    pub x_motivo_isencao: Option<String>,
+    /// Preço máximo ao consumidor.
+    pub v_pmc: Decimal,
+}
+
+/// Arma de fogo (`<arma>`). Um item aceita até 500 armas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Arma {
+    /// Tipo de arma: `0` = Uso permitido · `1` = Uso restrito.
+    pub tp_arma: u8,
+    /// Número de série da arma (até 15 caracteres).
+    pub n_serie: String,
+    /// Número de série do cano (até 15 caracteres).
+    pub n_cano: String,
+    /// Descrição completa da arma (calibre, marca, capacidade...).
+    pub descr: String,
+}
+
+/// Combustível (`<comb>`), conforme tabela de produtos da ANP.
+///
+/// ```
+/// use dfe::tipos::{Comb, Encerrante, OrigComb};
+/// use rust_decimal_macros::dec;
+///
+/// let comb = Comb {
+///     c_prod_anp: "320102001".into(),
+///     desc_anp: "GASOLINA C COMUM".into(),
+///     uf_cons: "SP".into(),
+///     encerrante: Some(Encerrante {
+///         n_bico: 1,
+///         n_bomba: Some(1),
+///         n_tanque: 2,
+///         v_enc_ini: dec!(1500.000),
+///         v_enc_fin: dec!(1540.000),
+///     }),
+///     orig_comb: vec![OrigComb { ind_import: 0, c_uf_orig: 35, p_orig: dec!(100) }],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Comb {
+    /// Código de produto da ANP (9 dígitos).
+    pub c_prod_anp: String,
+    /// Descrição do produto conforme a ANP.
+    pub desc_anp: String,
+    /// Percentual de GLP derivado do petróleo no GLP.
+    pub p_glp: Option<Decimal>,
+    /// Percentual de gás natural nacional no GLP.
+    pub p_gn_n: Option<Decimal>,
+    /// Percentual de gás natural importado no GLP.
+    pub p_gn_i: Option<Decimal>,
+    /// Valor de partida (apenas GLP).
+    pub v_part: Option<Decimal>,
+    /// Código de autorização/registro do CODIF (até 21 dígitos).
+    pub codif: Option<String>,
+    /// Quantidade faturada à temperatura ambiente.
+    pub q_temp: Option<Decimal>,
+    /// Sigla da UF de consumo.
+    pub uf_cons: String,
+    /// CIDE combustíveis.
+    pub cide: Option<Cide>,
+    /// Encerrante da bomba (obrigatório no varejo de combustível).
+    pub encerrante: Option<Encerrante>,
+    /// Percentual do índice de mistura do biodiesel.
+    pub p_bio: Option<Decimal>,
+    /// Origem do combustível (até 30 ocorrências).
+    #[serde(default)]
+    pub orig_comb: Vec<OrigComb>,
+}
+
+/// CIDE sobre combustíveis (`<comb><CIDE>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cide {
+    /// Base de cálculo (quantidade).
+    pub q_bc_prod: Decimal,
+    /// Alíquota em reais.
+    pub v_aliq_prod: Decimal,
+    /// Valor da CIDE.
+    pub v_cide: Decimal,
+}
+
+/// Encerrante da bomba de combustível (`<comb><encerrante>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Encerrante {
+    /// Número do bico.
+    pub n_bico: u16,
+    /// Número da bomba. Opcional.
+    pub n_bomba: Option<u16>,
+    /// Número do tanque.
+    pub n_tanque: u16,
+    /// Leitura do encerrante no início do abastecimento.
+    pub v_enc_ini: Decimal,
+    /// Leitura do encerrante no final do abastecimento.
+    pub v_enc_fin: Decimal,
+}
+
+/// Origem do combustível (`<comb><origComb>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrigComb {
+    /// Indicador de importação: `0` = Nacional · `1` = Importado.
+    pub ind_import: u8,
+    /// Código IBGE da UF de origem do produtor ou importador.
+    pub c_uf_orig: u16,
+    /// Percentual originário da UF.
+    pub p_orig: Decimal,
+}
+
+// ─── Comércio exterior ────────────────────────────────────────────────────────
+
+/// Declaração de Importação do item (`<DI>`): DI, DSI, DIRE ou DUImp.
+///
+/// Obrigatória nos CFOP de importação (3xxx), exceto 3201, 3202, 3503 e 3553.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Di {
+    /// Número do documento de importação (até 15 caracteres).
+    pub n_di: String,
+    /// Data de registro do documento (`AAAA-MM-DD`).
+    pub d_di: String,
+    /// Local do desembaraço aduaneiro.
+    pub x_loc_desemb: String,
+    /// UF onde ocorreu o desembaraço aduaneiro.
+    pub uf_desemb: String,
+    /// Data do desembaraço aduaneiro (`AAAA-MM-DD`).
+    pub d_desemb: String,
+    /// Via de transporte internacional: `1` = Marítima · `2` = Fluvial · `3` = Lacustre ·
+    /// `4` = Aérea · `5` = Postal · `6` = Ferroviária · `7` = Rodoviária · `8` = Conduto ·
+    /// `9` = Meios próprios · `10` = Entrada/saída ficta · `11` = Courier · `12` = Em mãos ·
+    /// `13` = Por reboque.
+    pub tp_via_transp: u8,
+    /// Adicional ao frete para renovação da marinha mercante — obrigatório na via marítima.
+    pub v_afrmm: Option<Decimal>,
+    /// Forma de importação: `1` = Por conta própria · `2` = Por conta e ordem · `3` = Encomenda.
+    pub tp_intermedio: u8,
+    /// CNPJ do adquirente ou encomendante (`tp_intermedio` 2 ou 3).
+    pub cnpj: Option<String>,
+    /// CPF do adquirente ou encomendante, no lugar do `cnpj`.
+    pub cpf: Option<String>,
+    /// UF do adquirente ou encomendante (`tp_intermedio` 2 ou 3).
+    pub uf_terceiro: Option<String>,
+    /// Código do exportador no sistema do emitente.
+    pub c_exportador: String,
+    /// Adições da declaração (ao menos uma).
+    pub adi: Vec<Adi>,
+}
+
+/// Adição da Declaração de Importação (`<DI><adi>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Adi {
+    /// Número da adição. Opcional na DUImp.
+    pub n_adicao: Option<u16>,
+    /// Número sequencial do item dentro da adição.
+    pub n_seq_adic: u32,
+    /// Código do fabricante estrangeiro no sistema do emitente.
+    pub c_fabricante: String,
+    /// Desconto do item na DI.
+    pub v_desc_di: Option<Decimal>,
+    /// Número do ato concessório de drawback — obrigatório nos CFOP 3127 e 3211.
+    pub n_draw: Option<String>,
+}
+
+/// Detalhe da exportação do item (`<detExport>`). Só em operação com o exterior (`id_dest` 3).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DetExport {
+    /// Número do ato concessório de drawback — obrigatório nos CFOP 7127 e 7211.
+    pub n_draw: Option<String>,
+    /// Exportação indireta — obrigatória nos CFOP 3503 e 7501.
+    pub export_ind: Option<ExportInd>,
+}
+
+/// Exportação indireta (`<detExport><exportInd>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportInd {
+    /// Número do registro de exportação (até 12 dígitos).
+    pub n_re: String,
+    /// Chave de acesso da NF-e recebida para exportação (também referenciada em `NFref`).
+    pub ch_nfe: String,
+    /// Quantidade do item efetivamente exportada.
+    pub q_export: Decimal,
+}
+
+/// Imposto de Importação do item (`<imposto><II>`). Não é aceito na NFC-e.
+///
+/// O `vII` de cada item é somado em `<ICMSTot><vII>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ii {
+    /// Base de cálculo do II.
+    pub v_bc: Decimal,
+    /// Despesas aduaneiras.
+    pub v_desp_adu: Decimal,
+    /// Valor do Imposto de Importação.
+    pub v_ii: Decimal,
+    /// Valor do IOF.
+    pub v_iof: Decimal,
+}
+
+/// Local de embarque ou de transposição de fronteira da exportação (`<exporta>`).
+///
+/// Obrigatório na NF-e de saída com destino ao exterior (`tp_nf` 1 e `id_dest` 3) e
+/// recusado nas demais. Informe com [`NFeBuilder::exportacao`](crate::NFeBuilder::exportacao).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Exporta {
+    /// UF de embarque ou de transposição de fronteira.
+    pub uf_saida_pais: String,
+    /// Local de embarque ou de transposição de fronteira.
+    pub x_loc_exporta: String,
+    /// Local de despacho (recinto alfandegado). Opcional.
+    pub x_loc_despacho: Option<String>,
+}
+
 // ─── Det ──────────────────────────────────────────────────────────────────────
 
 /// Dados de um item da NF-e (`<det>`).
 ///
 /// Os totais de ICMS, PIS e COFINS são **calculados automaticamente** pelo builder
 /// a partir dos valores declarados em `icms`, `pis` e `cofins`.
+/// Para calcular os próprios `icms`, `fcp`, `fcp_st` e `icms_uf_dest` a partir de
+/// alíquotas, veja [`CalculoIcms`](crate::tipos::CalculoIcms).
 /// Use `..Default::default()` para preencher os campos opcionais com zeros/`None`.
 ///
+/// Valores, quantidades e alíquotas são [`Decimal`]: os cálculos são exatos e cada
+/// campo vai ao XML com as casas decimais do leiaute, arredondado meio para longe do zero.
+///
 /// # Exemplo
 ///
 /// ```
 /// use dfe::tipos::{Det, Icms, Pis, Cofins};
+/// use rust_decimal_macros::dec;
 ///
 /// let item = Det {
 ///     c_prod: "001".into(),
@@ -512,9 +857,9 @@ pub struct IbsCbs {
 ///     ncm: "22030000".into(),
 ///     cfop: 5102,
 ///     u_com: "UN".into(),
-///     q_com: 2.0,
-///     v_un_com: 50.0,
-///     v_prod: 100.0,
+///     q_com: dec!(2),
+///     v_un_com: dec!(50.00),
+///     v_prod: dec!(100.00),
 ///     icms: Icms::sn102(0, "400"),
 ///     pis: Pis::Nt { cst: "07".into() },
 ///     cofins: Cofins::Nt { cst: "07".into() },
@@ -532,17 +877,17 @@ pub struct Det {
     pub cest: Option<String>,
     pub cfop: u16,
     pub u_com: String,
-    pub q_com: f64,
-    pub v_un_com: f64,
-    pub v_prod: f64,
+    pub q_com: Decimal,
+    pub v_un_com: Decimal,
+    pub v_prod: Decimal,
     pub c_ean_trib: String,
     pub u_trib: String,
-    pub q_trib: f64,
-    pub v_un_trib: f64,
-    pub v_frete: Option<f64>,
-    pub v_seg: Option<f64>,
-    pub v_desc: Option<f64>,
-    pub v_outro: Option<f64>,
+    pub q_trib: Decimal,
+    pub v_un_trib: Decimal,
+    pub v_frete: Option<Decimal>,
+    pub v_seg: Option<Decimal>,
+    pub v_desc: Option<Decimal>,
+    pub v_outro: Option<Decimal>,
     pub ind_tot: u8,
     pub x_ped: Option<String>,
     pub n_item_ped: Option<String>,
@@ -550,9 +895,35 @@ pub struct Det {
     pub ipi: Option<Ipi>,
     pub pis: Pis,
     pub cofins: Cofins,
-    pub v_tot_trib: f64,
+    pub v_tot_trib: Decimal,
     pub inf_ad_prod: Option<String>,
     pub ibs_cbs: Option<IbsCbs>,
+    /// FCP próprio do item.
+    pub fcp: Option<Fcp>,
+    /// FCP retido por substituição tributária.
+    pub fcp_st: Option<Fcp>,
+    /// DIFAL do item (`<ICMSUFDest>`).
+    pub icms_uf_dest: Option<IcmsUfDest>,
+    /// Lotes do produto (`<rastro>`).
+    #[serde(default)]
+    pub rastro: Vec<Rastro>,
+    /// Veículo novo. Exclusivo com `med`, `arma` e `comb`.
+    pub veic_prod: Option<VeicProd>,
+    /// Medicamento. Exclusivo com `veic_prod`, `arma` e `comb`.
+    pub med: Option<Med>,
+    /// Armas de fogo. Exclusivo com `veic_prod`, `med` e `comb`.
+    #[serde(default)]
+    pub arma: Vec<Arma>,
+    /// Combustível. Exclusivo com `veic_prod`, `med` e `arma`.
+    pub comb: Option<Comb>,
+    /// Declarações de importação (`<DI>`), nos CFOP 3xxx.
+    #[serde(default)]
+    pub di: Vec<Di>,
+    /// Detalhes da exportação (`<detExport>`), nos CFOP 7xxx.
+    #[serde(default)]
+    pub det_export: Vec<DetExport>,
+    /// Imposto de Importação (`<II>`). Somado automaticamente em `Total.v_ii`.
+    pub ii: Option<Ii>,
 }
 
 impl Default for Det {
@@ -567,13 +938,13 @@ impl Default for Det {
             cest: None,
             cfop: 5102,
             u_com: "".to_string(),
-            q_com: 0.0,
-            v_un_com: 0.0,
-            v_prod: 0.0,
+            q_com: Decimal::ZERO,
+            v_un_com: Decimal::ZERO,
+            v_prod: Decimal::ZERO,
             c_ean_trib: "SEM GTIN".to_string(),
             u_trib: "UN".to_string(),
-            q_trib: 0.0,
-            v_un_trib: 0.0,
+            q_trib: Decimal::ZERO,
+            v_un_trib: Decimal::ZERO,
             v_frete: None,
             v_seg: None,
             v_desc: None,
@@ -585,9 +956,20 @@ impl Default for Det {
             ipi: None,
             pis: Pis::Outr,
             cofins: Cofins::Outr { cst: "99".to_string() },
-            v_tot_trib: 0.0,
+            v_tot_trib: Decimal::ZERO,
             inf_ad_prod: None,
             ibs_cbs: None,
+            fcp: None,
+            fcp_st: None,
+            icms_uf_dest: None,
+            rastro: Vec::new(),
+            veic_prod: None,
+            med: None,
+            arma: Vec::new(),
+            comb: None,
+            di: Vec::new(),
+            det_export: Vec::new(),
+            ii: None,
         }
     }
 }
@@ -602,48 +984,50 @@ impl Default for Det {
 ///
 /// ```
 /// use dfe::tipos::Total;
+/// use rust_decimal_macros::dec;
 ///
 /// // Venda simples sem frete ou extras
 /// let total = Total::default();
 ///
 /// // Venda com frete e DIFAL
 /// let total = Total {
-///     v_frete: 15.00,
-///     v_icms_uf_dest: 7.00,
-///     v_icms_uf_remet: 3.00,
+///     v_frete: dec!(15.00),
+///     v_icms_uf_dest: dec!(7.00),
+///     v_icms_uf_remet: dec!(3.00),
 ///     ..Default::default()
 /// };
 /// ```
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Total {
     /// BC do ICMS ST — auto-calculado dos itens com ICMS10/30/70; informe apenas ST global.
-    pub v_bc_st: f64,
+    pub v_bc_st: Decimal,
     /// Valor do ICMS ST — auto-calculado dos itens; informe apenas ST global.
-    pub v_st: f64,
-    /// FCP (Fundo de Combate à Pobreza).
-    pub v_fcp: f64,
-    /// FCP retido por ST.
-    pub v_fcpst: f64,
+    pub v_st: Decimal,
+    /// FCP (Fundo de Combate à Pobreza) — somado automaticamente a `Det.fcp`.
+    pub v_fcp: Decimal,
+    /// FCP retido por ST — somado automaticamente a `Det.fcp_st`.
+    pub v_fcpst: Decimal,
     /// FCP retido anteriormente por ST.
-    pub v_fcpst_ret: f64,
-    /// FCP diferencial de alíquota UF destino (DIFAL).
-    pub v_fcpuf_dest: f64,
-    /// ICMS diferencial de alíquota UF destino (DIFAL).
-    pub v_icms_uf_dest: f64,
-    /// ICMS diferencial de alíquota UF remetente (DIFAL).
-    pub v_icms_uf_remet: f64,
-    /// Frete global (não por item).
-    pub v_frete: f64,
-    /// Seguro global.
-    pub v_seg: f64,
-    /// Outras despesas globais.
-    pub v_outro: f64,
-    /// Imposto de Importação.
-    pub v_ii: f64,
+    pub v_fcpst_ret: Decimal,
+    /// FCP diferencial de alíquota UF destino (DIFAL) — somado a `Det.icms_uf_dest`.
+    pub v_fcpuf_dest: Decimal,
+    /// ICMS diferencial de alíquota UF destino (DIFAL) — somado a `Det.icms_uf_dest`.
+    pub v_icms_uf_dest: Decimal,
+    /// ICMS diferencial de alíquota UF remetente (DIFAL) — somado a `Det.icms_uf_dest`.
+    pub v_icms_uf_remet: Decimal,
+    /// Frete total — a SEFAZ confere com a soma de `Det.v_frete` (rejeição 535).
+    pub v_frete: Decimal,
+    /// Seguro total — soma de `Det.v_seg` (rejeição 536).
+    pub v_seg: Decimal,
+    /// Outras despesas — soma de `Det.v_outro` (rejeição 604).
+    pub v_outro: Decimal,
+    /// Ignorado: o `vII` do total é sempre a soma de `Det.ii`. Se informado, deve coincidir com
+    /// essa soma — [`NFeBuilder::validar`](crate::emissao::NFeBuilder::validar) aponta a diferença (rejeição 601).
+    pub v_ii: Decimal,
     /// IPI global — somado automaticamente aos itens com `Det.ipi`.
-    pub v_ipi: f64,
+    pub v_ipi: Decimal,
     /// IPI devolvido.
-    pub v_ipi_devol: f64,
+    pub v_ipi_devol: Decimal,
 }
 
 // ─── Transp ───────────────────────────────────────────────────────────────────
@@ -682,7 +1066,7 @@ pub struct Pag {
     pub t_pag: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x_pag: Option<String>,
-    pub v_pag: f64,
+    pub v_pag: Decimal,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tp_integra: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -701,7 +1085,7 @@ impl Default for Pag {
             ind_pag: 0,
             t_pag: "99".to_string(),
             x_pag: None,
-            v_pag: 0.0,
+            v_pag: Decimal::ZERO,
             tp_integra: None,
             cnpj: None,
             t_band: None,
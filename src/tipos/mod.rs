@@ -1,8 +1,14 @@
+pub mod calculo;
 pub mod cancelar;
 pub mod config;
 pub mod emissao;
 pub mod manifestacao;
 pub mod service_status;
 
-pub use emissao::{Cofins, Det, Dest, Emit, IbsCbs, Icms, Ide, InfAdic, Ipi, Pag, Pis, Total, Transp};
+pub use rust_decimal::Decimal;
+pub use calculo::{CalculoIcms, Difal};
+pub use emissao::{
+    Adi, Arma, Cide, Cofins, Comb, Det, DetExport, Dest, Di, Emit, Encerrante, ExportInd, Exporta, Fcp, IbsCbs,
+    Icms, IcmsUfDest, Ide, Ii, InfAdic, Ipi, Med, OrigComb, Pag, Pis, Rastro, Total, Transp, VeicProd,
+};
 pub use config::{Environment, Fields, PassFile, Password, Use};
@@ -59,3 +59,20 @@ impl XmlExtractorSignature for XmlExtractor {
         self.nfe_from_string(&xml_content)
     }
 }
+
+/// `nfeProc` autorizado ou, para a nota emitida sem autorização prévia e ainda não
+/// transmitida (NFC-e offline, `tpEmis` 9; NF-e em FS-DA, `tpEmis` 5), a `<NFe>`
+/// assinada com protocolo vazio.
+pub(crate) fn nfe_proc_ou_offline(xml: &str) -> Result<NFeProc> {
+    let extractor = XmlExtractor::new();
+    if xml.contains("<nfeProc") {
+        return extractor.nfe_proc_from_string(xml);
+    }
+    let nfe = extractor.nfe_from_string(xml)?;
+    if !matches!(nfe.inf_nfe.ide.tp_emis.as_deref(), Some("5") | Some("9")) {
+        return Err(DfeError::Xml(
+            "XML sem protocolo de autorização (nfeProc); só a FS-DA (tpEmis 5) e a NFC-e offline (tpEmis 9) são aceitas sem ele".to_string(),
+        ));
+    }
+    Ok(NFeProc { versao: "4.00".to_string(), nfe, prot_nfe: ProtNFe { inf_prot: None } })
+}
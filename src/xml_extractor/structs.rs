@@ -216,6 +216,90 @@ pub struct Prod {
     pub v_un_trib: Option<String>,
     #[serde(rename = "indTot")]
     pub ind_tot: Option<String>,
+    #[serde(rename = "rastro", default)]
+    pub rastro: Vec<Rastro>,
+    #[serde(rename = "veicProd")]
+    pub veic_prod: Option<VeicProd>,
+    #[serde(rename = "med")]
+    pub med: Option<Med>,
+    #[serde(rename = "arma", default)]
+    pub arma: Vec<Arma>,
+    #[serde(rename = "comb")]
+    pub comb: Option<Comb>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rastro {
+    #[serde(rename = "nLote")]
+    pub n_lote: Option<String>,
+    #[serde(rename = "qLote")]
+    pub q_lote: Option<String>,
+    #[serde(rename = "dFab")]
+    pub d_fab: Option<String>,
+    #[serde(rename = "dVal")]
+    pub d_val: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VeicProd {
+    #[serde(rename = "chassi")]
+    pub chassi: Option<String>,
+    #[serde(rename = "xCor")]
+    pub x_cor: Option<String>,
+    #[serde(rename = "nMotor")]
+    pub n_motor: Option<String>,
+    #[serde(rename = "anoMod")]
+    pub ano_mod: Option<String>,
+    #[serde(rename = "anoFab")]
+    pub ano_fab: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Med {
+    #[serde(rename = "cProdANVISA")]
+    pub c_prod_anvisa: Option<String>,
+    #[serde(rename = "vPMC")]
+    pub v_pmc: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Arma {
+    #[serde(rename = "tpArma")]
+    pub tp_arma: Option<String>,
+    #[serde(rename = "nSerie")]
+    pub n_serie: Option<String>,
+    #[serde(rename = "nCano")]
+    pub n_cano: Option<String>,
+    #[serde(rename = "descr")]
+    pub descr: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Comb {
+    #[serde(rename = "cProdANP")]
+    pub c_prod_anp: Option<String>,
+    #[serde(rename = "descANP")]
+    pub desc_anp: Option<String>,
+    #[serde(rename = "CODIF")]
+    pub codif: Option<String>,
+    #[serde(rename = "UFCons")]
+    pub uf_cons: Option<String>,
+    #[serde(rename = "encerrante")]
+    pub encerrante: Option<Encerrante>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Encerrante {
+    #[serde(rename = "nBico")]
+    pub n_bico: Option<String>,
+    #[serde(rename = "nBomba")]
+    pub n_bomba: Option<String>,
+    #[serde(rename = "nTanque")]
+    pub n_tanque: Option<String>,
+    #[serde(rename = "vEncIni")]
+    pub v_enc_ini: Option<String>,
+    #[serde(rename = "vEncFin")]
+    pub v_enc_fin: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]